@@ -0,0 +1,63 @@
+export interface AnalyzeOptions {
+  /** Directory paths are relative to, the current working directory by default */
+  cwd?: string
+  /** TypeScript `tsconfig.json` path */
+  tsconfig?: string
+  collapsePackages?: boolean
+  mainFields?: Array<string>
+  conditionNames?: Array<string>
+  trackStyles?: boolean
+  ignoreDynamicImports?: boolean
+  ignoreTypeImports?: boolean
+}
+
+export interface GraphNode {
+  /** Relative to `cwd`, or the name of the package */
+  path: string
+  kind: 'module' | 'package' | 'unresolved' | 'style' | 'asset' | 'json' | 'declaration'
+}
+
+export interface GraphEdge {
+  from: string
+  to: string
+  kind: 'static' | 'reference' | 'dynamic' | 'require' | 'worker' | 'glob' | 'context' | 'type'
+  /** Names of the bindings imported through the edge, sorted */
+  symbols: Array<string>
+}
+
+export interface DiagnosticSpan {
+  /** Byte offsets of the range, in the whole file */
+  start: number
+  end: number
+  /** Line and column of `start`, from 1 */
+  line: number
+  column: number
+}
+
+export interface Diagnostic {
+  /** Relative to `cwd` */
+  path: string
+  /** `null` for diagnostics about the whole file */
+  span: DiagnosticSpan | null
+  severity: 'warn' | 'error'
+  code:
+    | 'unreadable-file'
+    | 'file-too-large'
+    | 'syntax-error'
+    | 'unfollowed-import'
+    | 'unresolved-import'
+    | 'deep-import'
+    | 'missing-export'
+    | 'ambiguous-export'
+  message: string
+}
+
+export interface DependencyGraph {
+  nodes: Array<GraphNode>
+  edges: Array<GraphEdge>
+  /** Reported while analyzing, e.g. unresolved imports and syntax errors */
+  diagnostics: Array<Diagnostic>
+}
+
+/** Analyzes the files reachable from `paths`, returning the graph */
+export function analyze(paths: Array<string>, options?: AnalyzeOptions): DependencyGraph
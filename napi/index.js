@@ -0,0 +1,2 @@
+// Built by `npm run build`, which names the binding after `napi.name` of package.json
+module.exports = require('./marsh.node');
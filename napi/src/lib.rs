@@ -0,0 +1,56 @@
+//! Node.js bindings of marsh, so JavaScript tooling, e.g. bundler plugins and lint rules,
+//! can analyze files in-process instead of spawning the binary and parsing its output.
+
+use std::path::PathBuf;
+
+use marsh::{utils::evict_unused, AnalyzeService, AnalyzeServiceOptions};
+use napi::{Error, Result};
+use napi_derive::napi;
+use serde_json::Value;
+
+/// Options of `analyze`, named like the options of the command line
+#[napi(object)]
+#[derive(Debug, Default)]
+pub struct AnalyzeOptions {
+    /// Directory paths are relative to, the current working directory by default
+    pub cwd: Option<String>,
+    /// TypeScript `tsconfig.json` path
+    pub tsconfig: Option<String>,
+    pub collapse_packages: Option<bool>,
+    pub main_fields: Option<Vec<String>>,
+    pub condition_names: Option<Vec<String>>,
+    pub track_styles: Option<bool>,
+    pub ignore_dynamic_imports: Option<bool>,
+    pub ignore_type_imports: Option<bool>,
+}
+
+/// Analyzes the files reachable from `paths`, returning the graph as
+/// `{ nodes, edges, diagnostics }`
+#[napi]
+pub fn analyze(paths: Vec<String>, options: Option<AnalyzeOptions>) -> Result<Value> {
+    let options = options.unwrap_or_default();
+    let cwd = match options.cwd {
+        Some(cwd) => PathBuf::from(cwd),
+        None => std::env::current_dir().map_err(|error| Error::from_reason(error.to_string()))?,
+    };
+    let paths = paths
+        .iter()
+        .map(|path| cwd.join(path).into_boxed_path())
+        .collect();
+    let mut service_options = AnalyzeServiceOptions::new(cwd.as_path(), paths)
+        .with_cross_module(true)
+        .with_collapse_packages(options.collapse_packages.unwrap_or_default())
+        .with_main_fields(options.main_fields.unwrap_or_default())
+        .with_condition_names(options.condition_names.unwrap_or_default())
+        .with_track_styles(options.track_styles.unwrap_or_default())
+        .with_dynamic_imports(!options.ignore_dynamic_imports.unwrap_or_default())
+        .with_type_imports(!options.ignore_type_imports.unwrap_or_default());
+    if let Some(tsconfig) = options.tsconfig {
+        service_options = service_options.with_tsconfig(tsconfig);
+    }
+    let graph = AnalyzeService::new(service_options).analyze();
+    let graph = serde_json::to_value(graph).map_err(|error| Error::from_reason(error.to_string()));
+    // The process outlives the run, its files are not kept interned
+    evict_unused();
+    graph
+}
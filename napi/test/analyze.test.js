@@ -0,0 +1,36 @@
+// Smoke test of the built binding, run by `npm test` after `npm run build`
+const assert = require('node:assert');
+const fs = require('node:fs');
+const os = require('node:os');
+const path = require('node:path');
+const test = require('node:test');
+
+const { analyze } = require('..');
+
+test('analyze returns the graph', () => {
+  const cwd = fs.mkdtempSync(path.join(os.tmpdir(), 'marsh-napi-'));
+  try {
+    fs.writeFileSync(path.join(cwd, 'main.ts'), "import { a } from './a'; import './missing';");
+    fs.writeFileSync(path.join(cwd, 'a.ts'), 'export const a = 1;');
+
+    const graph = analyze(['main.ts'], { cwd });
+    assert.deepStrictEqual(Object.keys(graph).sort(), ['diagnostics', 'edges', 'nodes']);
+    assert.deepStrictEqual(
+      graph.nodes.find((node) => node.path === 'a.ts'),
+      { path: 'a.ts', kind: 'module' },
+    );
+    const edge = graph.edges.find((edge) => edge.to === 'a.ts');
+    assert.deepStrictEqual(edge, { from: 'main.ts', to: 'a.ts', kind: 'static', symbols: ['a'] });
+
+    const [diagnostic] = graph.diagnostics;
+    assert.deepStrictEqual(
+      Object.keys(diagnostic).sort(),
+      ['code', 'message', 'path', 'severity', 'span'],
+    );
+    assert.strictEqual(diagnostic.path, 'main.ts');
+    assert.strictEqual(diagnostic.code, 'unresolved-import');
+    assert.strictEqual(diagnostic.severity, 'error');
+  } finally {
+    fs.rmSync(cwd, { recursive: true, force: true });
+  }
+});
@@ -0,0 +1,430 @@
+//! Files affected by the changes since a git ref: the changed files and every file importing
+//! them, directly or not, to only test or build what a change can break. In workspaces, the
+//! same for packages: the changed packages and every package depending on them.
+
+use std::{
+    collections::{BTreeSet, VecDeque},
+    fmt,
+    path::Path,
+    process::Command,
+};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::Serialize;
+
+use crate::{
+    graph::GraphBuilder,
+    package_level::PackageLevelGraph,
+    unused_dependencies::{find_manifest, package_name},
+    workspace::Workspace,
+};
+
+#[derive(Debug, Default, Serialize)]
+pub struct AffectedReport {
+    /// Files changed since the ref, relative to the current working directory, sorted
+    pub changed: Vec<String>,
+    /// Changed files in the graph and the files importing them, sorted
+    pub files: Vec<String>,
+    /// Names of the packages owning the affected files, sorted
+    pub packages: Vec<String>,
+    /// Affected test files, sorted
+    pub tests: Vec<String>,
+}
+
+impl AffectedReport {
+    /// Maps `changed` files, relative to `cwd` like the nodes of `graph`, to the files importing
+    /// them. Changed files the entries do not reach affect nothing, so test files have to be
+    /// entries to be reported. Deleted files affect the files still importing them, which
+    /// the graph holds as unresolved imports, and the package which owned them.
+    pub fn new(cwd: &Path, changed: Vec<String>, graph: &GraphBuilder) -> Self {
+        let deleted = changed
+            .iter()
+            .filter(|file| !cwd.join(file).exists())
+            .collect::<Vec<_>>();
+        let unresolved = deleted
+            .iter()
+            .flat_map(|file| unresolved_names(file))
+            .collect::<FxHashSet<_>>();
+        let files = graph
+            .dependents_of(
+                changed
+                    .iter()
+                    .map(String::as_str)
+                    .chain(unresolved.iter().map(String::as_str)),
+            )
+            .into_iter()
+            .filter(|file| !unresolved.contains(*file))
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+
+        let mut nearest = FxHashMap::default();
+        let mut names = FxHashMap::default();
+        let mut packages = FxHashSet::default();
+        for file in files.iter().chain(deleted.iter().copied()) {
+            let Some(manifest) = cwd
+                .join(file)
+                .parent()
+                .and_then(|dir| find_manifest(dir, &mut nearest))
+            else {
+                continue;
+            };
+            let name = names
+                .entry(manifest)
+                .or_insert_with_key(|manifest| package_name(manifest));
+            if let Some(name) = name {
+                packages.insert(name.clone());
+            }
+        }
+        let mut packages = packages.into_iter().collect::<Vec<_>>();
+        packages.sort_unstable();
+
+        let tests = files
+            .iter()
+            .filter(|file| is_test_file(file))
+            .cloned()
+            .collect();
+        Self {
+            changed,
+            files,
+            packages,
+            tests,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct AffectedPackagesReport {
+    /// Workspace packages owning changed files, sorted
+    pub changed: Vec<String>,
+    /// Changed packages and the packages importing them, directly or not, sorted
+    pub packages: Vec<String>,
+}
+
+impl AffectedPackagesReport {
+    /// Maps `changed` files, relative to `cwd`, to the packages of `workspace` owning them,
+    /// then follows the imports of `graph` back to the packages depending on them. Any file
+    /// of a package changes it, not only those of the graph, e.g. its `package.json`.
+    pub fn new(
+        cwd: &Path,
+        workspace: &Workspace,
+        changed: &[String],
+        graph: &PackageLevelGraph,
+    ) -> Self {
+        let changed = changed
+            .iter()
+            .filter_map(|file| workspace.package_of_file(&cwd.join(file)))
+            .collect::<BTreeSet<_>>();
+
+        let mut importers = FxHashMap::<&str, Vec<&str>>::default();
+        for edge in &graph.edges {
+            importers
+                .entry(edge.to.as_str())
+                .or_default()
+                .push(edge.from.as_str());
+        }
+        let mut packages = changed.clone();
+        let mut queue = changed.iter().copied().collect::<VecDeque<_>>();
+        while let Some(package) = queue.pop_front() {
+            for &importer in importers.get(package).into_iter().flatten() {
+                if packages.insert(importer) {
+                    queue.push_back(importer);
+                }
+            }
+        }
+        Self {
+            changed: changed.into_iter().map(str::to_string).collect(),
+            packages: packages.into_iter().map(str::to_string).collect(),
+        }
+    }
+}
+
+impl fmt::Display for AffectedPackagesReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for package in &self.packages {
+            writeln!(f, "{package}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Files changed since the merge base of `since` and `HEAD`, committed or not, and untracked
+/// files. Deleted files are included, renamed files under both their paths. Paths are
+/// relative to `cwd` like the nodes of the graph, files of the repository outside of it,
+/// e.g. of a sibling workspace package, are absolute.
+///
+/// # Errors
+/// When git fails, e.g. outside of a repository or for an unknown ref
+pub fn changed_files(cwd: &Path, since: &str) -> Result<Vec<String>, String> {
+    let prefix = git(cwd, &["rev-parse", "--show-prefix"])?;
+    let prefix = Path::new(prefix.trim());
+    // The root of the repository as an ancestor of `cwd`, so symbolic links are kept
+    let root = cwd
+        .ancestors()
+        .nth(prefix.components().count())
+        .unwrap_or(cwd);
+    let base = git(root, &["merge-base", since, "HEAD"])?;
+    let diff = git(root, &["diff", "--name-only", "--no-renames", base.trim()])?;
+    let untracked = git(root, &["ls-files", "--others", "--exclude-standard"])?;
+    let mut files = diff
+        .lines()
+        .chain(untracked.lines())
+        .filter(|line| !line.is_empty())
+        .map(|line| match Path::new(line).strip_prefix(prefix) {
+            Ok(relative) => relative.display().to_string(),
+            Err(_) => root.join(line).display().to_string(),
+        })
+        .collect::<Vec<_>>();
+    files.sort_unstable();
+    files.dedup();
+    Ok(files)
+}
+
+fn git(cwd: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .map_err(|error| format!("Failed to run git: {error}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|error| format!("git {}: {error}", args.join(" ")))
+}
+
+/// Names of the unresolved nodes importers of the deleted `file` are left with besides
+/// `file` itself, e.g. `src/old` and `src/old.js` for `src/old.ts`, and `src/old` for
+/// `src/old/index.ts`
+fn unresolved_names(file: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let (dir, file_name) = file.rsplit_once('/').unwrap_or(("", file));
+    let Some((stem, extension)) = file_name.rsplit_once('.') else {
+        return names;
+    };
+    let path = file.strip_suffix(&format!(".{extension}")).unwrap_or(file);
+    names.push(path.to_string());
+    let script = match extension {
+        "ts" => "js",
+        "tsx" => "jsx",
+        "mts" => "mjs",
+        "cts" => "cjs",
+        _ => extension,
+    };
+    if script != extension {
+        names.push(format!("{path}.{script}"));
+    }
+    if stem == "index" && !dir.is_empty() {
+        names.push(dir.to_string());
+    }
+    names
+}
+
+/// Returns `true` for files test runners pick up by default, e.g. `a.test.ts` or
+/// `__tests__/a.ts`
+fn is_test_file(path: &str) -> bool {
+    let mut components = path.split('/');
+    let file_name = components.next_back().unwrap_or_default();
+    file_name.contains(".test.")
+        || file_name.contains(".spec.")
+        || components.any(|component| component == "__tests__")
+}
+
+#[cfg(test)]
+mod test {
+    use std::{path::Path, process::Command};
+
+    use super::{changed_files, is_test_file, AffectedPackagesReport, AffectedReport};
+    use crate::{
+        collector::CollectorService,
+        graph::{EdgeType, GraphBuilder, NodeKind},
+        message::Message,
+        package_level::PackageLevelGraph,
+        service::{AnalyzeService, AnalyzeServiceOptions},
+        utils::{PathAtom, TestDir},
+        workspace::test_workspace,
+    };
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args([
+                "-c",
+                "user.name=marsh",
+                "-c",
+                "user.email=marsh@example.com",
+            ])
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?}");
+    }
+
+    #[test]
+    fn test_affected_report() {
+        let root = TestDir::new("affected");
+        root.write("package.json", r#"{ "name": "app" }"#);
+        root.write("packages/ui/package.json", r#"{ "name": "ui" }"#);
+
+        let deps = [
+            ("src/main.ts", "packages/ui/src/button.ts"),
+            ("src/main.ts", "src/utils.ts"),
+            ("src/utils.test.ts", "src/utils.ts"),
+            ("packages/ui/src/button.ts", "packages/ui/src/theme.ts"),
+            (
+                "packages/ui/src/button.test.ts",
+                "packages/ui/src/button.ts",
+            ),
+        ]
+        .map(|(src, dst)| {
+            (
+                PathAtom::new(src),
+                Message::new(dst, NodeKind::Module, EdgeType::Static),
+            )
+        });
+        let mut graph = GraphBuilder::new();
+        graph.add_deps(&deps);
+        graph.add_deps(&[(
+            PathAtom::new("src/utils.test.ts"),
+            Message::new("src/helpers", NodeKind::Unresolved, EdgeType::Static),
+        )]);
+
+        let changed = vec![
+            "README.md".to_string(),
+            "packages/ui/src/theme.ts".to_string(),
+        ];
+        let report = AffectedReport::new(&root, changed, &graph);
+        assert_eq!(
+            report.files,
+            [
+                "packages/ui/src/button.test.ts",
+                "packages/ui/src/button.ts",
+                "packages/ui/src/theme.ts",
+                "src/main.ts",
+            ]
+        );
+        assert_eq!(report.packages, ["app", "ui"]);
+        assert_eq!(report.tests, ["packages/ui/src/button.test.ts"]);
+
+        // The importers of a deleted file import it unresolved
+        let report = AffectedReport::new(&root, vec!["src/helpers.ts".to_string()], &graph);
+        assert_eq!(report.files, ["src/utils.test.ts"]);
+        assert_eq!(report.packages, ["app"]);
+    }
+
+    #[test]
+    fn test_changed_files() {
+        let root = TestDir::new("changed-files");
+        let git = |args: &[&str]| git(&root, args);
+        git(&["init", "-q"]);
+        for file in ["a.ts", "b.ts", "c.ts", "d.ts"] {
+            root.write(file, &format!("export const {} = 1;", &file[..1]));
+        }
+        git(&["add", "."]);
+        git(&["commit", "-qm", "Initial"]);
+        root.write("a.ts", "export const a = 2;");
+        std::fs::remove_file(root.join("b.ts")).unwrap();
+        git(&["mv", "c.ts", "renamed.ts"]);
+        root.write("untracked.ts", "");
+
+        assert_eq!(
+            changed_files(&root, "HEAD").unwrap(),
+            ["a.ts", "b.ts", "c.ts", "renamed.ts", "untracked.ts"]
+        );
+        assert!(changed_files(&root, "unknown-ref").is_err());
+
+        // Changes outside of the current working directory are kept, by absolute path
+        root.write("packages/a/index.ts", "");
+        assert_eq!(
+            changed_files(&root.join("packages/a"), "HEAD").unwrap(),
+            [
+                root.join("a.ts").display().to_string(),
+                root.join("b.ts").display().to_string(),
+                root.join("c.ts").display().to_string(),
+                root.join("renamed.ts").display().to_string(),
+                root.join("untracked.ts").display().to_string(),
+                "index.ts".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_affected_from_package() {
+        let (root, _) = test_workspace(
+            "affected-from-package",
+            &[
+                (
+                    "packages/a",
+                    r#"{"name":"a","dependencies":{"b":"workspace:*"}}"#,
+                ),
+                ("packages/b", r#"{"name":"b"}"#),
+            ],
+        );
+        root.write("packages/a/src/index.ts", "import 'b';");
+        root.write("packages/b/src/index.ts", "export {};");
+        git(&root, &["init", "-q"]);
+        git(&root, &["add", "."]);
+        git(&root, &["commit", "-qm", "Initial"]);
+        root.write("packages/b/src/index.ts", "export const b = 1;");
+
+        // Only the sibling package changed, the package analyzed imports it
+        let cwd = root.join("packages/a");
+        let changed = changed_files(&cwd, "HEAD").unwrap();
+        let entries = vec![cwd.join("src/index.ts").into()];
+        let options = AnalyzeServiceOptions::new(cwd.as_path(), entries).with_cross_module(true);
+        let mut collector = CollectorService::default();
+        AnalyzeService::new(options).run(collector.sender());
+        collector.start();
+        let report = AffectedReport::new(&cwd, changed, &collector.graph());
+        let b = root.join("packages/b/src/index.ts").display().to_string();
+        assert_eq!(report.files, [b.as_str(), "src/index.ts"]);
+        assert_eq!(report.packages, ["a", "b"]);
+    }
+
+    #[test]
+    fn test_affected_packages_report() {
+        let (root, workspace) = test_workspace(
+            "affected-packages",
+            &[
+                ("packages/theme", r#"{ "name": "theme" }"#),
+                ("packages/ui", r#"{ "name": "ui" }"#),
+                ("packages/icons", r#"{ "name": "icons" }"#),
+                ("apps/web", r#"{ "name": "web" }"#),
+            ],
+        );
+
+        let deps = [
+            ("apps/web/main.ts", "packages/ui/button.ts"),
+            ("apps/web/main.ts", "packages/icons/index.ts"),
+            ("packages/ui/button.ts", "packages/theme/colors.ts"),
+        ]
+        .map(|(src, dst)| {
+            (
+                PathAtom::new(src),
+                Message::new(dst, NodeKind::Module, EdgeType::Static),
+            )
+        });
+        let graph = PackageLevelGraph::new(&root, &workspace, &deps);
+
+        let changed = ["packages/theme/package.json", "README.md"].map(str::to_string);
+        let report = AffectedPackagesReport::new(&root, &workspace, &changed, &graph);
+        assert_eq!(report.changed, ["theme"]);
+        assert_eq!(report.packages, ["theme", "ui", "web"]);
+        assert_eq!(report.to_string(), "theme\nui\nweb\n");
+
+        let changed = ["README.md".to_string()];
+        let report = AffectedPackagesReport::new(&root, &workspace, &changed, &graph);
+        assert_eq!(report.to_string(), "");
+    }
+
+    #[test]
+    fn test_is_test_file() {
+        assert!(is_test_file("src/a.test.ts"));
+        assert!(is_test_file("src/__tests__/a.ts"));
+        assert!(is_test_file("a.spec.tsx"));
+        assert!(!is_test_file("src/tests.ts"));
+        assert!(!is_test_file("__tests__.ts"));
+    }
+}
@@ -0,0 +1,121 @@
+//! Barrel files, modules which only re-export other modules.
+
+use std::{fmt, path::Path, sync::Arc};
+
+use dashmap::DashMap;
+use oxc_semantic::ModuleRecord;
+use serde::Serialize;
+
+use crate::{exports::trace_export, scanner::TypeExports};
+
+/// Returns `true` when `record` re-exports other modules and has no exports of its own.
+pub fn is_barrel(record: &ModuleRecord) -> bool {
+    record.local_export_entries.is_empty()
+        && !(record.indirect_export_entries.is_empty() && record.star_export_entries.is_empty())
+}
+
+/// Follows re-exports of `name` from `record` to the module which defines it.
+pub fn definition_of(
+    record: &Arc<ModuleRecord>,
+    name: &str,
+    type_exports: &DashMap<Arc<Path>, TypeExports>,
+) -> Option<Arc<ModuleRecord>> {
+    let mut trace = trace_export(record, name, type_exports);
+    if !trace.found {
+        return None;
+    }
+    trace.steps.pop().map(|step| step.record)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Barrel {
+    pub path: String,
+    /// Number of modules reachable from the barrel, which importing it drags in
+    pub transitive_modules: usize,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BarrelReport {
+    pub barrels: Vec<Barrel>,
+}
+
+impl BarrelReport {
+    /// Sorts barrels by the number of modules they drag in, largest first.
+    pub fn new(mut barrels: Vec<Barrel>) -> Self {
+        barrels.sort_unstable_by(|a, b| {
+            b.transitive_modules
+                .cmp(&a.transitive_modules)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        Self { barrels }
+    }
+}
+
+impl fmt::Display for BarrelReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.barrels.is_empty() {
+            return write!(f, "No barrel files found.");
+        }
+        for barrel in &self.barrels {
+            writeln!(f, "{:>6}  {}", barrel.transitive_modules, barrel.path)?;
+        }
+        write!(
+            f,
+            "{} barrel files, counts are transitive modules pulled in by each",
+            self.barrels.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{path::Path, sync::Arc};
+
+    use dashmap::DashMap;
+    use oxc_allocator::Allocator;
+    use oxc_parser::Parser;
+    use oxc_semantic::{ModuleRecord, SemanticBuilder};
+    use oxc_span::SourceType;
+
+    use super::{definition_of, is_barrel};
+
+    fn module_record(path: &str, source_text: &str) -> Arc<ModuleRecord> {
+        let allocator = Allocator::default();
+        let source_type = SourceType::from_path(path).unwrap();
+        let ret = Parser::new(&allocator, source_text, source_type).parse();
+        let program = allocator.alloc(ret.program);
+        SemanticBuilder::new(source_text)
+            .build_module_record(Path::new(path), program)
+            .module_record()
+    }
+
+    #[test]
+    fn test_definition_of() {
+        let barrel = module_record(
+            "index.ts",
+            "export * from './button'; export { Input as TextInput } from './input';",
+        );
+        let button = module_record("button.ts", "export const Button = 1;");
+        let input = module_record("input.ts", "export const Input = 1;");
+        barrel
+            .loaded_modules
+            .insert("./button".into(), Arc::clone(&button));
+        barrel
+            .loaded_modules
+            .insert("./input".into(), Arc::clone(&input));
+
+        assert!(is_barrel(&barrel));
+        assert!(!is_barrel(&button));
+
+        let type_exports = DashMap::default();
+        let definition = |name| {
+            definition_of(&barrel, name, &type_exports)
+                .map(|record| record.resolved_absolute_path.clone())
+        };
+        assert_eq!(definition("Button"), Some("button.ts".into()));
+        assert_eq!(definition("TextInput"), Some("input.ts".into()));
+        assert_eq!(definition("Input"), None);
+        assert_eq!(definition("default"), None);
+    }
+}
@@ -0,0 +1,138 @@
+//! Violations recorded when adopting rules, which no longer fail the run.
+
+use std::{fs, path::Path};
+
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{rules::RuleViolation, utils::read_to_string};
+
+/// Name of the baseline file looked up in the current working directory
+pub const BASELINE_FILE: &str = "marsh-baseline.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BaselineViolation {
+    /// Path the violation is reported for, relative to the current working directory
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    /// Sorted
+    pub violations: Vec<BaselineViolation>,
+}
+
+impl Baseline {
+    /// Records `violations`, warnings included.
+    pub fn new(violations: &[RuleViolation]) -> Self {
+        let mut violations = violations
+            .iter()
+            .map(|violation| BaselineViolation {
+                path: violation.path.clone(),
+                message: violation.message.clone(),
+            })
+            .collect::<Vec<_>>();
+        violations.sort_unstable_by(|a, b| (&a.path, &a.message).cmp(&(&b.path, &b.message)));
+        Self { violations }
+    }
+
+    /// Loads `path`, or the baseline file in `cwd` when no path is given.
+    /// Without a given path, a missing baseline file is an empty baseline.
+    ///
+    /// # Errors
+    /// When the baseline file cannot be read or is not a valid baseline
+    pub fn load(cwd: &Path, path: Option<&Path>) -> Result<Self, String> {
+        let file = cwd.join(path.unwrap_or(Path::new(BASELINE_FILE)));
+        if path.is_none() && !file.is_file() {
+            return Ok(Self::default());
+        }
+        let display = file
+            .strip_prefix(cwd)
+            .unwrap_or(&file)
+            .display()
+            .to_string();
+        let source_text = read_to_string(&file).map_err(|error| format!("{display}: {error}"))?;
+        serde_json::from_str(&source_text).map_err(|error| format!("{display}: {error}"))
+    }
+
+    /// Writes the baseline to `path`, or to the baseline file in `cwd` when no path is given.
+    ///
+    /// # Errors
+    /// When the baseline file cannot be written
+    pub fn write(&self, cwd: &Path, path: Option<&Path>) -> Result<(), String> {
+        let file = cwd.join(path.unwrap_or(Path::new(BASELINE_FILE)));
+        let json = serde_json::to_string_pretty(self).unwrap();
+        fs::write(&file, json + "\n").map_err(|error| format!("{}: {error}", file.display()))
+    }
+
+    /// Keeps the violations not recorded in the baseline. A violation recorded once only
+    /// excuses one occurrence, so new duplicates of a known violation still fail the run.
+    pub fn new_violations(&self, violations: Vec<RuleViolation>) -> Vec<RuleViolation> {
+        let mut excuses = self.excuses();
+        violations
+            .into_iter()
+            .filter(|violation| !excuses.excuse(violation))
+            .collect()
+    }
+
+    /// The occurrences the baseline excuses, to tell violations apart as they are found
+    pub fn excuses(&self) -> Excuses {
+        let mut known = FxHashMap::<(String, String), usize>::default();
+        for violation in &self.violations {
+            *known
+                .entry((violation.path.clone(), violation.message.clone()))
+                .or_default() += 1;
+        }
+        Excuses(known)
+    }
+}
+
+/// Occurrences of violations a baseline still excuses, see [`Baseline::new_violations`]
+#[derive(Debug, Default)]
+pub struct Excuses(FxHashMap<(String, String), usize>);
+
+impl Excuses {
+    /// Whether `violation` is recorded in the baseline, using up one occurrence
+    pub fn excuse(&mut self, violation: &RuleViolation) -> bool {
+        let key = (violation.path.clone(), violation.message.clone());
+        match self.0.get_mut(&key) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Baseline;
+    use crate::rules::{RuleViolation, Severity};
+
+    #[test]
+    fn test_new_violations() {
+        let violation = |path: &str, message: &str| {
+            RuleViolation::new(path, "layers", Severity::Error, message.to_string())
+        };
+        let baseline = Baseline::new(&[
+            violation("src/b.ts", "Importing \"src/c.ts\" violates rule"),
+            violation("src/a.ts", "Importing \"src/b.ts\" violates rule"),
+        ]);
+        assert_eq!(baseline.violations[0].path, "src/a.ts");
+
+        let violations = baseline.new_violations(vec![
+            violation("src/a.ts", "Importing \"src/b.ts\" violates rule"),
+            violation("src/a.ts", "Importing \"src/b.ts\" violates rule"),
+            violation("src/d.ts", "Importing \"src/c.ts\" violates rule"),
+        ]);
+        assert_eq!(
+            violations,
+            [
+                violation("src/a.ts", "Importing \"src/b.ts\" violates rule"),
+                violation("src/d.ts", "Importing \"src/c.ts\" violates rule"),
+            ]
+        );
+    }
+}
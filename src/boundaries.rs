@@ -0,0 +1,255 @@
+//! Tag-based module boundaries: directories and packages get tags, constraints tell which tags
+//! may depend on which, like Nx's `enforce-module-boundaries`.
+
+use std::path::Path;
+
+use glob::Pattern;
+use rustc_hash::FxHashMap;
+use serde::Deserialize;
+
+use crate::{
+    rules::{matches_any, Patterns, RuleDocs, Severity},
+    workspace::Workspace,
+};
+
+/// Tags of the directories or packages matching `path`, e.g.
+/// `{ "path": "packages/features/*", "tags": ["scope:feature"] }`
+#[derive(Debug, Clone, Deserialize)]
+struct TaggedPathConfig {
+    path: Patterns,
+    tags: Vec<String>,
+}
+
+/// Tags `source_tag` may and may not depend on, `*` applies to every tagged project
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConstraintConfig {
+    source_tag: String,
+    /// When not empty, dependencies must have one of these tags
+    #[serde(default)]
+    only_depend_on_tags: Vec<String>,
+    /// Dependencies must not have any of these tags
+    #[serde(default)]
+    not_depend_on_tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BoundariesConfig {
+    tags: Vec<TaggedPathConfig>,
+    constraints: Vec<ConstraintConfig>,
+    #[serde(default)]
+    pub severity: Severity,
+    #[serde(flatten)]
+    docs: RuleDocs,
+}
+
+#[derive(Debug, Clone)]
+struct TaggedPath {
+    path: Vec<Pattern>,
+    tags: Vec<String>,
+}
+
+/// A directory or package matched by the tagged paths, edges within it are not constrained
+#[derive(Debug, PartialEq, Eq)]
+struct Project<'a> {
+    path: &'a str,
+    tags: Vec<&'a str>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Boundaries {
+    tags: Vec<TaggedPath>,
+    constraints: Vec<ConstraintConfig>,
+    /// Name of each workspace package by directory, relative to the current working directory
+    packages: FxHashMap<String, String>,
+    pub severity: Severity,
+    pub docs: RuleDocs,
+}
+
+impl Boundaries {
+    /// # Errors
+    /// When a path is not a valid glob
+    pub fn new(config: &BoundariesConfig) -> Result<Self, String> {
+        let tags = config
+            .tags
+            .iter()
+            .map(|tagged| {
+                Ok(TaggedPath {
+                    path: tagged.path.compile("boundaries")?,
+                    tags: tagged.tags.clone(),
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self {
+            tags,
+            constraints: config.constraints.clone(),
+            packages: FxHashMap::default(),
+            severity: config.severity,
+            docs: config.docs.clone(),
+        })
+    }
+
+    /// Lets tagged paths match the files of the packages of `workspace` by package name, with
+    /// `cwd` the directory nodes are relative to
+    #[must_use]
+    pub fn with_workspace_packages(mut self, cwd: &Path, workspace: Option<&Workspace>) -> Self {
+        self.packages = workspace
+            .into_iter()
+            .flat_map(Workspace::packages)
+            .filter_map(|(name, dir)| {
+                let dir = dir.strip_prefix(cwd).ok()?.to_str()?;
+                Some((dir.to_string(), name.to_string()))
+            })
+            .collect();
+        self
+    }
+
+    /// The project `path` belongs to: its nearest ancestor, or itself, matching a tagged path.
+    /// Package nodes, and the directories of workspace packages, are matched by name.
+    fn project<'a>(&'a self, path: &'a str) -> Option<Project<'a>> {
+        Path::new(path).ancestors().find_map(|ancestor| {
+            let ancestor = ancestor.to_str()?;
+            let package = self.packages.get(ancestor);
+            let mut tags = self
+                .tags
+                .iter()
+                .filter(|tagged| {
+                    matches_any(&tagged.path, ancestor)
+                        || package.is_some_and(|name| matches_any(&tagged.path, name))
+                })
+                .flat_map(|tagged| tagged.tags.iter().map(String::as_str))
+                .peekable();
+            tags.peek()?;
+            Some(Project {
+                path: ancestor,
+                tags: tags.collect(),
+            })
+        })
+    }
+
+    /// Checks an edge between the nodes `from` and `to`, returning the broken constraint
+    /// described as a message.
+    pub fn check(&self, from: &str, to: &str) -> Option<String> {
+        let source = self.project(from)?;
+        let target = self.project(to)?;
+        if source.path == target.path {
+            return None;
+        }
+        self.constraints
+            .iter()
+            .filter(|constraint| {
+                constraint.source_tag == "*" || source.tags.contains(&constraint.source_tag.as_str())
+            })
+            .find_map(|constraint| {
+                let has_tag = |tags: &[String]| {
+                    tags.iter()
+                        .any(|tag| target.tags.contains(&tag.as_str()))
+                };
+                let reason = if !constraint.only_depend_on_tags.is_empty()
+                    && !has_tag(&constraint.only_depend_on_tags)
+                {
+                    format!(
+                        "may only depend on {}",
+                        constraint.only_depend_on_tags.join(", ")
+                    )
+                } else if has_tag(&constraint.not_depend_on_tags) {
+                    format!(
+                        "must not depend on {}",
+                        constraint.not_depend_on_tags.join(", ")
+                    )
+                } else {
+                    return None;
+                };
+                Some(format!(
+                    "Importing \"{to}\" crosses a module boundary: \"{}\" ({}) {reason}, \"{}\" is tagged {}",
+                    source.path,
+                    constraint.source_tag,
+                    target.path,
+                    target.tags.join(", ")
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Boundaries, BoundariesConfig};
+    use crate::workspace::test_workspace;
+
+    #[test]
+    fn test_boundaries() {
+        let config: BoundariesConfig = serde_json::from_str(
+            r#"{
+                "tags": [
+                    { "path": "packages/features/*", "tags": ["scope:feature"] },
+                    { "path": "packages/shared", "tags": ["scope:shared"] },
+                    { "path": ["react", "lodash"], "tags": ["scope:vendor"] },
+                    { "path": "lodash", "tags": ["deprecated"] },
+                    { "path": "@myorg/legacy", "tags": ["scope:vendor", "deprecated"] }
+                ],
+                "constraints": [
+                    { "sourceTag": "scope:feature", "onlyDependOnTags": ["scope:shared", "scope:vendor"] },
+                    { "sourceTag": "scope:shared", "onlyDependOnTags": ["scope:vendor"] },
+                    { "sourceTag": "*", "notDependOnTags": ["deprecated"] }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let boundaries = Boundaries::new(&config).unwrap();
+        let allowed = |from, to| boundaries.check(from, to).is_none();
+
+        assert!(allowed(
+            "packages/features/a/src/index.ts",
+            "packages/shared/src/button.ts"
+        ));
+        assert!(allowed(
+            "packages/features/a/src/index.ts",
+            "packages/features/a/src/util.ts"
+        ));
+        assert!(allowed("packages/features/a/src/index.ts", "react"));
+        assert!(!allowed(
+            "packages/features/a/src/index.ts",
+            "packages/features/b/src/index.ts"
+        ));
+        assert!(!allowed(
+            "packages/shared/src/button.ts",
+            "packages/features/a/src/index.ts"
+        ));
+        assert!(!allowed("packages/shared/src/button.ts", "lodash"));
+        // Untagged modules are not constrained
+        assert!(allowed(
+            "scripts/build.ts",
+            "packages/features/a/src/index.ts"
+        ));
+        assert!(allowed("packages/features/a/src/index.ts", "src/main.ts"));
+
+        let message = boundaries
+            .check(
+                "packages/features/a/src/index.ts",
+                "packages/features/b/src/index.ts",
+            )
+            .unwrap();
+        assert_eq!(
+            message,
+            "Importing \"packages/features/b/src/index.ts\" crosses a module boundary: \
+             \"packages/features/a\" (scope:feature) may only depend on scope:shared, scope:vendor, \
+             \"packages/features/b\" is tagged scope:feature"
+        );
+
+        // Workspace packages are tagged by name
+        let (root, workspace) = test_workspace(
+            "boundaries",
+            &[("packages/legacy", r#"{ "name": "@myorg/legacy" }"#)],
+        );
+        let legacy = "packages/legacy/src/index.ts";
+        assert!(allowed("packages/features/a/src/index.ts", legacy));
+        let boundaries = boundaries.with_workspace_packages(&root, Some(&workspace));
+        let message = boundaries
+            .check("packages/features/a/src/index.ts", legacy)
+            .unwrap();
+        assert!(message.ends_with(
+            "\"packages/features/a\" (*) must not depend on deprecated, \
+             \"packages/legacy\" is tagged scope:vendor, deprecated"
+        ));
+    }
+}
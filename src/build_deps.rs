@@ -0,0 +1,233 @@
+//! Dependencies of each file for build systems, so incremental builds follow the imports
+//! instead of hand-written lists: Makefile rules, which Ninja also reads as depfiles, or the
+//! `deps` of Bazel packages.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    path::Path,
+};
+
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+
+use crate::{deep_imports::package_name, graph::NodeKind, message::Message, utils::PathAtom};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildDepsKind {
+    /// `file: dependencies...`, with every file reachable from `file`
+    Make,
+    /// `deps` of each Bazel package, with the packages it imports directly
+    Bazel,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BuildDepsReport {
+    #[serde(skip)]
+    pub kind: BuildDepsKind,
+    /// Dependencies of each file or package, sorted
+    #[serde(flatten)]
+    pub targets: BTreeMap<String, Vec<String>>,
+}
+
+impl BuildDepsReport {
+    /// Rules making each importer of `deps` depend on the files it reaches, directly or not.
+    /// Unresolved imports and collapsed packages are not files and are left out.
+    pub fn make(deps: &[(PathAtom, Message)]) -> Self {
+        let mut imports = FxHashMap::<&str, Vec<&str>>::default();
+        for (src, dst) in deps {
+            if matches!(dst.kind, NodeKind::Unresolved | NodeKind::Package) {
+                continue;
+            }
+            imports
+                .entry(src.as_str())
+                .or_default()
+                .push(dst.file_path.as_str());
+        }
+        let targets = imports
+            .keys()
+            .map(|&file| {
+                let mut reached = BTreeSet::new();
+                let mut stack = vec![file];
+                while let Some(file) = stack.pop() {
+                    for &dependency in imports.get(file).into_iter().flatten() {
+                        if reached.insert(dependency) {
+                            stack.push(dependency);
+                        }
+                    }
+                }
+                reached.remove(file);
+                let reached = reached.into_iter().map(str::to_string).collect();
+                (file.to_string(), reached)
+            })
+            .collect();
+        Self {
+            kind: BuildDepsKind::Make,
+            targets,
+        }
+    }
+
+    /// `deps` of the Bazel packages owning the importers of `deps`, relative to `cwd`, the
+    /// workspace root. A package is the nearest directory with a `BUILD` or `BUILD.bazel`
+    /// file, or the directory of the file without one, and is labeled like its directory.
+    /// Installed packages, collapsed or not, are labeled like the `node_modules` targets of
+    /// `rules_js`.
+    pub fn bazel(cwd: &Path, deps: &[(PathAtom, Message)]) -> Self {
+        let mut labels = FxHashMap::default();
+        let mut label = |file: &str| -> String {
+            let dir = Path::new(file).parent().unwrap_or(Path::new(""));
+            labels
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| bazel_label(cwd, dir))
+                .clone()
+        };
+        let mut targets = BTreeMap::<String, BTreeSet<String>>::new();
+        for (src, dst) in deps {
+            let source = label(src.as_str());
+            let target = match dst.file_path.as_str().rsplit_once("node_modules/") {
+                _ if dst.kind == NodeKind::Unresolved => continue,
+                _ if dst.kind == NodeKind::Package => format!("//:node_modules/{}", dst.file_path),
+                Some((_, installed)) => {
+                    let Some(package) = package_name(installed) else {
+                        continue;
+                    };
+                    format!("//:node_modules/{package}")
+                }
+                None => label(dst.file_path.as_str()),
+            };
+            let dependencies = targets.entry(source.clone()).or_default();
+            if target != source {
+                dependencies.insert(target);
+            }
+        }
+        Self {
+            kind: BuildDepsKind::Bazel,
+            targets: targets
+                .into_iter()
+                .map(|(target, deps)| (target, deps.into_iter().collect()))
+                .collect(),
+        }
+    }
+}
+
+/// Label of the package owning the files of `dir`
+fn bazel_label(cwd: &Path, dir: &Path) -> String {
+    let package = dir
+        .ancestors()
+        .find(|dir| {
+            let dir = cwd.join(dir);
+            dir.join("BUILD").is_file() || dir.join("BUILD.bazel").is_file()
+        })
+        .unwrap_or(dir);
+    let name = package
+        .file_name()
+        .or_else(|| cwd.file_name())
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    format!("//{}:{name}", package.display())
+}
+
+/// `path` escaped for Makefile rules
+fn escape_make(path: &str) -> String {
+    path.replace('$', "$$")
+        .replace(' ', "\\ ")
+        .replace('#', "\\#")
+}
+
+impl fmt::Display for BuildDepsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (target, deps) in &self.targets {
+            match self.kind {
+                BuildDepsKind::Make => {
+                    write!(f, "{}:", escape_make(target))?;
+                    for dep in deps {
+                        write!(f, " \\\n  {}", escape_make(dep))?;
+                    }
+                    writeln!(f)?;
+                }
+                BuildDepsKind::Bazel => {
+                    writeln!(f, "# {target}")?;
+                    writeln!(f, "deps = [")?;
+                    for dep in deps {
+                        writeln!(f, "    \"{dep}\",")?;
+                    }
+                    writeln!(f, "]")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::BuildDepsReport;
+    use crate::{
+        graph::{EdgeType, NodeKind},
+        message::Message,
+        utils::{PathAtom, TestDir},
+    };
+
+    #[test]
+    fn test_build_deps() {
+        let root = TestDir::new("build-deps");
+        std::fs::create_dir_all(root.join("src/ui/icons")).unwrap();
+        root.write("src/ui/BUILD.bazel", "");
+
+        let deps = [
+            ("src/main.ts", "src/app.ts", NodeKind::Module),
+            ("src/app.ts", "src/ui/button.ts", NodeKind::Module),
+            ("src/app.ts", "./missing", NodeKind::Unresolved),
+            ("src/app.ts", "lodash", NodeKind::Package),
+            (
+                "src/ui/button.ts",
+                "src/ui/icons/my icon.svg",
+                NodeKind::Asset,
+            ),
+            (
+                "src/ui/button.ts",
+                "node_modules/@scope/react/index.js",
+                NodeKind::Module,
+            ),
+        ]
+        .map(|(src, dst, kind)| {
+            (
+                PathAtom::new(src),
+                Message::new(dst, kind, EdgeType::Static),
+            )
+        });
+
+        let make = BuildDepsReport::make(&deps);
+        assert_eq!(
+            make.targets["src/main.ts"],
+            [
+                "node_modules/@scope/react/index.js",
+                "src/app.ts",
+                "src/ui/button.ts",
+                "src/ui/icons/my icon.svg",
+            ]
+        );
+        let rule = "src/ui/button.ts: \\\n  node_modules/@scope/react/index.js \\\n  \
+                    src/ui/icons/my\\ icon.svg\n";
+        assert!(make.to_string().contains(rule));
+
+        let bazel = BuildDepsReport::bazel(&root, &deps);
+        assert_eq!(
+            bazel.targets["//src:src"],
+            ["//:node_modules/lodash", "//src/ui:ui"]
+        );
+        assert_eq!(
+            bazel.targets["//src/ui:ui"],
+            ["//:node_modules/@scope/react"]
+        );
+        assert_eq!(
+            bazel.to_string().lines().take(4).collect::<Vec<_>>(),
+            [
+                "# //src/ui:ui",
+                "deps = [",
+                "    \"//:node_modules/@scope/react\",",
+                "]"
+            ]
+        );
+    }
+}
@@ -0,0 +1,671 @@
+//! Parsed sources persisted between runs, so unchanged files are not parsed again.
+//!
+//! An entry holds what parsing a file yields: its module record, the dependencies found by
+//! the scanner, the exports left out of the record and the diagnostics reported while parsing.
+//! Files with several sources, e.g. the script blocks of a component, have one merged entry.
+//! Entries are keyed by the file content and the options affecting parsing, a changed file
+//! misses the cache. Resolution is not cached, where a specifier resolves to depends on files
+//! besides the importer, e.g. a new `index.ts` next to an `index.js`.
+//!
+//! The cache only makes runs faster, entries which cannot be read or written are skipped.
+//! It is kept in memory instead of a directory by `marsh daemon`, whose runs share it.
+
+use std::{
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use dashmap::DashMap;
+use oxc_semantic::ModuleRecord;
+use oxc_span::{CompactStr, Span};
+use oxc_syntax::module_record::{
+    ExportEntry, ExportExportName, ExportImportName, ExportLocalName, ImportEntry,
+    ImportImportName, NameSpan, RequestedModule,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    diagnostic::Diagnostic,
+    scanner::{ScannedDependencies, TypeExports},
+    suppressions::Suppressions,
+};
+
+/// Directory of the cache in the current working directory
+pub const CACHE_DIR: &str = ".marsh-cache";
+
+/// What parsing a source, or every source of a file, yields
+#[derive(Debug, Clone)]
+pub struct ParsedSource {
+    pub module_record: Arc<ModuleRecord>,
+    pub scanned: ScannedDependencies,
+    pub type_exports: TypeExports,
+    /// Syntax errors, with their span in the file
+    pub errors: Vec<Diagnostic>,
+    /// Dependencies which cannot be followed, with their span in the file
+    pub unfollowed: Vec<Diagnostic>,
+    pub suppressions: Suppressions,
+}
+
+impl ParsedSource {
+    /// Merges the parsed sources of the file `path` into one, as if they were one module.
+    /// Bindings exported by several sources are reported as duplicates. Spans in the merged
+    /// record stay relative to the source they come from. Without sources the record is empty.
+    pub fn merge(path: &Path, mut sources: Vec<Self>) -> Self {
+        if sources.len() == 1 {
+            if let Some(source) = sources.pop() {
+                return source;
+            }
+        }
+        let mut record = ModuleRecord::new(path.to_path_buf());
+        record.not_esm = true;
+        let mut scanned = ScannedDependencies::default();
+        let mut type_exports = TypeExports::default();
+        let (mut errors, mut unfollowed) = (vec![], vec![]);
+        let mut suppressions = Suppressions::default();
+        for source in sources {
+            let part = &source.module_record;
+            record.not_esm &= part.not_esm;
+            for (specifier, requested) in &part.requested_modules {
+                record
+                    .requested_modules
+                    .entry(specifier.clone())
+                    .or_default()
+                    .extend(requested.iter().cloned());
+            }
+            record
+                .import_entries
+                .extend_from_slice(&part.import_entries);
+            record
+                .local_export_entries
+                .extend_from_slice(&part.local_export_entries);
+            record
+                .indirect_export_entries
+                .extend_from_slice(&part.indirect_export_entries);
+            record
+                .star_export_entries
+                .extend_from_slice(&part.star_export_entries);
+            for (name, span) in &part.exported_bindings {
+                if record
+                    .exported_bindings
+                    .insert(name.clone(), *span)
+                    .is_some()
+                {
+                    record
+                        .exported_bindings_duplicated
+                        .push(NameSpan::new(name.clone(), *span));
+                }
+            }
+            record
+                .exported_bindings_duplicated
+                .extend_from_slice(&part.exported_bindings_duplicated);
+            match (record.export_default, part.export_default) {
+                (None, span) => record.export_default = span,
+                (Some(_), Some(span)) => record.export_default_duplicated.push(span),
+                (Some(_), None) => {}
+            }
+            record
+                .export_default_duplicated
+                .extend_from_slice(&part.export_default_duplicated);
+
+            scanned.dependencies.extend(source.scanned.dependencies);
+            scanned.globs.extend(source.scanned.globs);
+            scanned.contexts.extend(source.scanned.contexts);
+            scanned.non_literal.extend(source.scanned.non_literal);
+            type_exports.names.extend(source.type_exports.names);
+            type_exports.types.extend(source.type_exports.types);
+            type_exports.reexports.extend(source.type_exports.reexports);
+            type_exports.export_assignment |= source.type_exports.export_assignment;
+            type_exports.commonjs |= source.type_exports.commonjs;
+            errors.extend(source.errors);
+            unfollowed.extend(source.unfollowed);
+            suppressions.extend(source.suppressions);
+        }
+        Self {
+            module_record: Arc::new(record),
+            scanned,
+            type_exports,
+            errors,
+            unfollowed,
+            suppressions,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AnalysisCache {
+    store: Store,
+}
+
+/// Where the entries of the cache are kept
+#[derive(Debug, Clone)]
+enum Store {
+    Dir(PathBuf),
+    /// Entries keyed by the path of their file, shared by the clones of the cache
+    Memory(Arc<DashMap<PathBuf, String>>),
+}
+
+impl AnalysisCache {
+    /// Uses `dir` for the cache, creating it when missing
+    pub fn new(dir: PathBuf) -> Self {
+        if !dir.is_dir() && fs::create_dir_all(&dir).is_ok() {
+            // Keep the cache out of version control
+            let _ = fs::write(dir.join(".gitignore"), "*\n");
+        }
+        Self {
+            store: Store::Dir(dir),
+        }
+    }
+
+    /// A cache kept in memory for as long as one of its clones lives
+    pub fn in_memory() -> Self {
+        Self {
+            store: Store::Memory(Arc::default()),
+        }
+    }
+
+    /// Key of a file with `file_text`, changing with anything that changes what parsing it
+    /// yields, `options` included
+    pub fn key(file_text: &str, options: impl Hash) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        file_text.hash(&mut hasher);
+        options.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// The parsed sources of `path` when cached with `key`
+    pub fn get(&self, path: &Path, key: u64) -> Option<ParsedSource> {
+        let json = match &self.store {
+            Store::Dir(dir) => fs::read_to_string(entry_path(dir, path)).ok()?,
+            Store::Memory(entries) => entries.get(path)?.clone(),
+        };
+        let entry = serde_json::from_str::<CacheEntry>(&json).ok()?;
+        (entry.key == key).then(|| entry.source.into_parsed(path))
+    }
+
+    /// Stores the parsed sources of `path` with `key`, replacing its previous entry
+    pub fn put(&self, path: &Path, key: u64, parsed: &ParsedSource) {
+        let entry = CacheEntryRef {
+            key,
+            source: CachedSourceRef {
+                record: CachedRecord::new(&parsed.module_record),
+                scanned: &parsed.scanned,
+                type_exports: &parsed.type_exports,
+                errors: &parsed.errors,
+                unfollowed: &parsed.unfollowed,
+                suppressions: &parsed.suppressions,
+            },
+        };
+        let Ok(json) = serde_json::to_string(&entry) else {
+            return;
+        };
+        match &self.store {
+            Store::Dir(dir) => {
+                let _ = fs::write(entry_path(dir, path), json);
+            }
+            Store::Memory(entries) => {
+                entries.insert(path.to_path_buf(), json);
+            }
+        }
+    }
+}
+
+/// Each file has one entry in `dir`, named after its path
+fn entry_path(dir: &Path, path: &Path) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    dir.join(format!("{:016x}.json", hasher.finish()))
+}
+
+#[derive(Deserialize)]
+struct CacheEntry {
+    key: u64,
+    source: CachedSource,
+}
+
+/// [`ParsedSource`] with a module record which can be serialized
+#[derive(Deserialize)]
+struct CachedSource {
+    record: CachedRecord,
+    scanned: ScannedDependencies,
+    type_exports: TypeExports,
+    errors: Vec<Diagnostic>,
+    unfollowed: Vec<Diagnostic>,
+    suppressions: Suppressions,
+}
+
+/// [`CacheEntry`] borrowing what it is serialized from
+#[derive(Serialize)]
+struct CacheEntryRef<'a> {
+    key: u64,
+    source: CachedSourceRef<'a>,
+}
+
+#[derive(Serialize)]
+struct CachedSourceRef<'a> {
+    record: CachedRecord,
+    scanned: &'a ScannedDependencies,
+    type_exports: &'a TypeExports,
+    errors: &'a [Diagnostic],
+    unfollowed: &'a [Diagnostic],
+    suppressions: &'a Suppressions,
+}
+
+impl CachedSource {
+    fn into_parsed(self, path: &Path) -> ParsedSource {
+        ParsedSource {
+            module_record: Arc::new(self.record.into_record(path)),
+            scanned: self.scanned,
+            type_exports: self.type_exports,
+            errors: self.errors,
+            unfollowed: self.unfollowed,
+            suppressions: self.suppressions,
+        }
+    }
+}
+
+/// The parts of a [`ModuleRecord`] built from the source. Loaded modules and star export
+/// bindings are left out, they are linked on every run.
+#[derive(Serialize, Deserialize)]
+struct CachedRecord {
+    not_esm: bool,
+    requested_modules: Vec<(String, Vec<CachedRequest>)>,
+    import_entries: Vec<CachedImport>,
+    local_export_entries: Vec<CachedExport>,
+    indirect_export_entries: Vec<CachedExport>,
+    star_export_entries: Vec<CachedExport>,
+    exported_bindings: Vec<CachedName>,
+    exported_bindings_duplicated: Vec<CachedName>,
+    export_default: Option<CachedSpan>,
+    export_default_duplicated: Vec<CachedSpan>,
+}
+
+impl CachedRecord {
+    fn new(record: &ModuleRecord) -> Self {
+        Self {
+            not_esm: record.not_esm,
+            requested_modules: record
+                .requested_modules
+                .iter()
+                .map(|(specifier, requests)| {
+                    let requests = requests.iter().map(CachedRequest::new).collect();
+                    (specifier.to_string(), requests)
+                })
+                .collect(),
+            import_entries: record
+                .import_entries
+                .iter()
+                .map(CachedImport::new)
+                .collect(),
+            local_export_entries: exports(&record.local_export_entries),
+            indirect_export_entries: exports(&record.indirect_export_entries),
+            star_export_entries: exports(&record.star_export_entries),
+            exported_bindings: record
+                .exported_bindings
+                .iter()
+                .map(|(name, span)| CachedName(name.to_string(), CachedSpan::new(*span)))
+                .collect(),
+            exported_bindings_duplicated: record
+                .exported_bindings_duplicated
+                .iter()
+                .map(CachedName::new)
+                .collect(),
+            export_default: record.export_default.map(CachedSpan::new),
+            export_default_duplicated: record
+                .export_default_duplicated
+                .iter()
+                .copied()
+                .map(CachedSpan::new)
+                .collect(),
+        }
+    }
+
+    fn into_record(self, path: &Path) -> ModuleRecord {
+        let mut record = ModuleRecord::new(path.to_path_buf());
+        record.not_esm = self.not_esm;
+        record.requested_modules = self
+            .requested_modules
+            .into_iter()
+            .map(|(specifier, requests)| {
+                let requests = requests.into_iter().map(CachedRequest::into_request);
+                (CompactStr::from(specifier), requests.collect())
+            })
+            .collect();
+        record.import_entries = self
+            .import_entries
+            .into_iter()
+            .map(CachedImport::into_entry)
+            .collect();
+        record.local_export_entries = export_entries(self.local_export_entries);
+        record.indirect_export_entries = export_entries(self.indirect_export_entries);
+        record.star_export_entries = export_entries(self.star_export_entries);
+        record.exported_bindings = self
+            .exported_bindings
+            .into_iter()
+            .map(|CachedName(name, span)| (CompactStr::from(name), span.into_span()))
+            .collect();
+        record.exported_bindings_duplicated = self
+            .exported_bindings_duplicated
+            .into_iter()
+            .map(CachedName::into_name_span)
+            .collect();
+        record.export_default = self.export_default.map(CachedSpan::into_span);
+        record.export_default_duplicated = self
+            .export_default_duplicated
+            .into_iter()
+            .map(CachedSpan::into_span)
+            .collect();
+        record
+    }
+}
+
+fn exports(entries: &[ExportEntry]) -> Vec<CachedExport> {
+    entries.iter().map(CachedExport::new).collect()
+}
+
+fn export_entries(entries: Vec<CachedExport>) -> Vec<ExportEntry> {
+    entries.into_iter().map(CachedExport::into_entry).collect()
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct CachedSpan(u32, u32);
+
+impl CachedSpan {
+    fn new(span: Span) -> Self {
+        Self(span.start, span.end)
+    }
+
+    fn into_span(self) -> Span {
+        Span::new(self.0, self.1)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedName(String, CachedSpan);
+
+impl CachedName {
+    fn new(name: &NameSpan) -> Self {
+        Self(name.name().to_string(), CachedSpan::new(name.span()))
+    }
+
+    fn into_name_span(self) -> NameSpan {
+        NameSpan::new(CompactStr::from(self.0), self.1.into_span())
+    }
+}
+
+/// A [`RequestedModule`] as its span, `is_type` and `is_import`
+#[derive(Serialize, Deserialize)]
+struct CachedRequest(CachedSpan, bool, bool);
+
+impl CachedRequest {
+    fn new(request: &RequestedModule) -> Self {
+        Self(
+            CachedSpan::new(request.span()),
+            request.is_type(),
+            request.is_import(),
+        )
+    }
+
+    fn into_request(self) -> RequestedModule {
+        RequestedModule::new(self.0.into_span(), self.1, self.2)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedImport {
+    module_request: CachedName,
+    import_name: CachedImportName,
+    local_name: CachedName,
+    is_type: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+enum CachedImportName {
+    Name(CachedName),
+    NamespaceObject,
+    Default(CachedSpan),
+}
+
+impl CachedImport {
+    fn new(entry: &ImportEntry) -> Self {
+        Self {
+            module_request: CachedName::new(&entry.module_request),
+            import_name: match &entry.import_name {
+                ImportImportName::Name(name) => CachedImportName::Name(CachedName::new(name)),
+                ImportImportName::NamespaceObject => CachedImportName::NamespaceObject,
+                ImportImportName::Default(span) => {
+                    CachedImportName::Default(CachedSpan::new(*span))
+                }
+            },
+            local_name: CachedName::new(&entry.local_name),
+            is_type: entry.is_type,
+        }
+    }
+
+    fn into_entry(self) -> ImportEntry {
+        ImportEntry {
+            module_request: self.module_request.into_name_span(),
+            import_name: match self.import_name {
+                CachedImportName::Name(name) => ImportImportName::Name(name.into_name_span()),
+                CachedImportName::NamespaceObject => ImportImportName::NamespaceObject,
+                CachedImportName::Default(span) => ImportImportName::Default(span.into_span()),
+            },
+            local_name: self.local_name.into_name_span(),
+            is_type: self.is_type,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedExport {
+    span: CachedSpan,
+    module_request: Option<CachedName>,
+    import_name: CachedExportImport,
+    export_name: CachedExportName,
+    local_name: CachedLocalName,
+}
+
+#[derive(Serialize, Deserialize)]
+enum CachedExportImport {
+    Name(CachedName),
+    All,
+    AllButDefault,
+    Null,
+}
+
+#[derive(Serialize, Deserialize)]
+enum CachedExportName {
+    Name(CachedName),
+    Default(CachedSpan),
+    Null,
+}
+
+#[derive(Serialize, Deserialize)]
+enum CachedLocalName {
+    Name(CachedName),
+    Default(CachedName),
+    Null,
+}
+
+impl CachedExport {
+    fn new(entry: &ExportEntry) -> Self {
+        Self {
+            span: CachedSpan::new(entry.span),
+            module_request: entry.module_request.as_ref().map(CachedName::new),
+            import_name: match &entry.import_name {
+                ExportImportName::Name(name) => CachedExportImport::Name(CachedName::new(name)),
+                ExportImportName::All => CachedExportImport::All,
+                ExportImportName::AllButDefault => CachedExportImport::AllButDefault,
+                ExportImportName::Null => CachedExportImport::Null,
+            },
+            export_name: match &entry.export_name {
+                ExportExportName::Name(name) => CachedExportName::Name(CachedName::new(name)),
+                ExportExportName::Default(span) => {
+                    CachedExportName::Default(CachedSpan::new(*span))
+                }
+                ExportExportName::Null => CachedExportName::Null,
+            },
+            local_name: match &entry.local_name {
+                ExportLocalName::Name(name) => CachedLocalName::Name(CachedName::new(name)),
+                ExportLocalName::Default(name) => CachedLocalName::Default(CachedName::new(name)),
+                ExportLocalName::Null => CachedLocalName::Null,
+            },
+        }
+    }
+
+    fn into_entry(self) -> ExportEntry {
+        ExportEntry {
+            span: self.span.into_span(),
+            module_request: self.module_request.map(CachedName::into_name_span),
+            import_name: match self.import_name {
+                CachedExportImport::Name(name) => ExportImportName::Name(name.into_name_span()),
+                CachedExportImport::All => ExportImportName::All,
+                CachedExportImport::AllButDefault => ExportImportName::AllButDefault,
+                CachedExportImport::Null => ExportImportName::Null,
+            },
+            export_name: match self.export_name {
+                CachedExportName::Name(name) => ExportExportName::Name(name.into_name_span()),
+                CachedExportName::Default(span) => ExportExportName::Default(span.into_span()),
+                CachedExportName::Null => ExportExportName::Null,
+            },
+            local_name: match self.local_name {
+                CachedLocalName::Name(name) => ExportLocalName::Name(name.into_name_span()),
+                CachedLocalName::Default(name) => ExportLocalName::Default(name.into_name_span()),
+                CachedLocalName::Null => ExportLocalName::Null,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use oxc_allocator::Allocator;
+    use oxc_parser::Parser;
+    use oxc_semantic::SemanticBuilder;
+    use oxc_span::SourceType;
+
+    use super::{AnalysisCache, ParsedSource};
+    use crate::{
+        diagnostic::{Diagnostic, DiagnosticCode, DiagnosticSpan},
+        scanner::{scan_dependencies, type_exports, ScanOptions},
+        suppressions::Suppressions,
+        utils::TestDir,
+    };
+
+    fn parse(path: &Path, source_text: &str) -> ParsedSource {
+        let allocator = Allocator::default();
+        let ret = Parser::new(&allocator, source_text, SourceType::ts()).parse();
+        let program = allocator.alloc(ret.program);
+        let scanned = scan_dependencies(source_text, &ret.trivias, program, ScanOptions::default());
+        let module_record = SemanticBuilder::new(source_text)
+            .build_module_record(path, program)
+            .module_record();
+        ParsedSource {
+            module_record,
+            scanned,
+            type_exports: type_exports(program),
+            errors: vec![],
+            unfollowed: vec![],
+            suppressions: Suppressions::default(),
+        }
+    }
+
+    #[test]
+    fn test_analysis_cache() {
+        let root = TestDir::new("analysis-cache");
+        let dir = root.join(".cache");
+        let cache = AnalysisCache::new(dir.clone());
+        assert!(dir.join(".gitignore").is_file());
+
+        let source_text = r"
+            import type { A } from './a';
+            import b, * as c from './b';
+            export { d as e } from './d';
+            export * from './f';
+            export default b;
+            export type G = A;
+            const h = import.meta.glob('./pages/*.ts');
+            require(name);
+        ";
+        let path = Path::new("/app/src/index.ts");
+        let start = source_text.find("require").unwrap() as u32;
+        let parsed = ParsedSource {
+            unfollowed: vec![Diagnostic::new(
+                "src/index.ts",
+                DiagnosticCode::UnfollowedImport,
+                "require(name) cannot be followed",
+            )
+            .with_span(DiagnosticSpan::new(source_text, start, start + 13))],
+            ..parse(path, source_text)
+        };
+
+        let key = AnalysisCache::key(source_text, ScanOptions::default());
+        cache.put(path, key, &parsed);
+        assert!(cache.get(path, key + 1).is_none());
+        assert!(cache.get(Path::new("/app/src/main.ts"), key).is_none());
+
+        let cached = cache.get(path, key).unwrap();
+        let (record, expected) = (&cached.module_record, &parsed.module_record);
+        assert_eq!(record.resolved_absolute_path, path);
+        let mut requested = record.requested_modules.keys().collect::<Vec<_>>();
+        requested.sort_unstable();
+        assert_eq!(requested, ["./a", "./b", "./d", "./f"]);
+        assert_eq!(
+            record.requested_modules["./a"][0].is_type(),
+            expected.requested_modules["./a"][0].is_type()
+        );
+        assert_eq!(record.import_entries, expected.import_entries);
+        assert_eq!(record.local_export_entries, expected.local_export_entries);
+        assert_eq!(
+            record.indirect_export_entries,
+            expected.indirect_export_entries
+        );
+        assert_eq!(record.star_export_entries, expected.star_export_entries);
+        assert_eq!(record.exported_bindings, expected.exported_bindings);
+        assert_eq!(record.export_default, expected.export_default);
+        assert_eq!(cached.scanned.dependencies, parsed.scanned.dependencies);
+        assert_eq!(cached.scanned.globs, [["./pages/*.ts"]]);
+        assert_eq!(cached.type_exports, parsed.type_exports);
+        assert_eq!(cached.unfollowed, parsed.unfollowed);
+
+        // Clones of a cache in memory share its entries
+        let memory = AnalysisCache::in_memory();
+        memory.clone().put(path, key, &parsed);
+        assert!(memory.get(path, key + 1).is_none());
+        let cached = memory.get(path, key).unwrap();
+        assert_eq!(cached.module_record.import_entries, expected.import_entries);
+    }
+
+    #[test]
+    fn test_merge() {
+        let path = Path::new("/app/src/App.vue");
+        let parsed = ParsedSource::merge(
+            path,
+            vec![
+                parse(
+                    path,
+                    "import a from './a'; export const b = a; export default {};",
+                ),
+                parse(
+                    path,
+                    "import { c } from './a'; export * from './d'; export const b = c;",
+                ),
+                parse(path, "const e = import.meta.glob('./e/*.ts');"),
+            ],
+        );
+        let record = &parsed.module_record;
+        assert_eq!(record.resolved_absolute_path, path);
+        assert!(!record.not_esm);
+        assert_eq!(record.requested_modules["./a"].len(), 2);
+        assert_eq!(record.import_entries.len(), 2);
+        assert_eq!(record.star_export_entries.len(), 1);
+        assert!(record.exported_bindings.contains_key("b"));
+        assert_eq!(record.exported_bindings_duplicated.len(), 1);
+        assert!(record.export_default.is_some());
+        assert_eq!(parsed.scanned.globs, [["./e/*.ts"]]);
+    }
+}
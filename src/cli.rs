@@ -0,0 +1,725 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use marsh::{
+    cache::CACHE_DIR,
+    config::Config,
+    service::{AnalyzeServiceOptions, DeclarationMode},
+    telemetry::{Tracer, ENDPOINT_VARIABLE},
+};
+
+#[derive(Debug, Default, Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    /// Graphviz DOT, reports are printed as text
+    #[default]
+    Dot,
+    /// JSON, for scripts and codemods
+    Json,
+}
+
+/// Output format of the commands listing files or packages
+#[derive(Debug, Default, Clone, Copy, ValueEnum)]
+pub enum ListFormat {
+    /// One per line
+    #[default]
+    Text,
+    /// JSON, for scripts
+    Json,
+}
+
+/// How diagnostics and violations are printed to stderr
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiagnosticsFormat {
+    /// Grouped by file, with counts
+    #[default]
+    Human,
+    /// One JSON array, for scripts
+    Json,
+}
+
+/// A report asked for with `--reporter KIND[:PATH]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reporter {
+    /// Grouped by file, with counts, printed to stderr
+    Human,
+    /// One JSON array printed to stderr, or an object of violations and diagnostics
+    /// written to the file
+    Json(Option<PathBuf>),
+    /// SARIF log, e.g. for GitHub code scanning
+    Sarif(PathBuf),
+    /// GitLab Code Quality report, shown inline in merge requests
+    CodeQuality(PathBuf),
+}
+
+impl FromStr for Reporter {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (name, path) = match value.split_once(':') {
+            Some((name, path)) => (name, Some(PathBuf::from(path))),
+            None => (value, None),
+        };
+        match (name, path) {
+            ("human", None) => Ok(Self::Human),
+            ("human", Some(_)) => Err("the human reporter only prints to stderr".to_string()),
+            ("json", path) => Ok(Self::Json(path)),
+            ("sarif", Some(path)) => Ok(Self::Sarif(path)),
+            ("code-quality", Some(path)) => Ok(Self::CodeQuality(path)),
+            ("sarif" | "code-quality", None) => Err(format!(
+                "the {name} reporter writes to a file, pass it as {name}:PATH"
+            )),
+            _ => Err(format!(
+                "unknown reporter `{name}`, expected human, json, sarif or code-quality"
+            )),
+        }
+    }
+}
+
+/// Build system `--build-deps` lists dependencies for
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum BuildDeps {
+    /// Makefile rules listing every file each file reaches, also read by Ninja as depfiles
+    Make,
+    /// `deps` of each Bazel package, the packages it imports directly
+    Bazel,
+}
+
+/// Nodes of the printed graph
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Granularity {
+    /// One node per file
+    #[default]
+    File,
+    /// One node per workspace package, with edges counting the imports between their files
+    Package,
+}
+
+/// What `marsh affected` lists
+#[derive(Debug, Default, Clone, Copy, ValueEnum)]
+pub enum AffectedList {
+    /// Changed files and the files importing them
+    #[default]
+    Files,
+    /// Names of the packages owning those files
+    Packages,
+    /// Test files among those files
+    Tests,
+}
+
+/// Analyze the dependency graph of your TypeScript files with zero configuration.
+#[derive(Debug, Parser)]
+#[command(name = "marsh", version, args_conflicts_with_subcommands = true)]
+pub struct CliOptions {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub entries: Entries,
+
+    #[command(flatten)]
+    pub analyze: AnalyzeOptions,
+
+    /// Print every step taken to resolve SPECIFIER from each of the given files,
+    /// instead of analyzing the dependency graph
+    #[arg(long, value_name = "SPECIFIER")]
+    pub explain_resolution: Option<String>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+
+    /// Instead of the graph, list source files under the current directory that are
+    /// neither entries nor imported by anything, with their size
+    #[arg(long)]
+    pub dead_files: bool,
+
+    /// Instead of the graph, list circular dependencies, telling runtime cycles apart from
+    /// cycles closed by types only, along with the imports which can become `import type`.
+    /// With `--granularity package`, list the cycles between workspace packages instead
+    #[arg(long)]
+    pub cycles: bool,
+
+    /// Instead of the graph, list `dependencies` of the `package.json` files owning the analyzed
+    /// files which none of those files imports
+    #[arg(long)]
+    pub unused_dependencies: bool,
+
+    /// Instead of the graph, list barrel files which only re-export other modules,
+    /// with the number of modules each of them drags in
+    #[arg(long)]
+    pub barrels: bool,
+
+    /// Instead of the graph, list the heaviest imports reachable from each entry,
+    /// with the number of modules and bytes each of them pulls in
+    #[arg(long)]
+    pub import_cost: bool,
+
+    /// Instead of the graph, print the graph of the workspace packages owning the analyzed
+    /// files as JSON in the shape of Nx's `project-graph.json`, whatever the format
+    #[arg(long)]
+    pub nx_project_graph: bool,
+
+    /// Instead of the graph, list imports between packages which Turborepo does not know of:
+    /// missing from the `package.json` of the importer, or from the tasks of `turbo.json`
+    #[arg(long)]
+    pub turbo: bool,
+
+    /// Instead of the graph, analyze every file of the TypeScript projects referenced from
+    /// `tsconfig.json`, directly or not, and list imports between projects which do not
+    /// reference each other, and references without imports
+    #[arg(long)]
+    pub project_references: bool,
+
+    /// Instead of the graph, list per workspace package its imports reaching into the files of
+    /// another package, e.g. `@org/ui/src/internal/theme`, instead of going through its entry
+    #[arg(long)]
+    pub workspace_deep_imports: bool,
+
+    /// Instead of the graph, print what each specifier of each analyzed file resolves to as
+    /// JSON, whatever the format, e.g. for an `eslint-plugin-import` resolver to reuse
+    #[arg(long)]
+    pub resolutions: bool,
+
+    /// Instead of the graph, list the dependencies of each file for a build system
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub build_deps: Option<BuildDeps>,
+
+    /// Nodes of the graph: files, or the workspace packages owning them
+    #[arg(long, value_enum, default_value_t)]
+    pub granularity: Granularity,
+
+    /// Also write a report per workspace package to `DIR/packages`, with the graph of its
+    /// files, its metrics and its violations, baseline included, and a `DIR/summary.json` of
+    /// all packages
+    #[arg(long, value_name = "DIR")]
+    pub out_dir: Option<PathBuf>,
+
+    /// Number of imports listed per entry by `--import-cost`
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    pub top: usize,
+
+    /// Point edges into barrel files at the modules defining the imported bindings
+    #[arg(long)]
+    pub flatten_barrels: bool,
+
+    #[command(flatten)]
+    pub check: CheckOptions,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Follow NAME, exported or imported by FILE, through re-exports to the module defining it
+    TraceSymbol {
+        /// File to start from, also analyzed as the entry
+        file: PathBuf,
+
+        /// Name of the binding as exported or imported by FILE
+        name: String,
+
+        #[command(flatten)]
+        analyze: AnalyzeOptions,
+    },
+    /// Check the configured rules without printing the graph, failing on violations
+    /// missing from the baseline
+    Check {
+        #[command(flatten)]
+        entries: Entries,
+
+        #[command(flatten)]
+        check: CheckOptions,
+
+        /// Record every current violation into the baseline instead of failing on them,
+        /// so only new violations fail later runs
+        #[arg(long)]
+        write_baseline: bool,
+
+        #[command(flatten)]
+        analyze: AnalyzeOptions,
+    },
+    /// Analyze the files and report statistics of the run instead of the graph: files parsed,
+    /// bytes processed, resolver calls, cache hits, peak memory and throughput
+    Stats {
+        #[command(flatten)]
+        entries: Entries,
+
+        /// Number of threads to analyze with, one per CPU by default
+        #[arg(long, value_name = "N")]
+        threads: Option<usize>,
+
+        /// Output format, statistics are printed as text for `dot`
+        #[arg(long, value_enum, default_value_t)]
+        format: OutputFormat,
+
+        #[command(flatten)]
+        analyze: AnalyzeOptions,
+    },
+    /// Stay resident and answer JSON-RPC requests about the graph of PATHS over a local socket,
+    /// e.g. for editor integrations: the graph, the dependents of a file, why a file is
+    /// imported. Parsed files are kept in memory between analyses
+    Daemon {
+        #[command(flatten)]
+        entries: Entries,
+
+        /// Unix domain socket to listen on, defaults to `daemon.sock` in the cache directory
+        #[arg(long, value_name = "PATH")]
+        socket: Option<PathBuf>,
+
+        /// Answer requests read from stdin on stdout instead of listening on a socket
+        #[arg(long, conflicts_with = "socket")]
+        stdio: bool,
+
+        #[command(flatten)]
+        analyze: AnalyzeOptions,
+    },
+
+    /// List the files affected by the changes since a git ref: the changed files reachable
+    /// from PATHS and every file importing them, e.g. to only run the tests a change can break.
+    /// Include test files in PATHS to list the affected tests
+    Affected {
+        #[command(flatten)]
+        entries: Entries,
+
+        /// Git ref to compare with, from its merge base with HEAD. Uncommitted and untracked
+        /// files count as changed
+        #[arg(long, value_name = "REF")]
+        since: String,
+
+        /// What to list, one per line. JSON lists everything
+        #[arg(long, value_enum, default_value_t)]
+        list: AffectedList,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t)]
+        format: ListFormat,
+
+        #[command(flatten)]
+        analyze: AnalyzeOptions,
+    },
+
+    /// List the workspace packages affected by the changes since a git ref: the packages
+    /// owning changed files and every package importing them, directly or not, to only
+    /// rebuild and retest those in CI
+    AffectedPackages {
+        #[command(flatten)]
+        entries: Entries,
+
+        /// Git ref to compare with, from its merge base with HEAD. Uncommitted and untracked
+        /// files count as changed
+        #[arg(long, value_name = "REF")]
+        since: String,
+
+        /// Output format, one package per line or JSON
+        #[arg(long, value_enum, default_value_t)]
+        format: ListFormat,
+
+        #[command(flatten)]
+        analyze: AnalyzeOptions,
+    },
+
+    /// Run a language server over stdio for editors, reporting diagnostics of the graph of
+    /// PATHS whenever a file is saved and answering which files import a file and what it
+    /// depends on
+    Lsp {
+        #[command(flatten)]
+        entries: Entries,
+
+        #[command(flatten)]
+        analyze: AnalyzeOptions,
+    },
+
+    /// List the files importing FILE, directly or not, from the run stored by `--incremental`
+    /// without analyzing anything, e.g. bound to an editor keystroke
+    DepsOf {
+        /// File to list the dependents of
+        file: PathBuf,
+
+        /// Directory the run was stored in by `--incremental`
+        #[arg(long, value_name = "DIR", default_value = CACHE_DIR)]
+        cache_dir: PathBuf,
+
+        /// Print the dependents and the files changed since the stored run as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Serve the graph of PATHS over HTTP with a viewer to explore it in a browser, as
+    /// `/graph.json` and `/dependents?path=...` for other tools
+    Serve {
+        #[command(flatten)]
+        entries: Entries,
+
+        /// Address to listen on
+        #[arg(long, value_name = "HOST:PORT", default_value = "127.0.0.1:4000")]
+        address: String,
+
+        #[command(flatten)]
+        analyze: AnalyzeOptions,
+    },
+}
+
+/// Entry files of the commands analyzing files
+#[derive(Debug, Args)]
+pub struct Entries {
+    /// Entry files to start the analysis from. Defaults to the entry of each package of the
+    /// npm, Yarn or pnpm workspace of the current working directory
+    pub paths: Vec<PathBuf>,
+}
+
+/// Options deciding which rule violations fail the run
+#[derive(Debug, Args)]
+pub struct CheckOptions {
+    /// Baseline of violations which do not fail the run, as written by
+    /// `marsh check --write-baseline`. Defaults to `marsh-baseline.json` when present
+    #[arg(long, value_name = "PATH")]
+    pub baseline: Option<PathBuf>,
+
+    /// Fail the run when rules set to `warn` report more than N violations,
+    /// warnings never fail it otherwise
+    #[arg(long, value_name = "N")]
+    pub max_warnings: Option<usize>,
+
+    /// Stop the analysis and fail the run once N errors are reported, instead of analyzing
+    /// every file. Violations recorded in the baseline are not counted
+    #[arg(long, value_name = "N")]
+    pub max_errors: Option<usize>,
+
+    /// Also write the violations missing from the baseline and the diagnostics, e.g. unresolved
+    /// imports, to PATH as a GitLab Code Quality report, shown inline in merge requests
+    #[arg(long, value_name = "PATH")]
+    pub code_quality: Option<PathBuf>,
+
+    /// Also write the violations missing from the baseline and the diagnostics to PATH as a
+    /// SARIF log, e.g. for GitHub code scanning
+    #[arg(long, value_name = "PATH")]
+    pub sarif: Option<PathBuf>,
+
+    /// Also write the violations missing from the baseline and the diagnostics to PATH as JSON
+    #[arg(long, value_name = "PATH")]
+    pub json_report: Option<PathBuf>,
+
+    /// How the violations and diagnostics are printed to stderr, whatever the format of the
+    /// graph on stdout
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t)]
+    pub diagnostics_format: DiagnosticsFormat,
+
+    /// Report the violations missing from the baseline and the diagnostics with each of the
+    /// reporters given, e.g. `--reporter human --reporter sarif:marsh.sarif`, instead of
+    /// printing them to stderr. KIND is `human`, `json`, `sarif` or `code-quality`, the
+    /// report is written to PATH when given. Replaces `--diagnostics-format`, `--sarif`,
+    /// `--code-quality` and `--json-report`, which cannot be combined with it
+    #[arg(
+        long = "reporter",
+        value_name = "KIND[:PATH]",
+        conflicts_with_all = ["diagnostics_format", "code_quality", "sarif", "json_report"]
+    )]
+    pub reporters: Vec<Reporter>,
+}
+
+/// Options shared by every command which analyzes files
+#[derive(Debug, Args)]
+pub struct AnalyzeOptions {
+    /// Configuration file, defaults to `marsh.json` in the current working directory when present
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// TypeScript `tsconfig.json` path, defaults to `tsconfig.json` in the current working directory
+    #[arg(long)]
+    pub tsconfig: Option<PathBuf>,
+
+    /// Collapse imports resolving into `node_modules` into one node per package,
+    /// without traversing into the package
+    #[arg(long)]
+    pub collapse_packages: bool,
+
+    /// `package.json` field used to resolve a package entry, can be repeated to set the lookup
+    /// order, e.g. `--main-field browser --main-field module --main-field main`
+    #[arg(long = "main-field", value_name = "FIELD")]
+    pub main_fields: Vec<String>,
+
+    /// Additional export condition used when resolving `package.json` `exports`,
+    /// e.g. `--condition import --condition browser`
+    #[arg(long = "condition", value_name = "CONDITION")]
+    pub condition_names: Vec<String>,
+
+    /// Record imported stylesheets (`.css`, `.scss`, `.less`, ...) as nodes,
+    /// following the `composes` declarations of CSS modules
+    #[arg(long)]
+    pub track_styles: bool,
+
+    /// Do not follow `import()` expressions
+    #[arg(long)]
+    pub ignore_dynamic_imports: bool,
+
+    /// Do not follow `import type` and other type-only imports,
+    /// leaving a runtime dependency graph
+    #[arg(long)]
+    pub ignore_type_imports: bool,
+
+    /// Report named imports which the imported module does not export,
+    /// following `export * from` re-exports
+    #[arg(long)]
+    pub check_exports: bool,
+
+    /// Report names which a module re-exports from several `export * from` declarations
+    /// resolving to different bindings, which bundlers and TypeScript handle differently
+    #[arg(long)]
+    pub check_star_exports: bool,
+
+    /// How imports resolving to TypeScript declaration files are handled:
+    /// `keep` them as nodes, `pair` them with their implementation sibling, or `exclude` them
+    #[arg(long, value_name = "MODE", default_value = "keep")]
+    pub declarations: DeclarationMode,
+
+    /// Follow `sourceMappingURL` comments of resolved files, so imports of build outputs
+    /// such as `dist/index.js` are attributed to their original source file
+    #[arg(long)]
+    pub follow_source_maps: bool,
+
+    /// Do not report syntax errors, e.g. of known broken vendored files. Files are still
+    /// analyzed as far as they parse
+    #[arg(long)]
+    pub no_syntax_errors: bool,
+
+    /// Fail the run on unresolved imports, besides those of `--external`. They are reported
+    /// as errors either way
+    #[arg(long)]
+    pub error_on_unresolved: bool,
+
+    /// Imports of PACKAGE, or of a URL scheme such as `virtual:`, are resolved by another
+    /// tool, e.g. a bundler plugin. They are reported as warnings when unresolved and never
+    /// fail the run. Can be repeated
+    #[arg(long = "external", value_name = "PACKAGE")]
+    pub externals: Vec<String>,
+
+    /// Resolve imports of workspace packages to the package's `src` entry and continue
+    /// traversal there, instead of stopping at its build output
+    #[arg(long)]
+    pub workspace_sources: bool,
+
+    /// Report imports reaching into the internals of PACKAGE, such as `lodash/internal/baseGet`,
+    /// instead of its public entry. Subpaths listed in the package `exports` are allowed.
+    /// Can be repeated, `*` applies to every package
+    #[arg(long = "deny-deep-imports", value_name = "PACKAGE")]
+    pub deny_deep_imports: Vec<String>,
+
+    /// Load `.md` files and follow the imports of their fenced JavaScript and TypeScript
+    /// code blocks. `.mdx` files are always loaded through their `import`/`export` statements
+    #[arg(long)]
+    pub markdown_code_blocks: bool,
+
+    /// Cache parsed files in the cache directory, so later runs only parse the files which
+    /// changed
+    #[arg(long)]
+    pub cache: bool,
+
+    /// Directory of `--cache` and `--incremental`, and of the socket of `marsh daemon`
+    #[arg(long, value_name = "DIR", default_value = CACHE_DIR)]
+    pub cache_dir: PathBuf,
+
+    /// Only read, parse and resolve the files changed since the previous run with
+    /// `--incremental`, reusing the rest of its graph. Implies `--cache`
+    #[arg(long)]
+    pub incremental: bool,
+
+    /// Only extract imports and exports from each file, skipping checks which do not change
+    /// the graph such as the syntax of regular expressions
+    #[arg(long)]
+    pub fast: bool,
+
+    /// Build the semantic model of each file with its control flow graph, which the analysis
+    /// does not read. Ignored with `--fast`
+    #[arg(long)]
+    pub build_cfg: bool,
+
+    /// Build the semantic model of each file with its JSDoc comments, which the analysis does
+    /// not read. Ignored with `--fast`
+    #[arg(long)]
+    pub build_jsdoc: bool,
+
+    /// Memory-map source files instead of reading them, which lowers peak memory on large
+    /// repositories. Files must not be modified or truncated during the analysis, which may
+    /// otherwise crash
+    #[arg(long)]
+    pub mmap: bool,
+
+    /// Skip files larger than N MiB with a warning, e.g. bundles. 0, the default, disables
+    /// the limit
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub max_file_size: u64,
+
+    /// Stop with an error when more than N files are reached from the entries, e.g. when
+    /// following imports into `node_modules`. 0 disables the limit
+    #[arg(long, value_name = "N", default_value_t = 100_000)]
+    pub max_files: usize,
+
+    /// Export the spans of the analysis, such as the parse and resolution of each file, to the
+    /// OpenTelemetry collector at URL over OTLP/HTTP, e.g. `http://localhost:4318`.
+    /// Defaults to `OTEL_EXPORTER_OTLP_ENDPOINT` when set
+    #[arg(long, value_name = "URL")]
+    pub otlp_endpoint: Option<String>,
+}
+
+impl AnalyzeOptions {
+    /// Service options for analyzing `paths` relative to `cwd`
+    ///
+    /// # Errors
+    /// When the configuration file cannot be loaded
+    pub fn service_options(
+        &self,
+        cwd: &Path,
+        paths: &[PathBuf],
+    ) -> Result<AnalyzeServiceOptions, String> {
+        let config = Config::load(cwd, self.config.as_deref())?;
+        let paths = paths
+            .iter()
+            .map(|path| cwd.join(path).into_boxed_path())
+            .collect();
+        // SAFETY: users opt into `--mmap`, which documents that files must not change during
+        // the analysis
+        let options = unsafe { AnalyzeServiceOptions::new(cwd, paths).with_mmap(self.mmap) };
+        let mut options = options
+            .with_cross_module(true)
+            .with_collapse_packages(self.collapse_packages)
+            .with_main_fields(self.main_fields.clone())
+            .with_condition_names(self.condition_names.clone())
+            .with_track_styles(self.track_styles)
+            .with_dynamic_imports(!self.ignore_dynamic_imports)
+            .with_type_imports(!self.ignore_type_imports)
+            .with_check_exports(self.check_exports)
+            .with_check_star_exports(self.check_star_exports)
+            .with_declarations(self.declarations)
+            .with_follow_source_maps(self.follow_source_maps)
+            .with_syntax_errors(!self.no_syntax_errors)
+            .with_externals(self.externals.clone())
+            .with_workspace_sources(self.workspace_sources)
+            .with_deny_deep_imports(self.deny_deep_imports.clone())
+            .with_markdown_code_blocks(self.markdown_code_blocks)
+            .with_cache_dir((self.cache || self.incremental).then(|| cwd.join(&self.cache_dir)))
+            .with_incremental(self.incremental)
+            .with_fast(self.fast)
+            .with_build_cfg(self.build_cfg)
+            .with_build_jsdoc(self.build_jsdoc)
+            .with_max_file_size(
+                (self.max_file_size > 0).then_some(self.max_file_size.saturating_mul(1 << 20)),
+            )
+            .with_max_files((self.max_files > 0).then_some(self.max_files))
+            .with_rules(config.rules)
+            .with_no_circular(config.no_circular)
+            .with_boundaries(config.boundaries)
+            .with_package_rules(config.package_rules)
+            .with_max_depth(config.max_depth)
+            .with_no_orphans(config.no_orphans)
+            .with_public_entries(config.public_entries)
+            .with_relative_imports(config.relative_imports)
+            .with_scripts(config.scripts)
+            .with_overrides(config.overrides);
+        if let Some(tsconfig) = &self.tsconfig {
+            options = options.with_tsconfig(tsconfig);
+        }
+        let endpoint = self
+            .otlp_endpoint
+            .clone()
+            .or_else(|| std::env::var(ENDPOINT_VARIABLE).ok())
+            .filter(|endpoint| !endpoint.is_empty());
+        Ok(options.with_tracer(endpoint.map(|endpoint| Arc::new(Tracer::new(endpoint)))))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use clap::Parser;
+
+    use super::{CliOptions, Command, Reporter};
+    use marsh::{file_system::MemoryFileSystem, service::AnalyzeService};
+
+    #[test]
+    fn test_reporter() {
+        assert_eq!("human".parse(), Ok(Reporter::Human));
+        assert_eq!("json".parse(), Ok(Reporter::Json(None)));
+        assert_eq!(
+            "json:out/marsh.json".parse(),
+            Ok(Reporter::Json(Some(PathBuf::from("out/marsh.json"))))
+        );
+        assert_eq!(
+            "sarif:marsh.sarif".parse(),
+            Ok(Reporter::Sarif(PathBuf::from("marsh.sarif")))
+        );
+        assert_eq!(
+            "code-quality:gl-code-quality.json".parse(),
+            Ok(Reporter::CodeQuality(PathBuf::from("gl-code-quality.json")))
+        );
+        assert!("human:out.txt".parse::<Reporter>().is_err());
+        assert!("sarif".parse::<Reporter>().is_err());
+        assert!("junit:out.xml".parse::<Reporter>().is_err());
+
+        let options = CliOptions::parse_from([
+            "marsh",
+            "check",
+            "--reporter",
+            "human",
+            "--reporter",
+            "json",
+        ]);
+        let Some(Command::Check { check, .. }) = options.command else {
+            panic!("not the check command");
+        };
+        assert_eq!(check.reporters, [Reporter::Human, Reporter::Json(None)]);
+        for flag in [
+            ["--diagnostics-format", "json"],
+            ["--sarif", "marsh.sarif"],
+            ["--code-quality", "gl-code-quality.json"],
+            ["--json-report", "marsh.json"],
+        ] {
+            let args = ["marsh", "check", "--reporter", "human", flag[0], flag[1]];
+            assert!(CliOptions::try_parse_from(args).is_err(), "{flag:?}");
+        }
+    }
+
+    #[test]
+    fn test_resolver_options() {
+        let root = Path::new("/marsh-resolver-options");
+        let resolved = |args: &[&str]| {
+            let exports =
+                r#"{ "exports": { "worker": "./worker.js", "default": "./default.js" } }"#;
+            let file_system = MemoryFileSystem::default()
+                .with_file(root.join("main.ts"), "import 'a'; import 'b';")
+                .with_file(
+                    root.join("node_modules/a/package.json"),
+                    r#"{ "main": "main.js", "browser": "browser.js" }"#,
+                )
+                .with_file(root.join("node_modules/a/main.js"), "")
+                .with_file(root.join("node_modules/a/browser.js"), "")
+                .with_file(root.join("node_modules/b/package.json"), exports)
+                .with_file(root.join("node_modules/b/worker.js"), "")
+                .with_file(root.join("node_modules/b/default.js"), "");
+            let cli = CliOptions::parse_from([&["marsh"], args].concat());
+            let options = cli
+                .analyze
+                .service_options(root, &[root.join("main.ts")])
+                .unwrap();
+            let graph = AnalyzeService::new(options.with_file_system(file_system)).analyze();
+            let mut targets = graph
+                .edges
+                .iter()
+                .map(|edge| edge.to.to_string())
+                .collect::<Vec<_>>();
+            targets.sort_unstable();
+            targets
+        };
+        assert_eq!(
+            resolved(&[]),
+            ["node_modules/a/main.js", "node_modules/b/default.js"]
+        );
+        assert_eq!(
+            resolved(&["--main-field", "browser", "--condition", "worker"]),
+            ["node_modules/a/browser.js", "node_modules/b/worker.js"]
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! Rule violations and diagnostics as a GitLab Code Quality report, which GitLab shows inline
+//! in merge requests when a job uploads it as a `codequality` report artifact.
+//!
+//! <https://docs.gitlab.com/ee/ci/testing/code_quality.html#code-quality-report-format>
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{
+    collector::CollectorSink,
+    diagnostic::Diagnostic,
+    rules::{RuleViolation, Severity},
+};
+
+#[derive(Debug, Serialize)]
+pub struct CodeQualityIssue {
+    pub description: String,
+    /// Name of the broken rule, or the kind of diagnostic
+    pub check_name: String,
+    /// Identifies the issue across pipelines, so GitLab tells new issues from fixed ones
+    pub fingerprint: String,
+    /// One of `info`, `minor`, `major`, `critical` and `blocker`
+    pub severity: &'static str,
+    pub location: Location,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Location {
+    /// Relative to the root of the repository
+    pub path: String,
+    pub lines: Lines,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Lines {
+    pub begin: usize,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(transparent)]
+pub struct CodeQualityReport {
+    pub issues: Vec<CodeQualityIssue>,
+}
+
+impl CodeQualityReport {
+    /// Reports `violations` and `diagnostics`, whose paths are relative to the current working
+    /// directory, expected to be the root of the repository.
+    pub fn new(violations: &[RuleViolation], diagnostics: &[Diagnostic]) -> Self {
+        let mut issues = Vec::new();
+        for violation in violations {
+            let severity = match violation.severity {
+                Severity::Off => continue,
+                Severity::Warn => "minor",
+                Severity::Error => "major",
+            };
+            issues.push(CodeQualityIssue::new(
+                &violation.path,
+                1,
+                &violation.rule,
+                severity,
+                violation.to_string(),
+            ));
+        }
+        for diagnostic in diagnostics {
+            let severity = match diagnostic.severity {
+                Severity::Off => continue,
+                Severity::Warn => "minor",
+                Severity::Error => "major",
+            };
+            issues.push(CodeQualityIssue::new(
+                &diagnostic.path.display().to_string(),
+                diagnostic.line(),
+                diagnostic.code.as_str(),
+                severity,
+                diagnostic.message.clone(),
+            ));
+        }
+        Self { issues }
+    }
+
+    /// # Errors
+    /// When the report cannot be written
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).unwrap();
+        fs::write(path, json)
+            .map_err(|error| format!("Failed to write {}: {error}", path.display()))
+    }
+}
+
+/// Writes a [`CodeQualityReport`] once the run is over
+#[derive(Debug, Clone)]
+pub struct CodeQualitySink {
+    path: PathBuf,
+}
+
+impl CodeQualitySink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl CollectorSink for CodeQualitySink {
+    fn finish(
+        &mut self,
+        violations: &[RuleViolation],
+        diagnostics: &[Diagnostic],
+    ) -> Result<(), String> {
+        CodeQualityReport::new(violations, diagnostics).write(&self.path)
+    }
+}
+
+impl CodeQualityIssue {
+    fn new(
+        path: &str,
+        line: usize,
+        check_name: &str,
+        severity: &'static str,
+        description: String,
+    ) -> Self {
+        let fingerprint = fingerprint(&[check_name, path, &line.to_string(), &description]);
+        Self {
+            description,
+            check_name: check_name.to_string(),
+            fingerprint,
+            severity,
+            location: Location {
+                path: path.to_string(),
+                lines: Lines { begin: line },
+            },
+        }
+    }
+}
+
+/// FNV-1a hash of `parts`, which unlike the hasher of the standard library stays the same
+/// across Rust versions.
+fn fingerprint(parts: &[&str]) -> String {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    for part in parts {
+        for &byte in part.as_bytes().iter().chain(&[0]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::CodeQualityReport;
+    use crate::{
+        diagnostic::{Diagnostic, DiagnosticCode, DiagnosticSpan},
+        rules::{RuleViolation, Severity},
+    };
+
+    #[test]
+    fn test_code_quality_report() {
+        let violations = [
+            RuleViolation::new(
+                "src/a.ts",
+                "no-lodash",
+                Severity::Warn,
+                "imports lodash".to_string(),
+            ),
+            RuleViolation::new("src/b.ts", "off", Severity::Off, String::new()),
+        ];
+        let diagnostics = [
+            Diagnostic::new(
+                "src/c.ts",
+                DiagnosticCode::UnresolvedImport,
+                "Unable to resolve \"./d\": Cannot find module './d'",
+            ),
+            Diagnostic::new("src/e.ts", DiagnosticCode::SyntaxError, "Unexpected token")
+                .with_span(DiagnosticSpan::new("\n\nconst = 1;", 8, 9)),
+        ];
+        let report = CodeQualityReport::new(&violations, &diagnostics);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 3);
+        assert_eq!(json[0]["check_name"], "no-lodash");
+        assert_eq!(json[0]["severity"], "minor");
+        assert_eq!(json[0]["location"]["path"], "src/a.ts");
+        assert_eq!(json[1]["check_name"], "unresolved-import");
+        assert_eq!(json[1]["location"]["lines"]["begin"], 1);
+        assert_eq!(json[2]["check_name"], "syntax-error");
+        assert_eq!(json[2]["location"]["lines"]["begin"], 3);
+        assert_eq!(json[2]["description"], "Unexpected token");
+        assert_ne!(json[1]["fingerprint"], json[2]["fingerprint"]);
+        assert_eq!(
+            report.issues[0].fingerprint,
+            CodeQualityReport::new(&violations[..1], &diagnostics[..0]).issues[0].fingerprint
+        );
+
+        // The same issue on another line of the file is another issue
+        let moved = Diagnostic::new("src/e.ts", DiagnosticCode::SyntaxError, "Unexpected token")
+            .with_span(DiagnosticSpan::new("\nconst = 1;", 7, 8));
+        let moved = CodeQualityReport::new(&[], &[moved]);
+        assert_eq!(moved.issues[0].location.lines.begin, 2);
+        assert_ne!(moved.issues[0].fingerprint, report.issues[2].fingerprint);
+    }
+}
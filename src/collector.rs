@@ -1,15 +1,281 @@
 use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs,
     path::{Path, PathBuf},
     sync::mpsc,
 };
 
-use crate::message::Message;
-pub type CollectorTuple = (PathBuf, Vec<String>);
-pub type CollectorSender = mpsc::Sender<Option<CollectorTuple>>;
-pub type CollectorReceiver = mpsc::Receiver<Option<CollectorTuple>>;
+use rustc_hash::FxHashSet;
+use serde_json::{json, Value};
+
+use crate::{
+    baseline::{Baseline, Excuses},
+    diagnostic::{Diagnostic, DiagnosticCode, DiagnosticSpan},
+    graph::{GraphBuilder, GraphIndex},
+    message::Message,
+    rules::{RuleViolation, Severity},
+    service::CancellationToken,
+    utils::PathAtom,
+};
+
+/// A file, relative to the current working directory, with its dependencies
+pub type FileDependencies = (PathAtom, Vec<Message>);
+
+pub enum CollectorMessage {
+    /// Dependencies found in files, batched by the thread which analyzed them
+    Dependencies(Vec<FileDependencies>),
+    /// Diagnostics reported, batched by file
+    Diagnostics(Vec<Diagnostic>),
+    /// Configured rules broken while analyzing a file
+    Violations(Vec<RuleViolation>),
+}
+
+pub type CollectorSender = mpsc::Sender<Option<CollectorMessage>>;
+pub type CollectorReceiver = mpsc::Receiver<Option<CollectorMessage>>;
+
+/// Where the results of a run are reported, e.g. the terminal or a file read by CI. Several
+/// sinks can be active at once.
+pub trait CollectorSink {
+    /// A diagnostic, as it arrives
+    fn diagnostic(&mut self, _diagnostic: &Diagnostic) {}
+
+    /// Reports the violations of the run, and every diagnostic, once the run is over
+    ///
+    /// # Errors
+    /// When the results cannot be reported, e.g. written to a file
+    fn finish(
+        &mut self,
+        _violations: &[RuleViolation],
+        _diagnostics: &[Diagnostic],
+    ) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Prints the diagnostics and violations to stderr once the run is over, grouped by file
+/// and sorted by position, followed by their counts
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TerminalSink;
+
+impl TerminalSink {
+    /// What is printed for `violations` and `diagnostics`, nothing when there are none
+    pub fn render(violations: &[RuleViolation], diagnostics: &[Diagnostic]) -> String {
+        // Lines of each file by position, those about the whole file first
+        let mut files = BTreeMap::<String, Vec<(Option<(usize, usize)>, Severity, String)>>::new();
+        for diagnostic in diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity != Severity::Off)
+        {
+            let position = diagnostic.span.map(|span| (span.line, span.column));
+            let message = format!("{}  {}", diagnostic.message, diagnostic.code);
+            files
+                .entry(diagnostic.path.display().to_string())
+                .or_default()
+                .push((position, diagnostic.severity, message));
+        }
+        for violation in violations
+            .iter()
+            .filter(|violation| violation.severity != Severity::Off)
+        {
+            let message = format!("{}  {}", violation.description(), violation.rule);
+            files.entry(violation.path.clone()).or_default().push((
+                None,
+                violation.severity,
+                message,
+            ));
+        }
+
+        let mut output = String::new();
+        let (mut errors, mut warnings) = (0, 0);
+        for (path, lines) in &mut files {
+            lines.sort_by_key(|(position, ..)| *position);
+            let positions = lines
+                .iter()
+                .map(|(position, ..)| {
+                    position.map_or_else(String::new, |(line, column)| format!("{line}:{column}"))
+                })
+                .collect::<Vec<_>>();
+            let width = positions.iter().map(String::len).max().unwrap_or_default();
+            writeln!(output, "{path}").unwrap();
+            for ((_, severity, message), position) in lines.iter().zip(positions) {
+                let severity = match severity {
+                    Severity::Off => continue,
+                    Severity::Warn => {
+                        warnings += 1;
+                        "warning"
+                    }
+                    Severity::Error => {
+                        errors += 1;
+                        "error"
+                    }
+                };
+                writeln!(output, "  {position:width$}  {severity:7}  {message}").unwrap();
+            }
+            writeln!(output).unwrap();
+        }
+        if errors + warnings > 0 {
+            write!(
+                output,
+                "{} in {}",
+                [(errors, "error"), (warnings, "warning")]
+                    .map(|(count, noun)| plural(count, noun))
+                    .join(", "),
+                plural(files.len(), "file")
+            )
+            .unwrap();
+        }
+        output
+    }
+}
+
+impl CollectorSink for TerminalSink {
+    fn finish(
+        &mut self,
+        violations: &[RuleViolation],
+        diagnostics: &[Diagnostic],
+    ) -> Result<(), String> {
+        let output = Self::render(violations, diagnostics);
+        if !output.is_empty() {
+            eprintln!("{output}");
+        }
+        Ok(())
+    }
+}
+
+/// Prints the diagnostics and violations to stderr as one JSON array once the run is over,
+/// for scripts, violations taking the shape of diagnostics with their rule as code
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TerminalJsonSink;
+
+impl TerminalJsonSink {
+    pub fn render(violations: &[RuleViolation], diagnostics: &[Diagnostic]) -> Value {
+        let violations = violations.iter().map(|violation| {
+            json!({
+                "path": violation.path,
+                "span": null,
+                "severity": violation.severity,
+                "code": violation.rule,
+                "message": violation.description(),
+            })
+        });
+        diagnostics
+            .iter()
+            .map(|diagnostic| serde_json::to_value(diagnostic).unwrap())
+            .chain(violations)
+            .collect()
+    }
+}
+
+impl CollectorSink for TerminalJsonSink {
+    fn finish(
+        &mut self,
+        violations: &[RuleViolation],
+        diagnostics: &[Diagnostic],
+    ) -> Result<(), String> {
+        let json = Self::render(violations, diagnostics);
+        eprintln!("{}", serde_json::to_string_pretty(&json).unwrap());
+        Ok(())
+    }
+}
+
+/// `1 file`, `2 files`
+fn plural(count: usize, noun: &str) -> String {
+    format!("{count} {noun}{}", if count == 1 { "" } else { "s" })
+}
+
+/// Writes the violations and diagnostics to a JSON file once the run is over
+#[derive(Debug, Clone)]
+pub struct JsonSink {
+    path: PathBuf,
+}
+
+impl JsonSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl CollectorSink for JsonSink {
+    fn finish(
+        &mut self,
+        violations: &[RuleViolation],
+        diagnostics: &[Diagnostic],
+    ) -> Result<(), String> {
+        let violations = violations
+            .iter()
+            .map(|violation| {
+                json!({
+                    "path": violation.path,
+                    "rule": violation.rule,
+                    "severity": violation.severity,
+                    "message": violation.message,
+                })
+            })
+            .collect::<Vec<_>>();
+        let json = json!({ "violations": violations, "diagnostics": diagnostics });
+        fs::write(&self.path, serde_json::to_string_pretty(&json).unwrap())
+            .map_err(|error| format!("Failed to write {}: {error}", self.path.display()))
+    }
+}
+
+/// What tells diagnostics apart: their file, span and code, and their message, which is all
+/// diagnostics about a whole file differ by
+#[derive(PartialEq, Eq, Hash)]
+struct DiagnosticKey {
+    path: PathBuf,
+    span: Option<DiagnosticSpan>,
+    code: DiagnosticCode,
+    message: String,
+}
+
+impl DiagnosticKey {
+    fn new(diagnostic: &Diagnostic) -> Self {
+        Self {
+            path: diagnostic.path.clone(),
+            span: diagnostic.span,
+            code: diagnostic.code,
+            message: diagnostic.message.clone(),
+        }
+    }
+}
+
+/// Diagnostics and violations reported, by severity
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SeverityCounts {
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+impl SeverityCounts {
+    fn add(&mut self, severity: Severity) {
+        match severity {
+            Severity::Error => self.errors += 1,
+            Severity::Warn => self.warnings += 1,
+            Severity::Off => {}
+        }
+    }
+}
 
 pub struct CollectorService {
-    pub deps: Vec<(String, String)>,
+    /// Dependencies found, only changed through [`Self::deps_mut`]
+    deps: Vec<(PathAtom, Message)>,
+    /// Nodes of `deps` numbered as they arrive, `None` once `deps` were changed
+    index: Option<GraphIndex>,
+    /// Rule violations reported
+    pub violations: Vec<RuleViolation>,
+    /// Diagnostics reported, also passed to the sinks as they arrive
+    pub diagnostics: Vec<Diagnostic>,
+    /// Diagnostics reported so far, a file processed again, e.g. from another importer,
+    /// reports the same diagnostics
+    seen: FxHashSet<DiagnosticKey>,
+    counts: SeverityCounts,
+    /// Violations of the baseline, which are not reported and so not counted
+    excuses: Excuses,
+    /// Errors after which the run is cancelled through the token
+    max_errors: Option<(usize, CancellationToken)>,
+    reached_max_errors: bool,
+    sinks: Vec<Box<dyn CollectorSink>>,
 
     sender: CollectorSender,
     receiver: CollectorReceiver,
@@ -22,27 +288,377 @@ impl Default for CollectorService {
             sender,
             receiver,
             deps: Vec::new(),
+            index: Some(GraphIndex::default()),
+            violations: Vec::new(),
+            diagnostics: Vec::new(),
+            seen: FxHashSet::default(),
+            counts: SeverityCounts::default(),
+            excuses: Excuses::default(),
+            max_errors: None,
+            reached_max_errors: false,
+            sinks: vec![Box::new(TerminalSink)],
         }
     }
 }
 
 impl CollectorService {
+    /// Replaces the sinks, only the [`TerminalSink`] by default. Without sinks nothing is
+    /// reported, the results are only kept in the collector.
+    #[inline]
+    #[must_use]
+    pub fn with_sinks(mut self, sinks: Vec<Box<dyn CollectorSink>>) -> Self {
+        self.sinks = sinks;
+        self
+    }
+
+    /// Reports to `sink` too
+    #[inline]
+    #[must_use]
+    pub fn with_sink(mut self, sink: impl CollectorSink + 'static) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    /// Leaves the violations recorded in `baseline` out of the counts of the run, as they
+    /// are not reported
+    #[inline]
+    #[must_use]
+    pub fn with_baseline(mut self, baseline: &Baseline) -> Self {
+        self.excuses = baseline.excuses();
+        self
+    }
+
+    /// Cancels the run through `cancellation`, the token of its options, once `max_errors`
+    /// errors are reported instead of waiting for every file
+    #[inline]
+    #[must_use]
+    pub fn with_max_errors(mut self, max_errors: usize, cancellation: CancellationToken) -> Self {
+        self.max_errors = Some((max_errors, cancellation));
+        self
+    }
+
     pub fn sender(&self) -> &CollectorSender {
         &self.sender
     }
+
     pub fn start(&mut self) {
-        while let Ok(Some((path, deps))) = self.receiver.recv() {
-            for dep in deps {
-                self.deps.push((path.display().to_string(), dep));
+        while let Ok(Some(message)) = self.receiver.recv() {
+            match message {
+                CollectorMessage::Dependencies(files) => {
+                    for (path, deps) in files {
+                        for dep in deps {
+                            if let Some(index) = &mut self.index {
+                                index.add(&path, &dep);
+                            }
+                            self.deps.push((path.clone(), dep));
+                        }
+                    }
+                }
+                CollectorMessage::Diagnostics(diagnostics) => {
+                    for diagnostic in diagnostics {
+                        if !self.seen.insert(DiagnosticKey::new(&diagnostic)) {
+                            continue;
+                        }
+                        for sink in &mut self.sinks {
+                            sink.diagnostic(&diagnostic);
+                        }
+                        self.counts.add(diagnostic.severity);
+                        self.diagnostics.push(diagnostic);
+                    }
+                }
+                CollectorMessage::Violations(violations) => {
+                    for violation in &violations {
+                        if !self.excuses.excuse(violation) {
+                            self.counts.add(violation.severity);
+                        }
+                    }
+                    self.violations.extend(violations);
+                }
+            }
+            if let Some((max_errors, cancellation)) = &self.max_errors {
+                if self.counts.errors >= *max_errors {
+                    cancellation.cancel();
+                    self.reached_max_errors = true;
+                }
             }
         }
     }
 
-    pub fn wrap_messages(path: &Path, messages: Vec<Message>) -> CollectorTuple {
-        let mut diagnostics = Vec::new();
-        for message in messages {
-            diagnostics.push(message.file_path);
+    /// Errors and warnings reported so far: the diagnostics and the violations received
+    /// during the run missing from the baseline, see [`Self::with_baseline`], then the
+    /// violations passed to [`Self::finish`] instead once finished
+    pub fn counts(&self) -> SeverityCounts {
+        self.counts
+    }
+
+    /// Whether the run was cancelled as it reached the maximum of errors, see
+    /// [`Self::with_max_errors`]
+    pub fn reached_max_errors(&self) -> bool {
+        self.reached_max_errors
+    }
+
+    /// Reports `violations`, those of the run left to report, to every sink
+    ///
+    /// # Errors
+    /// When a sink fails to report, the other sinks still do
+    pub fn finish(&mut self, violations: &[RuleViolation]) -> Result<(), String> {
+        let mut counts = SeverityCounts::default();
+        for severity in self
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.severity)
+            .chain(violations.iter().map(|violation| violation.severity))
+        {
+            counts.add(severity);
         }
-        (path.to_path_buf(), diagnostics)
+        self.counts = counts;
+        let errors = self
+            .sinks
+            .iter_mut()
+            .filter_map(|sink| sink.finish(violations, &self.diagnostics).err())
+            .collect::<Vec<_>>();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+
+    /// The dependencies found
+    pub fn deps(&self) -> &[(PathAtom, Message)] {
+        &self.deps
+    }
+
+    /// The dependencies found, to change them. The graph is then built from scratch.
+    pub fn deps_mut(&mut self) -> &mut Vec<(PathAtom, Message)> {
+        self.index = None;
+        &mut self.deps
+    }
+
+    /// The graph of the dependencies found, mostly built while they arrived
+    pub fn graph(&self) -> GraphBuilder {
+        match &self.index {
+            Some(index) => GraphBuilder::from_index(index, &self.deps),
+            None => {
+                let mut graph_builder = GraphBuilder::new();
+                graph_builder.add_deps(&self.deps);
+                graph_builder
+            }
+        }
+    }
+
+    /// The dependencies of `path`, to be sent in a batch with those of other files
+    pub fn wrap_messages(path: &Path, messages: Vec<Message>) -> FileDependencies {
+        (PathAtom::from(&*path.to_string_lossy()), messages)
+    }
+
+    pub fn wrap_diagnostics(diagnostics: Vec<Diagnostic>) -> CollectorMessage {
+        CollectorMessage::Diagnostics(diagnostics)
+    }
+
+    pub fn wrap_violations(violations: Vec<RuleViolation>) -> CollectorMessage {
+        CollectorMessage::Violations(violations)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use super::{
+        CollectorService, CollectorSink, JsonSink, SeverityCounts, TerminalJsonSink, TerminalSink,
+    };
+    use crate::{
+        baseline::Baseline,
+        diagnostic::{Diagnostic, DiagnosticCode, DiagnosticSpan},
+        rules::{RuleViolation, Severity},
+        service::CancellationToken,
+        utils::TestDir,
+    };
+
+    /// Records what it is reported
+    #[derive(Clone, Default)]
+    struct RecordingSink(Arc<Mutex<Vec<String>>>);
+
+    impl CollectorSink for RecordingSink {
+        fn diagnostic(&mut self, diagnostic: &Diagnostic) {
+            self.0.lock().unwrap().push(diagnostic.to_string());
+        }
+
+        fn finish(
+            &mut self,
+            violations: &[RuleViolation],
+            diagnostics: &[Diagnostic],
+        ) -> Result<(), String> {
+            let mut records = self.0.lock().unwrap();
+            records.push(format!("{} {}", violations.len(), diagnostics.len()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_sinks() {
+        let dir = TestDir::new("collector-sinks");
+        let report = dir.join("report.json");
+        let recording = RecordingSink::default();
+        let mut collector = CollectorService::default()
+            .with_sinks(Vec::new())
+            .with_sink(recording.clone())
+            .with_sink(JsonSink::new(&report));
+        let sender = collector.sender().clone();
+        let diagnostic = Diagnostic::new(
+            "a.ts",
+            DiagnosticCode::UnresolvedImport,
+            "Unable to resolve \"./b\"",
+        );
+        // Reported twice, e.g. as the file is processed from another importer
+        for _ in 0..2 {
+            sender
+                .send(Some(CollectorService::wrap_diagnostics(vec![
+                    diagnostic.clone()
+                ])))
+                .unwrap();
+        }
+        sender.send(None).unwrap();
+        collector.start();
+
+        let violations = [RuleViolation::new(
+            "a.ts",
+            "no-b",
+            Severity::Warn,
+            "imports b".to_string(),
+        )];
+        collector.finish(&violations).unwrap();
+        assert_eq!(
+            *recording.0.lock().unwrap(),
+            ["a.ts: Unable to resolve \"./b\"", "1 1"]
+        );
+        let json = std::fs::read_to_string(&report).unwrap();
+        let json = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+        assert_eq!(json["violations"][0]["severity"], "warn");
+        assert_eq!(json["diagnostics"][0]["path"], "a.ts");
+        assert_eq!(json["diagnostics"][0]["code"], "unresolved-import");
+
+        let mut collector = CollectorService::default()
+            .with_sinks(Vec::new())
+            .with_sink(JsonSink::new(
+                std::env::temp_dir().join("missing/dir/report.json"),
+            ));
+        assert!(collector.finish(&[]).is_err());
+    }
+
+    #[test]
+    fn test_terminal_sink() {
+        let file_text = "import a from './a';\nconst = 1;\nrequire(name);\n";
+        let diagnostics = [
+            Diagnostic::new(
+                "b.ts",
+                DiagnosticCode::UnfollowedImport,
+                "require(name) cannot be followed",
+            )
+            .with_span(DiagnosticSpan::new(file_text, 32, 44))
+            .with_severity(Severity::Warn),
+            Diagnostic::new("b.ts", DiagnosticCode::SyntaxError, "Unexpected token")
+                .with_span(DiagnosticSpan::new(file_text, 27, 28)),
+            Diagnostic::new(
+                "a.ts",
+                DiagnosticCode::UnresolvedImport,
+                "Unable to resolve \"./c\"",
+            ),
+        ];
+        let violations = [RuleViolation::new(
+            "b.ts",
+            "noOrphans",
+            Severity::Warn,
+            "orphan".to_string(),
+        )];
+        assert_eq!(
+            TerminalSink::render(&violations, &diagnostics),
+            "a.ts\n\
+             \x20   error    Unable to resolve \"./c\"  unresolved-import\n\
+             \n\
+             b.ts\n\
+             \x20      warning  orphan  noOrphans\n\
+             \x20 2:7  error    Unexpected token  syntax-error\n\
+             \x20 3:1  warning  require(name) cannot be followed  unfollowed-import\n\
+             \n\
+             2 errors, 2 warnings in 2 files"
+        );
+        assert_eq!(TerminalSink::render(&[], &[]), "");
+
+        let json = TerminalJsonSink::render(&violations, &diagnostics);
+        assert_eq!(json.as_array().unwrap().len(), 4);
+        assert_eq!(json[0]["code"], "unfollowed-import");
+        assert_eq!(json[0]["span"]["line"], 3);
+        assert_eq!(json[3]["code"], "noOrphans");
+        assert_eq!(json[3]["severity"], "warn");
+    }
+
+    #[test]
+    fn test_counts() {
+        let cancellation = CancellationToken::default();
+        let mut collector = CollectorService::default()
+            .with_sinks(Vec::new())
+            .with_max_errors(2, cancellation.clone());
+        let sender = collector.sender().clone();
+        let diagnostic = Diagnostic::new("a.ts", DiagnosticCode::SyntaxError, "Unexpected token");
+        let violation =
+            |severity| RuleViolation::new("a.ts", "no-b", severity, "imports b".to_string());
+        sender
+            .send(Some(CollectorService::wrap_diagnostics(vec![diagnostic])))
+            .unwrap();
+        sender
+            .send(Some(CollectorService::wrap_violations(vec![violation(
+                Severity::Warn,
+            )])))
+            .unwrap();
+        sender.send(None).unwrap();
+        collector.start();
+        assert_eq!(
+            collector.counts(),
+            SeverityCounts {
+                errors: 1,
+                warnings: 1
+            }
+        );
+        assert!(!cancellation.is_cancelled());
+
+        sender
+            .send(Some(CollectorService::wrap_violations(vec![violation(
+                Severity::Error,
+            )])))
+            .unwrap();
+        sender.send(None).unwrap();
+        collector.start();
+        assert!(cancellation.is_cancelled());
+        assert!(collector.reached_max_errors());
+
+        // The violations finished with replace those received
+        collector.finish(&[violation(Severity::Warn)]).unwrap();
+        assert_eq!(
+            collector.counts(),
+            SeverityCounts {
+                errors: 1,
+                warnings: 1
+            }
+        );
+
+        // A violation of the baseline is not reported, only its new duplicate counts
+        let cancellation = CancellationToken::default();
+        let mut collector = CollectorService::default()
+            .with_sinks(Vec::new())
+            .with_baseline(&Baseline::new(&[violation(Severity::Error)]))
+            .with_max_errors(2, cancellation.clone());
+        let sender = collector.sender().clone();
+        sender
+            .send(Some(CollectorService::wrap_violations(vec![
+                violation(Severity::Error),
+                violation(Severity::Error),
+            ])))
+            .unwrap();
+        sender.send(None).unwrap();
+        collector.start();
+        assert_eq!(collector.counts().errors, 1);
+        assert!(!cancellation.is_cancelled());
     }
 }
@@ -0,0 +1,166 @@
+//! The `marsh.json` configuration file.
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::{
+    boundaries::{Boundaries, BoundariesConfig},
+    public_entries::{PublicEntries, PublicEntriesConfig},
+    rules::{
+        MaxDepth, MaxDepthConfig, NoCircular, NoCircularConfig, NoOrphans, NoOrphansConfig,
+        OverrideConfig, Overrides, PackageRuleConfig, PackageRules, RelativeImports,
+        RelativeImportsConfig, RuleConfig, RuleSet, Severity,
+    },
+    scripts::{ScriptRuleConfig, ScriptRules},
+    utils::read_to_string,
+};
+
+/// Name of the configuration file looked up in the current working directory
+pub const CONFIG_FILE: &str = "marsh.json";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfigFile {
+    #[serde(default)]
+    rules: Vec<RuleConfig>,
+    #[serde(default)]
+    no_circular: Option<NoCircularConfig>,
+    #[serde(default)]
+    boundaries: Option<BoundariesConfig>,
+    #[serde(default)]
+    package_rules: Vec<PackageRuleConfig>,
+    #[serde(default)]
+    max_depth: Option<MaxDepthConfig>,
+    #[serde(default)]
+    no_orphans: Option<NoOrphansConfig>,
+    #[serde(default)]
+    public_entries: Option<PublicEntriesConfig>,
+    #[serde(default)]
+    relative_imports: Option<RelativeImportsConfig>,
+    #[serde(default)]
+    scripts: Vec<ScriptRuleConfig>,
+    #[serde(default)]
+    overrides: Vec<OverrideConfig>,
+}
+
+#[derive(Debug, Default)]
+pub struct Config {
+    pub rules: RuleSet,
+    pub no_circular: Option<NoCircular>,
+    pub boundaries: Option<Boundaries>,
+    pub package_rules: PackageRules,
+    pub max_depth: Option<MaxDepth>,
+    pub no_orphans: Option<NoOrphans>,
+    pub public_entries: Option<PublicEntries>,
+    pub relative_imports: Option<RelativeImports>,
+    pub scripts: ScriptRules,
+    pub overrides: Overrides,
+}
+
+impl Config {
+    /// Loads `path`, or `marsh.json` in `cwd` when present.
+    ///
+    /// # Errors
+    /// When the file cannot be read or is invalid
+    pub fn load(cwd: &Path, path: Option<&Path>) -> Result<Self, String> {
+        let path = match path {
+            Some(path) => cwd.join(path),
+            None => {
+                let path = cwd.join(CONFIG_FILE);
+                if !path.is_file() {
+                    return Ok(Self::default());
+                }
+                path
+            }
+        };
+        Self::parse(cwd, &path).map_err(|error| {
+            let path = path.strip_prefix(cwd).unwrap_or(&path);
+            format!("{}: {error}", path.display())
+        })
+    }
+
+    /// Script paths are relative to `cwd`, like the paths the rules match
+    fn parse(cwd: &Path, path: &Path) -> Result<Self, String> {
+        let source_text = read_to_string(path).map_err(|error| error.to_string())?;
+        let config =
+            serde_json::from_str::<ConfigFile>(&source_text).map_err(|error| error.to_string())?;
+        Ok(Self {
+            rules: RuleSet::new(&config.rules)?,
+            no_circular: config
+                .no_circular
+                .as_ref()
+                .filter(|config| config.severity != Severity::Off)
+                .map(NoCircular::new)
+                .transpose()?,
+            boundaries: config
+                .boundaries
+                .as_ref()
+                .filter(|config| config.severity != Severity::Off)
+                .map(Boundaries::new)
+                .transpose()?,
+            package_rules: PackageRules::new(&config.package_rules)?,
+            max_depth: config
+                .max_depth
+                .map(MaxDepth::from)
+                .filter(|max_depth| max_depth.severity != Severity::Off),
+            no_orphans: config
+                .no_orphans
+                .as_ref()
+                .filter(|config| config.severity != Severity::Off)
+                .map(NoOrphans::new)
+                .transpose()?,
+            public_entries: config
+                .public_entries
+                .as_ref()
+                .filter(|config| config.severity != Severity::Off)
+                .map(PublicEntries::new)
+                .transpose()?,
+            relative_imports: config
+                .relative_imports
+                .as_ref()
+                .filter(|config| config.severity != Severity::Off)
+                .map(RelativeImports::new)
+                .transpose()?,
+            scripts: ScriptRules::new(cwd, &config.scripts)?,
+            overrides: Overrides::new(&config.overrides)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Config;
+    use crate::utils::TestDir;
+
+    #[test]
+    fn test_load_config() {
+        let root = TestDir::new("config");
+        std::fs::write(
+            root.join("marsh.json"),
+            r#"{
+                "rules": [{ "name": "no-lodash", "from": "**", "to": "lodash" }],
+                "noCircular": { "include": "src/**", "ignoreTypeOnly": true },
+                "maxDepth": { "depth": 3, "severity": "off" }
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(root.join("broken.json"), r#"{ "rules": [{ "name": 1 }] }"#).unwrap();
+
+        let config = Config::load(&root, None).unwrap();
+        assert!(config.rules.check("src/a.ts", "lodash").is_some());
+        let no_circular = config.no_circular.unwrap();
+        assert!(no_circular.includes("src/a.ts"));
+        assert!(!no_circular.includes("scripts/a.ts"));
+        assert!(config.max_depth.is_none());
+
+        let error = Config::load(&root, Some("broken.json".as_ref())).unwrap_err();
+        assert!(error.starts_with("broken.json: "), "{error}");
+        assert!(Config::load(&root, Some("missing.json".as_ref())).is_err());
+
+        let empty = TestDir::new("config-empty");
+        let config = Config::load(&empty, None).unwrap();
+        assert!(config.rules.is_empty());
+        assert!(config.no_circular.is_none());
+    }
+}
@@ -0,0 +1,353 @@
+//! Circular dependencies, told apart by whether they exist at runtime, and circular
+//! dependencies between workspace packages, which keep them from being published or
+//! extracted on their own.
+
+use std::fmt;
+
+use petgraph::{
+    algo::{is_cyclic_directed, tarjan_scc},
+    graph::NodeIndex,
+    visit::EdgeRef,
+    Graph,
+};
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+
+use crate::{
+    graph::{EdgeType, NodeKind},
+    message::Message,
+    package_level::PackageLevelGraph,
+    utils::PathAtom,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CycleKind {
+    /// Modules depend on each other at runtime, their evaluation order matters
+    Value,
+    /// Only types close the cycle, it is erased at runtime and can be broken by `import type`
+    Type,
+}
+
+/// An import which only imports types without being marked `import type`
+#[derive(Debug, Serialize)]
+pub struct TypeOnlyImport {
+    pub from: String,
+    pub to: String,
+    pub symbols: Vec<String>,
+}
+
+/// Modules which all depend on each other, directly or transitively
+#[derive(Debug, Serialize)]
+pub struct Cycle {
+    pub kind: CycleKind,
+    /// Sorted
+    pub modules: Vec<String>,
+    /// Imports of the cycle which can become `import type`, sorted.
+    /// For cycles of kind `type`, converting them all breaks the cycle.
+    pub type_only_imports: Vec<TypeOnlyImport>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CycleReport {
+    pub cycles: Vec<Cycle>,
+}
+
+impl CycleReport {
+    /// Finds the cycles of the module graph `deps`.
+    ///
+    /// `imports_only_types` tells whether an edge only imports types, so it can become
+    /// `import type`. Edges which already are type-only never count as runtime dependencies.
+    pub fn new(
+        deps: &[(PathAtom, Message)],
+        imports_only_types: impl Fn(&Message) -> bool,
+    ) -> Self {
+        let mut graph = Graph::<&str, (&Message, bool)>::new();
+        let mut nodes = FxHashMap::<&str, NodeIndex>::default();
+        for (src, dst) in deps {
+            if dst.kind != NodeKind::Module {
+                continue;
+            }
+            let mut node = |path| *nodes.entry(path).or_insert_with(|| graph.add_node(path));
+            let (src, target) = (node(src.as_str()), node(dst.file_path.as_str()));
+            // Whether the edge is a runtime dependency
+            let is_value = dst.edge != EdgeType::Type && !imports_only_types(dst);
+            graph.add_edge(src, target, (dst, is_value));
+        }
+
+        let mut cycles = tarjan_scc(&graph)
+            .into_iter()
+            .filter(|component| {
+                component.len() > 1 || graph.contains_edge(component[0], component[0])
+            })
+            .map(|component| {
+                let runtime = graph.filter_map(
+                    |index, path| component.contains(&index).then_some(*path),
+                    |_, &(_, is_value)| is_value.then_some(()),
+                );
+                let kind = if is_cyclic_directed(&runtime) {
+                    CycleKind::Value
+                } else {
+                    CycleKind::Type
+                };
+
+                let mut type_only_imports = graph
+                    .edge_references()
+                    .filter(|edge| {
+                        let (dst, is_value) = edge.weight();
+                        !is_value
+                            && dst.edge != EdgeType::Type
+                            && component.contains(&edge.source())
+                            && component.contains(&edge.target())
+                    })
+                    .map(|edge| TypeOnlyImport {
+                        from: graph[edge.source()].to_string(),
+                        to: graph[edge.target()].to_string(),
+                        symbols: edge.weight().0.symbols.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                type_only_imports.sort_unstable_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+
+                let mut modules = component
+                    .iter()
+                    .map(|&index| graph[index].to_string())
+                    .collect::<Vec<_>>();
+                modules.sort_unstable();
+                Cycle {
+                    kind,
+                    modules,
+                    type_only_imports,
+                }
+            })
+            .collect::<Vec<_>>();
+        cycles.sort_unstable_by(|a, b| a.modules.cmp(&b.modules));
+        Self { cycles }
+    }
+}
+
+impl fmt::Display for CycleReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.cycles.is_empty() {
+            return write!(f, "No circular dependencies found.");
+        }
+        for cycle in &self.cycles {
+            let kind = match cycle.kind {
+                CycleKind::Value => "value",
+                CycleKind::Type => "type-only",
+            };
+            writeln!(f, "{kind} cycle between {} modules", cycle.modules.len())?;
+            for module in &cycle.modules {
+                writeln!(f, "  {module}")?;
+            }
+            if !cycle.type_only_imports.is_empty() {
+                writeln!(f, "  imports which can become `import type`:")?;
+                for import in &cycle.type_only_imports {
+                    writeln!(
+                        f,
+                        "    {} -> {} {{ {} }}",
+                        import.from,
+                        import.to,
+                        import.symbols.join(", ")
+                    )?;
+                }
+            }
+        }
+        let values = self
+            .cycles
+            .iter()
+            .filter(|cycle| cycle.kind == CycleKind::Value)
+            .count();
+        write!(
+            f,
+            "{} cycles, {values} of them at runtime",
+            self.cycles.len()
+        )
+    }
+}
+
+/// An import of a file of another package of the same cycle
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrossPackageImport {
+    pub from_package: String,
+    pub to_package: String,
+    /// Importing file
+    pub from: String,
+    /// Imported file
+    pub to: String,
+}
+
+/// Workspace packages which all depend on each other, directly or transitively
+#[derive(Debug, Serialize)]
+pub struct PackageCycle {
+    /// Sorted
+    pub packages: Vec<String>,
+    /// Imports between the packages of the cycle, sorted by packages. Removing those from
+    /// one package to another breaks the cycles through them.
+    pub imports: Vec<CrossPackageImport>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PackageCycleReport {
+    pub cycles: Vec<PackageCycle>,
+}
+
+impl PackageCycleReport {
+    /// Finds the cycles of the package graph `graph`
+    pub fn new(graph: &PackageLevelGraph) -> Self {
+        let mut packages = Graph::<&str, usize>::new();
+        let mut nodes = FxHashMap::<&str, NodeIndex>::default();
+        for (index, edge) in graph.edges.iter().enumerate() {
+            let mut node = |name| *nodes.entry(name).or_insert_with(|| packages.add_node(name));
+            let (from, to) = (node(edge.from.as_str()), node(edge.to.as_str()));
+            packages.add_edge(from, to, index);
+        }
+
+        let mut cycles = tarjan_scc(&packages)
+            .into_iter()
+            .filter(|component| component.len() > 1)
+            .map(|component| {
+                let mut names = component
+                    .iter()
+                    .map(|&index| packages[index].to_string())
+                    .collect::<Vec<_>>();
+                names.sort_unstable();
+                let imports = packages
+                    .edge_references()
+                    .filter(|edge| {
+                        component.contains(&edge.source()) && component.contains(&edge.target())
+                    })
+                    .map(|edge| &graph.edges[*edge.weight()])
+                    .flat_map(|edge| {
+                        edge.imports.iter().map(|(from, to)| CrossPackageImport {
+                            from_package: edge.from.clone(),
+                            to_package: edge.to.clone(),
+                            from: from.clone(),
+                            to: to.clone(),
+                        })
+                    })
+                    .collect();
+                PackageCycle {
+                    packages: names,
+                    imports,
+                }
+            })
+            .collect::<Vec<_>>();
+        cycles.sort_unstable_by(|a, b| a.packages.cmp(&b.packages));
+        Self { cycles }
+    }
+}
+
+impl fmt::Display for PackageCycleReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.cycles.is_empty() {
+            return write!(f, "No circular dependencies between packages found.");
+        }
+        for cycle in &self.cycles {
+            writeln!(f, "cycle between {} packages", cycle.packages.len())?;
+            for package in &cycle.packages {
+                writeln!(f, "  {package}")?;
+            }
+            writeln!(f, "  through the imports:")?;
+            for import in &cycle.imports {
+                writeln!(
+                    f,
+                    "    {} -> {}: {} -> {}",
+                    import.from_package, import.to_package, import.from, import.to
+                )?;
+            }
+        }
+        write!(f, "{} cycles between packages", self.cycles.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        graph::{EdgeType, NodeKind},
+        message::Message,
+        package_level::{PackageEdge, PackageLevelGraph},
+        utils::PathAtom,
+    };
+
+    use super::{CycleKind, CycleReport, PackageCycleReport};
+
+    #[test]
+    fn test_cycle_report() {
+        let edge = |from: &str, to: &str, edge, symbols: &[&str]| {
+            (
+                PathAtom::from(from),
+                Message::new(to, NodeKind::Module, edge)
+                    .with_symbols(symbols.iter().map(ToString::to_string).collect()),
+            )
+        };
+        let deps = vec![
+            // Runtime cycle
+            edge("a.ts", "b.ts", EdgeType::Static, &["b"]),
+            edge("b.ts", "a.ts", EdgeType::Static, &["a"]),
+            // Closed by an `import type` and an import of types only
+            edge("c.ts", "d.ts", EdgeType::Static, &["d"]),
+            edge("d.ts", "c.ts", EdgeType::Type, &["C"]),
+            edge("d.ts", "e.ts", EdgeType::Static, &["Props"]),
+            edge("e.ts", "c.ts", EdgeType::Static, &["c"]),
+            // Not a cycle
+            edge("a.ts", "c.ts", EdgeType::Static, &["c"]),
+        ];
+        let report = CycleReport::new(&deps, |message| message.symbols == ["Props"]);
+
+        assert_eq!(report.cycles.len(), 2);
+        assert_eq!(report.cycles[0].kind, CycleKind::Value);
+        assert_eq!(report.cycles[0].modules, ["a.ts", "b.ts"]);
+        assert!(report.cycles[0].type_only_imports.is_empty());
+
+        assert_eq!(report.cycles[1].kind, CycleKind::Type);
+        assert_eq!(report.cycles[1].modules, ["c.ts", "d.ts", "e.ts"]);
+        let imports = &report.cycles[1].type_only_imports;
+        assert_eq!(imports.len(), 1);
+        assert_eq!(
+            (imports[0].from.as_str(), imports[0].to.as_str()),
+            ("d.ts", "e.ts")
+        );
+    }
+
+    #[test]
+    fn test_package_cycle_report() {
+        let edge = |from: &str, to: &str, imports: &[(&str, &str)]| PackageEdge {
+            from: from.to_string(),
+            to: to.to_string(),
+            count: imports.len(),
+            imports: imports
+                .iter()
+                .map(|(from, to)| (from.to_string(), to.to_string()))
+                .collect(),
+            dynamic: false,
+        };
+        let graph = PackageLevelGraph {
+            edges: vec![
+                edge("app", "ui", &[("app/main.ts", "ui/button.ts")]),
+                edge("ui", "utils", &[("ui/button.ts", "utils/theme.ts")]),
+                edge("utils", "ui", &[("utils/theme.ts", "ui/colors.ts")]),
+            ],
+            ..PackageLevelGraph::default()
+        };
+        let report = PackageCycleReport::new(&graph);
+
+        assert_eq!(report.cycles.len(), 1);
+        assert_eq!(report.cycles[0].packages, ["ui", "utils"]);
+        let imports = report.cycles[0]
+            .imports
+            .iter()
+            .map(|import| (import.from.as_str(), import.to.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            imports,
+            [
+                ("ui/button.ts", "utils/theme.ts"),
+                ("utils/theme.ts", "ui/colors.ts")
+            ]
+        );
+        let import = serde_json::to_value(&report.cycles[0].imports[0]).unwrap();
+        assert_eq!(import["fromPackage"], "ui");
+        assert_eq!(import["toPackage"], "utils");
+    }
+}
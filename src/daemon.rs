@@ -0,0 +1,413 @@
+//! `marsh daemon`, which stays resident to answer requests about the graph, e.g. from editors,
+//! scripts and CI bots.
+//!
+//! The daemon analyzes the entries once and keeps the graph and the module records of the run
+//! in memory, answering requests in milliseconds instead of the time of a whole run. Clients
+//! connect to a Unix domain socket, or spawn the daemon with `--stdio`, and speak JSON-RPC 2.0
+//! with one message per line:
+//!
+//! ```text
+//! {"jsonrpc":"2.0","id":1,"method":"dependents","params":{"path":"src/utils.ts"}}
+//! {"jsonrpc":"2.0","id":1,"result":["src/app.ts","src/main.ts"]}
+//! ```
+//!
+//! - `analyze`: analyzes the entries again, `{ "edges", "elapsedMs" }`
+//! - `graph`: the graph, as printed by `--format json`
+//! - `dependents`, `{ "path" }`: the files which import `path`, directly or not
+//! - `why`, `{ "path" }`: the shortest chain of imports from an entry to `path`
+//! - `traceSymbol`, `{ "path", "name" }`: `name` followed to the module defining it,
+//!   `{ "steps", "found" }`
+//! - `invalidate`, `{ "path" }`: `path` changed, was added or removed, the entries are
+//!   analyzed again before the next request, which reads and parses it only, `null`
+//! - `shutdown`: `null`, the daemon stops once it answered
+//!
+//! Paths are relative to the working directory of the daemon, or absolute. Requests without an
+//! `id` are notifications and are not answered. Parsed files are kept in memory across
+//! analyses, so only the files invalidated since are read and parsed again.
+
+use std::{
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    collector::CollectorService,
+    graph::GraphBuilder,
+    service::{AnalyzeService, AnalyzeServiceOptions},
+    utils::{evict_unused, normalize_path},
+};
+
+/// Socket in the cache directory the daemon listens on by default
+pub const SOCKET_FILE: &str = "daemon.sock";
+
+/// Error codes of the JSON-RPC specification
+pub const PARSE_ERROR: i32 = -32700;
+pub const INVALID_REQUEST: i32 = -32600;
+pub const METHOD_NOT_FOUND: i32 = -32601;
+pub const INVALID_PARAMS: i32 = -32602;
+/// The request is valid but cannot be answered, e.g. `why` for a file no entry imports
+pub const REQUEST_FAILED: i32 = -32000;
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    /// Absent for notifications
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RpcError {
+    pub code: i32,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PathParams {
+    path: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SymbolParams {
+    path: String,
+    name: String,
+}
+
+pub struct Daemon {
+    cwd: PathBuf,
+    /// Entries, named like the nodes of the graph
+    entries: Vec<String>,
+    /// Service of the runs, which keeps the parsed files and the module records of the last
+    service: AnalyzeService,
+    /// Graph of the last run
+    graph: GraphBuilder,
+    /// Files changed since the last run, which is redone before the next request
+    stale: bool,
+}
+
+impl Daemon {
+    /// Analyzes the paths of `options`, whose nodes are named `entries`
+    pub fn new(cwd: &Path, options: AnalyzeServiceOptions, entries: Vec<String>) -> Self {
+        let (service, collector) = analyze(options.with_resident(true), true);
+        Self {
+            cwd: cwd.to_path_buf(),
+            entries,
+            service,
+            graph: collector.graph(),
+            stale: false,
+        }
+    }
+
+    /// Answers requests sent to the socket at `socket` until one asks to shut down
+    ///
+    /// # Errors
+    /// When the socket cannot be listened on, e.g. when another daemon already does
+    #[cfg(unix)]
+    pub fn serve(mut self, socket: &Path) -> Result<(), String> {
+        use std::{
+            io::BufReader,
+            os::unix::net::{UnixListener, UnixStream},
+        };
+
+        if UnixStream::connect(socket).is_ok() {
+            return Err(format!("Another daemon listens on {}", socket.display()));
+        }
+        // Left behind by a daemon which did not shut down
+        let _ = std::fs::remove_file(socket);
+        let listener = UnixListener::bind(socket)
+            .map_err(|error| format!("Failed to listen on {}: {error}", socket.display()))?;
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            if !self.serve_connection(BufReader::new(&stream), &stream) {
+                break;
+            }
+        }
+        let _ = std::fs::remove_file(socket);
+        Ok(())
+    }
+
+    /// # Errors
+    /// Always, Unix domain sockets are not available on this platform
+    #[cfg(not(unix))]
+    pub fn serve(self, _socket: &Path) -> Result<(), String> {
+        Err(
+            "marsh daemon listens on a Unix domain socket, which this platform lacks, \
+             use --stdio instead"
+                .to_string(),
+        )
+    }
+
+    /// Answers requests read from stdin on stdout until one asks to shut down or stdin ends
+    pub fn serve_stdio(mut self) {
+        self.serve_connection(std::io::stdin().lock(), std::io::stdout().lock());
+    }
+
+    /// Answers the requests read from a connection, returns `false` once asked to shut down
+    fn serve_connection(&mut self, reader: impl BufRead, mut writer: impl Write) -> bool {
+        for line in reader.lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (id, response, shutdown) = match serde_json::from_str::<Value>(&line) {
+                Err(error) => (
+                    Value::Null,
+                    Err(RpcError::new(PARSE_ERROR, format!("Invalid JSON: {error}"))),
+                    false,
+                ),
+                Ok(request) => match serde_json::from_value::<Request>(request) {
+                    Err(error) => (
+                        Value::Null,
+                        Err(RpcError::new(
+                            INVALID_REQUEST,
+                            format!("Invalid request: {error}"),
+                        )),
+                        false,
+                    ),
+                    Ok(request) => {
+                        let shutdown = request.method == "shutdown";
+                        let response = self.call(&request.method, request.params);
+                        // Notifications are not answered
+                        let Some(id) = request.id else {
+                            if shutdown {
+                                return false;
+                            }
+                            continue;
+                        };
+                        (id, response, shutdown)
+                    }
+                },
+            };
+            let response = match response {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+            };
+            if writeln!(writer, "{response}").is_err() || shutdown {
+                return !shutdown;
+            }
+        }
+        true
+    }
+
+    /// Answers a call of `method` from the last run
+    ///
+    /// # Errors
+    /// When the method or its params are unknown, or the request cannot be answered
+    pub fn call(&mut self, method: &str, params: Value) -> Result<Value, RpcError> {
+        if self.stale && !matches!(method, "analyze" | "invalidate" | "shutdown") {
+            self.analyze();
+        }
+        match method {
+            "analyze" => {
+                let start = Instant::now();
+                self.analyze();
+                let edges = self.graph.edge_count();
+                Ok(json!({ "edges": edges, "elapsedMs": start.elapsed().as_millis() }))
+            }
+            "graph" => Ok(self.graph.to_json()),
+            "dependents" => {
+                let PathParams { path } = parse_params(params)?;
+                let dependents = self.graph.dependents(&self.node_name(&path));
+                Ok(json!(dependents))
+            }
+            "why" => {
+                let PathParams { path } = parse_params(params)?;
+                let path = self.node_name(&path);
+                if self.entries.contains(&path) {
+                    return Ok(json!([path]));
+                }
+                match self.graph.import_chain(&self.entries, &path) {
+                    Some(chain) => Ok(json!(chain)),
+                    None => Err(RpcError::new(
+                        REQUEST_FAILED,
+                        format!("{path} is not reachable from the entries"),
+                    )),
+                }
+            }
+            "traceSymbol" => {
+                let SymbolParams { path, name } = parse_params(params)?;
+                let Some(trace) = self.service.trace_symbol(&self.cwd.join(&path), &name) else {
+                    return Err(RpcError::new(
+                        REQUEST_FAILED,
+                        format!("{path} could not be analyzed"),
+                    ));
+                };
+                let steps = trace
+                    .steps
+                    .iter()
+                    .map(|step| {
+                        let path = &step.record.resolved_absolute_path;
+                        let path = path.strip_prefix(&self.cwd).unwrap_or(path);
+                        json!({ "path": path, "name": step.name, "via": step.via })
+                    })
+                    .collect::<Vec<_>>();
+                Ok(json!({ "steps": steps, "found": trace.found }))
+            }
+            "invalidate" => {
+                let PathParams { path } = parse_params(params)?;
+                self.service.invalidate(&self.cwd.join(path));
+                self.stale = true;
+                Ok(Value::Null)
+            }
+            "shutdown" => Ok(Value::Null),
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method {method}"),
+            )),
+        }
+    }
+
+    fn analyze(&mut self) {
+        let collector = run(&self.service, true);
+        self.graph = collector.graph();
+        self.stale = false;
+        evict_unused();
+    }
+
+    /// Name of the node of `path`, relative to the working directory
+    fn node_name(&self, path: &str) -> String {
+        let path = self.cwd.join(path);
+        let path = path.strip_prefix(&self.cwd).unwrap_or(&path);
+        normalize_path(path).display().to_string()
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params)
+        .map_err(|error| RpcError::new(INVALID_PARAMS, format!("Invalid params: {error}")))
+}
+
+/// Runs the analysis, printing diagnostics to the standard error unless they are kept in
+/// the collector, and exports its spans when traced
+pub(crate) fn analyze(
+    options: AnalyzeServiceOptions,
+    print_diagnostics: bool,
+) -> (AnalyzeService, CollectorService) {
+    let service = AnalyzeService::new(options);
+    let collector = run(&service, print_diagnostics);
+    (service, collector)
+}
+
+/// Runs the analysis of `service` again, see [`analyze`]
+pub(crate) fn run(service: &AnalyzeService, print_diagnostics: bool) -> CollectorService {
+    let mut collector = CollectorService::default();
+    if !print_diagnostics {
+        collector = collector.with_sinks(Vec::new());
+    }
+    rayon::spawn({
+        let tx_error = collector.sender().clone();
+        let service = service.clone();
+        move || service.run(&tx_error)
+    });
+    collector.start();
+    if let Err(error) = collector.finish(&[]) {
+        eprintln!("{error}");
+    }
+    if let Err(error) = service.check_limits() {
+        eprintln!("{error}");
+    }
+    if let Some(tracer) = service.tracer() {
+        if let Err(error) = tracer.export() {
+            eprintln!("{error}");
+        }
+    }
+    collector
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io::Cursor, time::Duration};
+
+    use serde_json::{json, Value};
+
+    use super::{Daemon, INVALID_PARAMS, METHOD_NOT_FOUND, PARSE_ERROR, REQUEST_FAILED};
+    use crate::{service::AnalyzeServiceOptions, stats::StatsReport, utils::TestDir};
+
+    #[test]
+    fn test_daemon() {
+        let root = TestDir::new("daemon");
+        for (file, contents) in [
+            ("main.ts", "import './a'; import './b';"),
+            ("a.ts", "import { c } from './c';"),
+            ("b.ts", "import './a';"),
+            ("c.ts", "export const c = 1;"),
+        ] {
+            root.write(file, contents);
+        }
+
+        let options = AnalyzeServiceOptions::new(&*root, vec![root.join("main.ts").into()])
+            .with_cross_module(true);
+        let mut daemon = Daemon::new(&root, options, vec!["main.ts".to_string()]);
+        let requests = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"dependents","params":{"path":"c.ts"}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"why","params":{"path":"./c.ts"}}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"traceSymbol","params":{"path":"a.ts","name":"c"}}"#,
+            r#"{"jsonrpc":"2.0","id":4,"method":"unknown"}"#,
+            r#"{"jsonrpc":"2.0","id":5,"method":"why"}"#,
+            r#"{"jsonrpc":"2.0","id":6,"method":"why","params":{"path":"d.ts"}}"#,
+            r#"{"jsonrpc":"2.0","method":"invalidate","params":{"path":"b.ts"}}"#,
+            r#"{"jsonrpc":"2.0","id":7,"method":"why","params":{"path":"d.ts"}}"#,
+            r#"{"jsonrpc":"2.0","id":8,"method":"analyze"}"#,
+            "not json",
+            r#"{"jsonrpc":"2.0","id":9,"method":"shutdown"}"#,
+            r#"{"jsonrpc":"2.0","id":10,"method":"graph"}"#,
+        ];
+        let mut output = Vec::new();
+        let mut served = true;
+        for (i, request) in requests.iter().enumerate() {
+            // `b.ts` changes before it is invalidated
+            if i == 6 {
+                std::fs::write(root.join("d.ts"), "").unwrap();
+                std::fs::write(root.join("b.ts"), "import './a'; import './d';").unwrap();
+            }
+            served = daemon.serve_connection(Cursor::new(*request), &mut output);
+            if !served {
+                break;
+            }
+            // Only `b.ts` and the `d.ts` it now imports are parsed again
+            if i == 7 {
+                let report = StatsReport::new(daemon.service.counters(), 0, 1, Duration::ZERO);
+                assert_eq!((report.files_parsed, report.cache_hits), (2, 3));
+            }
+        }
+        assert!(!served);
+        let responses = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        // The notification is not answered, nor the last request as the daemon shut down
+        assert_eq!(responses.len(), 10);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["result"], json!(["a.ts", "b.ts", "main.ts"]));
+        assert_eq!(responses[1]["result"], json!(["main.ts", "a.ts", "c.ts"]));
+        assert_eq!(responses[2]["result"]["found"], true);
+        assert_eq!(responses[2]["result"]["steps"][1]["path"], "c.ts");
+        assert_eq!(responses[3]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(responses[4]["error"]["code"], INVALID_PARAMS);
+        assert_eq!(responses[5]["error"]["code"], REQUEST_FAILED);
+        assert_eq!(responses[6]["result"], json!(["main.ts", "b.ts", "d.ts"]));
+        assert_eq!(responses[7]["result"]["edges"], 5);
+        assert_eq!(responses[8]["error"]["code"], PARSE_ERROR);
+        assert_eq!(responses[8]["id"], Value::Null);
+        assert_eq!(responses[9]["result"], Value::Null);
+    }
+}
@@ -0,0 +1,153 @@
+//! Files under the project root that no entry reaches.
+
+use std::{fmt, fs, path::Path};
+
+use oxc_span::SourceType;
+use rustc_hash::FxHashSet;
+use serde::Serialize;
+
+use crate::loader::BuiltinLoader;
+
+#[derive(Debug, Serialize)]
+pub struct DeadFile {
+    /// Path relative to the scanned root
+    pub path: String,
+    /// Size in bytes
+    pub size: u64,
+}
+
+/// Deletable candidates: source files which are neither entries nor imported by anything.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadFileReport {
+    pub files: Vec<DeadFile>,
+    pub total_size: u64,
+}
+
+impl DeadFileReport {
+    /// Scans `root` for source files and keeps those missing from `reachable`,
+    /// which holds paths relative to `root`.
+    ///
+    /// `node_modules`, hidden directories and declaration files are skipped.
+    pub fn new(root: &Path, reachable: &FxHashSet<&str>) -> Self {
+        let mut files = Vec::new();
+        collect_files(root, root, reachable, &mut files);
+        files.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+        let total_size = files.iter().map(|file| file.size).sum();
+        Self { files, total_size }
+    }
+}
+
+impl fmt::Display for DeadFileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.files.is_empty() {
+            return write!(f, "No dead files found.");
+        }
+        for file in &self.files {
+            writeln!(f, "{:>10}  {}", format_size(file.size), file.path)?;
+        }
+        write!(
+            f,
+            "{} dead files, {} in total",
+            self.files.len(),
+            format_size(self.total_size)
+        )
+    }
+}
+
+fn collect_files(root: &Path, dir: &Path, reachable: &FxHashSet<&str>, files: &mut Vec<DeadFile>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let file_name = entry.file_name();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if file_name != "node_modules" && !file_name.to_string_lossy().starts_with('.') {
+                collect_files(root, &path, reachable, files);
+            }
+            continue;
+        }
+        if !file_type.is_file()
+            || !BuiltinLoader::can_load(&path)
+            || SourceType::from_path(&path)
+                .is_ok_and(|source_type| source_type.is_typescript_definition())
+        {
+            continue;
+        }
+        let relative = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        if !reachable.contains(relative.as_str()) {
+            let size = entry.metadata().map_or(0, |metadata| metadata.len());
+            files.push(DeadFile {
+                path: relative,
+                size,
+            });
+        }
+    }
+}
+
+pub fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{size} {}", UNITS[0])
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rustc_hash::FxHashSet;
+
+    use super::{format_size, DeadFileReport};
+    use crate::utils::TestDir;
+
+    #[test]
+    fn test_dead_file_report() {
+        let root = TestDir::new("dead-files");
+        std::fs::create_dir_all(root.join("src/node_modules/pkg")).unwrap();
+        std::fs::create_dir_all(root.join(".cache")).unwrap();
+        for (file, contents) in [
+            ("src/index.ts", "import './a';"),
+            ("src/a.ts", ""),
+            ("src/dead.ts", "export {};"),
+            ("src/globals.d.ts", ""),
+            ("src/readme.md", ""),
+            ("src/node_modules/pkg/index.js", ""),
+            (".cache/stale.js", ""),
+        ] {
+            root.write(file, contents);
+        }
+
+        let reachable = FxHashSet::from_iter(["src/index.ts", "src/a.ts"]);
+        let report = DeadFileReport::new(&root, &reachable);
+        let paths = report
+            .files
+            .iter()
+            .map(|file| file.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["src/dead.ts"]);
+        assert_eq!(report.total_size, 10);
+        assert_eq!(serde_json::to_value(&report).unwrap()["totalSize"], 10);
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(10), "10 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MiB");
+    }
+}
@@ -0,0 +1,164 @@
+//! Imports reaching into package internals instead of the package's public entry,
+//! e.g. `lodash/internal/baseGet` or `@myorg/ui/src/private/Button`.
+
+use serde_json::Value;
+
+use crate::incremental::ResolvedPackage;
+
+/// Packages whose internals must not be imported
+#[derive(Debug, Default, Clone)]
+pub struct DeepImportPolicy {
+    /// Package names, `*` denies deep imports into every package
+    packages: Vec<String>,
+}
+
+impl DeepImportPolicy {
+    pub fn new(packages: Vec<String>) -> Self {
+        Self { packages }
+    }
+
+    /// Returns the package `specifier` reaches into when that is denied.
+    ///
+    /// Subpaths the package exposes through its `package.json` `exports` are public entries,
+    /// `package` is the manifest the import resolved into, if any.
+    pub fn check<'a>(
+        &self,
+        specifier: &'a str,
+        package: Option<&ResolvedPackage>,
+    ) -> Option<&'a str> {
+        let (name, subpath) = split_package_specifier(specifier)?;
+        if !self
+            .packages
+            .iter()
+            .any(|package| package == "*" || package == name)
+        {
+            return None;
+        }
+        let exports = package
+            .filter(|package| package.name.as_deref() == Some(name))
+            .and_then(|package| package.exports.as_ref());
+        if exports.is_some_and(|exports| is_exported(exports, subpath)) {
+            return None;
+        }
+        Some(name)
+    }
+}
+
+/// Name of the package a bare specifier imports,
+/// returning `None` for relative and absolute specifiers and URLs.
+pub fn package_name(specifier: &str) -> Option<&str> {
+    Some(split_specifier(specifier)?.0)
+}
+
+/// Splits a bare specifier into its package name and subpath,
+/// returning `None` for relative and absolute specifiers and imports of the package entry.
+pub fn split_package_specifier(specifier: &str) -> Option<(&str, &str)> {
+    let (name, subpath) = split_specifier(specifier)?;
+    subpath
+        .filter(|subpath| !subpath.is_empty())
+        .map(|subpath| (name, subpath))
+}
+
+fn split_specifier(specifier: &str) -> Option<(&str, Option<&str>)> {
+    if specifier.is_empty() || specifier.starts_with(['.', '/', '#']) || specifier.contains(':') {
+        return None;
+    }
+    let separator = if specifier.starts_with('@') {
+        let scope_len = specifier.find('/')?;
+        specifier[scope_len + 1..]
+            .find('/')
+            .map(|name_len| scope_len + 1 + name_len)
+    } else {
+        specifier.find('/')
+    };
+    Some(match separator {
+        Some(separator) => (&specifier[..separator], Some(&specifier[separator + 1..])),
+        None => (specifier, None),
+    })
+}
+
+/// Returns `true` when the `exports` field exposes `./{subpath}`, directly or through a pattern.
+pub fn is_exported(exports: &Value, subpath: &str) -> bool {
+    // Only subpath maps expose anything besides the package entry
+    let Some(map) = exports
+        .as_object()
+        .filter(|map| map.keys().all(|key| key.starts_with('.')))
+    else {
+        return false;
+    };
+    let subpath = format!("./{subpath}");
+    if let Some(target) = map.get(&subpath) {
+        return !target.is_null();
+    }
+    // The pattern with the longest prefix wins, `null` targets exclude subpaths
+    map.iter()
+        .filter(|(key, _)| match key.split_once('*') {
+            Some((prefix, suffix)) => {
+                subpath.len() >= prefix.len() + suffix.len()
+                    && subpath.starts_with(prefix)
+                    && subpath.ends_with(suffix)
+            }
+            None => key.ends_with('/') && subpath.starts_with(key.as_str()),
+        })
+        .max_by_key(|(key, _)| key.find('*').unwrap_or(key.len()))
+        .is_some_and(|(_, target)| !target.is_null())
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{is_exported, package_name, split_package_specifier, DeepImportPolicy};
+
+    #[test]
+    fn test_split_package_specifier() {
+        assert_eq!(
+            split_package_specifier("lodash/internal/baseGet"),
+            Some(("lodash", "internal/baseGet"))
+        );
+        assert_eq!(
+            split_package_specifier("@myorg/ui/src/private/Button"),
+            Some(("@myorg/ui", "src/private/Button"))
+        );
+        assert_eq!(split_package_specifier("lodash"), None);
+        assert_eq!(split_package_specifier("@myorg/ui"), None);
+        assert_eq!(split_package_specifier("./lodash/get"), None);
+        assert_eq!(split_package_specifier("node:fs/promises"), None);
+        assert_eq!(split_package_specifier("#internal/a"), None);
+
+        assert_eq!(package_name("lodash/get"), Some("lodash"));
+        assert_eq!(package_name("@myorg/ui"), Some("@myorg/ui"));
+        assert_eq!(package_name("@myorg"), None);
+        assert_eq!(package_name("./a"), None);
+    }
+
+    #[test]
+    fn test_is_exported() {
+        let exports = json!({
+            ".": "./index.js",
+            "./client": { "import": "./client.mjs" },
+            "./utils/*": "./dist/utils/*.js",
+            "./utils/private/*": null,
+        });
+        assert!(is_exported(&exports, "client"));
+        assert!(is_exported(&exports, "utils/format"));
+        assert!(!is_exported(&exports, "utils/private/secret"));
+        assert!(!is_exported(&exports, "server"));
+        assert!(!is_exported(&json!("./index.js"), "client"));
+        assert!(!is_exported(&json!({ "import": "./index.mjs" }), "client"));
+    }
+
+    #[test]
+    fn test_check() {
+        let policy = DeepImportPolicy::new(vec!["lodash".to_string()]);
+        assert_eq!(
+            policy.check("lodash/internal/baseGet", None),
+            Some("lodash")
+        );
+        assert_eq!(policy.check("lodash", None), None);
+        assert_eq!(policy.check("react-dom/client", None), None);
+
+        let policy = DeepImportPolicy::new(vec!["*".to_string()]);
+        assert_eq!(policy.check("react-dom/client", None), Some("react-dom"));
+    }
+}
@@ -0,0 +1,192 @@
+//! `marsh deps-of`, the files importing a file, directly or not, answered from the run stored
+//! by `--incremental` without analyzing anything, fast enough to bind to an editor keystroke.
+//!
+//! The answer is as old as the stored run: files changed since, and source files added next
+//! to its files, are listed as stale, run `marsh --incremental` again to refresh it.
+
+use std::{
+    collections::{BTreeSet, VecDeque},
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+
+use crate::{
+    incremental::{FileState, Incremental, STATE_FILE},
+    utils::{normalize_path, source_type_from_path},
+};
+
+#[derive(Debug, Default, Serialize)]
+pub struct DepsOfReport {
+    /// The file, relative to the current working directory
+    pub path: String,
+    /// Files importing it, directly or not, sorted
+    pub dependents: Vec<String>,
+    /// Files of the stored run changed since, whose imports may differ, and source files
+    /// added since, which may import the file, sorted
+    pub stale: Vec<String>,
+}
+
+impl DepsOfReport {
+    /// Dependents of `file`, relative to `cwd`, from the run stored in `cache_dir`, relative
+    /// to `cwd` too
+    ///
+    /// # Errors
+    /// When no run was stored, or `file` is not part of it
+    pub fn from_cache(cwd: &Path, cache_dir: &Path, file: &Path) -> Result<Self, String> {
+        let cache_dir = cwd.join(cache_dir);
+        let Some(files) = Incremental::stored_files(&cache_dir) else {
+            return Err(format!(
+                "No run is stored in {}, analyze the project with --incremental first",
+                cache_dir.display()
+            ));
+        };
+
+        let mut importers = FxHashMap::<&Path, Vec<&Path>>::default();
+        for (importer, state) in &files {
+            for path in &state.targets {
+                importers.entry(path).or_default().push(importer);
+            }
+        }
+
+        let absolute = normalize_path(&cwd.join(file));
+        let start = [
+            absolute.clone(),
+            absolute.canonicalize().unwrap_or_default(),
+        ]
+        .into_iter()
+        .find(|path| files.contains_key(path) || importers.contains_key(path.as_path()))
+        .ok_or_else(|| format!("{} is not part of the stored run", file.display()))?;
+
+        let mut dependents = BTreeSet::new();
+        let mut queue = VecDeque::from([start.as_path()]);
+        while let Some(path) = queue.pop_front() {
+            for &importer in importers.get(path).into_iter().flatten() {
+                if importer != start && dependents.insert(importer) {
+                    queue.push_back(importer);
+                }
+            }
+        }
+
+        let relative = |path: &Path| path.strip_prefix(cwd).unwrap_or(path).display().to_string();
+        let stored = fs::metadata(cache_dir.join(STATE_FILE)).and_then(|m| m.modified());
+        let mut stale = files
+            .iter()
+            .filter(|(path, state)| !state.is_unchanged(path))
+            .map(|(path, _)| path.clone())
+            .chain(stored.map_or_else(|_| vec![], |stored| added_files(&files, stored)))
+            .map(|path| relative(&path))
+            .collect::<Vec<_>>();
+        stale.sort_unstable();
+        Ok(Self {
+            path: relative(&start),
+            dependents: dependents.into_iter().map(relative).collect(),
+            stale,
+        })
+    }
+}
+
+/// Source files next to the files of the stored run, modified after it was `stored` but not part
+/// of it, e.g. a new file importing one of the run
+fn added_files(files: &FxHashMap<PathBuf, FileState>, stored: SystemTime) -> Vec<PathBuf> {
+    let dirs = files
+        .keys()
+        .filter_map(|path| path.parent())
+        .collect::<BTreeSet<_>>();
+    dirs.into_iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| !files.contains_key(path) && source_type_from_path(path).is_ok())
+        .filter(|path| {
+            fs::metadata(path).is_ok_and(|metadata| {
+                metadata.is_file() && metadata.modified().is_ok_and(|modified| modified >= stored)
+            })
+        })
+        .collect()
+}
+
+impl fmt::Display for DepsOfReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for dependent in &self.dependents {
+            writeln!(f, "{dependent}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::DepsOfReport;
+    use crate::{
+        service::{AnalyzeService, AnalyzeServiceOptions},
+        utils::TestDir,
+        workspace::test_workspace,
+    };
+
+    #[test]
+    fn test_deps_of() {
+        let root = TestDir::new("deps-of");
+        for (file, contents) in [
+            ("main.ts", "import './a'; import './b';"),
+            ("a.ts", "import './c';"),
+            ("b.ts", ""),
+            ("c.ts", ""),
+        ] {
+            root.write(file, contents);
+        }
+        let (cache_dir, c) = (Path::new(".cache"), Path::new("c.ts"));
+        assert!(DepsOfReport::from_cache(&root, cache_dir, c).is_err());
+
+        let options = AnalyzeServiceOptions::new(&*root, vec![root.join("main.ts").into()])
+            .with_cross_module(true)
+            .with_cache_dir(Some(root.join(cache_dir)))
+            .with_incremental(true);
+        AnalyzeService::new(options).analyze();
+
+        let report = DepsOfReport::from_cache(&root, cache_dir, c).unwrap();
+        assert_eq!(report.path, "c.ts");
+        assert_eq!(report.dependents, ["a.ts", "main.ts"]);
+        assert!(report.stale.is_empty());
+        assert!(DepsOfReport::from_cache(&root, cache_dir, Path::new("d.ts")).is_err());
+
+        std::fs::write(root.join("b.ts"), "import './c';").unwrap();
+        std::fs::write(root.join("d.ts"), "import './c';").unwrap();
+        let report = DepsOfReport::from_cache(&root, cache_dir, c).unwrap();
+        assert_eq!(report.stale, ["b.ts", "d.ts"]);
+    }
+
+    #[test]
+    fn test_deps_of_workspace() {
+        let (root, _) = test_workspace(
+            "deps-of-workspace",
+            &[
+                (
+                    "packages/a",
+                    r#"{"name":"a","dependencies":{"b":"workspace:*"}}"#,
+                ),
+                ("packages/b", r#"{"name":"b","main":"dist/index.js"}"#),
+            ],
+        );
+        root.write("packages/a/src/index.ts", "import 'b';");
+        root.write("packages/b/src/index.ts", "");
+
+        let cache_dir = Path::new(".cache");
+        let entries = vec![root.join("packages/a/src/index.ts").into()];
+        let options = AnalyzeServiceOptions::new(&*root, entries)
+            .with_cross_module(true)
+            .with_cache_dir(Some(root.join(cache_dir)))
+            .with_incremental(true);
+        AnalyzeService::new(options).analyze();
+
+        let b = Path::new("packages/b/src/index.ts");
+        let report = DepsOfReport::from_cache(&root, cache_dir, b).unwrap();
+        assert_eq!(report.dependents, ["packages/a/src/index.ts"]);
+    }
+}
@@ -0,0 +1,176 @@
+//! Diagnostics reported while analyzing, e.g. unresolved imports and syntax errors, as they
+//! flow from the analysis through the collector to every sink.
+
+use std::{fmt, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{rules::Severity, utils::line_column};
+
+/// What a diagnostic is about, named like a rule for reports mixing both
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiagnosticCode {
+    /// The file could not be read
+    UnreadableFile,
+    /// The file was skipped for its size
+    FileTooLarge,
+    SyntaxError,
+    /// A dynamic import or `require` whose specifier is not a string literal
+    UnfollowedImport,
+    UnresolvedImport,
+    /// An import of a subpath of a package denied by `--deny-deep-imports`
+    DeepImport,
+    /// A named import the imported module does not export
+    MissingExport,
+    /// A name star-exported from several modules defining it differently
+    AmbiguousExport,
+}
+
+impl DiagnosticCode {
+    /// The name of the code, as serialized
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::UnreadableFile => "unreadable-file",
+            Self::FileTooLarge => "file-too-large",
+            Self::SyntaxError => "syntax-error",
+            Self::UnfollowedImport => "unfollowed-import",
+            Self::UnresolvedImport => "unresolved-import",
+            Self::DeepImport => "deep-import",
+            Self::MissingExport => "missing-export",
+            Self::AmbiguousExport => "ambiguous-export",
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Where a diagnostic was found in its file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DiagnosticSpan {
+    /// Byte offsets of the range, in the whole file
+    pub start: u32,
+    pub end: u32,
+    /// Line and column of `start`, from 1
+    pub line: usize,
+    pub column: usize,
+}
+
+impl DiagnosticSpan {
+    /// The range from `start` to `end` in `file_text`
+    pub fn new(file_text: &str, start: u32, end: u32) -> Self {
+        let (line, column) = line_column(file_text, start as usize);
+        Self {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// File the diagnostic is reported for, relative to the current working directory
+    pub path: PathBuf,
+    /// `None` for diagnostics about the whole file
+    pub span: Option<DiagnosticSpan>,
+    pub severity: Severity,
+    pub code: DiagnosticCode,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// An error about the whole file `path`
+    pub fn new(path: impl Into<PathBuf>, code: DiagnosticCode, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            span: None,
+            severity: Severity::Error,
+            code,
+            message: message.into(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_span(mut self, span: DiagnosticSpan) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Line of the diagnostic, 1 for diagnostics about the whole file
+    pub fn line(&self) -> usize {
+        self.span.map_or(1, |span| span.line)
+    }
+}
+
+/// `path: line:column: message`, without the position for diagnostics about the whole file
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.path.display())?;
+        if let Some(span) = self.span {
+            write!(f, "{}:{}: ", span.line, span.column)?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Diagnostic, DiagnosticCode, DiagnosticSpan};
+    use crate::rules::Severity;
+
+    #[test]
+    fn test_diagnostic() {
+        let file_text = "import a from './a';\nrequire(name);\n";
+        let diagnostic = Diagnostic::new(
+            "src/main.ts",
+            DiagnosticCode::UnfollowedImport,
+            "require(name) cannot be followed",
+        )
+        .with_span(DiagnosticSpan::new(file_text, 29, 33))
+        .with_severity(Severity::Warn);
+        assert_eq!(
+            diagnostic.to_string(),
+            "src/main.ts: 2:9: require(name) cannot be followed"
+        );
+        assert_eq!(diagnostic.line(), 2);
+
+        let json = serde_json::to_value(&diagnostic).unwrap();
+        assert_eq!(json["code"], "unfollowed-import");
+        assert_eq!(json["severity"], "warn");
+        assert_eq!(json["span"]["start"], 29);
+
+        let diagnostic = Diagnostic::new("a.ts", DiagnosticCode::UnresolvedImport, "Unable");
+        assert_eq!(diagnostic.to_string(), "a.ts: Unable");
+        assert_eq!(diagnostic.line(), 1);
+    }
+
+    #[test]
+    fn test_code_names() {
+        for code in [
+            DiagnosticCode::UnreadableFile,
+            DiagnosticCode::FileTooLarge,
+            DiagnosticCode::SyntaxError,
+            DiagnosticCode::UnfollowedImport,
+            DiagnosticCode::UnresolvedImport,
+            DiagnosticCode::DeepImport,
+            DiagnosticCode::MissingExport,
+            DiagnosticCode::AmbiguousExport,
+        ] {
+            assert_eq!(serde_json::to_value(code).unwrap(), code.as_str());
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Resolution tracing for `--explain-resolution`.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Every step taken to resolve one specifier from one importer.
+#[derive(Debug)]
+pub struct ResolutionExplanation {
+    pub directory: PathBuf,
+    pub specifier: String,
+    pub tsconfig: Option<PathBuf>,
+    pub extensions: Vec<String>,
+    pub condition_names: Vec<String>,
+    pub main_fields: Vec<String>,
+    /// Files and directories the resolver found, sorted
+    pub found: Vec<PathBuf>,
+    /// Files and directories the resolver tried but did not find, sorted
+    pub missing: Vec<PathBuf>,
+    /// Rewrites applied by marsh after the resolver returned, in order
+    pub rewrites: Vec<String>,
+    pub result: Result<PathBuf, String>,
+}
+
+impl ResolutionExplanation {
+    pub fn new(directory: &Path, specifier: &str) -> Self {
+        Self {
+            directory: directory.to_path_buf(),
+            specifier: specifier.to_string(),
+            tsconfig: None,
+            extensions: Vec::new(),
+            condition_names: Vec::new(),
+            main_fields: Vec::new(),
+            found: Vec::new(),
+            missing: Vec::new(),
+            rewrites: Vec::new(),
+            result: Err(String::new()),
+        }
+    }
+}
+
+impl fmt::Display for ResolutionExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Resolving \"{}\" from {}",
+            self.specifier,
+            self.directory.display()
+        )?;
+        match &self.tsconfig {
+            Some(tsconfig) => writeln!(f, "  tsconfig: {}", tsconfig.display())?,
+            None => writeln!(f, "  tsconfig: none")?,
+        }
+        writeln!(f, "  extensions: {}", self.extensions.join(", "))?;
+        writeln!(f, "  conditions: {}", self.condition_names.join(", "))?;
+        writeln!(f, "  main fields: {}", self.main_fields.join(", "))?;
+        for path in &self.found {
+            writeln!(f, "  found:   {}", path.display())?;
+        }
+        for path in &self.missing {
+            writeln!(f, "  missing: {}", path.display())?;
+        }
+        for rewrite in &self.rewrites {
+            writeln!(f, "  {rewrite}")?;
+        }
+        match &self.result {
+            Ok(path) => write!(f, "  resolved: {}", path.display()),
+            Err(error) => write!(f, "  unresolved: {error}"),
+        }
+    }
+}
@@ -0,0 +1,461 @@
+//! Checking imported names against the exports of the module they are imported from.
+
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use dashmap::DashMap;
+use oxc_semantic::ModuleRecord;
+use oxc_syntax::module_record::{
+    ExportEntry, ExportExportName, ExportImportName, ImportImportName,
+};
+use rustc_hash::FxHashMap;
+
+use crate::{loader::LINT_PARTIAL_LOADER_EXT, scanner::TypeExports};
+
+/// `export * from` chains longer than this are considered cyclic
+const MAX_STAR_EXPORT_DEPTH: usize = 32;
+
+/// The name `entry` is exported as, `default` included
+pub fn export_name(entry: &ExportEntry) -> Option<&str> {
+    match &entry.export_name {
+        ExportExportName::Name(name) => Some(name.name().as_str()),
+        ExportExportName::Default(_) => Some("default"),
+        ExportExportName::Null => None,
+    }
+}
+
+/// Returns `true` when `record` exports `name`, directly or through `export * from`.
+///
+/// CommonJS modules export the properties assigned to `exports` and `module.exports`.
+/// Modules whose exports cannot be known statically, such as scripts without any exports,
+/// `export =` or `module.exports =` assignments or star exports of modules which were not
+/// analyzed, are assumed to export every name.
+pub fn has_export(
+    record: &ModuleRecord,
+    name: &str,
+    type_exports: &DashMap<Arc<Path>, TypeExports>,
+) -> bool {
+    has_export_at_depth(record, name, type_exports, 0)
+}
+
+fn has_export_at_depth(
+    record: &ModuleRecord,
+    name: &str,
+    type_exports: &DashMap<Arc<Path>, TypeExports>,
+    depth: usize,
+) -> bool {
+    if depth > MAX_STAR_EXPORT_DEPTH {
+        return true;
+    }
+    let (commonjs, exported) = type_exports
+        .get(record.resolved_absolute_path.as_path())
+        .map_or((false, false), |exports| {
+            let exported = exports.export_assignment
+                || exports.names.contains(name)
+                || exports.reexports.contains_key(name);
+            (exports.commonjs, exported)
+        });
+    if exported {
+        return true;
+    }
+    // Components compiled from `.vue`, `.svelte` and `.astro` files are their default export
+    if name == "default"
+        && record
+            .resolved_absolute_path
+            .extension()
+            .and_then(OsStr::to_str)
+            .is_some_and(|ext| LINT_PARTIAL_LOADER_EXT.contains(&ext))
+    {
+        return true;
+    }
+    if record.not_esm {
+        // `module.exports` itself is the default export of a CommonJS module
+        return !commonjs || name == "default";
+    }
+    if record
+        .local_export_entries
+        .iter()
+        .chain(&record.indirect_export_entries)
+        .any(|entry| export_name(entry) == Some(name))
+    {
+        return true;
+    }
+
+    // `export * from "mod"` never re-exports `default`
+    name != "default"
+        && record.star_export_entries.iter().any(|entry| {
+            let Some(target) = entry
+                .module_request
+                .as_ref()
+                .and_then(|request| record.loaded_modules.get(request.name()))
+            else {
+                return true;
+            };
+            has_export_at_depth(target.value(), name, type_exports, depth + 1)
+        })
+}
+
+/// One module of a re-export chain
+#[derive(Debug)]
+pub struct TraceStep {
+    pub record: Arc<ModuleRecord>,
+    /// The name of the binding in this module, `None` for the module namespace object
+    pub name: Option<String>,
+    /// The statement the binding is reached through, `None` where it is defined
+    pub via: Option<String>,
+}
+
+/// A binding followed through imports and re-exports
+#[derive(Debug, Default)]
+pub struct SymbolTrace {
+    pub steps: Vec<TraceStep>,
+    /// Whether the chain ends where the binding is defined
+    pub found: bool,
+}
+
+/// Follows `name` from `record` through re-exports to the module defining it.
+///
+/// When `record` imports `name` rather than exporting it, the import is followed too.
+pub fn trace_export(
+    record: &Arc<ModuleRecord>,
+    name: &str,
+    type_exports: &DashMap<Arc<Path>, TypeExports>,
+) -> SymbolTrace {
+    let mut trace = SymbolTrace::default();
+    trace.found = trace_step(record, name, type_exports, &mut trace.steps, 0);
+    trace
+}
+
+fn trace_step(
+    record: &Arc<ModuleRecord>,
+    name: &str,
+    type_exports: &DashMap<Arc<Path>, TypeExports>,
+    steps: &mut Vec<TraceStep>,
+    depth: usize,
+) -> bool {
+    if depth > MAX_STAR_EXPORT_DEPTH {
+        return false;
+    }
+    let step = |via: Option<String>| TraceStep {
+        record: Arc::clone(record),
+        name: Some(name.to_string()),
+        via,
+    };
+    let target = |specifier: &str| {
+        record
+            .loaded_modules
+            .get(specifier)
+            .map(|target| Arc::clone(target.value()))
+    };
+
+    let declared_type = type_exports
+        .get(record.resolved_absolute_path.as_path())
+        .is_some_and(|exports| exports.names.contains(name));
+    if declared_type
+        || record
+            .local_export_entries
+            .iter()
+            .any(|entry| export_name(entry) == Some(name))
+    {
+        steps.push(step(None));
+        return true;
+    }
+
+    // `export { imported as name } from "specifier"`
+    let reexport = record
+        .indirect_export_entries
+        .iter()
+        .find(|entry| export_name(entry) == Some(name))
+        .and_then(|entry| {
+            let specifier = entry.module_request.as_ref()?.name().to_string();
+            let imported = match &entry.import_name {
+                ExportImportName::Name(imported) => Some(imported.name().to_string()),
+                _ => None,
+            };
+            Some((specifier, imported))
+        })
+        .or_else(|| {
+            let exports = type_exports.get(record.resolved_absolute_path.as_path())?;
+            let (specifier, imported) = exports.reexports.get(name)?;
+            Some((specifier.clone(), Some(imported.clone())))
+        });
+    if let Some((specifier, imported)) = reexport {
+        let via = match &imported {
+            Some(imported) if imported == name => format!("export {{ {name} }} from '{specifier}'"),
+            Some(imported) => format!("export {{ {imported} as {name} }} from '{specifier}'"),
+            None => format!("export * as {name} from '{specifier}'"),
+        };
+        steps.push(step(Some(via)));
+        let Some(target) = target(&specifier) else {
+            return false;
+        };
+        return match imported {
+            Some(imported) => trace_step(&target, &imported, type_exports, steps, depth + 1),
+            // A namespace re-export is defined by the module itself
+            None => {
+                steps.push(TraceStep {
+                    record: target,
+                    name: None,
+                    via: None,
+                });
+                true
+            }
+        };
+    }
+
+    // `export * from "specifier"`, which never re-exports `default`
+    if name != "default" {
+        for entry in &record.star_export_entries {
+            let Some(request) = &entry.module_request else {
+                continue;
+            };
+            let Some(target) = target(request.name()) else {
+                continue;
+            };
+            if has_export(&target, name, type_exports) {
+                steps.push(step(Some(format!("export * from '{}'", request.name()))));
+                return trace_step(&target, name, type_exports, steps, depth + 1);
+            }
+        }
+    }
+
+    // Only the module the trace starts from is looked up for imports,
+    // re-exported imports are indirect exports already
+    if depth > 0 {
+        return false;
+    }
+    let Some(import) = record
+        .import_entries
+        .iter()
+        .find(|entry| entry.local_name.name() == name)
+    else {
+        return false;
+    };
+    let specifier = import.module_request.name();
+    let (via, imported) = match &import.import_name {
+        ImportImportName::Name(imported) if imported.name() == name => (
+            format!("import {{ {name} }} from '{specifier}'"),
+            Some(name),
+        ),
+        ImportImportName::Name(imported) => (
+            format!(
+                "import {{ {} as {name} }} from '{specifier}'",
+                imported.name()
+            ),
+            Some(imported.name().as_str()),
+        ),
+        ImportImportName::Default(_) => {
+            (format!("import {name} from '{specifier}'"), Some("default"))
+        }
+        ImportImportName::NamespaceObject => {
+            (format!("import * as {name} from '{specifier}'"), None)
+        }
+    };
+    steps.push(step(Some(via)));
+    let Some(target) = target(specifier) else {
+        return false;
+    };
+    match imported {
+        Some(imported) => trace_step(&target, imported, type_exports, steps, depth + 1),
+        None => {
+            steps.push(TraceStep {
+                record: target,
+                name: None,
+                via: None,
+            });
+            true
+        }
+    }
+}
+
+/// A name re-exported by several `export * from` declarations of one module which resolve to
+/// different bindings. ECMAScript leaves such a name out of the module's exports, while
+/// bundlers and TypeScript may pick one of them.
+#[derive(Debug)]
+pub struct StarExportConflict {
+    pub name: String,
+    /// Specifiers of the conflicting `export * from` declarations, sorted
+    pub specifiers: Vec<String>,
+}
+
+/// Finds names `record` re-exports ambiguously through `export * from`.
+///
+/// Names `record` exports explicitly shadow star exports and are not ambiguous,
+/// neither are names which every star export resolves to the same binding.
+pub fn star_export_conflicts(
+    record: &ModuleRecord,
+    type_exports: &DashMap<Arc<Path>, TypeExports>,
+) -> Vec<StarExportConflict> {
+    let explicit_types = type_exports.get(record.resolved_absolute_path.as_path());
+    let is_explicit = |name: &str| {
+        explicit_types.as_ref().is_some_and(|exports| {
+            exports.names.contains(name) || exports.reexports.contains_key(name)
+        }) || record
+            .local_export_entries
+            .iter()
+            .chain(&record.indirect_export_entries)
+            .any(|entry| export_name(entry) == Some(name))
+    };
+
+    // Name to the star exports providing it, with the binding each of them resolves to
+    let mut candidates =
+        FxHashMap::<&str, Vec<(&str, Option<(PathBuf, Option<String>)>)>>::default();
+    let star_exports = record
+        .star_export_entries
+        .iter()
+        .filter_map(|entry| {
+            let specifier = entry.module_request.as_ref()?.name().as_str();
+            let target = record.loaded_modules.get(specifier)?;
+            let names = record
+                .exported_bindings_from_star_export
+                .get(&target.resolved_absolute_path)?;
+            Some((specifier, Arc::clone(target.value()), names))
+        })
+        .collect::<Vec<_>>();
+    for (specifier, target, names) in &star_exports {
+        for name in names.value() {
+            let name = name.as_str();
+            if name == "default" || is_explicit(name) {
+                continue;
+            }
+            let providers = candidates.entry(name).or_default();
+            if providers.iter().any(|(provider, _)| provider == specifier) {
+                continue;
+            }
+            let trace = trace_export(target, name, type_exports);
+            let definition = trace
+                .found
+                .then(|| trace.steps.last())
+                .flatten()
+                .map(|step| {
+                    (
+                        step.record.resolved_absolute_path.clone(),
+                        step.name.clone(),
+                    )
+                });
+            providers.push((specifier, definition));
+        }
+    }
+
+    let mut conflicts = candidates
+        .into_iter()
+        .filter(|(_, providers)| {
+            let first = &providers[0].1;
+            // Bindings which cannot be traced are assumed to be distinct
+            providers.len() > 1
+                && (first.is_none() || providers.iter().any(|(_, definition)| definition != first))
+        })
+        .map(|(name, providers)| {
+            let mut specifiers = providers
+                .into_iter()
+                .map(|(specifier, _)| specifier.to_string())
+                .collect::<Vec<_>>();
+            specifiers.sort_unstable();
+            StarExportConflict {
+                name: name.to_string(),
+                specifiers,
+            }
+        })
+        .collect::<Vec<_>>();
+    conflicts.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    conflicts
+}
+
+#[cfg(test)]
+mod test {
+    use std::{path::Path, sync::Arc};
+
+    use dashmap::DashMap;
+    use oxc_allocator::Allocator;
+    use oxc_parser::Parser;
+    use oxc_semantic::{ModuleRecord, SemanticBuilder};
+    use oxc_span::SourceType;
+
+    use super::{has_export, star_export_conflicts, trace_export};
+
+    fn module_record(path: &str, source_text: &str) -> Arc<ModuleRecord> {
+        let allocator = Allocator::default();
+        let source_type = SourceType::from_path(path).unwrap();
+        let ret = Parser::new(&allocator, source_text, source_type).parse();
+        let program = allocator.alloc(ret.program);
+        SemanticBuilder::new(source_text)
+            .build_module_record(Path::new(path), program)
+            .module_record()
+    }
+
+    #[test]
+    fn test_trace_export() {
+        let app = module_record("app.ts", "import { Button as B } from './ui';");
+        let ui = module_record("ui.ts", "export * from './button';");
+        let button = module_record("button.ts", "export const Button = 1;");
+        app.loaded_modules.insert("./ui".into(), Arc::clone(&ui));
+        ui.loaded_modules
+            .insert("./button".into(), Arc::clone(&button));
+        let type_exports = DashMap::default();
+
+        assert!(has_export(&ui, "Button", &type_exports));
+        assert!(!has_export(&ui, "Missing", &type_exports));
+        assert!(!has_export(&ui, "default", &type_exports));
+
+        let trace = trace_export(&app, "B", &type_exports);
+        assert!(trace.found);
+        let steps = trace
+            .steps
+            .iter()
+            .map(|step| {
+                (
+                    step.record.resolved_absolute_path.to_str().unwrap(),
+                    step.name.as_deref(),
+                    step.via.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            steps,
+            [
+                (
+                    "app.ts",
+                    Some("B"),
+                    Some("import { Button as B } from './ui'")
+                ),
+                ("ui.ts", Some("Button"), Some("export * from './button'")),
+                ("button.ts", Some("Button"), None),
+            ]
+        );
+
+        assert!(!trace_export(&ui, "Missing", &type_exports).found);
+    }
+
+    #[test]
+    fn test_star_export_conflicts() {
+        let index = module_record(
+            "index.ts",
+            "export * from './a'; export * from './b'; export * from './c'; export const own = 1;",
+        );
+        let a = module_record("a.ts", "export const x = 1, y = 1, own = 1;");
+        let b = module_record("b.ts", "export const x = 2;");
+        let c = module_record("c.ts", "export { y } from './a';");
+        c.loaded_modules.insert("./a".into(), Arc::clone(&a));
+        for (specifier, target, names) in [
+            ("./a", &a, ["x", "y", "own"].as_slice()),
+            ("./b", &b, ["x"].as_slice()),
+            ("./c", &c, ["y"].as_slice()),
+        ] {
+            index
+                .loaded_modules
+                .insert(specifier.into(), Arc::clone(target));
+            index.exported_bindings_from_star_export.insert(
+                target.resolved_absolute_path.clone(),
+                names.iter().map(|&name| name.into()).collect(),
+            );
+        }
+
+        let conflicts = star_export_conflicts(&index, &DashMap::default());
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].name, "x");
+        assert_eq!(conflicts[0].specifiers, ["./a", "./b"]);
+    }
+}
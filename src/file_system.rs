@@ -0,0 +1,192 @@
+//! Where the analysis reads files from: the disk by default, or a virtual file system, e.g.
+//! files held in memory by browser-based tooling, where there is no disk to read.
+//!
+//! The resolver reads through the same file system, so a virtual one is all an analysis sees.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+pub use oxc_resolver::{FileMetadata, FileSystem};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::utils::{normalize_path, read_to_string};
+
+/// Files on disk
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OsFileSystem;
+
+impl FileSystem for OsFileSystem {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        read_to_string(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        fs::metadata(path).map(FileMetadata::from)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        fs::symlink_metadata(path).map(FileMetadata::from)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::canonicalize(path)
+    }
+}
+
+/// Files held in memory, keyed by their absolute path. Directories are those of the files.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryFileSystem {
+    files: FxHashMap<PathBuf, String>,
+    dirs: FxHashSet<PathBuf>,
+}
+
+impl MemoryFileSystem {
+    /// Adds or replaces the file at `path`
+    pub fn add(&mut self, path: impl AsRef<Path>, contents: impl Into<String>) {
+        let path = normalize_path(path.as_ref());
+        self.dirs
+            .extend(path.ancestors().skip(1).map(Path::to_path_buf));
+        self.files.insert(path, contents.into());
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_file(mut self, path: impl AsRef<Path>, contents: impl Into<String>) -> Self {
+        self.add(path, contents);
+        self
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.files
+            .get(&normalize_path(path))
+            .cloned()
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        let path = normalize_path(path);
+        if self.files.contains_key(&path) {
+            Ok(FileMetadata::new(true, false, false))
+        } else if self.dirs.contains(&path) {
+            Ok(FileMetadata::new(false, true, false))
+        } else {
+            Err(not_found(&path))
+        }
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        self.metadata(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        let path = normalize_path(path);
+        if self.files.contains_key(&path) || self.dirs.contains(&path) {
+            Ok(path)
+        } else {
+            Err(not_found(&path))
+        }
+    }
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} does not exist", path.display()),
+    )
+}
+
+/// A file system shared by the analysis and its resolver
+#[derive(Clone)]
+pub(crate) struct SharedFileSystem(pub Arc<dyn FileSystem>);
+
+impl FileSystem for SharedFileSystem {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.0.read_to_string(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        self.0.metadata(path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        self.0.symlink_metadata(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        self.0.canonicalize(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MemoryFileSystem;
+    use crate::service::{AnalyzeService, AnalyzeServiceOptions};
+
+    #[test]
+    fn test_memory_file_system() {
+        // Nothing of the project exists on disk
+        let root = std::path::Path::new("/marsh-memory-file-system");
+        let file_system = MemoryFileSystem::default()
+            .with_file(
+                root.join("src/main.ts"),
+                "import { a } from './a'; import './dir'; import pkg from 'pkg'; import './b';",
+            )
+            .with_file(root.join("src/a.ts"), "export const a = 1;")
+            .with_file(root.join("src/dir/index.ts"), "")
+            .with_file(
+                root.join("node_modules/pkg/package.json"),
+                r#"{ "name": "pkg", "main": "lib.js" }"#,
+            )
+            .with_file(root.join("node_modules/pkg/lib.js"), "module.exports = 1;");
+
+        let options = AnalyzeServiceOptions::new(root, vec![root.join("src/main.ts").into()])
+            .with_cross_module(true)
+            .with_file_system(file_system);
+        let graph = AnalyzeService::new(options).analyze();
+        let mut edges = graph
+            .edges
+            .iter()
+            .map(|edge| (edge.from.as_str(), edge.to.as_str()))
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+        assert_eq!(
+            edges,
+            [
+                ("src/main.ts", "node_modules/pkg/lib.js"),
+                ("src/main.ts", "src/a.ts"),
+                ("src/main.ts", "src/b"),
+                ("src/main.ts", "src/dir/index.ts"),
+            ]
+        );
+        let a = graph
+            .edges
+            .iter()
+            .find(|edge| edge.to == "src/a.ts")
+            .unwrap();
+        assert_eq!(a.symbols, ["a"]);
+        // `./b` is unresolved
+        assert_eq!(graph.diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn test_memory_file_system_max_file_size() {
+        let root = std::path::Path::new("/marsh-memory-file-system-size");
+        let file_system = MemoryFileSystem::default()
+            .with_file(root.join("main.ts"), "import './bundle';")
+            .with_file(root.join("bundle.js"), "x".repeat(64));
+        let options = AnalyzeServiceOptions::new(root, vec![root.join("main.ts").into()])
+            .with_cross_module(true)
+            .with_max_file_size(Some(32))
+            .with_file_system(file_system);
+        let graph = AnalyzeService::new(options).analyze();
+        // The bundle is measured once read, then ignored with a warning
+        assert!(graph.edges.is_empty());
+        assert_eq!(graph.diagnostics.len(), 1);
+        assert_eq!(graph.diagnostics[0].path, std::path::Path::new("bundle.js"));
+    }
+}
@@ -1,43 +1,373 @@
-use std::collections::HashMap;
+use std::{
+    collections::{hash_map::Entry, HashMap, VecDeque},
+    fmt,
+};
 
-use super::edge::EdgeType;
+use super::{EdgeType, GraphIndex, NodeKind};
+use crate::{message::Message, utils::PathAtom};
 use petgraph::{
     dot::{Config, Dot},
     graph::NodeIndex,
+    visit::{Dfs, EdgeRef, NodeRef, Reversed},
     Graph,
 };
+use serde_json::{json, Value};
+
+#[derive(Clone)]
+struct Node {
+    path: PathAtom,
+    kind: NodeKind,
+}
+
+impl fmt::Debug for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.path.as_str(), f)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Edge {
+    kind: EdgeType,
+    /// Names of the bindings imported through the edge
+    symbols: Vec<String>,
+}
 
-pub struct GraphBuilder<'a> {
-    graph: Graph<&'a str, &'a str>,
-    nodes: HashMap<&'a str, NodeIndex>,
+/// The graph of the dependencies found, owning its nodes and edges so it outlives the run
+/// it was built from, e.g. to add the dependencies of later runs to it
+#[derive(Clone)]
+pub struct GraphBuilder {
+    graph: Graph<Node, Edge>,
+    nodes: HashMap<PathAtom, NodeIndex>,
+    /// Workspace package owning each node, see [`Self::attribute_packages`]
+    packages: HashMap<NodeIndex, String>,
 }
 
-impl<'a> GraphBuilder<'a> {
+impl Default for GraphBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GraphBuilder {
     pub fn new() -> Self {
-        let graph = Graph::<&str, &str>::new();
+        let graph = Graph::<Node, Edge>::new();
         let nodes = HashMap::new();
 
-        Self { graph, nodes }
+        Self {
+            graph,
+            nodes,
+            packages: HashMap::new(),
+        }
     }
 
-    pub fn add_deps(&mut self, deps: &'a Vec<(String, String)>) {
+    /// The graph of `deps`, whose nodes were numbered by `index` as they arrived.
+    /// Only the edges are left to add, without looking their nodes up.
+    pub fn from_index(index: &GraphIndex, deps: &[(PathAtom, Message)]) -> Self {
+        debug_assert_eq!(index.edge_count(), deps.len());
+        let mut graph = Graph::with_capacity(index.nodes().len(), deps.len());
+        let mut nodes = HashMap::with_capacity(index.nodes().len());
+        for (path, kind) in index.nodes() {
+            let node = Node {
+                path: path.clone(),
+                kind: kind.unwrap_or_default(),
+            };
+            nodes.insert(path.clone(), graph.add_node(node));
+        }
+        for (&(from, to), (_, dst)) in index.edges().iter().zip(deps) {
+            let edge = Edge {
+                kind: dst.edge,
+                symbols: dst.symbols.clone(),
+            };
+            graph.add_edge(NodeIndex::new(from), NodeIndex::new(to), edge);
+        }
+        Self {
+            graph,
+            nodes,
+            packages: HashMap::new(),
+        }
+    }
+
+    /// Adds the dependencies `deps`, along with their nodes when missing
+    pub fn add_deps(&mut self, deps: &[(PathAtom, Message)]) {
         for (src, dst) in deps {
-            let src = *self
-                .nodes
-                .entry(&src)
-                .or_insert_with(|| self.graph.add_node(&src));
-            let dst = *self
-                .nodes
-                .entry(&dst)
-                .or_insert_with(|| self.graph.add_node(&dst));
-            self.graph.add_edge(src, dst, "");
+            let edge = Edge {
+                kind: dst.edge,
+                symbols: dst.symbols.clone(),
+            };
+            let src = self.add_node(src, None);
+            let dst = self.add_node(&dst.file_path, Some(dst.kind));
+            self.graph.add_edge(src, dst, edge);
+        }
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.graph.edge_count()
+    }
+
+    /// Importers are modules unless an edge into them tells their `kind`,
+    /// e.g. stylesheets composing other stylesheets
+    fn add_node(&mut self, path: &PathAtom, kind: Option<NodeKind>) -> NodeIndex {
+        let index = *self.nodes.entry(path.clone()).or_insert_with(|| {
+            self.graph.add_node(Node {
+                path: path.clone(),
+                kind: kind.unwrap_or_default(),
+            })
+        });
+        if let Some(kind) = kind {
+            self.graph[index].kind = kind;
+        }
+        index
+    }
+
+    /// Attributes the file nodes to the package `package_of` their path, listed as the
+    /// `package` of the nodes printed by [`Self::json`]
+    pub fn attribute_packages(&mut self, package_of: impl Fn(&str) -> Option<String>) {
+        self.packages = self
+            .graph
+            .node_indices()
+            .filter(|&index| {
+                !matches!(
+                    self.graph[index].kind,
+                    NodeKind::Package | NodeKind::Unresolved
+                )
+            })
+            .filter_map(|index| Some((index, package_of(&self.graph[index].path)?)))
+            .collect();
+    }
+
+    /// Number of nodes reachable from `path`, excluding itself
+    pub fn dependency_count(&self, path: &str) -> usize {
+        let Some(&start) = self.nodes.get(path) else {
+            return 0;
+        };
+        let mut dfs = Dfs::new(&self.graph, start);
+        let mut count = 0;
+        while dfs.next(&self.graph).is_some() {
+            count += 1;
+        }
+        count - 1
+    }
+
+    /// Paths of the nodes reachable from `path`, itself included
+    pub fn subtree(&self, path: &str) -> Vec<&str> {
+        let Some(&start) = self.nodes.get(path) else {
+            return Vec::new();
+        };
+        let mut dfs = Dfs::new(&self.graph, start);
+        let mut paths = Vec::new();
+        while let Some(node) = dfs.next(&self.graph) {
+            paths.push(self.graph[node].path.as_str());
+        }
+        paths
+    }
+
+    /// Edges between the nodes reachable from `path` as `(from, to)`, without duplicates
+    pub fn reachable_edges(&self, path: &str) -> Vec<(&str, &str)> {
+        let Some(&start) = self.nodes.get(path) else {
+            return Vec::new();
+        };
+        let mut dfs = Dfs::new(&self.graph, start);
+        let mut edges = Vec::new();
+        while let Some(node) = dfs.next(&self.graph) {
+            let mut targets = self
+                .graph
+                .edges(node)
+                .map(|edge| self.graph[edge.target()].path.as_str())
+                .collect::<Vec<_>>();
+            targets.sort_unstable();
+            targets.dedup();
+            let from = self.graph[node].path.as_str();
+            edges.extend(targets.into_iter().map(|to| (from, to)));
         }
+        edges
+    }
+
+    /// Paths of the nodes which `path` is reachable from, excluding itself, sorted
+    pub fn dependents(&self, path: &str) -> Vec<&str> {
+        let Some(&start) = self.nodes.get(path) else {
+            return Vec::new();
+        };
+        let reversed = Reversed(&self.graph);
+        let mut dfs = Dfs::new(reversed, start);
+        let mut paths = Vec::new();
+        while let Some(node) = dfs.next(reversed) {
+            if node != start {
+                paths.push(self.graph[node].path.as_str());
+            }
+        }
+        paths.sort_unstable();
+        paths
+    }
+
+    /// Paths of the nodes which one of `paths` is reachable from, those of `paths` in the graph
+    /// included, sorted
+    pub fn dependents_of<'p>(&self, paths: impl IntoIterator<Item = &'p str>) -> Vec<&str> {
+        let reversed = Reversed(&self.graph);
+        let mut dfs = Dfs::empty(reversed);
+        let mut found = Vec::new();
+        for path in paths {
+            let Some(&start) = self.nodes.get(path) else {
+                continue;
+            };
+            // Nodes found from a previous path are not visited again
+            dfs.move_to(start);
+            while let Some(node) = dfs.next(reversed) {
+                found.push(self.graph[node].path.as_str());
+            }
+        }
+        found.sort_unstable();
+        found
+    }
+
+    /// Paths of the nodes along the shortest chain of edges from one of `entries` to `path`,
+    /// both included, `None` when `path` is not reachable from them
+    pub fn import_chain(&self, entries: &[String], path: &str) -> Option<Vec<&str>> {
+        let target = *self.nodes.get(path)?;
+        // Node each node was first reached from, entries are reached from themselves
+        let mut reached_from = HashMap::new();
+        let mut queue = VecDeque::new();
+        for entry in entries {
+            if let Some(&start) = self.nodes.get(entry.as_str()) {
+                if reached_from.insert(start, start).is_none() {
+                    queue.push_back(start);
+                }
+            }
+        }
+        while let Some(node) = queue.pop_front() {
+            if node == target {
+                let mut chain = vec![self.graph[node].path.as_str()];
+                let mut node = node;
+                while reached_from[&node] != node {
+                    node = reached_from[&node];
+                    chain.push(self.graph[node].path.as_str());
+                }
+                chain.reverse();
+                return Some(chain);
+            }
+            for next in self.graph.neighbors(node) {
+                if let Entry::Vacant(entry) = reached_from.entry(next) {
+                    entry.insert(node);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// The dependencies of `path`, directly or not, as a tree where each node is under the
+    /// node it is first reached from, `None` when `path` is not in the graph
+    pub fn dependency_tree(&self, path: &str) -> Option<Value> {
+        let start = *self.nodes.get(path)?;
+        let mut reached_from = HashMap::from([(start, start)]);
+        let mut order = vec![start];
+        let mut queue = VecDeque::from([start]);
+        while let Some(node) = queue.pop_front() {
+            for next in self.graph.neighbors(node) {
+                if let Entry::Vacant(entry) = reached_from.entry(next) {
+                    entry.insert(node);
+                    order.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        // Built from the deepest nodes up, so children are done before their parent
+        let mut children = HashMap::<NodeIndex, Vec<Value>>::new();
+        for &node in order.iter().rev() {
+            let mut node_children = children.remove(&node).unwrap_or_default();
+            node_children.reverse();
+            let tree = json!({
+                "path": self.graph[node].path.as_str(),
+                "kind": self.graph[node].kind,
+                "children": node_children,
+            });
+            if node == start {
+                return Some(tree);
+            }
+            children.entry(reached_from[&node]).or_default().push(tree);
+        }
+        None
+    }
+
+    /// The graph as printed by [`Self::json`]
+    pub fn to_json(&self) -> Value {
+        let nodes = self
+            .graph
+            .node_indices()
+            .map(|index| {
+                let node = &self.graph[index];
+                let mut json = json!({ "path": node.path.as_str(), "kind": node.kind });
+                if let Some(package) = self.packages.get(&index) {
+                    json["package"] = json!(package);
+                }
+                json
+            })
+            .collect::<Vec<_>>();
+        let edges = self
+            .graph
+            .edge_references()
+            .map(|edge| {
+                json!({
+                    "from": self.graph[edge.source()].path.as_str(),
+                    "to": self.graph[edge.target()].path.as_str(),
+                    "kind": edge.weight().kind,
+                    "symbols": edge.weight().symbols,
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({ "nodes": nodes, "edges": edges })
+    }
+
+    pub fn json(&mut self) {
+        println!("{}", serde_json::to_string_pretty(&self.to_json()).unwrap());
     }
 
     pub fn dot(&mut self) {
         println!(
             "{:?}",
-            Dot::with_config(&self.graph, &[Config::EdgeNoLabel])
+            Dot::with_attr_getters(
+                &self.graph,
+                &[Config::EdgeNoLabel],
+                &|_, edge| edge.weight().kind.dot_attributes().to_string(),
+                &|_, node| node.weight().kind.dot_attributes().to_string(),
+            )
         );
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::GraphBuilder;
+    use crate::{
+        graph::{EdgeType, NodeKind},
+        message::Message,
+        utils::PathAtom,
+    };
+
+    fn deps(edges: &[(&str, &str)]) -> Vec<(PathAtom, Message)> {
+        edges
+            .iter()
+            .map(|&(src, dst)| {
+                (
+                    PathAtom::new(src),
+                    Message::new(dst, NodeKind::Module, EdgeType::Static),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_owned_graph() {
+        // The graph outlives the dependencies it was built from
+        let mut graph = {
+            let mut graph = GraphBuilder::new();
+            graph.add_deps(&deps(&[("main.ts", "a.ts"), ("a.ts", "b.ts")]));
+            graph
+        };
+        assert_eq!(graph.dependents("b.ts"), ["a.ts", "main.ts"]);
+
+        // Dependencies of a later run extend it
+        graph.add_deps(&deps(&[("cli.ts", "b.ts")]));
+        assert_eq!(graph.dependents("b.ts"), ["a.ts", "cli.ts", "main.ts"]);
+        assert_eq!(graph.edge_count(), 3);
+    }
+}
@@ -0,0 +1,62 @@
+use serde::Serialize;
+
+use super::{EdgeType, GraphIndex, NodeKind};
+use crate::{diagnostic::Diagnostic, message::Message, utils::PathAtom};
+
+/// The graph found by an analysis, see [`crate::AnalyzeService::analyze`]
+#[derive(Debug, Default, Serialize)]
+pub struct DependencyGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    /// Diagnostics reported while analyzing, e.g. unresolved imports and syntax errors
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// A file or package, named by its path relative to the current working directory
+/// or by the package name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GraphNode {
+    pub path: String,
+    pub kind: NodeKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeType,
+    /// Names of the bindings imported through the edge, sorted
+    pub symbols: Vec<String>,
+}
+
+impl DependencyGraph {
+    /// The graph of `deps`, as collected by [`crate::collector::CollectorService`]
+    pub fn new(deps: Vec<(PathAtom, Message)>, diagnostics: Vec<Diagnostic>) -> Self {
+        let mut index = GraphIndex::default();
+        for (src, dst) in &deps {
+            index.add(src, dst);
+        }
+        let nodes = index
+            .nodes()
+            .iter()
+            .map(|(path, kind)| GraphNode {
+                path: path.to_string(),
+                kind: kind.unwrap_or_default(),
+            })
+            .collect();
+        let edges = deps
+            .into_iter()
+            .map(|(src, dst)| GraphEdge {
+                from: src.to_string(),
+                to: dst.file_path.to_string(),
+                kind: dst.edge,
+                symbols: dst.symbols,
+            })
+            .collect();
+        Self {
+            nodes,
+            edges,
+            diagnostics,
+        }
+    }
+}
@@ -1,19 +1,44 @@
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum EdgeType {
-    /// Conditional jumps
-    Jump,
-    /// Normal control flow path
-    Normal,
-    /// Cyclic aka loops
-    Backedge,
-    /// Marks start of a function subgraph
-    NewFunction,
-    /// Finally
-    Finalize,
+    /// `import` and `export ... from` declarations
+    #[default]
+    Static,
+    /// `/// <reference path="..." />` triple-slash directives
+    Reference,
+    /// `import()` expressions, which define code-splitting boundaries
+    Dynamic,
+    /// CommonJS `require()` calls
+    Require,
+    /// Web worker entries, `new Worker(new URL('./worker', import.meta.url))`
+    Worker,
+    /// Files matched by `import.meta.glob` patterns
+    Glob,
+    /// Files matched by webpack `require.context` calls
+    Context,
+    /// `import type` and type-only `export type ... from` declarations, erased at runtime
+    Type,
+}
+
+impl EdgeType {
+    /// Graphviz attributes used to render this kind of edge
+    pub fn dot_attributes(self) -> &'static str {
+        match self {
+            Self::Static => "",
+            Self::Reference => "style = dotted",
+            Self::Dynamic => "style = dashed",
+            Self::Require => "arrowhead = empty",
+            Self::Worker => "style = bold",
+            Self::Glob => "style = dashed, color = gray",
+            Self::Context => "style = dashed, color = gray, arrowhead = empty",
+            Self::Type => "style = dotted, color = blue",
+        }
+    }
 
-    // misc edges
-    Unreachable,
-    /// Used to mark the end of a finalizer. It is an experimental approach might
-    /// move to it's respective edge kind enum or get removed altogether.
-    Join,
-}
\ No newline at end of file
+    /// Edges from `import` and `export ... from` declarations, which link module records
+    pub fn is_static(self) -> bool {
+        matches!(self, Self::Static | Self::Type)
+    }
+}
@@ -0,0 +1,86 @@
+use rustc_hash::FxHashMap;
+
+use super::NodeKind;
+use crate::{message::Message, utils::PathAtom};
+
+/// Nodes of the graph numbered as dependencies arrive from the analysis, so most of the
+/// graph is built while files are still analyzed instead of after
+#[derive(Debug, Default)]
+pub struct GraphIndex {
+    numbers: FxHashMap<PathAtom, usize>,
+    /// Path of each node by number, with its kind once an edge into it tells it
+    nodes: Vec<(PathAtom, Option<NodeKind>)>,
+    /// Numbers of the nodes each dependency goes from and to, in the order they arrived
+    edges: Vec<(usize, usize)>,
+}
+
+impl GraphIndex {
+    /// Numbers the nodes of the dependency from `src` to `dst`
+    pub fn add(&mut self, src: &PathAtom, dst: &Message) {
+        let from = self.number(src, None);
+        let to = self.number(&dst.file_path, Some(dst.kind));
+        self.edges.push((from, to));
+    }
+
+    fn number(&mut self, path: &PathAtom, kind: Option<NodeKind>) -> usize {
+        let number = *self.numbers.entry(path.clone()).or_insert_with(|| {
+            self.nodes.push((path.clone(), None));
+            self.nodes.len() - 1
+        });
+        if kind.is_some() {
+            self.nodes[number].1 = kind;
+        }
+        number
+    }
+
+    /// Number of dependencies added
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub(super) fn nodes(&self) -> &[(PathAtom, Option<NodeKind>)] {
+        &self.nodes
+    }
+
+    pub(super) fn edges(&self) -> &[(usize, usize)] {
+        &self.edges
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::GraphIndex;
+    use crate::{
+        graph::{EdgeType, GraphBuilder, NodeKind},
+        message::Message,
+        utils::PathAtom,
+    };
+
+    #[test]
+    fn test_graph_index() {
+        let deps = [
+            ("main.ts", "a.ts", NodeKind::Module),
+            ("main.ts", "a.css", NodeKind::Style),
+            ("a.css", "b.css", NodeKind::Style),
+            ("a.ts", "react", NodeKind::Package),
+        ]
+        .map(|(src, dst, kind)| {
+            (
+                PathAtom::new(src),
+                Message::new(dst, kind, EdgeType::Static),
+            )
+        });
+        let mut index = GraphIndex::default();
+        for (src, dst) in &deps {
+            index.add(src, dst);
+        }
+        assert_eq!(index.edge_count(), 4);
+        // Importers are modules until an edge into them tells otherwise
+        assert_eq!(index.nodes()[2].1, Some(NodeKind::Style));
+
+        let streamed = GraphBuilder::from_index(&index, &deps).to_json();
+        let mut graph_builder = GraphBuilder::new();
+        graph_builder.add_deps(&deps);
+        assert_eq!(streamed, graph_builder.to_json());
+    }
+}
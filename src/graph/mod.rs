@@ -1,5 +1,11 @@
 mod builder;
+mod dependency_graph;
 mod edge;
+mod index;
+mod node;
 
-
-pub use builder::GraphBuilder;
\ No newline at end of file
+pub use builder::GraphBuilder;
+pub use dependency_graph::{DependencyGraph, GraphEdge, GraphNode};
+pub use edge::EdgeType;
+pub use index::GraphIndex;
+pub use node::NodeKind;
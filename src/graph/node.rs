@@ -0,0 +1,36 @@
+use serde::Serialize;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    /// A source file that was parsed and traversed
+    #[default]
+    Module,
+    /// An external package collapsed into a single node
+    Package,
+    /// A specifier the resolver could not handle
+    Unresolved,
+    /// An imported stylesheet, which is not parsed
+    Style,
+    /// An imported non-code asset such as an image or WebAssembly file
+    Asset,
+    /// An imported JSON data file, which is not parsed
+    Json,
+    /// A TypeScript declaration file, e.g. `foo.d.ts`
+    Declaration,
+}
+
+impl NodeKind {
+    /// Graphviz attributes used to render this kind of node
+    pub fn dot_attributes(self) -> &'static str {
+        match self {
+            Self::Module => "",
+            Self::Package => "shape = box",
+            Self::Unresolved => "color = red, style = dashed",
+            Self::Style => "shape = note",
+            Self::Asset => "shape = component",
+            Self::Json => "shape = cylinder",
+            Self::Declaration => "style = dashed",
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Estimated cost of each import: the modules and bytes of the subtree it pulls in.
+
+use std::{fmt, fs, path::Path};
+
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+
+use crate::{dead_files::format_size, graph::GraphBuilder};
+
+#[derive(Debug, Serialize)]
+pub struct ImportCost {
+    pub from: String,
+    pub to: String,
+    /// Number of modules reachable through the import, its target included
+    pub modules: usize,
+    /// Total size in bytes of those modules, packages and unresolved imports count as empty
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EntryImportCosts {
+    pub entry: String,
+    /// Heaviest imports reachable from the entry, largest first
+    pub imports: Vec<ImportCost>,
+}
+
+/// A lightweight bundle impact analysis which does not run a bundler.
+#[derive(Debug, Default, Serialize)]
+pub struct ImportCostReport {
+    pub entries: Vec<EntryImportCosts>,
+}
+
+impl ImportCostReport {
+    /// Keeps the `limit` heaviest imports reachable from each of `entries`,
+    /// which are node paths relative to `cwd`.
+    pub fn new(cwd: &Path, entries: &[String], graph: &GraphBuilder, limit: usize) -> Self {
+        let mut sizes = FxHashMap::<&str, u64>::default();
+        let mut subtrees = FxHashMap::<&str, (usize, u64)>::default();
+        let entries = entries
+            .iter()
+            .map(|entry| {
+                let mut imports = graph
+                    .reachable_edges(entry)
+                    .into_iter()
+                    .map(|(from, to)| {
+                        let (modules, size) = *subtrees.entry(to).or_insert_with(|| {
+                            let subtree = graph.subtree(to);
+                            let size = subtree
+                                .iter()
+                                .map(|path| {
+                                    *sizes.entry(path).or_insert_with(|| file_size(cwd, path))
+                                })
+                                .sum();
+                            (subtree.len(), size)
+                        });
+                        ImportCost {
+                            from: from.to_string(),
+                            to: to.to_string(),
+                            modules,
+                            size,
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                imports.sort_unstable_by(|a, b| {
+                    b.size
+                        .cmp(&a.size)
+                        .then_with(|| b.modules.cmp(&a.modules))
+                        .then_with(|| (&a.from, &a.to).cmp(&(&b.from, &b.to)))
+                });
+                imports.truncate(limit);
+                EntryImportCosts {
+                    entry: entry.clone(),
+                    imports,
+                }
+            })
+            .collect();
+        Self { entries }
+    }
+}
+
+impl fmt::Display for ImportCostReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", entry.entry)?;
+            if entry.imports.is_empty() {
+                write!(f, "\n  no imports")?;
+            }
+            for import in &entry.imports {
+                write!(
+                    f,
+                    "\n{:>10} {:>6}  {} -> {}",
+                    format_size(import.size),
+                    import.modules,
+                    import.from,
+                    import.to
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Size of the file a node stands for, `0` for nodes which are not files
+fn file_size(cwd: &Path, path: &str) -> u64 {
+    fs::metadata(cwd.join(path))
+        .ok()
+        .filter(fs::Metadata::is_file)
+        .map_or(0, |metadata| metadata.len())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        graph::{EdgeType, GraphBuilder, NodeKind},
+        message::Message,
+        utils::{PathAtom, TestDir},
+    };
+
+    use super::ImportCostReport;
+
+    #[test]
+    fn test_import_cost_report() {
+        let root = TestDir::new("import-cost");
+        for (file, size) in [("main.ts", 10), ("a.ts", 100), ("b.ts", 1000), ("c.ts", 1)] {
+            std::fs::write(root.join(file), "x".repeat(size)).unwrap();
+        }
+        let edge = |from: &str, to: &str, kind| {
+            (
+                PathAtom::from(from),
+                Message::new(to, kind, EdgeType::Static),
+            )
+        };
+        let deps = vec![
+            edge("main.ts", "a.ts", NodeKind::Module),
+            edge("main.ts", "c.ts", NodeKind::Module),
+            edge("a.ts", "b.ts", NodeKind::Module),
+            edge("b.ts", "react", NodeKind::Package),
+        ];
+        let mut graph = GraphBuilder::new();
+        graph.add_deps(&deps);
+
+        let report = ImportCostReport::new(&root, &["main.ts".to_string()], &graph, 3);
+        let imports = report.entries[0]
+            .imports
+            .iter()
+            .map(|import| {
+                (
+                    import.from.as_str(),
+                    import.to.as_str(),
+                    import.modules,
+                    import.size,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            imports,
+            [
+                ("main.ts", "a.ts", 3, 1100),
+                ("a.ts", "b.ts", 2, 1000),
+                ("main.ts", "c.ts", 1, 1),
+            ]
+        );
+    }
+}
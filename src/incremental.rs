@@ -0,0 +1,269 @@
+//! Incremental runs, which only read, parse and resolve the files changed since the previous run.
+//!
+//! The previous run is stored next to the parsed sources in the cache: for each file, its size
+//! and modification time, its cache key, where its specifiers resolved to and the files its
+//! imports are linked to.
+//! A file whose size and modification time did not change is taken from the cache without being
+//! read, and its dependencies keep their previous resolution while the resolved file exists.
+//! Everything else, rules included, is checked again, so the graph is the previous one patched
+//! with the edges of the changed files.
+//!
+//! Resolutions of unchanged files are not revisited when files are added, e.g. a new `index.ts`
+//! next to the `index.js` an import resolved to. Running without `--incremental` starts over.
+
+use std::{
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use dashmap::DashMap;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// File in the cache directory the previous run is stored in
+pub const STATE_FILE: &str = "files.json";
+
+/// Where a specifier resolved to, in a form which outlives the resolver
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Resolved {
+    Path {
+        path: PathBuf,
+        /// The `package.json` the path belongs to
+        package: Option<Arc<ResolvedPackage>>,
+    },
+    /// Node.js builtins and paths ignored by the `browser` field, left out of the graph
+    Skipped,
+    /// The resolution error
+    Failed(String),
+}
+
+impl Resolved {
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Path { path, .. } => Some(path),
+            Self::Skipped | Self::Failed(_) => None,
+        }
+    }
+
+    pub fn package(&self) -> Option<&ResolvedPackage> {
+        match self {
+            Self::Path { package, .. } => package.as_deref(),
+            Self::Skipped | Self::Failed(_) => None,
+        }
+    }
+
+    /// Whether a resolution from a previous run can be reused, failures are always retried
+    fn is_reusable(&self) -> bool {
+        match self {
+            Self::Path { path, .. } => path.is_file(),
+            Self::Skipped => true,
+            Self::Failed(_) => false,
+        }
+    }
+}
+
+/// The parts of a `package.json` the analysis looks at
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ResolvedPackage {
+    pub directory: PathBuf,
+    pub name: Option<String>,
+    pub exports: Option<Value>,
+}
+
+/// A file as seen by a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileState {
+    size: u64,
+    modified: SystemTime,
+    /// Cache key of the file, see [`crate::cache::AnalysisCache::key`]
+    pub key: Option<u64>,
+    /// Resolutions of the specifiers of every source of the file
+    pub resolutions: FxHashMap<String, Resolved>,
+    /// Files its imports are linked to, which differ from the resolutions for workspace
+    /// packages continuing from their source and for declarations paired with their module
+    #[serde(default)]
+    pub targets: Vec<PathBuf>,
+}
+
+impl FileState {
+    /// State of the file with `metadata`, `None` when its modification time is unknown
+    pub fn new(metadata: &fs::Metadata) -> Option<Self> {
+        Some(Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok()?,
+            key: None,
+            resolutions: FxHashMap::default(),
+            targets: Vec::new(),
+        })
+    }
+
+    /// Whether `path` has the size and modification time it had when the state was taken
+    pub fn is_unchanged(&self, path: &Path) -> bool {
+        fs::metadata(path)
+            .ok()
+            .and_then(|metadata| Self::new(&metadata))
+            .is_some_and(|current| self.size == current.size && self.modified == current.modified)
+    }
+
+    /// Resolutions which can be reused instead of resolving again
+    pub fn reusable_resolutions(&self) -> FxHashMap<String, Resolved> {
+        self.resolutions
+            .iter()
+            .filter(|(_, resolved)| resolved.is_reusable())
+            .map(|(specifier, resolved)| (specifier.clone(), resolved.clone()))
+            .collect()
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct StoredRun {
+    fingerprint: u64,
+    files: FxHashMap<PathBuf, FileState>,
+}
+
+/// The previous run, and the current one stored when it completes
+#[derive(Debug)]
+pub struct Incremental {
+    file: PathBuf,
+    fingerprint: u64,
+    previous: FxHashMap<PathBuf, FileState>,
+    current: DashMap<PathBuf, FileState>,
+}
+
+impl Incremental {
+    /// Loads the previous run from the cache directory `dir`. It is discarded unless it was
+    /// resolved with the same `fingerprint`, see [`fingerprint`].
+    pub fn load(dir: &Path, fingerprint: u64) -> Self {
+        let file = dir.join(STATE_FILE);
+        let previous = fs::read_to_string(&file)
+            .ok()
+            .and_then(|json| serde_json::from_str::<StoredRun>(&json).ok())
+            .filter(|run| run.fingerprint == fingerprint)
+            .map(|run| run.files)
+            .unwrap_or_default();
+        Self {
+            file,
+            fingerprint,
+            previous,
+            current: DashMap::default(),
+        }
+    }
+
+    /// Files of the run stored in the cache directory `dir`, whatever it was run with
+    pub fn stored_files(dir: &Path) -> Option<FxHashMap<PathBuf, FileState>> {
+        let json = fs::read_to_string(dir.join(STATE_FILE)).ok()?;
+        serde_json::from_str::<StoredRun>(&json)
+            .ok()
+            .map(|run| run.files)
+    }
+
+    /// The state of `path` in the previous run, when it did not change since
+    pub fn unchanged(&self, path: &Path, metadata: &fs::Metadata) -> Option<&FileState> {
+        let previous = self.previous.get(path)?;
+        let current = FileState::new(metadata)?;
+        (previous.size == current.size && previous.modified == current.modified).then_some(previous)
+    }
+
+    /// Records the state of `path` in the current run
+    pub fn record(&self, path: &Path, state: FileState) {
+        self.current.insert(path.to_path_buf(), state);
+    }
+
+    /// Stores the current run for the next one. Files not analyzed by this run are dropped.
+    pub fn save(&self) {
+        let run = StoredRun {
+            fingerprint: self.fingerprint,
+            files: self
+                .current
+                .iter()
+                .map(|entry| (entry.key().clone(), entry.value().clone()))
+                .collect(),
+        };
+        if let Ok(json) = serde_json::to_string(&run) {
+            let _ = fs::write(&self.file, json);
+        }
+    }
+}
+
+/// Changes whenever a previous run could load or resolve files differently, from the `options`
+/// deciding that, as printed by `Debug`, and the `tsconfig.json` paths are resolved with.
+pub fn fingerprint(options: &str, tsconfig: Option<&Path>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    options.hash(&mut hasher);
+    tsconfig
+        .and_then(|tsconfig| fs::read(tsconfig).ok())
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{fingerprint, FileState, Incremental, Resolved, ResolvedPackage};
+    use crate::utils::TestDir;
+
+    #[test]
+    fn test_incremental() {
+        let dir = TestDir::new("incremental");
+        let (a, b) = (dir.join("a.ts"), dir.join("b.ts"));
+        std::fs::write(&a, "import './b';").unwrap();
+        std::fs::write(&b, "").unwrap();
+
+        let mut state = FileState::new(&a.metadata().unwrap()).unwrap();
+        state.key = Some(1);
+        let package = Arc::new(ResolvedPackage {
+            directory: dir.to_path_buf(),
+            name: Some("app".to_string()),
+            exports: None,
+        });
+        for (specifier, resolved) in [
+            (
+                "./b",
+                Resolved::Path {
+                    path: b.clone(),
+                    package: Some(package),
+                },
+            ),
+            (
+                "./c",
+                Resolved::Path {
+                    path: dir.join("c.ts"),
+                    package: None,
+                },
+            ),
+            ("fs", Resolved::Skipped),
+            ("./d", Resolved::Failed("not found".to_string())),
+        ] {
+            state.resolutions.insert(specifier.to_string(), resolved);
+        }
+
+        let fingerprint = fingerprint("options", None);
+        let incremental = Incremental::load(&dir, fingerprint);
+        assert!(incremental.unchanged(&a, &a.metadata().unwrap()).is_none());
+        incremental.record(&a, state);
+        incremental.save();
+
+        assert!(Incremental::load(&dir, fingerprint + 1).previous.is_empty());
+        let incremental = Incremental::load(&dir, fingerprint);
+        let state = incremental.unchanged(&a, &a.metadata().unwrap()).unwrap();
+        assert_eq!(state.key, Some(1));
+        let mut reusable = state.reusable_resolutions().into_keys().collect::<Vec<_>>();
+        reusable.sort_unstable();
+        assert_eq!(reusable, ["./b", "fs"]);
+        assert_eq!(
+            state.resolutions["./b"]
+                .package()
+                .and_then(|package| package.name.as_deref()),
+            Some("app")
+        );
+
+        std::fs::write(&a, "import './b'; import './c';").unwrap();
+        assert!(incremental.unchanged(&a, &a.metadata().unwrap()).is_none());
+    }
+}
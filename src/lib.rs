@@ -0,0 +1,71 @@
+//! Analyze the dependency graph of JavaScript and TypeScript files with zero configuration.
+//!
+//! The `marsh` binary is a thin layer over this library, which other tools can embed instead
+//! of running the binary and parsing its output:
+//!
+//! ```no_run
+//! use marsh::{AnalyzeService, AnalyzeServiceOptions};
+//!
+//! let cwd = std::env::current_dir().unwrap();
+//! let entry = cwd.join("src/index.ts").into_boxed_path();
+//! let options = AnalyzeServiceOptions::new(cwd.as_path(), vec![entry]).with_cross_module(true);
+//! let graph = AnalyzeService::new(options).analyze();
+//! for edge in &graph.edges {
+//!     println!("{} -> {}", edge.from, edge.to);
+//! }
+//! for diagnostic in &graph.diagnostics {
+//!     eprintln!("{}: {}", diagnostic.path.display(), diagnostic.message);
+//! }
+//! ```
+
+pub mod affected;
+pub mod barrel;
+pub mod baseline;
+mod boundaries;
+pub mod build_deps;
+pub mod cache;
+pub mod code_quality;
+pub mod collector;
+pub mod config;
+pub mod cycles;
+pub mod daemon;
+pub mod dead_files;
+mod deep_imports;
+pub mod deps_of;
+pub mod diagnostic;
+mod explain;
+mod exports;
+pub mod file_system;
+pub mod graph;
+pub mod import_cost;
+mod incremental;
+mod loader;
+pub mod lsp;
+mod message;
+pub mod nx;
+pub mod package_level;
+pub mod package_reports;
+pub mod progress;
+pub mod project_references;
+mod public_entries;
+pub mod resolutions;
+pub mod rules;
+pub mod sarif;
+mod scanner;
+mod scripts;
+pub mod serve;
+pub mod service;
+pub mod stats;
+mod suppressions;
+pub mod telemetry;
+pub mod turbo;
+mod unused_dependencies;
+pub mod utils;
+pub mod workspace;
+pub mod workspace_deep_imports;
+
+pub use diagnostic::{Diagnostic, DiagnosticCode};
+pub use graph::{DependencyGraph, EdgeType, GraphEdge, GraphNode, NodeKind};
+pub use loader::{JavaScriptSource, Loader};
+pub use message::Message;
+pub use service::{AnalyzeService, AnalyzeServiceOptions, CancellationToken, DeclarationMode};
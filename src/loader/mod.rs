@@ -1,19 +1,34 @@
 mod partial_loader;
 mod source;
 
-use std::{error::Error, fmt, path::Path};
+use std::path::Path;
 
 use oxc_span::SourceType;
 
-pub use partial_loader::{PartialLoader, LINT_PARTIAL_LOADER_EXT};
+pub use partial_loader::{PartialLoader, LINT_PARTIAL_LOADER_EXT, MARKDOWN_EXT};
 pub use source::JavaScriptSource;
 
+/// Extracts JavaScript from files of a custom type, such as an in-house template format.
+///
+/// Loaders are registered with [`AnalyzeServiceOptions::with_loader`] and take precedence over
+/// the built-in ones for their extensions.
+///
+/// [`AnalyzeServiceOptions::with_loader`]: crate::service::AnalyzeServiceOptions::with_loader
+pub trait Loader: Send + Sync {
+    /// Extensions of the files handled by this loader, without the leading dot, e.g. `hbs`
+    fn extensions(&self) -> &[&str];
+
+    /// JavaScript sources embedded in `source_text`, the contents of the file at `path`.
+    /// Files without scripts yield no sources and are still nodes of the graph, without imports.
+    fn load<'a>(&self, path: &Path, source_text: &'a str) -> Vec<JavaScriptSource<'a>>;
+}
+
 // TODO: use oxc_resolver::FileSystem. We can't do so until that crate exposes FileSystemOs
 // externally.
 #[derive(Default, Clone)]
-pub struct Loader;
+pub struct BuiltinLoader;
 
-impl Loader {
+impl BuiltinLoader {
     pub fn can_load<P: AsRef<Path>>(path: P) -> bool {
         let path = path.as_ref();
         SourceType::from_path(path).is_ok()
@@ -22,60 +37,8 @@ impl Loader {
                 .and_then(std::ffi::OsStr::to_str)
                 .is_some_and(|ext| LINT_PARTIAL_LOADER_EXT.contains(&ext))
     }
-
-    /// # Errors
-    /// - If the file is too large (> 4GB, or u32::MAX)
-    /// - If the file has no extension
-    /// - If the file extension is not supported
-    pub fn load_str<'a, P: AsRef<Path>>(
-        &self,
-        path: P,
-        source_text: &'a str,
-    ) -> Result<Vec<JavaScriptSource<'a>>, LoadError> {
-        if source_text.len() > u32::MAX as usize {
-            return Err(LoadError::TooLarge);
-        }
-
-        let path = path.as_ref();
-        let ext = path.extension().ok_or(LoadError::NoExtension)?;
-        // file extension is not unicode, we definitely don't support it.
-        let ext = ext.to_str().ok_or_else(|| LoadError::unsupported(ext))?;
-
-        // let source_type = SourceType::from_path(path);
-        if let Ok(source_type) = SourceType::from_path(path) {
-            Ok(vec![JavaScriptSource::new(source_text, source_type)])
-        } else {
-            let partial = PartialLoader::parse(ext, source_text);
-            partial.ok_or_else(|| LoadError::UnsupportedFileType(ext.to_string()))
-        }
-    }
 }
 
-#[derive(Debug, Clone)]
-pub enum LoadError {
-    TooLarge,
-    NoExtension,
-    UnsupportedFileType(String),
-}
-
-impl LoadError {
-    pub(super) fn unsupported(ext: &std::ffi::OsStr) -> Self {
-        Self::UnsupportedFileType(ext.to_string_lossy().to_string())
-    }
-}
-
-impl fmt::Display for LoadError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::TooLarge => write!(f, "file is too large. Only files up to 4GB are supported."),
-            Self::NoExtension => write!(f, "no extension"),
-            Self::UnsupportedFileType(ext) => write!(f, "unsupported file type: {ext}"),
-        }
-    }
-}
-
-impl Error for LoadError {}
-
 #[cfg(test)]
 mod test {
     use super::*;
@@ -98,10 +61,12 @@ mod test {
             "foo.astro",
             "foo.svelte",
             "foo.vue",
+            "foo.html",
+            "foo.mdx",
         ];
 
         for path in paths {
-            assert!(Loader::can_load(path));
+            assert!(BuiltinLoader::can_load(path));
         }
     }
-}
\ No newline at end of file
+}
@@ -1,7 +1,7 @@
 use memchr::memmem::Finder;
-use oxc_span::{SourceType, Span};
+use oxc_span::SourceType;
 
-use super::{SCRIPT_END, SCRIPT_START};
+use super::{find_script_closing_angle, script_source_type, SCRIPT_END, SCRIPT_START};
 use crate::loader::JavaScriptSource;
 
 const ASTRO_SPLIT: &str = "---";
@@ -18,36 +18,34 @@ impl<'a> AstroPartialLoader<'a> {
     pub fn parse(self) -> Vec<JavaScriptSource<'a>> {
         let mut results = vec![];
         let frontmatter = self.parse_frontmatter();
-        let start = frontmatter.as_ref().map_or(0, |r| r.source_text.len() + ASTRO_SPLIT.len() * 2);
-        results.extend(frontmatter);
+        let start = frontmatter.as_ref().map_or(0, |(_, end)| *end);
+        results.extend(frontmatter.map(|(source, _)| source));
         results.extend(self.parse_scripts(start));
         results
     }
 
-    /// Parse `---` frontmatter block
+    /// Parse the `---` fenced frontmatter, which must open the file,
+    /// returning it with the offset following its closing fence.
+    /// <https://docs.astro.build/en/basics/astro-components/#the-component-script>
     #[allow(clippy::cast_possible_truncation)]
-    fn parse_frontmatter(&self) -> Option<JavaScriptSource<'a>> {
-        let split_finder = Finder::new(ASTRO_SPLIT);
-        let offsets = split_finder.find_iter(self.source_text.as_bytes()).collect::<Vec<_>>();
-        if offsets.len() <= 1 {
+    fn parse_frontmatter(&self) -> Option<(JavaScriptSource<'a>, usize)> {
+        let content = self.source_text.trim_start();
+        if !content.starts_with(ASTRO_SPLIT) {
             return None;
         }
-
-        let start = offsets.first()?;
-        let end = offsets.last()?;
-        let Ok(start) = u32::try_from(*start) else {
-            return None;
-        };
-        let Ok(end) = u32::try_from(*end) else {
-            return None;
-        };
-
-        let js_code =
-            Span::new(start + ASTRO_SPLIT.len() as u32, end).source_text(self.source_text);
-        Some(JavaScriptSource::partial(js_code, SourceType::ts(), start))
+        let js_start = self.source_text.len() - content.len() + ASTRO_SPLIT.len();
+        let offset = Finder::new(ASTRO_SPLIT).find(&self.source_text.as_bytes()[js_start..])?;
+        let js_end = js_start + offset;
+
+        let js_code = &self.source_text[js_start..js_end];
+        // NOTE: loader checked that source_text.len() is less than u32::MAX
+        let source = JavaScriptSource::partial(js_code, SourceType::ts(), js_start as u32);
+        Some((source, js_end + ASTRO_SPLIT.len()))
     }
 
     /// In .astro files, you can add client-side JavaScript by adding one (or more) `<script>` tags.
+    /// They are processed as TypeScript, blocks which do not hold JavaScript such as
+    /// `<script type="application/ld+json">` are skipped.
     /// <https://docs.astro.build/en/guides/client-side-scripts/#using-script-in-astro>
     fn parse_scripts(&self, start: usize) -> Vec<JavaScriptSource<'a>> {
         let script_start_finder = Finder::new(SCRIPT_START);
@@ -66,14 +64,16 @@ impl<'a> AstroPartialLoader<'a> {
                 break;
             };
             // find closing ">"
-            if let Some(offset) = self.source_text[pointer..].find('>') {
+            let attributes_start = pointer;
+            if let Some(offset) = find_script_closing_angle(self.source_text, pointer) {
                 pointer += offset + 1;
                 js_start = pointer;
             } else {
                 break;
             };
+            let attributes = &self.source_text[attributes_start..js_start - 1];
             // check for the / of a self closing script tag
-            if let Some('/') = self.source_text.chars().nth(js_start - 2) {
+            if attributes.ends_with('/') {
                 js_end = pointer;
             // find "</script>" if no self closing tag was found
             } else if let Some(offset) =
@@ -85,11 +85,14 @@ impl<'a> AstroPartialLoader<'a> {
                 break;
             };
 
+            let Some(source_type) = script_source_type(attributes) else {
+                continue;
+            };
             // NOTE: loader checked that source_text.len() is less than u32::MAX
             #[allow(clippy::cast_possible_truncation)]
             results.push(JavaScriptSource::partial(
                 &self.source_text[js_start..js_end],
-                SourceType::ts(),
+                source_type.with_typescript(true),
                 js_start as u32,
             ));
         }
@@ -178,4 +181,30 @@ mod test {
         assert!(sources[0].source_text.is_empty());
         assert_eq!(sources[1].source_text.trim(), r#"console.log("Hi");"#);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parse_astro_frontmatter_only_at_start() {
+        let source_text = r#"<h1>Title</h1>
+        <hr>---
+        <script type="application/ld+json">{ "a": 1 }</script>
+        <p>---</p>
+        "#;
+
+        assert!(parse_astro(source_text).is_empty());
+    }
+
+    #[test]
+    fn test_parse_astro_offsets() {
+        let source_text = "---\nimport A from './A.astro';\n---\n<div>---</div><script>b</script>";
+
+        let sources = parse_astro(source_text);
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].source_text, "\nimport A from './A.astro';\n");
+        assert_eq!(sources[0].start, 3);
+        assert_eq!(sources[1].source_text, "b");
+        assert_eq!(
+            sources[1].start as usize,
+            source_text.find("b</script>").unwrap()
+        );
+    }
+}
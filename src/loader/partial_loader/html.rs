@@ -0,0 +1,120 @@
+use memchr::memmem::Finder;
+
+use super::{
+    attribute_value, find_script_closing_angle, script_source_type, SCRIPT_END, SCRIPT_START,
+};
+use crate::loader::JavaScriptSource;
+
+/// Module scripts of an HTML entry, as bundlers such as Vite consume them.
+///
+/// <https://vite.dev/guide/#index-html-and-project-root>
+pub struct HtmlPartialLoader<'a> {
+    source_text: &'a str,
+}
+
+/// A `<script type="module">` tag
+struct ModuleScript<'a> {
+    /// Value of the `src` attribute
+    src: Option<&'a str>,
+    source: JavaScriptSource<'a>,
+}
+
+impl<'a> HtmlPartialLoader<'a> {
+    pub fn new(source_text: &'a str) -> Self {
+        Self { source_text }
+    }
+
+    /// Inline `<script type="module">` blocks. Classic scripts are not bundled and skipped.
+    pub fn parse(self) -> Vec<JavaScriptSource<'a>> {
+        self.module_scripts()
+            .into_iter()
+            .filter(|script| script.src.is_none())
+            .map(|script| script.source)
+            .collect()
+    }
+
+    /// The `src` of `<script type="module" src="...">` tags.
+    pub fn script_references(self) -> Vec<&'a str> {
+        self.module_scripts()
+            .into_iter()
+            .filter_map(|script| script.src)
+            .collect()
+    }
+
+    fn module_scripts(&self) -> Vec<ModuleScript<'a>> {
+        let script_start_finder = Finder::new(SCRIPT_START);
+        let script_end_finder = Finder::new(SCRIPT_END);
+
+        let mut scripts = vec![];
+        let mut pointer = 0;
+        while let Some(offset) = script_start_finder.find(&self.source_text.as_bytes()[pointer..]) {
+            pointer += offset + SCRIPT_START.len();
+
+            // find closing ">"
+            let Some(offset) = find_script_closing_angle(self.source_text, pointer) else {
+                break;
+            };
+            let attributes = &self.source_text[pointer..pointer + offset];
+            pointer += offset + 1;
+            let js_start = pointer;
+
+            // find "</script>"
+            let Some(offset) = script_end_finder.find(&self.source_text.as_bytes()[pointer..])
+            else {
+                break;
+            };
+            let js_end = pointer + offset;
+            pointer += offset + SCRIPT_END.len();
+
+            if attribute_value(attributes, "type") != Some("module") {
+                continue;
+            }
+            let Some(source_type) = script_source_type(attributes) else {
+                continue;
+            };
+            // NOTE: loader checked that source_text.len() is less than u32::MAX
+            #[allow(clippy::cast_possible_truncation)]
+            scripts.push(ModuleScript {
+                src: attribute_value(attributes, "src"),
+                source: JavaScriptSource::partial(
+                    &self.source_text[js_start..js_end],
+                    source_type,
+                    js_start as u32,
+                ),
+            });
+        }
+        scripts
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::HtmlPartialLoader;
+
+    #[test]
+    fn test_parse_html() {
+        let source_text = r#"
+        <!doctype html>
+        <html>
+          <head>
+            <script type="module" src="/src/main.ts"></script>
+            <script src="/legacy.js"></script>
+            <script type="importmap">{ "imports": {} }</script>
+          </head>
+          <body>
+            <script type="module">
+              import './inline';
+            </script>
+            <script>console.log('classic')</script>
+          </body>
+        </html>
+        "#;
+
+        let sources = HtmlPartialLoader::new(source_text).parse();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].source_text.trim(), "import './inline';");
+
+        let references = HtmlPartialLoader::new(source_text).script_references();
+        assert_eq!(references, ["/src/main.ts"]);
+    }
+}
@@ -0,0 +1,162 @@
+use oxc_span::SourceType;
+
+use crate::loader::JavaScriptSource;
+
+/// Fenced JavaScript and TypeScript code blocks of Markdown documents.
+///
+/// <https://spec.commonmark.org/0.31.2/#fenced-code-blocks>
+pub struct MarkdownPartialLoader<'a> {
+    source_text: &'a str,
+}
+
+impl<'a> MarkdownPartialLoader<'a> {
+    pub fn new(source_text: &'a str) -> Self {
+        Self { source_text }
+    }
+
+    pub fn parse(self) -> Vec<JavaScriptSource<'a>> {
+        code_blocks(self.source_text)
+            .into_iter()
+            .filter_map(|block| {
+                let source_type = code_block_source_type(block.info)?;
+                // NOTE: loader checked that source_text.len() is less than u32::MAX
+                #[allow(clippy::cast_possible_truncation)]
+                let start = block.start as u32;
+                Some(JavaScriptSource::partial(
+                    &self.source_text[block.start..block.end],
+                    source_type,
+                    start,
+                ))
+            })
+            .collect()
+    }
+}
+
+/// A fenced code block
+pub(super) struct CodeBlock<'a> {
+    /// First word of the info string, e.g. `ts` for ```` ```ts title="a.ts" ````
+    pub info: &'a str,
+    /// Byte range of the content, without the fences
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Fenced code blocks of `source_text`, an unclosed block runs to the end of the document.
+pub(super) fn code_blocks(source_text: &str) -> Vec<CodeBlock<'_>> {
+    let mut blocks = vec![];
+    // Fence and code block of the block being read
+    let mut open: Option<(&str, CodeBlock)> = None;
+    let mut offset = 0;
+    for line in source_text.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        match &mut open {
+            Some((fence, block)) => {
+                if is_closing_fence(line, fence) {
+                    block.end = line_start;
+                    blocks.push(open.take().unwrap().1);
+                }
+            }
+            None => {
+                if let Some((fence, info)) = opening_fence(line) {
+                    let block = CodeBlock {
+                        info,
+                        start: offset,
+                        end: offset,
+                    };
+                    open = Some((fence, block));
+                }
+            }
+        }
+    }
+    if let Some((_, mut block)) = open {
+        block.end = source_text.len();
+        blocks.push(block);
+    }
+    blocks
+}
+
+/// The fence and the first word of the info string of an opening code fence
+fn opening_fence(line: &str) -> Option<(&str, &str)> {
+    let trimmed = strip_indentation(line)?;
+    let marker = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let fence_len = trimmed.len() - trimmed.trim_start_matches(marker).len();
+    if fence_len < 3 {
+        return None;
+    }
+    let (fence, info) = trimmed.split_at(fence_len);
+    // Backtick fences can not have backticks in their info string
+    if marker == '`' && info.contains('`') {
+        return None;
+    }
+    let info = info.split_whitespace().next().unwrap_or_default();
+    Some((fence, info))
+}
+
+fn is_closing_fence(line: &str, fence: &str) -> bool {
+    let Some(trimmed) = strip_indentation(line) else {
+        return false;
+    };
+    let marker = fence.chars().next().unwrap_or_default();
+    let rest = trimmed.trim_start_matches(marker);
+    trimmed.len() - rest.len() >= fence.len() && rest.trim().is_empty()
+}
+
+/// Strips up to three spaces of indentation, more make an indented code block
+fn strip_indentation(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start_matches(' ');
+    (line.len() - trimmed.len() <= 3).then_some(trimmed)
+}
+
+fn code_block_source_type(info: &str) -> Option<SourceType> {
+    let source_type = SourceType::mjs();
+    match info.to_ascii_lowercase().as_str() {
+        "js" | "javascript" | "mjs" => Some(source_type),
+        "jsx" => Some(source_type.with_jsx(true)),
+        "ts" | "typescript" | "mts" => Some(source_type.with_typescript(true)),
+        "tsx" => Some(source_type.with_typescript(true).with_jsx(true)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MarkdownPartialLoader;
+
+    #[test]
+    fn test_parse_markdown() {
+        let source_text = r#"
+# Usage
+
+```ts title="main.ts"
+import { a } from './a';
+```
+
+```sh
+npm install
+```
+
+~~~jsx
+import B from './b';
+~~~
+
+````md
+```js
+import nested from './nested';
+```
+````
+"#;
+
+        let sources = MarkdownPartialLoader::new(source_text).parse();
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].source_text, "import { a } from './a';\n");
+        assert!(sources[0].source_type.is_typescript());
+        assert_eq!(sources[1].source_text, "import B from './b';\n");
+        assert!(sources[1].source_type.is_jsx());
+        let start = sources[0].start as usize;
+        assert_eq!(
+            &source_text[start..start + sources[0].source_text.len()],
+            sources[0].source_text
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use oxc_span::SourceType;
+
+use super::markdown::code_blocks;
+use crate::loader::JavaScriptSource;
+
+/// ESM blocks of MDX documents: paragraphs starting with `import` or `export` at the start of
+/// a line, outside of code blocks.
+///
+/// <https://mdxjs.com/docs/what-is-mdx/#esm>
+pub struct MdxPartialLoader<'a> {
+    source_text: &'a str,
+}
+
+impl<'a> MdxPartialLoader<'a> {
+    pub fn new(source_text: &'a str) -> Self {
+        Self { source_text }
+    }
+
+    /// Blocks separated by blank lines only are merged, so the imports and exports at the top
+    /// of a document form one module.
+    pub fn parse(self) -> Vec<JavaScriptSource<'a>> {
+        let code_blocks = code_blocks(self.source_text);
+        let in_code_block = |offset: usize| {
+            code_blocks
+                .iter()
+                .any(|block| block.start <= offset && offset <= block.end)
+        };
+
+        let mut blocks: Vec<(usize, usize)> = vec![];
+        // Start of the block being read
+        let mut open: Option<usize> = None;
+        let mut offset = self.frontmatter_end();
+        for line in self.source_text[offset..].split_inclusive('\n') {
+            let line_start = offset;
+            offset += line.len();
+            if line.trim().is_empty() {
+                if let Some(start) = open.take() {
+                    blocks.push((start, line_start));
+                }
+                continue;
+            }
+            if open.is_none() && is_esm_line(line) && !in_code_block(line_start) {
+                match blocks.last_mut() {
+                    // Only blank lines since the previous block
+                    Some((_, end)) if self.source_text[*end..line_start].trim().is_empty() => {
+                        open = Some(blocks.pop().unwrap().0);
+                    }
+                    _ => open = Some(line_start),
+                }
+            }
+        }
+        if let Some(start) = open {
+            blocks.push((start, self.source_text.len()));
+        }
+
+        blocks
+            .into_iter()
+            .map(|(start, end)| {
+                // NOTE: loader checked that source_text.len() is less than u32::MAX
+                #[allow(clippy::cast_possible_truncation)]
+                JavaScriptSource::partial(
+                    &self.source_text[start..end],
+                    SourceType::mjs().with_jsx(true),
+                    start as u32,
+                )
+            })
+            .collect()
+    }
+
+    /// End of the YAML frontmatter opening the document, `0` without one
+    fn frontmatter_end(&self) -> usize {
+        let Some(rest) = self
+            .source_text
+            .strip_prefix("---\n")
+            .or_else(|| self.source_text.strip_prefix("---\r\n"))
+        else {
+            return 0;
+        };
+        let mut offset = self.source_text.len() - rest.len();
+        for line in rest.split_inclusive('\n') {
+            offset += line.len();
+            if line.trim_end() == "---" {
+                return offset;
+            }
+        }
+        0
+    }
+}
+
+fn is_esm_line(line: &str) -> bool {
+    ["import", "export"].iter().any(|keyword| {
+        line.strip_prefix(keyword).is_some_and(|rest| {
+            rest.starts_with(|c: char| c.is_whitespace() || c == '{' || c == '*')
+        })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::MdxPartialLoader;
+
+    #[test]
+    fn test_parse_mdx() {
+        let source_text = r#"---
+title: Buttons
+---
+import { Button } from './button';
+import Chart from './chart';
+
+export const meta = { title: 'Buttons' };
+
+# Buttons
+
+Important information about the export button.
+
+```js
+import { Example } from './example';
+```
+
+<Button />
+
+export function Footer() {
+  return <Chart />;
+}
+"#;
+
+        let sources = MdxPartialLoader::new(source_text).parse();
+        assert_eq!(sources.len(), 2);
+        assert!(sources[0].source_text.starts_with("import { Button }"));
+        assert!(sources[0]
+            .source_text
+            .trim_end()
+            .ends_with("{ title: 'Buttons' };"));
+        assert!(sources[1]
+            .source_text
+            .starts_with("export function Footer()"));
+        let start = sources[1].start as usize;
+        assert_eq!(
+            &source_text[start..start + sources[1].source_text.len()],
+            sources[1].source_text
+        );
+    }
+}
@@ -1,14 +1,27 @@
+#![allow(clippy::sliced_string_as_bytes)]
+
 mod astro;
+mod html;
+mod markdown;
+mod mdx;
 mod svelte;
 mod vue;
 
-pub use self::{astro::AstroPartialLoader, svelte::SveltePartialLoader, vue::VuePartialLoader};
+pub use self::{
+    astro::AstroPartialLoader, html::HtmlPartialLoader, markdown::MarkdownPartialLoader,
+    mdx::MdxPartialLoader, svelte::SveltePartialLoader, vue::VuePartialLoader,
+};
+use oxc_span::SourceType;
+
 use crate::loader::JavaScriptSource;
 
 const SCRIPT_START: &str = "<script";
 const SCRIPT_END: &str = "</script>";
 
-pub const LINT_PARTIAL_LOADER_EXT: &[&str] = &["vue", "astro", "svelte"];
+pub const LINT_PARTIAL_LOADER_EXT: &[&str] = &["vue", "astro", "svelte", "html", "mdx"];
+
+/// Markdown documents, only loaded on demand since their code blocks are usually examples
+pub const MARKDOWN_EXT: &str = "md";
 
 pub struct PartialLoader;
 
@@ -20,9 +33,21 @@ impl PartialLoader {
             "vue" => Some(VuePartialLoader::new(source_text).parse()),
             "astro" => Some(AstroPartialLoader::new(source_text).parse()),
             "svelte" => Some(SveltePartialLoader::new(source_text).parse()),
+            "html" => Some(HtmlPartialLoader::new(source_text).parse()),
+            "mdx" => Some(MdxPartialLoader::new(source_text).parse()),
+            MARKDOWN_EXT => Some(MarkdownPartialLoader::new(source_text).parse()),
             _ => None,
         }
     }
+
+    /// Scripts a special file references by path instead of embedding them,
+    /// e.g. `<script type="module" src="/src/main.ts">` in `.html` files.
+    pub fn script_references<'a>(ext: &str, source_text: &'a str) -> Vec<&'a str> {
+        match ext {
+            "html" => HtmlPartialLoader::new(source_text).script_references(),
+            _ => vec![],
+        }
+    }
 }
 
 /// Find closing angle for situations where there is another `>` in between.
@@ -44,4 +69,91 @@ fn find_script_closing_angle(source_text: &str, pointer: usize) -> Option<usize>
         }
     }
     None
-}
\ No newline at end of file
+}
+
+/// Value of the attribute `name` among the `attributes` of an opening tag,
+/// e.g. `ts` for `lang` in ` setup lang="ts"`. Attributes without a value yield `""`.
+fn attribute_value<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attributes;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if rest.is_empty() {
+            return None;
+        }
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        let attribute = &rest[..name_end];
+        rest = rest[name_end..].trim_start();
+        let value = if let Some(after_equals) = rest.strip_prefix('=') {
+            let after_equals = after_equals.trim_start();
+            let (value, remaining) = match after_equals.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let value = &after_equals[1..];
+                    let end = value.find(quote).unwrap_or(value.len());
+                    (&value[..end], &value[(end + 1).min(value.len())..])
+                }
+                _ => {
+                    let end = after_equals
+                        .find(char::is_whitespace)
+                        .unwrap_or(after_equals.len());
+                    (&after_equals[..end], &after_equals[end..])
+                }
+            };
+            rest = remaining;
+            value
+        } else {
+            ""
+        };
+        if attribute.eq_ignore_ascii_case(name) {
+            return Some(value);
+        }
+    }
+}
+
+/// Source type of a script block from the `lang` and `type` attributes of its opening tag.
+/// Returns `None` for blocks which do not hold JavaScript, e.g. `type="text/x-template"`.
+fn script_source_type(attributes: &str) -> Option<SourceType> {
+    if let Some(kind) = attribute_value(attributes, "type") {
+        let is_javascript = matches!(
+            kind.to_ascii_lowercase().as_str(),
+            "" | "module" | "text/javascript" | "application/javascript" | "text/typescript"
+        );
+        if !is_javascript {
+            return None;
+        }
+    }
+    let source_type = SourceType::mjs();
+    Some(match attribute_value(attributes, "lang") {
+        Some("ts") => source_type.with_typescript(true),
+        Some("tsx") => source_type.with_typescript(true).with_jsx(true),
+        Some("jsx") => source_type.with_jsx(true),
+        _ => source_type,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{attribute_value, script_source_type};
+
+    #[test]
+    fn test_attribute_value() {
+        let attributes = r#" setup lang="ts" generic='T' type=module data-posts"#;
+        assert_eq!(attribute_value(attributes, "lang"), Some("ts"));
+        assert_eq!(attribute_value(attributes, "generic"), Some("T"));
+        assert_eq!(attribute_value(attributes, "type"), Some("module"));
+        assert_eq!(attribute_value(attributes, "setup"), Some(""));
+        assert_eq!(attribute_value(attributes, "data-posts"), Some(""));
+        assert_eq!(attribute_value(attributes, "src"), None);
+        assert_eq!(attribute_value(" lang = tsx", "lang"), Some("tsx"));
+    }
+
+    #[test]
+    fn test_script_source_type() {
+        let source_type = script_source_type(r#" lang="tsx""#).unwrap();
+        assert!(source_type.is_typescript() && source_type.is_jsx());
+        let source_type = script_source_type(r#" data-tests="1""#).unwrap();
+        assert!(!source_type.is_typescript());
+        assert!(script_source_type(r#" type="text/x-template""#).is_none());
+    }
+}
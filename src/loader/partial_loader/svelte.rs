@@ -1,7 +1,6 @@
 use memchr::memmem::Finder;
-use oxc_span::SourceType;
 
-use super::{find_script_closing_angle, SCRIPT_END, SCRIPT_START};
+use super::{find_script_closing_angle, script_source_type, SCRIPT_END, SCRIPT_START};
 use crate::loader::JavaScriptSource;
 
 pub struct SveltePartialLoader<'a> {
@@ -13,40 +12,50 @@ impl<'a> SveltePartialLoader<'a> {
         Self { source_text }
     }
 
+    /// A component has at most one instance `<script>` and one module script,
+    /// `<script context="module">` or `<script module>` since Svelte 5.
+    ///
+    /// <https://svelte.dev/docs/svelte/svelte-files>
     pub fn parse(self) -> Vec<JavaScriptSource<'a>> {
-        self.parse_script().map_or_else(Vec::new, |source| vec![source])
+        let mut pointer = 0;
+        let mut scripts = vec![];
+        while let Some(script) = self.parse_script(&mut pointer) {
+            scripts.extend(script);
+        }
+        scripts
     }
 
-    fn parse_script(&self) -> Option<JavaScriptSource<'a>> {
+    /// Parses the next `<script>` block, returning `Some(None)` for blocks to skip.
+    fn parse_script(&self, pointer: &mut usize) -> Option<Option<JavaScriptSource<'a>>> {
         let script_start_finder = Finder::new(SCRIPT_START);
         let script_end_finder = Finder::new(SCRIPT_END);
 
-        let mut pointer = 0;
-
         // find opening "<script"
-        let offset = script_start_finder.find(self.source_text[pointer..].as_bytes())?;
-        pointer += offset + SCRIPT_START.len();
+        let offset = script_start_finder.find(self.source_text[*pointer..].as_bytes())?;
+        *pointer += offset + SCRIPT_START.len();
 
         // find closing ">"
-        let offset = find_script_closing_angle(self.source_text, pointer)?;
+        let offset = find_script_closing_angle(self.source_text, *pointer)?;
 
-        // get lang="ts" attribute
-        let content = &self.source_text[pointer..pointer + offset];
-        let is_ts = content.contains("ts");
+        // get lang and type attributes
+        let attributes = &self.source_text[*pointer..*pointer + offset];
+        let source_type = script_source_type(attributes);
 
-        pointer += offset + 1;
-        let js_start = pointer;
+        *pointer += offset + 1;
+        let js_start = *pointer;
 
         // find "</script>"
-        let offset = script_end_finder.find(self.source_text[pointer..].as_bytes())?;
-        let js_end = pointer + offset;
+        let offset = script_end_finder.find(self.source_text[*pointer..].as_bytes())?;
+        let js_end = *pointer + offset;
+        *pointer += offset + SCRIPT_END.len();
 
+        let Some(source_type) = source_type else {
+            return Some(None);
+        };
         let source_text = &self.source_text[js_start..js_end];
-        let source_type = SourceType::mjs().with_typescript(is_ts);
-
         // NOTE: loader checked that source_text.len() is less than u32::MAX
         #[allow(clippy::cast_possible_truncation)]
-        Some(JavaScriptSource::partial(source_text, source_type, js_start as u32))
+        Some(Some(JavaScriptSource::partial(source_text, source_type, js_start as u32)))
     }
 }
 
@@ -84,4 +93,30 @@ mod test {
         let result = parse_svelte(source_text);
         assert_eq!(result.source_text.trim(), r#"console.log("hi");"#);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parse_svelte_module_script() {
+        let source_text = r#"
+        <script context="module" lang="ts">
+          export const prerender = true;
+        </script>
+        <script>
+          import Child from './Child.svelte';
+        </script>
+        <script type="application/ld+json">{}</script>
+        "#;
+
+        let sources = SveltePartialLoader::new(source_text).parse();
+        assert_eq!(sources.len(), 2);
+        assert_eq!(
+            sources[0].source_text.trim(),
+            "export const prerender = true;"
+        );
+        assert!(sources[0].source_type.is_typescript());
+        assert_eq!(
+            sources[1].source_text.trim(),
+            "import Child from './Child.svelte';"
+        );
+        assert!(!sources[1].source_type.is_typescript());
+    }
+}
@@ -1,7 +1,8 @@
 use memchr::memmem::Finder;
-use oxc_span::SourceType;
 
-use super::{find_script_closing_angle, JavaScriptSource, SCRIPT_END, SCRIPT_START};
+use super::{
+    find_script_closing_angle, script_source_type, JavaScriptSource, SCRIPT_END, SCRIPT_START,
+};
 
 pub struct VuePartialLoader<'a> {
     source_text: &'a str,
@@ -19,19 +20,20 @@ impl<'a> VuePartialLoader<'a> {
     /// Each *.vue file can contain at most
     ///  * one `<script>` block (excluding `<script setup>`).
     ///  * one `<script setup>` block (excluding normal `<script>`).
+    ///
+    /// Blocks which do not hold JavaScript, e.g. `<script type="text/x-template">`, are skipped.
     /// <https://vuejs.org/api/sfc-spec.html#script>
     fn parse_scripts(&self) -> Vec<JavaScriptSource<'a>> {
         let mut pointer = 0;
-        let Some(result1) = self.parse_script(&mut pointer) else {
-            return vec![];
-        };
-        let Some(result2) = self.parse_script(&mut pointer) else {
-            return vec![result1];
-        };
-        vec![result1, result2]
+        let mut scripts = vec![];
+        while let Some(script) = self.parse_script(&mut pointer) {
+            scripts.extend(script);
+        }
+        scripts
     }
 
-    fn parse_script(&self, pointer: &mut usize) -> Option<JavaScriptSource<'a>> {
+    /// Parses the next `<script>` block, returning `Some(None)` for blocks to skip.
+    fn parse_script(&self, pointer: &mut usize) -> Option<Option<JavaScriptSource<'a>>> {
         let script_start_finder = Finder::new(SCRIPT_START);
         let script_end_finder = Finder::new(SCRIPT_END);
 
@@ -42,10 +44,9 @@ impl<'a> VuePartialLoader<'a> {
         // find closing ">"
         let offset = find_script_closing_angle(self.source_text, *pointer)?;
 
-        // get ts and jsx attribute
-        let content = &self.source_text[*pointer..*pointer + offset];
-        let is_ts = content.contains("ts");
-        let is_jsx = content.contains("tsx") || content.contains("jsx");
+        // get lang and type attributes
+        let attributes = &self.source_text[*pointer..*pointer + offset];
+        let source_type = script_source_type(attributes);
 
         *pointer += offset + 1;
         let js_start = *pointer;
@@ -55,11 +56,13 @@ impl<'a> VuePartialLoader<'a> {
         let js_end = *pointer + offset;
         *pointer += offset + SCRIPT_END.len();
 
+        let Some(source_type) = source_type else {
+            return Some(None);
+        };
         let source_text = &self.source_text[js_start..js_end];
-        let source_type = SourceType::mjs().with_typescript(is_ts).with_jsx(is_jsx);
         // NOTE: loader checked that source_text.len() is less than u32::MAX
         #[allow(clippy::cast_possible_truncation)]
-        Some(JavaScriptSource::partial(source_text, source_type, js_start as u32))
+        Some(Some(JavaScriptSource::partial(source_text, source_type, js_start as u32)))
     }
 }
 
@@ -209,6 +212,21 @@ mod test {
         assert_eq!(sources[1].source_text, "b");
     }
 
+    #[test]
+    fn test_lang_attribute() {
+        let source_text = r#"
+        <script setup data-posts="1">a</script>
+        <script type="text/x-template" id="item">b</script>
+        <script lang="ts">c</script>
+        "#;
+        let sources = VuePartialLoader::new(source_text).parse();
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].source_text, "a");
+        assert!(!sources[0].source_type.is_typescript());
+        assert_eq!(sources[1].source_text, "c");
+        assert!(sources[1].source_type.is_typescript());
+    }
+
     #[test]
     fn test_unicode() {
         let source_text = r"
@@ -236,4 +254,4 @@ mod test {
             .trim()
         );
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,375 @@
+//! `marsh lsp`, a language server reporting diagnostics and answering questions about the
+//! graph in editors.
+//!
+//! The server speaks the language server protocol over stdio. Diagnostics, e.g. syntax errors
+//! and rule violations, are published once initialized and again whenever a file is saved.
+//! Beyond the standard requests, it answers two requests of its own, both taking
+//! `{ "textDocument": { "uri": ... } }` like standard ones:
+//!
+//! - `marsh/dependents`: the URIs of the files importing the document, directly or not
+//! - `marsh/dependencyTree`: the dependencies of the document as a tree of
+//!   `{ "path", "kind", "children" }`, with paths relative to the working directory of the
+//!   server
+//!
+//! Parsed files are kept in memory, so only the saved files are read and parsed again.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    collector::CollectorService,
+    daemon::{analyze, run, METHOD_NOT_FOUND},
+    rules::Severity,
+    service::{AnalyzeService, AnalyzeServiceOptions},
+    utils::{evict_unused, normalize_path, percent_decode},
+};
+
+pub struct LanguageServer {
+    cwd: PathBuf,
+    /// Entries, named like the nodes of the graph
+    entries: Vec<String>,
+    service: AnalyzeService,
+    collector: CollectorService,
+    /// Files diagnostics were last published for, to clear them once fixed
+    published: BTreeSet<PathBuf>,
+}
+
+impl LanguageServer {
+    /// Analyzes the paths of `options`, whose nodes are named `entries`
+    pub fn new(cwd: &Path, options: AnalyzeServiceOptions, entries: Vec<String>) -> Self {
+        let (service, collector) = analyze(options.with_resident(true), false);
+        Self {
+            cwd: cwd.to_path_buf(),
+            entries,
+            service,
+            collector,
+            published: BTreeSet::new(),
+        }
+    }
+
+    /// Handles the messages read from `reader` until the client exits or disconnects
+    ///
+    /// # Errors
+    /// When a message cannot be read or written
+    pub fn serve(mut self, mut reader: impl BufRead, mut writer: impl Write) -> Result<(), String> {
+        while let Some(message) = read_message(&mut reader)? {
+            if !self.handle(&message, &mut writer)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles one message, returns `false` once the client exits
+    fn handle(&mut self, message: &Value, writer: &mut impl Write) -> Result<bool, String> {
+        // Responses to requests of the server, which sends none
+        let Some(method) = message["method"].as_str() else {
+            return Ok(true);
+        };
+        let params = &message["params"];
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": { "textDocumentSync": { "save": true } },
+                "serverInfo": { "name": "marsh", "version": env!("CARGO_PKG_VERSION") },
+            }),
+            "initialized" => {
+                self.publish_diagnostics(writer)?;
+                return Ok(true);
+            }
+            "textDocument/didSave" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                self.service.invalidate(&self.cwd.join(path_from_uri(uri)));
+                self.collector = run(&self.service, false);
+                evict_unused();
+                self.publish_diagnostics(writer)?;
+                return Ok(true);
+            }
+            "marsh/dependents" => {
+                let path = self.node_name(params);
+                let dependents = self
+                    .collector
+                    .graph()
+                    .dependents(&path)
+                    .into_iter()
+                    .map(|path| uri_from_path(&self.cwd.join(path)))
+                    .collect::<Vec<_>>();
+                json!(dependents)
+            }
+            "marsh/dependencyTree" => {
+                let path = self.node_name(params);
+                self.collector
+                    .graph()
+                    .dependency_tree(&path)
+                    .unwrap_or(Value::Null)
+            }
+            "shutdown" => Value::Null,
+            "exit" => return Ok(false),
+            _ => {
+                if let Some(id) = message.get("id") {
+                    let error = json!({
+                        "code": METHOD_NOT_FOUND,
+                        "message": format!("Unhandled method {method}"),
+                    });
+                    write_message(
+                        writer,
+                        &json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+                    )?;
+                }
+                return Ok(true);
+            }
+        };
+        if let Some(id) = message.get("id") {
+            write_message(
+                writer,
+                &json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            )?;
+        }
+        Ok(true)
+    }
+
+    /// Publishes the diagnostics of the last run, clearing those of files which have none left
+    fn publish_diagnostics(&mut self, writer: &mut impl Write) -> Result<(), String> {
+        let mut diagnostics = self.diagnostics();
+        for path in std::mem::take(&mut self.published) {
+            diagnostics.entry(path).or_default();
+        }
+        for (path, diagnostics) in &diagnostics {
+            let params = json!({ "uri": uri_from_path(path), "diagnostics": diagnostics });
+            write_message(
+                writer,
+                &json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": params,
+                }),
+            )?;
+        }
+        self.published = diagnostics
+            .into_iter()
+            .filter(|(_, diagnostics)| !diagnostics.is_empty())
+            .map(|(path, _)| path)
+            .collect();
+        Ok(())
+    }
+
+    /// Diagnostics and rule violations of the last run by absolute path. Rule violations, and
+    /// diagnostics about a whole file, are shown at the start of the file.
+    fn diagnostics(&self) -> BTreeMap<PathBuf, Vec<Value>> {
+        let mut diagnostics = BTreeMap::<_, Vec<_>>::new();
+        for found in &self.collector.diagnostics {
+            let severity = match found.severity {
+                Severity::Off => continue,
+                Severity::Warn => 2,
+                Severity::Error => 1,
+            };
+            let position = found
+                .span
+                .map_or((0, 0), |span| (span.line - 1, span.column - 1));
+            diagnostics
+                .entry(self.cwd.join(&found.path))
+                .or_default()
+                .push(diagnostic(
+                    severity,
+                    Some(found.code.as_str()),
+                    position,
+                    &found.message,
+                ));
+        }
+        let graph_violations = self
+            .service
+            .graph_violations(self.collector.deps(), &self.entries);
+        for violation in self.collector.violations.iter().chain(&graph_violations) {
+            let severity = match violation.severity {
+                Severity::Off => continue,
+                Severity::Warn => 2,
+                Severity::Error => 1,
+            };
+            diagnostics
+                .entry(self.cwd.join(&violation.path))
+                .or_default()
+                .push(diagnostic(
+                    severity,
+                    Some(&violation.rule),
+                    (0, 0),
+                    &violation.message,
+                ));
+        }
+        diagnostics
+    }
+
+    /// Name of the node of the document of `params`, relative to the working directory
+    fn node_name(&self, params: &Value) -> String {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let path = self.cwd.join(path_from_uri(uri));
+        let path = path.strip_prefix(&self.cwd).unwrap_or(&path);
+        normalize_path(path).display().to_string()
+    }
+}
+
+/// An LSP diagnostic at `(line, character)`, both from 0
+fn diagnostic(severity: u8, code: Option<&str>, position: (usize, usize), message: &str) -> Value {
+    let start = json!({ "line": position.0, "character": position.1 });
+    json!({
+        "range": { "start": start, "end": start },
+        "severity": severity,
+        "code": code,
+        "source": "marsh",
+        "message": message,
+    })
+}
+
+/// Reads a message framed by its `Content-Length` header, `None` once the input ends
+fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>, String> {
+    let mut content_length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let read = reader
+            .read_line(&mut line)
+            .map_err(|error| format!("Failed to read a message: {error}"))?;
+        if read == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let content_length =
+        content_length.ok_or_else(|| "A message lacks its Content-Length".to_string())?;
+    let mut content = vec![0; content_length];
+    reader
+        .read_exact(&mut content)
+        .map_err(|error| format!("Failed to read a message: {error}"))?;
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(|error| format!("Invalid message: {error}"))
+}
+
+fn write_message(writer: &mut impl Write, message: &Value) -> Result<(), String> {
+    let content = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{content}", content.len())
+        .and_then(|()| writer.flush())
+        .map_err(|error| format!("Failed to write a message: {error}"))
+}
+
+/// Path of a `file://` URI, with its percent-encoded bytes decoded
+fn path_from_uri(uri: &str) -> PathBuf {
+    PathBuf::from(percent_decode(uri.strip_prefix("file://").unwrap_or(uri)))
+}
+
+/// `file://` URI of an absolute path, percent-encoding the bytes which need it
+fn uri_from_path(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for &byte in path.to_string_lossy().as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            uri.push(char::from(byte));
+        } else {
+            uri.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use serde_json::{json, Value};
+
+    use super::{path_from_uri, read_message, uri_from_path, LanguageServer};
+    use crate::{service::AnalyzeServiceOptions, utils::TestDir};
+
+    #[test]
+    fn test_lsp() {
+        let root = TestDir::new("lsp");
+        for (file, contents) in [
+            ("main.ts", "import './a'; import './b'; import './d';"),
+            ("a.ts", "import './c';"),
+            ("b.ts", "import './a';"),
+            ("c.ts", ""),
+            ("d.ts", "const = 1;"),
+        ] {
+            root.write(file, contents);
+        }
+
+        let options = AnalyzeServiceOptions::new(&*root, vec![root.join("main.ts").into()])
+            .with_cross_module(true);
+        let server = LanguageServer::new(&root, options, vec!["main.ts".to_string()]);
+        let document =
+            |file: &str| json!({ "textDocument": { "uri": uri_from_path(&root.join(file)) } });
+        let mut input = String::new();
+        for message in [
+            json!({ "id": 1, "method": "initialize", "params": {} }),
+            json!({ "method": "initialized", "params": {} }),
+            json!({ "id": 2, "method": "marsh/dependents", "params": document("c.ts") }),
+            json!({ "id": 3, "method": "marsh/dependencyTree", "params": document("main.ts") }),
+            json!({ "id": 4, "method": "textDocument/hover", "params": document("a.ts") }),
+            json!({ "method": "textDocument/didSave", "params": document("d.ts") }),
+            json!({ "id": 5, "method": "shutdown" }),
+            json!({ "method": "exit" }),
+            json!({ "id": 6, "method": "shutdown" }),
+        ] {
+            let content = message.to_string();
+            input.push_str(&format!(
+                "Content-Length: {}\r\n\r\n{content}",
+                content.len()
+            ));
+        }
+        // Fixed before the save
+        std::fs::write(root.join("d.ts"), "const d = 1;").unwrap();
+
+        let mut output = Vec::new();
+        server.serve(Cursor::new(input), &mut output).unwrap();
+        let mut output = Cursor::new(output);
+        let mut messages = Vec::<Value>::new();
+        while let Some(message) = read_message(&mut output).unwrap() {
+            messages.push(message);
+        }
+        // The last request is not answered, the client exited before
+        assert_eq!(messages.len(), 7);
+        assert_eq!(messages[0]["id"], 1);
+        assert_eq!(messages[1]["method"], "textDocument/publishDiagnostics");
+        assert_eq!(
+            messages[1]["params"]["uri"],
+            uri_from_path(&root.join("d.ts"))
+        );
+        assert_eq!(messages[1]["params"]["diagnostics"][0]["severity"], 1);
+        assert_eq!(
+            messages[2]["result"],
+            json!([
+                uri_from_path(&root.join("a.ts")),
+                uri_from_path(&root.join("b.ts")),
+                uri_from_path(&root.join("main.ts")),
+            ])
+        );
+        let tree = &messages[3]["result"];
+        assert_eq!(tree["path"], "main.ts");
+        assert_eq!(tree["children"][0]["path"], "a.ts");
+        assert_eq!(tree["children"][0]["children"][0]["path"], "c.ts");
+        // `a.ts` is under `main.ts` only, where it is first reached from
+        assert_eq!(tree["children"][1]["children"], json!([]));
+        assert_eq!(messages[4]["error"]["code"], -32601);
+        // The diagnostics of `d.ts` are cleared once it is fixed
+        assert_eq!(messages[5]["params"]["diagnostics"], json!([]));
+        assert_eq!(messages[6]["id"], 5);
+    }
+
+    #[test]
+    fn test_uri() {
+        let path = std::path::Path::new("/tmp/my project/a#b.ts");
+        let uri = uri_from_path(path);
+        assert_eq!(uri, "file:///tmp/my%20project/a%23b.ts");
+        assert_eq!(path_from_uri(&uri), path);
+    }
+}
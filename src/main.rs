@@ -1,23 +1,514 @@
-mod collector;
-mod graph;
-mod loader;
-mod message;
-mod service;
-mod utils;
+mod cli;
+#[cfg(test)]
+#[allow(dead_code)]
+#[path = "utils/test_dir.rs"]
+mod test_dir;
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use clap::Parser;
+use marsh::{
+    affected, barrel, baseline, build_deps, code_quality, collector, cycles, daemon, dead_files,
+    deps_of, import_cost, lsp, nx, package_level, package_reports, progress, project_references,
+    rules, sarif, serve, service, stats, turbo, utils, workspace, workspace_deep_imports,
+};
+use serde::Serialize;
 
 fn main() {
+    let cli_options = cli::CliOptions::parse();
+
     let cwd = std::env::current_dir().unwrap();
-    // get args
-    let args: Vec<Box<std::path::Path>> = std::env::args()
-        .map(|arg| std::path::Path::new(&arg).into())
-        .collect();
 
-    let mut collector = collector::CollectorService::default();
+    if let Some(command) = &cli_options.command {
+        match command {
+            cli::Command::TraceSymbol {
+                file,
+                name,
+                analyze,
+            } => trace_symbol(&cwd, file, name, analyze),
+            cli::Command::Check {
+                entries,
+                check: check_options,
+                write_baseline,
+                analyze,
+            } => check(
+                &cwd,
+                &entry_paths(&cwd, entries),
+                check_options,
+                *write_baseline,
+                analyze,
+            ),
+            cli::Command::Stats {
+                entries,
+                threads,
+                format,
+                analyze,
+            } => stats(
+                &cwd,
+                &entry_paths(&cwd, entries),
+                *threads,
+                *format,
+                analyze,
+            ),
+            cli::Command::Daemon {
+                entries,
+                socket,
+                stdio,
+                analyze,
+            } => daemon(
+                &cwd,
+                &entry_paths(&cwd, entries),
+                socket.as_deref(),
+                *stdio,
+                analyze,
+            ),
+            cli::Command::Affected {
+                entries,
+                since,
+                list,
+                format,
+                analyze,
+            } => affected(
+                &cwd,
+                &entry_paths(&cwd, entries),
+                since,
+                *list,
+                *format,
+                analyze,
+            ),
+            cli::Command::AffectedPackages {
+                entries,
+                since,
+                format,
+                analyze,
+            } => affected_packages(&cwd, &entry_paths(&cwd, entries), since, *format, analyze),
+            cli::Command::Lsp { entries, analyze } => {
+                lsp(&cwd, &entry_paths(&cwd, entries), analyze)
+            }
+            cli::Command::DepsOf {
+                file,
+                cache_dir,
+                json,
+            } => deps_of(&cwd, file, cache_dir, *json),
+            cli::Command::Serve {
+                entries,
+                address,
+                analyze,
+            } => serve(&cwd, &entry_paths(&cwd, entries), address, analyze),
+        }
+        return;
+    }
 
-    let options = service::AnalyzeServiceOptions::new(cwd, args.clone()).with_cross_module(true);
-    let ana_service = service::AnalyzeService::new(options);
+    let references = cli_options.project_references.then(|| {
+        let tsconfig = cli_options.analyze.tsconfig.as_deref();
+        let tsconfig = cwd.join(tsconfig.unwrap_or(Path::new("tsconfig.json")));
+        project_references::ProjectReferences::discover(&tsconfig)
+            .unwrap_or_else(|error| exit_with_error(&error))
+    });
+    // The files of the projects are analyzed along with the paths given
+    let paths = match &references {
+        Some(references) => cli_options
+            .entries
+            .paths
+            .iter()
+            .cloned()
+            .chain(references.files())
+            .collect(),
+        None => entry_paths(&cwd, &cli_options.entries),
+    };
+    let options = cli_options
+        .analyze
+        .service_options(&cwd, &paths)
+        .unwrap_or_else(|error| exit_with_error(&error))
+        .with_record_resolutions(cli_options.resolutions || cli_options.workspace_deep_imports);
+    let cancellation = service::CancellationToken::default();
+    let ana_service = service::AnalyzeService::new(options.with_cancellation(cancellation.clone()));
+
+    if let Some(specifier) = &cli_options.explain_resolution {
+        for path in &paths {
+            println!(
+                "{}",
+                ana_service.explain_resolution(&cwd.join(path), specifier)
+            );
+        }
+        return;
+    }
+
+    let baseline = load_baseline(&cwd, &cli_options.check);
+    let collector = collector(&cwd, &cli_options.check, &baseline, cancellation);
+    let mut collector = analyze(&ana_service, collector);
+
+    let entries = entries(&cwd, &paths);
+    let violations = violations(&ana_service, &mut collector, &entries);
+    if let Some(out_dir) = &cli_options.out_dir {
+        let Some(workspace) = ana_service.workspace() else {
+            exit_with_error("--out-dir needs an npm, Yarn or pnpm workspace");
+        };
+        package_reports::PackageReports::new(&cwd, workspace, collector.deps(), &violations)
+            .write(&cwd.join(out_dir))
+            .unwrap_or_else(|error| exit_with_error(&error));
+    }
+    let failed = report_violations(
+        &cli_options.check,
+        cli_options.analyze.error_on_unresolved,
+        &baseline,
+        violations,
+        &mut collector,
+    );
+
+    print_output(
+        &cli_options,
+        &cwd,
+        &entries,
+        &ana_service,
+        &mut collector,
+        references.as_ref(),
+    );
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// The paths of `entries`, or the entries of the packages of the workspace of `cwd` when none
+/// are given
+fn entry_paths(cwd: &Path, entries: &cli::Entries) -> Vec<PathBuf> {
+    if !entries.paths.is_empty() {
+        return entries.paths.clone();
+    }
+    let entries = workspace::Workspace::discover(cwd)
+        .map(|workspace| workspace.entries())
+        .unwrap_or_default();
+    if entries.is_empty() {
+        exit_with_error(
+            "No entry files given, and no workspace package entries were found: \
+             pass the entry files to start the analysis from",
+        );
+    }
+    entries
+        .into_iter()
+        .map(|entry| {
+            entry
+                .strip_prefix(cwd)
+                .map_or(entry.clone(), Path::to_path_buf)
+        })
+        .collect()
+}
+
+/// Entry paths relative to `cwd`, as the nodes of the graph are named
+fn entries(cwd: &Path, paths: &[PathBuf]) -> Vec<String> {
+    paths
+        .iter()
+        .map(|path| {
+            let path = cwd.join(path);
+            let path = path.strip_prefix(cwd).unwrap_or(&path);
+            utils::normalize_path(path).display().to_string()
+        })
+        .collect()
+}
+
+/// Every violation of the configured rules
+fn violations(
+    ana_service: &service::AnalyzeService,
+    collector: &mut collector::CollectorService,
+    entries: &[String],
+) -> Vec<rules::RuleViolation> {
+    let mut violations = std::mem::take(&mut collector.violations);
+    violations.extend(ana_service.graph_violations(collector.deps(), entries));
+    violations
+}
+
+/// The baseline of `--baseline`, or the baseline file of `cwd`
+fn load_baseline(cwd: &Path, check_options: &cli::CheckOptions) -> baseline::Baseline {
+    baseline::Baseline::load(cwd, check_options.baseline.as_deref())
+        .unwrap_or_else(|error| exit_with_error(&error))
+}
+
+/// A collector reporting to the terminal and to the reports asked for, cancelling the run
+/// through `cancellation` past `--max-errors` errors missing from `baseline`
+fn collector(
+    cwd: &Path,
+    check_options: &cli::CheckOptions,
+    baseline: &baseline::Baseline,
+    cancellation: service::CancellationToken,
+) -> collector::CollectorService {
+    let mut collector = collector::CollectorService::default().with_baseline(baseline);
+    if let Some(max_errors) = check_options.max_errors {
+        collector = collector.with_max_errors(max_errors, cancellation);
+    }
+    if !check_options.reporters.is_empty() {
+        let sinks = check_options
+            .reporters
+            .iter()
+            .map(|reporter| reporter_sink(cwd, reporter))
+            .collect();
+        return collector.with_sinks(sinks);
+    }
+    if check_options.diagnostics_format == cli::DiagnosticsFormat::Json {
+        collector = collector.with_sinks(vec![Box::new(collector::TerminalJsonSink)]);
+    }
+    if let Some(path) = &check_options.code_quality {
+        collector = collector.with_sink(code_quality::CodeQualitySink::new(cwd.join(path)));
+    }
+    if let Some(path) = &check_options.sarif {
+        collector = collector.with_sink(sarif::SarifSink::new(cwd.join(path)));
+    }
+    if let Some(path) = &check_options.json_report {
+        collector = collector.with_sink(collector::JsonSink::new(cwd.join(path)));
+    }
+    collector
+}
+
+/// The sink reporting what `--reporter` asked for
+fn reporter_sink(cwd: &Path, reporter: &cli::Reporter) -> Box<dyn collector::CollectorSink> {
+    match reporter {
+        cli::Reporter::Human => Box::new(collector::TerminalSink),
+        cli::Reporter::Json(None) => Box::new(collector::TerminalJsonSink),
+        cli::Reporter::Json(Some(path)) => Box::new(collector::JsonSink::new(cwd.join(path))),
+        cli::Reporter::Sarif(path) => Box::new(sarif::SarifSink::new(cwd.join(path))),
+        cli::Reporter::CodeQuality(path) => {
+            Box::new(code_quality::CodeQualitySink::new(cwd.join(path)))
+        }
+    }
+}
+
+/// Reports the violations missing from the baseline to the sinks of `collector`, returns
+/// `true` when they fail the run: when there are errors, or more warnings than allowed, or
+/// unresolved imports with `--error-on-unresolved`, or when the run stopped at `--max-errors`.
+fn report_violations(
+    check_options: &cli::CheckOptions,
+    error_on_unresolved: bool,
+    baseline: &baseline::Baseline,
+    violations: Vec<rules::RuleViolation>,
+    collector: &mut collector::CollectorService,
+) -> bool {
+    let violations = baseline.new_violations(violations);
+    collector
+        .finish(&violations)
+        .unwrap_or_else(|error| exit_with_error(&error));
+    let warnings = violations
+        .iter()
+        .filter(|violation| violation.severity == rules::Severity::Warn)
+        .count();
+    let too_many_warnings = check_options
+        .max_warnings
+        .is_some_and(|max_warnings| warnings > max_warnings);
+    if too_many_warnings {
+        eprintln!(
+            "{warnings} warnings exceed the maximum of {}",
+            check_options.max_warnings.unwrap_or_default()
+        );
+    }
+    // Unresolved imports of `--external` are warnings
+    let unresolved = collector
+        .diagnostics
+        .iter()
+        .filter(|diagnostic| {
+            error_on_unresolved
+                && diagnostic.code == marsh::DiagnosticCode::UnresolvedImport
+                && diagnostic.severity == rules::Severity::Error
+        })
+        .count();
+    match unresolved {
+        0 => {}
+        1 => eprintln!("1 unresolved import fails the run"),
+        _ => eprintln!("{unresolved} unresolved imports fail the run"),
+    }
+    let reached_max_errors = collector.reached_max_errors();
+    if reached_max_errors {
+        eprintln!(
+            "Stopped after {} errors, the analysis is incomplete",
+            check_options.max_errors.unwrap_or_default()
+        );
+    }
+    let errors = violations
+        .iter()
+        .any(|violation| violation.severity == rules::Severity::Error);
+    errors || too_many_warnings || unresolved > 0 || reached_max_errors
+}
+
+/// Prints the graph, or the report asked for
+fn print_output(
+    cli_options: &cli::CliOptions,
+    cwd: &Path,
+    entries: &[String],
+    ana_service: &service::AnalyzeService,
+    collector: &mut collector::CollectorService,
+    references: Option<&project_references::ProjectReferences>,
+) {
+    if let Some(references) = references {
+        print_report(
+            &references.report(cwd, collector.deps()),
+            cli_options.format,
+        );
+        return;
+    }
+
+    if cli_options.resolutions {
+        let dump = ana_service.resolutions();
+        println!("{}", serde_json::to_string_pretty(&dump).unwrap());
+        return;
+    }
+
+    if cli_options.workspace_deep_imports {
+        let Some(workspace) = ana_service.workspace() else {
+            exit_with_error("--workspace-deep-imports needs an npm, Yarn or pnpm workspace");
+        };
+        let report = workspace_deep_imports::WorkspaceDeepImportReport::new(
+            cwd,
+            workspace,
+            &ana_service.resolutions(),
+        );
+        print_report(&report, cli_options.format);
+        return;
+    }
+
+    if cli_options.flatten_barrels {
+        ana_service.flatten_barrels(collector.deps_mut());
+    }
+
+    if cli_options.dead_files {
+        let reachable = collector
+            .deps()
+            .iter()
+            .flat_map(|(src, dst)| [src.as_str(), dst.file_path.as_str()])
+            .chain(entries.iter().map(String::as_str))
+            .collect();
+        let report = dead_files::DeadFileReport::new(cwd, &reachable);
+        print_report(&report, cli_options.format);
+        return;
+    }
+
+    if cli_options.cycles && cli_options.granularity == cli::Granularity::Package {
+        let graph = package_level_graph(cwd, ana_service, collector.deps());
+        print_report(&cycles::PackageCycleReport::new(&graph), cli_options.format);
+        return;
+    }
+
+    if cli_options.cycles {
+        let report = cycles::CycleReport::new(collector.deps(), |message| {
+            ana_service.imports_only_types(message)
+        });
+        print_report(&report, cli_options.format);
+        return;
+    }
+
+    if cli_options.unused_dependencies {
+        print_report(&ana_service.unused_dependencies(), cli_options.format);
+        return;
+    }
+
+    if cli_options.nx_project_graph {
+        let Some(workspace) = ana_service.workspace() else {
+            exit_with_error("--nx-project-graph needs an npm, Yarn or pnpm workspace");
+        };
+        let graph = nx::NxProjectGraph::new(cwd, workspace, collector.deps());
+        println!("{}", serde_json::to_string_pretty(&graph).unwrap());
+        return;
+    }
+
+    if let Some(build_deps) = cli_options.build_deps {
+        let report = match build_deps {
+            cli::BuildDeps::Make => build_deps::BuildDepsReport::make(collector.deps()),
+            cli::BuildDeps::Bazel => build_deps::BuildDepsReport::bazel(cwd, collector.deps()),
+        };
+        print_report(&report, cli_options.format);
+        return;
+    }
+
+    if cli_options.turbo {
+        let Some(workspace) = ana_service.workspace() else {
+            exit_with_error("--turbo needs an npm, Yarn or pnpm workspace");
+        };
+        let report = turbo::TurboReport::new(cwd, workspace, collector.deps())
+            .unwrap_or_else(|error| exit_with_error(&error));
+        print_report(&report, cli_options.format);
+        return;
+    }
+
+    if cli_options.granularity == cli::Granularity::Package {
+        let graph = package_level_graph(cwd, ana_service, collector.deps());
+        print_report(&graph, cli_options.format);
+        return;
+    }
+
+    let mut graph_builder = collector.graph();
+
+    if cli_options.barrels {
+        let barrels = ana_service
+            .barrels()
+            .into_iter()
+            .map(|path| barrel::Barrel {
+                transitive_modules: graph_builder.dependency_count(&path),
+                path,
+            })
+            .collect();
+        let report = barrel::BarrelReport::new(barrels);
+        print_report(&report, cli_options.format);
+        return;
+    }
+
+    if cli_options.import_cost {
+        let report =
+            import_cost::ImportCostReport::new(cwd, entries, &graph_builder, cli_options.top);
+        print_report(&report, cli_options.format);
+        return;
+    }
+
+    // Nodes are attributed to the workspace package owning them in the JSON output
+    if let (cli::OutputFormat::Json, Some(workspace)) =
+        (cli_options.format, ana_service.workspace())
+    {
+        graph_builder.attribute_packages(|path| {
+            workspace
+                .package_of_file(&cwd.join(path))
+                .map(str::to_string)
+        });
+    }
 
-    // Spawn linting in another thread so diagnostics can be printed immediately from diagnostic_service.run.
+    match cli_options.format {
+        cli::OutputFormat::Dot => graph_builder.dot(),
+        cli::OutputFormat::Json => graph_builder.json(),
+    }
+}
+
+/// `deps` collapsed into the packages of the workspace, for `--granularity package`
+fn package_level_graph(
+    cwd: &Path,
+    ana_service: &service::AnalyzeService,
+    deps: &[(utils::PathAtom, marsh::Message)],
+) -> package_level::PackageLevelGraph {
+    let Some(workspace) = ana_service.workspace() else {
+        exit_with_error("--granularity package needs an npm, Yarn or pnpm workspace");
+    };
+    package_level::PackageLevelGraph::new(cwd, workspace, deps)
+}
+
+fn exit_with_error(error: &str) -> ! {
+    eprintln!("{error}");
+    std::process::exit(1);
+}
+
+/// Prints a report as text, or as JSON for `--format json`.
+fn print_report<T: fmt::Display + Serialize>(report: &T, format: cli::OutputFormat) {
+    match format {
+        cli::OutputFormat::Dot => println!("{report}"),
+        cli::OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(report).unwrap());
+        }
+    }
+}
+
+/// Runs the analysis, collecting diagnostics in `collector` for its sinks.
+fn analyze(
+    ana_service: &service::AnalyzeService,
+    mut collector: collector::CollectorService,
+) -> collector::CollectorService {
+    // Spawn linting in another thread so the collector receives results as they arrive
     rayon::spawn({
         let tx_error = collector.sender().clone();
         let lint_service = ana_service.clone();
@@ -25,10 +516,286 @@ fn main() {
             lint_service.run(&tx_error);
         }
     });
+    let progress = progress::Progress::start(ana_service);
     collector.start();
+    if let Some(progress) = progress {
+        progress.finish();
+    }
+    if let Some(tracer) = ana_service.tracer() {
+        if let Err(error) = tracer.export() {
+            eprintln!("{error}");
+        }
+    }
+    ana_service
+        .check_limits()
+        .unwrap_or_else(|error| exit_with_error(&error));
+    collector
+}
 
-    let mut graph_builder = graph::GraphBuilder::new();
+/// Runs the analysis for a command which checks no rule, printing its diagnostics
+fn analyze_diagnostics(ana_service: &service::AnalyzeService) -> collector::CollectorService {
+    let mut collector = analyze(ana_service, collector::CollectorService::default());
+    collector
+        .finish(&[])
+        .unwrap_or_else(|error| exit_with_error(&error));
+    collector
+}
+
+fn check(
+    cwd: &Path,
+    paths: &[PathBuf],
+    check_options: &cli::CheckOptions,
+    write_baseline: bool,
+    analyze_options: &cli::AnalyzeOptions,
+) {
+    let options = analyze_options
+        .service_options(cwd, paths)
+        .unwrap_or_else(|error| exit_with_error(&error));
+    let cancellation = service::CancellationToken::default();
+    let ana_service = service::AnalyzeService::new(options.with_cancellation(cancellation.clone()));
+    // The baseline is written over, not read
+    let baseline = if write_baseline {
+        baseline::Baseline::default()
+    } else {
+        load_baseline(cwd, check_options)
+    };
+    let collector = collector(cwd, check_options, &baseline, cancellation);
+    let mut collector = analyze(&ana_service, collector);
+    let violations = violations(&ana_service, &mut collector, &entries(cwd, paths));
+
+    if write_baseline {
+        collector
+            .finish(&[])
+            .unwrap_or_else(|error| exit_with_error(&error));
+        let baseline = baseline::Baseline::new(&violations);
+        baseline
+            .write(cwd, check_options.baseline.as_deref())
+            .unwrap_or_else(|error| exit_with_error(&error));
+        println!("{} violations recorded in the baseline", violations.len());
+        return;
+    }
 
-    graph_builder.add_deps(&collector.deps);
-    graph_builder.dot();
+    let known = violations.len();
+    if report_violations(
+        check_options,
+        analyze_options.error_on_unresolved,
+        &baseline,
+        violations,
+        &mut collector,
+    ) {
+        std::process::exit(1);
+    }
+    println!("No new violations found, {known} recorded in the baseline.");
+}
+
+fn stats(
+    cwd: &Path,
+    paths: &[PathBuf],
+    threads: Option<usize>,
+    format: cli::OutputFormat,
+    analyze_options: &cli::AnalyzeOptions,
+) {
+    if let Some(threads) = threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .unwrap_or_else(|error| exit_with_error(&error.to_string()));
+    }
+    let options = analyze_options
+        .service_options(cwd, paths)
+        .unwrap_or_else(|error| exit_with_error(&error));
+    let start = Instant::now();
+    let ana_service = service::AnalyzeService::new(options);
+    let collector = analyze_diagnostics(&ana_service);
+    let report = stats::StatsReport::new(
+        ana_service.counters(),
+        collector.deps().len(),
+        rayon::current_num_threads(),
+        start.elapsed(),
+    );
+    print_report(&report, format);
+}
+
+fn daemon(
+    cwd: &Path,
+    paths: &[PathBuf],
+    socket: Option<&Path>,
+    stdio: bool,
+    analyze_options: &cli::AnalyzeOptions,
+) {
+    let options = analyze_options
+        .service_options(cwd, paths)
+        .unwrap_or_else(|error| exit_with_error(&error));
+    if stdio {
+        daemon::Daemon::new(cwd, options, entries(cwd, paths)).serve_stdio();
+        return;
+    }
+    let cache_dir = cwd.join(&analyze_options.cache_dir);
+    let socket = socket.map_or_else(|| cache_dir.join(daemon::SOCKET_FILE), |s| cwd.join(s));
+    if let Some(dir) = socket.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let daemon = daemon::Daemon::new(cwd, options, entries(cwd, paths));
+    eprintln!("Listening on {}", socket.display());
+    daemon
+        .serve(&socket)
+        .unwrap_or_else(|error| exit_with_error(&error));
+}
+
+fn affected(
+    cwd: &Path,
+    paths: &[PathBuf],
+    since: &str,
+    list: cli::AffectedList,
+    format: cli::ListFormat,
+    analyze_options: &cli::AnalyzeOptions,
+) {
+    let options = analyze_options
+        .service_options(cwd, paths)
+        .unwrap_or_else(|error| exit_with_error(&error));
+    let changed =
+        affected::changed_files(cwd, since).unwrap_or_else(|error| exit_with_error(&error));
+    let ana_service = service::AnalyzeService::new(options);
+    let collector = analyze_diagnostics(&ana_service);
+    let report = affected::AffectedReport::new(cwd, changed, &collector.graph());
+    match format {
+        cli::ListFormat::Text => {
+            let list = match list {
+                cli::AffectedList::Files => &report.files,
+                cli::AffectedList::Packages => &report.packages,
+                cli::AffectedList::Tests => &report.tests,
+            };
+            for item in list {
+                println!("{item}");
+            }
+        }
+        cli::ListFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        }
+    }
+}
+
+fn affected_packages(
+    cwd: &Path,
+    paths: &[PathBuf],
+    since: &str,
+    format: cli::ListFormat,
+    analyze_options: &cli::AnalyzeOptions,
+) {
+    let options = analyze_options
+        .service_options(cwd, paths)
+        .unwrap_or_else(|error| exit_with_error(&error));
+    let changed =
+        affected::changed_files(cwd, since).unwrap_or_else(|error| exit_with_error(&error));
+    let ana_service = service::AnalyzeService::new(options);
+    let Some(workspace) = ana_service.workspace() else {
+        exit_with_error("marsh affected-packages needs an npm, Yarn or pnpm workspace");
+    };
+    let collector = analyze_diagnostics(&ana_service);
+    let graph = package_level::PackageLevelGraph::new(cwd, workspace, collector.deps());
+    let report = affected::AffectedPackagesReport::new(cwd, workspace, &changed, &graph);
+    match format {
+        cli::ListFormat::Text => print!("{report}"),
+        cli::ListFormat::Json => println!("{}", serde_json::to_string_pretty(&report).unwrap()),
+    }
+}
+
+fn lsp(cwd: &Path, paths: &[PathBuf], analyze_options: &cli::AnalyzeOptions) {
+    let options = analyze_options
+        .service_options(cwd, paths)
+        .unwrap_or_else(|error| exit_with_error(&error));
+    let server = lsp::LanguageServer::new(cwd, options, entries(cwd, paths));
+    server
+        .serve(std::io::stdin().lock(), std::io::stdout().lock())
+        .unwrap_or_else(|error| exit_with_error(&error));
+}
+
+fn deps_of(cwd: &Path, file: &Path, cache_dir: &Path, json: bool) {
+    let report = deps_of::DepsOfReport::from_cache(cwd, cache_dir, file)
+        .unwrap_or_else(|error| exit_with_error(&error));
+    if json {
+        print_report(&report, cli::OutputFormat::Json);
+        return;
+    }
+    print!("{report}");
+    if !report.stale.is_empty() {
+        eprintln!(
+            "{} files changed or added since the stored run, run with --incremental to refresh it",
+            report.stale.len()
+        );
+    }
+}
+
+fn serve(cwd: &Path, paths: &[PathBuf], address: &str, analyze_options: &cli::AnalyzeOptions) {
+    let options = analyze_options
+        .service_options(cwd, paths)
+        .unwrap_or_else(|error| exit_with_error(&error));
+    let server = serve::Server::new(cwd, options);
+    eprintln!("Serving the graph on http://{address}");
+    server
+        .serve(address)
+        .unwrap_or_else(|error| exit_with_error(&error));
+}
+
+fn trace_symbol(cwd: &Path, file: &Path, name: &str, analyze_options: &cli::AnalyzeOptions) {
+    let options = analyze_options
+        .service_options(cwd, &[file.to_path_buf()])
+        .unwrap_or_else(|error| exit_with_error(&error));
+    let ana_service = service::AnalyzeService::new(options);
+    analyze_diagnostics(&ana_service);
+
+    let Some(trace) = ana_service.trace_symbol(&cwd.join(file), name) else {
+        eprintln!("{}: could not be analyzed", file.display());
+        std::process::exit(1);
+    };
+    for step in &trace.steps {
+        let path = &step.record.resolved_absolute_path;
+        let path = path.strip_prefix(cwd).unwrap_or(path).display();
+        match (&step.via, &step.name) {
+            (Some(via), _) => println!("{path}: {via}"),
+            (None, Some(name)) => println!("{path}: defines '{name}'"),
+            (None, None) => println!("{path}: module namespace object"),
+        }
+    }
+    if !trace.found {
+        eprintln!("'{name}' could not be traced to its definition");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use clap::Parser;
+    use marsh::{baseline::Baseline, service::CancellationToken};
+
+    use super::{cli, collector, test_dir::TestDir};
+
+    #[test]
+    fn test_reporters() {
+        let cwd = TestDir::new("reporters");
+        let options = cli::CliOptions::parse_from([
+            "marsh",
+            "check",
+            "--reporter",
+            "json:marsh.json",
+            "--reporter",
+            "sarif:marsh.sarif",
+            "--reporter",
+            "code-quality:gl-code-quality.json",
+        ]);
+        let Some(cli::Command::Check { check, .. }) = options.command else {
+            panic!("not the check command");
+        };
+        collector(
+            &cwd,
+            &check,
+            &Baseline::default(),
+            CancellationToken::default(),
+        )
+        .finish(&[])
+        .unwrap();
+        for report in ["marsh.json", "marsh.sarif", "gl-code-quality.json"] {
+            assert!(cwd.join(report).is_file(), "{report}");
+        }
+    }
 }
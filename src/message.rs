@@ -1,4 +1,32 @@
-#[derive(Debug)]
+use crate::{
+    graph::{EdgeType, NodeKind},
+    utils::PathAtom,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Message {
-    pub file_path: String,
+    pub file_path: PathAtom,
+    pub kind: NodeKind,
+    pub edge: EdgeType,
+    /// Names of the bindings imported through the edge, sorted.
+    /// `default` for default imports and `*` for namespace imports and star re-exports
+    pub symbols: Vec<String>,
+}
+
+impl Message {
+    pub fn new(file_path: impl Into<PathAtom>, kind: NodeKind, edge: EdgeType) -> Self {
+        Self {
+            file_path: file_path.into(),
+            kind,
+            edge,
+            symbols: Vec::new(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_symbols(mut self, symbols: Vec<String>) -> Self {
+        self.symbols = symbols;
+        self
+    }
 }
@@ -0,0 +1,139 @@
+//! The graph of packages in the shape of Nx's `project-graph.json`, as written by
+//! `nx graph --file`, for Nx-based monorepos to take their project graph from marsh.
+//!
+//! <https://nx.dev/nx-api/nx/documents/dep-graph>
+
+use std::{collections::BTreeMap, path::Path};
+
+use serde::Serialize;
+
+use crate::{
+    message::Message, package_level::PackageLevelGraph, utils::PathAtom, workspace::Workspace,
+};
+
+#[derive(Debug, Default, Serialize)]
+pub struct NxProjectGraph {
+    pub graph: NxGraph,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct NxGraph {
+    /// Projects by name
+    pub nodes: BTreeMap<String, NxProject>,
+    /// Dependencies of each project, empty for projects without any
+    pub dependencies: BTreeMap<String, Vec<NxDependency>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NxProject {
+    pub name: String,
+    /// `app` for projects under `apps/`, `lib` otherwise, as Nx lays out workspaces
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub data: NxProjectData,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NxProjectData {
+    /// Directory of the project, relative to the workspace root
+    pub root: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NxDependency {
+    pub source: String,
+    pub target: String,
+    /// `dynamic` when only `import()` expressions import the target, `static` otherwise
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+}
+
+impl NxProjectGraph {
+    /// Graph of the packages of `workspace` owning the files of `deps`, relative to `cwd`,
+    /// expected to be the workspace root
+    pub fn new(cwd: &Path, workspace: &Workspace, deps: &[(PathAtom, Message)]) -> Self {
+        let packages = PackageLevelGraph::new(cwd, workspace, deps);
+        let mut graph = NxGraph::default();
+        for (name, package) in &packages.nodes {
+            let kind = if package.path.starts_with("apps/") {
+                "app"
+            } else {
+                "lib"
+            };
+            let root = if package.path.is_empty() {
+                ".".to_string()
+            } else {
+                package.path.clone()
+            };
+            let project = NxProject {
+                name: name.clone(),
+                kind,
+                data: NxProjectData { root },
+            };
+            graph.nodes.insert(name.clone(), project);
+            graph.dependencies.insert(name.clone(), Vec::new());
+        }
+        for edge in &packages.edges {
+            graph
+                .dependencies
+                .entry(edge.from.clone())
+                .or_default()
+                .push(NxDependency {
+                    source: edge.from.clone(),
+                    target: edge.to.clone(),
+                    kind: if edge.dynamic { "dynamic" } else { "static" },
+                });
+        }
+        Self { graph }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::NxProjectGraph;
+    use crate::{
+        graph::{EdgeType, NodeKind},
+        message::Message,
+        utils::PathAtom,
+        workspace::test_workspace,
+    };
+
+    #[test]
+    fn test_nx_project_graph() {
+        let (root, workspace) = test_workspace(
+            "nx",
+            &[
+                ("apps/web", r#"{ "name": "web" }"#),
+                ("libs/ui", r#"{ "name": "ui" }"#),
+                ("tools/env", r#"{ "name": "env" }"#),
+            ],
+        );
+
+        let deps = [
+            ("apps/web/main.ts", "libs/ui/index.ts", EdgeType::Static),
+            ("apps/web/main.ts", "tools/env/index.ts", EdgeType::Dynamic),
+        ]
+        .map(|(src, dst, edge)| {
+            (
+                PathAtom::new(src),
+                Message::new(dst, NodeKind::Module, edge),
+            )
+        });
+        let graph = NxProjectGraph::new(&root, &workspace, &deps);
+        let json = serde_json::to_value(&graph).unwrap();
+        let nodes = &json["graph"]["nodes"];
+        assert_eq!(nodes["web"]["type"], "app");
+        assert_eq!(nodes["web"]["data"]["root"], "apps/web");
+        assert_eq!(nodes["ui"]["type"], "lib");
+        assert_eq!(nodes["env"]["data"]["root"], "tools/env");
+        let dependencies = &json["graph"]["dependencies"];
+        assert_eq!(
+            dependencies["web"],
+            serde_json::json!([
+                { "source": "web", "target": "env", "type": "dynamic" },
+                { "source": "web", "target": "ui", "type": "static" },
+            ])
+        );
+        assert_eq!(dependencies["ui"], serde_json::json!([]));
+    }
+}
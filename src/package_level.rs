@@ -0,0 +1,199 @@
+//! The graph collapsed to the packages of the workspace, for `--granularity package` and the
+//! reports about packages: one node per package and one edge per pair of packages, counting
+//! the imports between their files. Files outside of the workspace packages, and installed
+//! packages, are left out.
+
+use std::{collections::BTreeMap, fmt, path::Path};
+
+use serde::Serialize;
+
+use crate::{
+    graph::{EdgeType, NodeKind},
+    message::Message,
+    utils::PathAtom,
+    workspace::Workspace,
+};
+
+#[derive(Debug, Serialize)]
+pub struct PackageNode {
+    /// Directory of the package, relative to the current working directory
+    pub path: String,
+    /// Number of its files in the graph
+    pub files: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageEdge {
+    pub from: String,
+    pub to: String,
+    /// Number of imports from the files of `from` of the files of `to`
+    pub count: usize,
+    /// The imports as `(importer, imported)` file paths, in the order they were found
+    #[serde(skip)]
+    pub imports: Vec<(String, String)>,
+    /// Only `import()` expressions import `to`
+    #[serde(skip)]
+    pub dynamic: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PackageLevelGraph {
+    /// Packages owning files of the graph, by name
+    pub nodes: BTreeMap<String, PackageNode>,
+    /// Edges between different packages, sorted by packages
+    pub edges: Vec<PackageEdge>,
+}
+
+impl PackageLevelGraph {
+    /// Collapses the files of `deps`, relative to `cwd`, into the packages of `workspace`
+    /// owning them
+    pub fn new<'d>(cwd: &Path, workspace: &Workspace, deps: &'d [(PathAtom, Message)]) -> Self {
+        let mut owners = BTreeMap::<&str, Option<&str>>::new();
+        let mut owner = |path: &'d str| {
+            *owners
+                .entry(path)
+                .or_insert_with(|| workspace.package_of_file(&cwd.join(path)))
+        };
+        let mut edges = BTreeMap::<(&str, &str), (Vec<(String, String)>, bool)>::new();
+        for (src, dst) in deps {
+            let source = owner(src.as_str());
+            if matches!(dst.kind, NodeKind::Package | NodeKind::Unresolved) {
+                continue;
+            }
+            let target = owner(dst.file_path.as_str());
+            if let (Some(source), Some(target)) = (source, target) {
+                if source != target {
+                    let dynamic = dst.edge == EdgeType::Dynamic;
+                    let (imports, only_dynamic) = edges
+                        .entry((source, target))
+                        .or_insert_with(|| (Vec::new(), dynamic));
+                    imports.push((src.as_str().to_string(), dst.file_path.as_str().to_string()));
+                    *only_dynamic &= dynamic;
+                }
+            }
+        }
+
+        let mut graph = Self::default();
+        let dirs = workspace.packages().collect::<BTreeMap<_, _>>();
+        for (_, package) in owners {
+            let Some(package) = package else {
+                continue;
+            };
+            let dir = dirs[package];
+            graph
+                .nodes
+                .entry(package.to_string())
+                .or_insert_with(|| PackageNode {
+                    path: dir.strip_prefix(cwd).unwrap_or(dir).display().to_string(),
+                    files: 0,
+                })
+                .files += 1;
+        }
+        graph.edges = edges
+            .into_iter()
+            .map(|((from, to), (imports, dynamic))| PackageEdge {
+                from: from.to_string(),
+                to: to.to_string(),
+                count: imports.len(),
+                imports,
+                dynamic,
+            })
+            .collect();
+        graph
+    }
+}
+
+impl fmt::Display for PackageLevelGraph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "digraph {{")?;
+        for (name, node) in &self.nodes {
+            writeln!(
+                f,
+                "    {name:?} [ label = \"{} ({} file{})\" shape = box ]",
+                name.escape_debug(),
+                node.files,
+                if node.files == 1 { "" } else { "s" }
+            )?;
+        }
+        for edge in &self.edges {
+            writeln!(
+                f,
+                "    {:?} -> {:?} [ label = \"{}\" ]",
+                edge.from, edge.to, edge.count
+            )?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PackageLevelGraph;
+    use crate::{
+        graph::{EdgeType, NodeKind},
+        message::Message,
+        utils::PathAtom,
+        workspace::test_workspace,
+    };
+
+    #[test]
+    fn test_package_level_graph() {
+        let (root, workspace) = test_workspace(
+            "package-level",
+            &[
+                ("packages/ui", r#"{ "name": "@myorg/ui" }"#),
+                ("apps/web", r#"{ "name": "web" }"#),
+            ],
+        );
+
+        let deps = [
+            ("apps/web/main.ts", "apps/web/app.ts", NodeKind::Module),
+            ("apps/web/main.ts", "packages/ui/index.ts", NodeKind::Module),
+            ("apps/web/app.ts", "packages/ui/button.ts", NodeKind::Module),
+            ("apps/web/app.ts", "react", NodeKind::Package),
+            (
+                "packages/ui/button.ts",
+                "scripts/theme.ts",
+                NodeKind::Module,
+            ),
+        ]
+        .map(|(src, dst, kind)| {
+            (
+                PathAtom::new(src),
+                Message::new(dst, kind, EdgeType::Static),
+            )
+        })
+        .into_iter()
+        .chain([
+            (
+                PathAtom::new("apps/web/app.ts"),
+                Message::new("packages/ui/modal.ts", NodeKind::Module, EdgeType::Dynamic),
+            ),
+            (
+                PathAtom::new("apps/web/app.ts"),
+                Message::new("./missing", NodeKind::Unresolved, EdgeType::Static),
+            ),
+        ])
+        .collect::<Vec<_>>();
+        let graph = PackageLevelGraph::new(&root, &workspace, &deps);
+
+        assert_eq!(
+            graph
+                .nodes
+                .iter()
+                .map(|(name, node)| (name.as_str(), node.path.as_str(), node.files))
+                .collect::<Vec<_>>(),
+            [("@myorg/ui", "packages/ui", 3), ("web", "apps/web", 2)]
+        );
+        assert_eq!(graph.edges.len(), 1);
+        assert_eq!(
+            (graph.edges[0].from.as_str(), graph.edges[0].to.as_str()),
+            ("web", "@myorg/ui")
+        );
+        assert_eq!(graph.edges[0].count, 3);
+        assert!(!graph.edges[0].dynamic);
+        assert!(graph
+            .to_string()
+            .contains("    \"web\" -> \"@myorg/ui\" [ label = \"3\" ]\n"));
+    }
+}
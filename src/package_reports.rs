@@ -0,0 +1,250 @@
+//! One report per workspace package for `--out-dir`, so each team of a monorepo reads its own
+//! slice: the graph of the files of the package, its metrics and its violations, along with a
+//! `summary.json` listing every package. Reports of packages are kept apart from the summary,
+//! in `packages/`, so no package name collides with it.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use serde_json::{json, Value};
+
+use crate::{
+    graph::NodeKind, message::Message, package_level::PackageLevelGraph, rules::RuleViolation,
+    utils::PathAtom, workspace::Workspace,
+};
+
+/// Name of the report listing the packages in the output directory
+pub const SUMMARY_FILE: &str = "summary.json";
+/// Directory of the reports of the packages in the output directory
+pub const PACKAGES_DIR: &str = "packages";
+
+#[derive(Debug, Default)]
+struct PackageReport {
+    path: String,
+    /// Files of the package in the graph
+    files: Vec<String>,
+    /// Imports of the files of the package
+    edges: Vec<Value>,
+    violations: Vec<Value>,
+}
+
+#[derive(Debug, Default)]
+pub struct PackageReports {
+    /// Report of each package, by name
+    packages: BTreeMap<String, PackageReport>,
+    graph: PackageLevelGraph,
+    /// Violations of files outside of the workspace packages
+    other_violations: usize,
+}
+
+impl PackageReports {
+    /// Splits the graph `deps` and the `violations`, relative to `cwd`, by package of
+    /// `workspace`
+    pub fn new<'d>(
+        cwd: &Path,
+        workspace: &Workspace,
+        deps: &'d [(PathAtom, Message)],
+        violations: &'d [RuleViolation],
+    ) -> Self {
+        let mut reports = Self {
+            graph: PackageLevelGraph::new(cwd, workspace, deps),
+            ..Self::default()
+        };
+        for (name, dir) in workspace.packages() {
+            let path = dir.strip_prefix(cwd).unwrap_or(dir).display().to_string();
+            reports.packages.insert(
+                name.to_string(),
+                PackageReport {
+                    path,
+                    ..PackageReport::default()
+                },
+            );
+        }
+
+        let mut owners = BTreeMap::<&str, Option<&str>>::new();
+        let mut owner = |path: &'d str| -> Option<String> {
+            let owner = owners
+                .entry(path)
+                .or_insert_with(|| workspace.package_of_file(&cwd.join(path)));
+            owner.map(str::to_string)
+        };
+        for (src, dst) in deps {
+            if !matches!(dst.kind, NodeKind::Package | NodeKind::Unresolved) {
+                if let Some(package) = owner(dst.file_path.as_str()) {
+                    let report = reports.packages.get_mut(&package).unwrap();
+                    report.files.push(dst.file_path.as_str().to_string());
+                }
+            }
+            let Some(package) = owner(src.as_str()) else {
+                continue;
+            };
+            let report = reports.packages.get_mut(&package).unwrap();
+            report.files.push(src.as_str().to_string());
+            report.edges.push(json!({
+                "from": src.as_str(),
+                "to": dst.file_path.as_str(),
+                "kind": dst.edge,
+            }));
+        }
+        for violation in violations {
+            let Some(package) = owner(&violation.path) else {
+                reports.other_violations += 1;
+                continue;
+            };
+            reports
+                .packages
+                .get_mut(&package)
+                .unwrap()
+                .violations
+                .push(json!({
+                    "path": violation.path,
+                    "rule": violation.rule,
+                    "severity": violation.severity,
+                    "message": violation.message,
+                }));
+        }
+        for report in reports.packages.values_mut() {
+            report.files.sort_unstable();
+            report.files.dedup();
+        }
+        reports
+    }
+
+    /// Writes the report of each package and the summary to `dir`, created if missing
+    ///
+    /// # Errors
+    /// When a report cannot be written
+    pub fn write(&self, dir: &Path) -> Result<(), String> {
+        let write = |file: &str, json: &Value| {
+            let path = dir.join(file);
+            fs::write(&path, serde_json::to_string_pretty(json).unwrap())
+                .map_err(|error| format!("Failed to write {}: {error}", path.display()))
+        };
+        let packages_dir = dir.join(PACKAGES_DIR);
+        fs::create_dir_all(&packages_dir)
+            .map_err(|error| format!("Failed to create {}: {error}", packages_dir.display()))?;
+
+        let mut summary = Vec::new();
+        for (name, report) in &self.packages {
+            let file = format!("{PACKAGES_DIR}/{}", report_file(name));
+            let dependencies = self
+                .graph
+                .edges
+                .iter()
+                .filter(|edge| &edge.from == name)
+                .map(|edge| &edge.to)
+                .collect::<Vec<_>>();
+            let dependents = self
+                .graph
+                .edges
+                .iter()
+                .filter(|edge| &edge.to == name)
+                .map(|edge| &edge.from)
+                .collect::<Vec<_>>();
+            let metrics = json!({
+                "files": report.files.len(),
+                "imports": report.edges.len(),
+                "dependencies": dependencies,
+                "dependents": dependents,
+                "violations": report.violations.len(),
+            });
+            write(
+                &file,
+                &json!({
+                    "name": name,
+                    "path": report.path,
+                    "metrics": metrics,
+                    "graph": { "nodes": report.files, "edges": report.edges },
+                    "violations": report.violations,
+                }),
+            )?;
+            summary.push(json!({
+                "name": name,
+                "path": report.path,
+                "report": file,
+                "metrics": metrics,
+            }));
+        }
+        write(
+            SUMMARY_FILE,
+            &json!({
+                "packages": summary,
+                "otherViolations": self.other_violations,
+            }),
+        )
+    }
+}
+
+/// File name of the report of the package `name`, e.g. `@org__ui.json` for `@org/ui`
+fn report_file(name: &str) -> String {
+    format!("{}.json", name.replace('/', "__"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PackageReports, PACKAGES_DIR, SUMMARY_FILE};
+    use crate::{
+        graph::{EdgeType, NodeKind},
+        message::Message,
+        rules::{RuleViolation, Severity},
+        utils::PathAtom,
+        workspace::test_workspace,
+    };
+
+    #[test]
+    fn test_package_reports() {
+        let (root, workspace) = test_workspace(
+            "package-reports",
+            &[
+                ("packages/ui", r#"{ "name": "@org/ui" }"#),
+                ("apps/web", r#"{ "name": "web" }"#),
+            ],
+        );
+
+        let deps = [
+            ("apps/web/main.ts", "packages/ui/index.ts", NodeKind::Module),
+            ("apps/web/main.ts", "apps/web/app.ts", NodeKind::Module),
+            ("packages/ui/index.ts", "react", NodeKind::Package),
+        ]
+        .map(|(src, dst, kind)| {
+            (
+                PathAtom::new(src),
+                Message::new(dst, kind, EdgeType::Static),
+            )
+        });
+        let violations = [
+            RuleViolation::new(
+                "apps/web/app.ts",
+                "noOrphans",
+                Severity::Warn,
+                String::new(),
+            ),
+            RuleViolation::new("scripts/a.ts", "noOrphans", Severity::Warn, String::new()),
+        ];
+        let out_dir = root.join("reports");
+        PackageReports::new(&root, &workspace, &deps, &violations)
+            .write(&out_dir)
+            .unwrap();
+
+        let read = |file: &str| -> serde_json::Value {
+            serde_json::from_slice(&std::fs::read(out_dir.join(file)).unwrap()).unwrap()
+        };
+        let web = read(&format!("{PACKAGES_DIR}/web.json"));
+        assert_eq!(
+            web["graph"]["nodes"],
+            serde_json::json!(["apps/web/app.ts", "apps/web/main.ts"])
+        );
+        assert_eq!(web["metrics"]["imports"], 2);
+        assert_eq!(
+            web["metrics"]["dependencies"],
+            serde_json::json!(["@org/ui"])
+        );
+        assert_eq!(web["violations"][0]["path"], "apps/web/app.ts");
+        let ui = read(&format!("{PACKAGES_DIR}/@org__ui.json"));
+        assert_eq!(ui["metrics"]["files"], 1);
+        assert_eq!(ui["metrics"]["dependents"], serde_json::json!(["web"]));
+        assert_eq!(ui["graph"]["edges"][0]["to"], "react");
+        let summary = read(SUMMARY_FILE);
+        assert_eq!(summary["packages"][0]["report"], "packages/@org__ui.json");
+        assert_eq!(summary["otherViolations"], 1);
+    }
+}
@@ -0,0 +1,90 @@
+//! Progress of a run drawn on stderr while it goes, so runs over large repositories are not
+//! silent for minutes. It is only drawn when stderr is a terminal.
+
+use std::{
+    io::IsTerminal,
+    sync::mpsc::{self, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+use crate::{service::AnalyzeService, stats::Counters};
+
+/// How often the progress is updated
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// Draws the progress of a run until finished
+pub struct Progress {
+    bar: ProgressBar,
+    stop: Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl Progress {
+    /// Starts drawing the progress of the run of `service`, unless stderr is not a terminal
+    pub fn start(service: &AnalyzeService) -> Option<Self> {
+        if !std::io::stderr().is_terminal() {
+            return None;
+        }
+        let style = ProgressStyle::with_template("[{bar:24}] {pos}/{len} files, {msg}")
+            .unwrap()
+            .progress_chars("=> ");
+        let bar =
+            ProgressBar::with_draw_target(Some(0), ProgressDrawTarget::stderr()).with_style(style);
+        let service = service.clone();
+        let (stop, stopped) = mpsc::channel();
+        let thread = thread::spawn({
+            let bar = bar.clone();
+            move || loop {
+                update(&bar, service.counters());
+                if stopped.recv_timeout(INTERVAL) != Err(RecvTimeoutError::Timeout) {
+                    break;
+                }
+            }
+        });
+        Some(Self { bar, stop, thread })
+    }
+
+    /// Stops drawing the progress, erasing it for what the run prints next
+    pub fn finish(self) {
+        let _ = self.stop.send(());
+        let _ = self.thread.join();
+        self.bar.finish_and_clear();
+    }
+}
+
+/// Shows `counters` on `bar`, e.g.
+/// `[=========>              ] 120/310 files, 190 queued, 1432 specifiers resolved`
+fn update(bar: &ProgressBar, counters: &Counters) {
+    let (processed, discovered) = counters.files();
+    bar.set_length(discovered as u64);
+    bar.set_position(processed as u64);
+    bar.set_message(format!(
+        "{} queued, {} specifiers resolved",
+        discovered.saturating_sub(processed),
+        counters.resolver_calls()
+    ));
+}
+
+#[cfg(test)]
+mod test {
+    use indicatif::ProgressBar;
+
+    use super::update;
+    use crate::stats::Counters;
+
+    #[test]
+    fn test_update() {
+        let (bar, counters) = (ProgressBar::hidden(), Counters::default());
+        for _ in 0..4 {
+            counters.discovered();
+        }
+        counters.processed();
+        counters.resolved();
+        update(&bar, &counters);
+        assert_eq!((bar.position(), bar.length()), (1, Some(4)));
+        assert_eq!(bar.message(), "3 queued, 1 specifiers resolved");
+    }
+}
@@ -0,0 +1,519 @@
+//! TypeScript project references: the DAG of projects declared by the `references` of
+//! `tsconfig.json` files, from the root `tsconfig.json` down. Every file of every project is
+//! analyzed, and the imports between projects are checked against the references, as `tsc
+//! --build` only sees the outputs of referenced projects.
+//!
+//! <https://www.typescriptlang.org/docs/handbook/project-references.html>
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    graph::NodeKind,
+    message::Message,
+    utils::{normalize_path, PathAtom},
+};
+
+/// Extensions of the files a project compiles
+const EXTENSIONS: &[&str] = &["ts", "tsx", "mts", "cts"];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TsconfigJson {
+    extends: Option<ExtendsJson>,
+    #[serde(default)]
+    references: Vec<ReferenceJson>,
+    files: Option<Vec<String>>,
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    #[serde(default)]
+    compiler_options: CompilerOptionsJson,
+}
+
+/// One config or, since TypeScript 5.0, several applied in order
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ExtendsJson {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug, Deserialize)]
+struct ReferenceJson {
+    path: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompilerOptionsJson {
+    out_dir: Option<String>,
+}
+
+/// A `tsconfig.json` with the configs it extends applied. Like `tsc`, `files`, `include`,
+/// `exclude` and `outDir` are inherited unless set, relative to the config setting them, and
+/// `references` are not inherited.
+#[derive(Debug, Default)]
+struct Tsconfig {
+    references: Vec<ReferenceJson>,
+    files: Option<Vec<PathBuf>>,
+    /// Absolute globs
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    out_dir: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+struct Project {
+    /// Path of its `tsconfig.json`
+    tsconfig: PathBuf,
+    dir: PathBuf,
+    /// Indices of the projects it references
+    references: Vec<usize>,
+    /// Files it compiles, sorted
+    files: Vec<PathBuf>,
+}
+
+#[derive(Debug)]
+pub struct ProjectReferences {
+    /// Projects in the order they were found, the root first
+    projects: Vec<Project>,
+}
+
+impl ProjectReferences {
+    /// Follows the references from `tsconfig`, the root project
+    ///
+    /// # Errors
+    /// When a `tsconfig.json` cannot be read or parsed
+    pub fn discover(tsconfig: &Path) -> Result<Self, String> {
+        let mut references = Self {
+            projects: Vec::new(),
+        };
+        let mut indices = FxHashMap::default();
+        references.add(&normalize_path(tsconfig), &mut indices)?;
+        Ok(references)
+    }
+
+    /// Adds the project of `tsconfig` and those it references, returning its index
+    fn add(
+        &mut self,
+        tsconfig: &Path,
+        indices: &mut FxHashMap<PathBuf, usize>,
+    ) -> Result<usize, String> {
+        if let Some(&index) = indices.get(tsconfig) {
+            return Ok(index);
+        }
+        let json = load_tsconfig(tsconfig, &mut Vec::new())?;
+        let dir = tsconfig.parent().unwrap_or(Path::new("")).to_path_buf();
+        let index = self.projects.len();
+        indices.insert(tsconfig.to_path_buf(), index);
+        self.projects.push(Project {
+            tsconfig: tsconfig.to_path_buf(),
+            files: project_files(&dir, &json),
+            dir: dir.clone(),
+            references: Vec::new(),
+        });
+        for reference in &json.references {
+            let path = normalize_path(&dir.join(&reference.path));
+            let path = if path.is_dir() {
+                path.join("tsconfig.json")
+            } else {
+                path
+            };
+            let referenced = self.add(&path, indices)?;
+            self.projects[index].references.push(referenced);
+        }
+        Ok(index)
+    }
+
+    /// Files of every project, to analyze them all
+    pub fn files(&self) -> Vec<PathBuf> {
+        let files = self
+            .projects
+            .iter()
+            .flat_map(|project| &project.files)
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        files.into_iter().collect()
+    }
+
+    /// Checks the imports between the files of `deps`, relative to `cwd`, against the
+    /// references
+    pub fn report(&self, cwd: &Path, deps: &[(PathAtom, Message)]) -> ProjectReferencesReport {
+        // Files listed by several projects belong to the innermost one
+        let mut owners = FxHashMap::default();
+        let mut by_depth = (0..self.projects.len()).collect::<Vec<_>>();
+        by_depth.sort_by_key(|&index| self.projects[index].dir.components().count());
+        for &index in &by_depth {
+            for file in &self.projects[index].files {
+                owners.insert(file.as_path(), index);
+            }
+        }
+        let project_of = |path: &Path| {
+            owners.get(path).copied().or_else(|| {
+                // Files no project lists, e.g. reached through an import, belong to the
+                // innermost project compiling files of their directory
+                by_depth.iter().rev().copied().find(|&index| {
+                    let project = &self.projects[index];
+                    !project.files.is_empty() && path.starts_with(&project.dir)
+                })
+            })
+        };
+
+        let relative = |path: &Path| path.strip_prefix(cwd).unwrap_or(path).display().to_string();
+        let mut imports = BTreeMap::<(usize, usize), Vec<FileImport>>::new();
+        for (src, dst) in deps {
+            if matches!(dst.kind, NodeKind::Package | NodeKind::Unresolved) {
+                continue;
+            }
+            let from = cwd.join(src.as_str());
+            let to = cwd.join(dst.file_path.as_str());
+            let (Some(source), Some(target)) = (project_of(&from), project_of(&to)) else {
+                continue;
+            };
+            if source != target {
+                imports
+                    .entry((source, target))
+                    .or_default()
+                    .push(FileImport {
+                        from: relative(&from),
+                        to: relative(&to),
+                    });
+            }
+        }
+
+        let name = |index: usize| relative(&self.projects[index].tsconfig);
+        let mut report = ProjectReferencesReport::default();
+        for (index, project) in self.projects.iter().enumerate() {
+            let references = project.references.iter().map(|&index| name(index));
+            report.projects.insert(name(index), references.collect());
+            // Solution-style projects only reference the projects to build
+            if project.files.is_empty() {
+                continue;
+            }
+            for &referenced in &project.references {
+                if !imports.contains_key(&(index, referenced)) {
+                    report.unused.push(ProjectReference {
+                        from: name(index),
+                        to: name(referenced),
+                        imports: Vec::new(),
+                    });
+                }
+            }
+        }
+        for ((source, target), imports) in imports {
+            if !self.projects[source].references.contains(&target) {
+                report.missing.push(ProjectReference {
+                    from: name(source),
+                    to: name(target),
+                    imports,
+                });
+            }
+        }
+        for references in [&mut report.missing, &mut report.unused] {
+            references.sort_unstable_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+        }
+        report
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileImport {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectReference {
+    /// `tsconfig.json` of the importing project, relative to the current working directory
+    pub from: String,
+    /// `tsconfig.json` of the imported project
+    pub to: String,
+    /// Imports of files of `to` by files of `from`, empty for unused references
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub imports: Vec<FileImport>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ProjectReferencesReport {
+    /// References of each project, by `tsconfig.json`
+    pub projects: BTreeMap<String, Vec<String>>,
+    /// Projects importing files of projects they do not reference
+    pub missing: Vec<ProjectReference>,
+    /// References of projects importing none of the files of the referenced project, except
+    /// those of projects without files, which only tell `tsc --build` what to build
+    pub unused: Vec<ProjectReference>,
+}
+
+impl fmt::Display for ProjectReferencesReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for missing in &self.missing {
+            writeln!(
+                f,
+                "{} imports {} without referencing it:",
+                missing.from, missing.to
+            )?;
+            for import in &missing.imports {
+                writeln!(f, "  {} imports {}", import.from, import.to)?;
+            }
+        }
+        for unused in &self.unused {
+            writeln!(
+                f,
+                "{} references {} without importing it",
+                unused.from, unused.to
+            )?;
+        }
+        write!(
+            f,
+            "{} missing and {} unused references between {} projects",
+            self.missing.len(),
+            self.unused.len(),
+            self.projects.len()
+        )
+    }
+}
+
+fn read_tsconfig(path: &Path) -> Result<TsconfigJson, String> {
+    let mut source_text = std::fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read {}: {error}", path.display()))?;
+    json_strip_comments::strip(&mut source_text)
+        .map_err(|error| format!("Failed to parse {}: {error}", path.display()))?;
+    serde_json::from_str(&source_text)
+        .map_err(|error| format!("Failed to parse {}: {error}", path.display()))
+}
+
+/// `path` with the configs it extends applied, `extending` holding the configs being loaded
+fn load_tsconfig(path: &Path, extending: &mut Vec<PathBuf>) -> Result<Tsconfig, String> {
+    if extending.iter().any(|config| config == path) {
+        return Err(format!("{} extends itself", path.display()));
+    }
+    let json = read_tsconfig(path)?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let mut config = Tsconfig::default();
+    let extends = match json.extends {
+        Some(ExtendsJson::One(extends)) => vec![extends],
+        Some(ExtendsJson::Many(extends)) => extends,
+        None => Vec::new(),
+    };
+    extending.push(path.to_path_buf());
+    for extends in extends {
+        let base = resolve_extends(dir, &extends)
+            .ok_or_else(|| format!("{}: cannot find {extends}", path.display()))?;
+        let base = load_tsconfig(&base, extending)?;
+        config.files = base.files.or(config.files);
+        config.include = base.include.or(config.include);
+        config.exclude = base.exclude.or(config.exclude);
+        config.out_dir = base.out_dir.or(config.out_dir);
+    }
+    extending.pop();
+
+    let patterns = |patterns: Vec<String>| {
+        patterns
+            .iter()
+            .map(|pattern| directory_pattern(dir, pattern))
+            .collect::<Vec<_>>()
+    };
+    if let Some(files) = json.files {
+        config.files = Some(
+            files
+                .iter()
+                .map(|file| normalize_path(&dir.join(file)))
+                .collect(),
+        );
+    }
+    config.include = json.include.map(patterns).or(config.include);
+    config.exclude = json.exclude.map(patterns).or(config.exclude);
+    if let Some(out_dir) = json.compiler_options.out_dir {
+        config.out_dir = Some(normalize_path(&dir.join(out_dir)));
+    }
+    config.references = json.references;
+    Ok(config)
+}
+
+/// The config `extends` names from a config in `dir`: a path, `.json` being optional, or a
+/// file or the `tsconfig.json` of an installed package
+fn resolve_extends(dir: &Path, extends: &str) -> Option<PathBuf> {
+    let candidates = |path: PathBuf| {
+        let with_extension = PathBuf::from(format!("{}.json", path.display()));
+        [path.clone(), with_extension, path.join("tsconfig.json")]
+    };
+    if extends.starts_with('.') || Path::new(extends).is_absolute() {
+        return candidates(normalize_path(&dir.join(extends)))
+            .into_iter()
+            .find(|path| path.is_file());
+    }
+    dir.ancestors()
+        .flat_map(|dir| candidates(dir.join("node_modules").join(extends)))
+        .find(|path| path.is_file())
+}
+
+/// Files of `files` and `include` of the project in `dir`, `include` defaulting to every
+/// file unless `files` is set. Like `tsc`, `include` patterns naming a directory include its
+/// files, and `node_modules` and the output directory are left out.
+fn project_files(dir: &Path, config: &Tsconfig) -> Vec<PathBuf> {
+    let is_source = |path: &Path| {
+        let name = path.to_string_lossy();
+        path.extension()
+            .is_some_and(|ext| EXTENSIONS.iter().any(|source| ext == *source))
+            && !name.ends_with(".d.ts")
+            && path.is_file()
+    };
+    let mut files = config
+        .files
+        .iter()
+        .flatten()
+        .filter(|path| is_source(path))
+        .cloned()
+        .collect::<BTreeSet<_>>();
+
+    let default_include = if config.files.is_some() {
+        Vec::new()
+    } else {
+        vec![directory_pattern(dir, "**/*")]
+    };
+    let include = config.include.as_ref().unwrap_or(&default_include);
+    let out_dir = config
+        .out_dir
+        .as_ref()
+        .map(|out_dir| out_dir.join("**/*").display().to_string());
+    let excludes = config
+        .exclude
+        .iter()
+        .flatten()
+        .chain(&out_dir)
+        .filter_map(|pattern| glob::Pattern::new(pattern).ok())
+        .collect::<Vec<_>>();
+    for pattern in include {
+        let Ok(paths) = glob::glob(pattern) else {
+            continue;
+        };
+        for path in paths.flatten() {
+            let path = normalize_path(&path);
+            let relative = path.strip_prefix(dir).unwrap_or(&path);
+            if relative
+                .components()
+                .any(|component| component.as_os_str() == "node_modules")
+                || excludes.iter().any(|exclude| exclude.matches_path(&path))
+                || !is_source(&path)
+            {
+                continue;
+            }
+            files.insert(path);
+        }
+    }
+    files.into_iter().collect()
+}
+
+/// `pattern` of a project in `dir` as an absolute glob, directories standing for their files
+fn directory_pattern(dir: &Path, pattern: &str) -> String {
+    let path = normalize_path(&dir.join(pattern));
+    let is_file_pattern = pattern.contains('*') || Path::new(pattern).extension().is_some();
+    if is_file_pattern {
+        path.display().to_string()
+    } else {
+        path.join("**/*").display().to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ProjectReferences;
+    use crate::{
+        collector::CollectorService,
+        service::{AnalyzeService, AnalyzeServiceOptions},
+        utils::TestDir,
+    };
+
+    #[test]
+    fn test_project_references() {
+        let root = TestDir::new("project-references");
+        for (file, contents) in [
+            (
+                "tsconfig.json",
+                r#"{
+                    // Solution-style, building the projects
+                    "files": [],
+                    "references": [{ "path": "./app" }, { "path": "./shared" }],
+                }"#,
+            ),
+            (
+                "app/tsconfig.json",
+                r#"{ "include": ["src"], "references": [{ "path": "../ui/tsconfig.json" }] }"#,
+            ),
+            (
+                "app/src/main.ts",
+                "import '../../ui/src/button'; import '../../shared/src/log';",
+            ),
+            (
+                "ui/tsconfig.json",
+                r#"{ "compilerOptions": { "outDir": "dist" }, "references": [{ "path": "../shared" }] }"#,
+            ),
+            ("ui/src/button.ts", "export {};"),
+            ("ui/dist/button.ts", "export {};"),
+            (
+                "tsconfig.base.json",
+                r#"{ "exclude": ["**/*.test.ts"], "references": [{ "path": "./app" }] }"#,
+            ),
+            (
+                "shared/tsconfig.json",
+                r#"{ "extends": "../tsconfig.base", "compilerOptions": { "outDir": "lib" } }"#,
+            ),
+            ("shared/src/log.ts", "export {};"),
+            ("shared/src/log.test.ts", "export {};"),
+            ("shared/lib/log.ts", "export {};"),
+        ] {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let references = ProjectReferences::discover(&root.join("tsconfig.json")).unwrap();
+        // Excluded by the config `shared` extends, and by its own `outDir`
+        assert_eq!(
+            references.files(),
+            ["app/src/main.ts", "shared/src/log.ts", "ui/src/button.ts"]
+                .map(|file| root.join(file))
+        );
+
+        let options = AnalyzeServiceOptions::new(
+            &*root,
+            references.files().into_iter().map(Into::into).collect(),
+        )
+        .with_cross_module(true);
+        let mut collector = CollectorService::default().with_sinks(Vec::new());
+        AnalyzeService::new(options).run(collector.sender());
+        collector.start();
+        let report = references.report(&root, collector.deps());
+
+        assert_eq!(report.projects.len(), 4);
+        assert_eq!(
+            report.projects["ui/tsconfig.json"],
+            ["shared/tsconfig.json"]
+        );
+        // References are not inherited
+        assert!(report.projects["shared/tsconfig.json"].is_empty());
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!(
+            (
+                report.missing[0].from.as_str(),
+                report.missing[0].to.as_str()
+            ),
+            ("app/tsconfig.json", "shared/tsconfig.json")
+        );
+        assert_eq!(report.missing[0].imports[0].from, "app/src/main.ts");
+        assert_eq!(
+            report
+                .unused
+                .iter()
+                .map(|unused| (unused.from.as_str(), unused.to.as_str()))
+                .collect::<Vec<_>>(),
+            [("ui/tsconfig.json", "shared/tsconfig.json")]
+        );
+    }
+}
@@ -0,0 +1,255 @@
+//! Imports crossing into another first-party package must go through its public entries,
+//! the files its `package.json` declares, instead of arbitrary internal files.
+
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use dashmap::DashMap;
+use glob::Pattern;
+use rustc_hash::FxHashSet;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    deep_imports::{is_exported, package_name, split_package_specifier},
+    rules::{matches_any, Patterns, RuleDocs, Severity},
+    utils::read_to_string,
+    workspace::OUTPUT_DIRS,
+};
+
+/// `package.json` fields declaring entries besides `exports`
+const ENTRY_FIELDS: &[&str] = &["main", "module", "types", "typings", "browser"];
+
+/// The `publicEntries` rule as written in the configuration
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct PublicEntriesConfig {
+    /// Names of the packages whose internals are protected, every first-party package by default
+    #[serde(default)]
+    packages: Patterns,
+    #[serde(default)]
+    pub severity: Severity,
+    #[serde(flatten)]
+    docs: RuleDocs,
+}
+
+/// A package found through its `package.json`
+#[derive(Debug)]
+struct Package {
+    dir: PathBuf,
+    name: Option<String>,
+    exports: Option<Value>,
+    /// Declared entries relative to the package directory, without extension
+    entries: FxHashSet<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PublicEntries {
+    packages: Vec<Pattern>,
+    pub severity: Severity,
+    pub docs: RuleDocs,
+    /// Directory to the package it belongs to, packages are the nearest `package.json`
+    nearest: DashMap<PathBuf, Option<Arc<Package>>>,
+}
+
+impl PublicEntries {
+    /// # Errors
+    /// When a pattern is not a valid glob
+    pub fn new(config: &PublicEntriesConfig) -> Result<Self, String> {
+        Ok(Self {
+            packages: config.packages.compile("publicEntries")?,
+            severity: config.severity,
+            docs: config.docs.clone(),
+            nearest: DashMap::default(),
+        })
+    }
+
+    /// Checks an import of `specifier` from `importer` resolving to `target`, both absolute
+    /// paths, returning the violation message when it reaches into another package's internals.
+    ///
+    /// Packages inside `node_modules` are left to `--deny-deep-imports`.
+    pub fn check(&self, importer: &Path, specifier: &str, target: &Path) -> Option<String> {
+        if target
+            .components()
+            .any(|component| component.as_os_str() == "node_modules")
+        {
+            return None;
+        }
+        let package = self.package_of(target.parent()?)?;
+        let importer_package = importer.parent().and_then(|dir| self.package_of(dir));
+        if importer_package.is_some_and(|importer_package| importer_package.dir == package.dir) {
+            return None;
+        }
+        let name = package.name.as_deref().unwrap_or_default();
+        if !self.packages.is_empty() && !matches_any(&self.packages, name) {
+            return None;
+        }
+
+        // Bare imports of the package entry, or of a subpath it exports
+        if package_name(specifier).is_some_and(|specifier_name| specifier_name == name) {
+            let exported = match split_package_specifier(specifier) {
+                None => true,
+                Some((_, subpath)) => package
+                    .exports
+                    .as_ref()
+                    .is_some_and(|exports| is_exported(exports, subpath)),
+            };
+            if exported {
+                return None;
+            }
+        }
+        let relative = target.strip_prefix(&package.dir).ok()?;
+        if package.entries.contains(&entry_stem(relative)) {
+            return None;
+        }
+
+        let package = match &package.name {
+            Some(name) => format!("package \"{name}\""),
+            None => format!("the package in \"{}\"", package.dir.display()),
+        };
+        Some(format!(
+            "Importing \"{specifier}\" reaches into the internals of {package} instead of its public entries, violates rule \"publicEntries\""
+        ))
+    }
+
+    /// The package owning `dir`, memoized per directory
+    fn package_of(&self, dir: &Path) -> Option<Arc<Package>> {
+        if let Some(package) = self.nearest.get(dir) {
+            return package.clone();
+        }
+        let manifest = dir.join("package.json");
+        let package = if manifest.is_file() {
+            read_package(dir, &manifest).map(Arc::new)
+        } else if dir
+            .components()
+            .next_back()
+            .is_some_and(|component| component.as_os_str() == "node_modules")
+        {
+            None
+        } else {
+            dir.parent().and_then(|parent| self.package_of(parent))
+        };
+        self.nearest.insert(dir.to_path_buf(), package.clone());
+        package
+    }
+}
+
+fn read_package(dir: &Path, manifest: &Path) -> Option<Package> {
+    let json = serde_json::from_str::<Value>(&read_to_string(manifest).ok()?).ok()?;
+    let mut declared = vec![];
+    for field in ENTRY_FIELDS {
+        if let Some(Value::String(entry)) = json.get(field) {
+            declared.push(entry.as_str());
+        }
+    }
+    if let Some(exports) = json.get("exports") {
+        export_targets(exports, &mut declared);
+    }
+    if declared.is_empty() {
+        declared.push("index.js");
+    }
+
+    let mut entries = FxHashSet::default();
+    for entry in declared {
+        let stem = entry_stem(Path::new(entry));
+        // Build outputs stand for their counterpart in `src`, as with `--workspace-sources`
+        let mut components = stem.components().peekable();
+        let mut is_output = false;
+        while let Some(Component::Normal(name)) = components.peek() {
+            if !OUTPUT_DIRS.iter().any(|output| name == output) {
+                break;
+            }
+            is_output = true;
+            components.next();
+        }
+        if is_output {
+            entries.insert(Path::new("src").join(components.collect::<PathBuf>()));
+        }
+        entries.insert(stem);
+    }
+    Some(Package {
+        dir: dir.to_path_buf(),
+        name: json.get("name").and_then(Value::as_str).map(String::from),
+        exports: json.get("exports").cloned(),
+        entries,
+    })
+}
+
+/// Collects the file targets of an `exports` field, patterns left out
+fn export_targets<'a>(exports: &'a Value, targets: &mut Vec<&'a str>) {
+    match exports {
+        Value::String(target) if !target.contains('*') => targets.push(target),
+        Value::Array(values) => values
+            .iter()
+            .for_each(|value| export_targets(value, targets)),
+        Value::Object(map) => map
+            .values()
+            .for_each(|value| export_targets(value, targets)),
+        _ => {}
+    }
+}
+
+/// `./dist/index.d.ts` and `dist/index.js` both become `dist/index`
+fn entry_stem(path: &Path) -> PathBuf {
+    let path = path
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect::<PathBuf>();
+    let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+        return path;
+    };
+    let stem = stem.strip_suffix(".d").unwrap_or(stem);
+    path.with_file_name(stem)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PublicEntries, PublicEntriesConfig};
+    use crate::utils::TestDir;
+
+    #[test]
+    fn test_public_entries() {
+        let root = TestDir::new("public-entries");
+        for dir in [
+            "packages/app/src",
+            "packages/ui/src/internal",
+            "node_modules/lib",
+        ] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for (file, contents) in [
+            ("packages/app/package.json", r#"{ "name": "app" }"#),
+            (
+                "packages/ui/package.json",
+                r#"{ "name": "@acme/ui", "main": "./dist/index.js", "exports": { ".": "./dist/index.js", "./button": "./dist/button.js" } }"#,
+            ),
+            ("node_modules/lib/package.json", r#"{ "name": "lib" }"#),
+        ] {
+            root.write(file, contents);
+        }
+
+        let public_entries = PublicEntries::new(&PublicEntriesConfig::default()).unwrap();
+        let app = root.join("packages/app/src/main.ts");
+        let check =
+            |specifier, target: &str| public_entries.check(&app, specifier, &root.join(target));
+
+        assert!(check("@acme/ui", "packages/ui/src/index.ts").is_none());
+        assert!(check("@acme/ui/button", "packages/ui/src/button.ts").is_none());
+        assert!(check("../../ui/src/index", "packages/ui/src/index.ts").is_none());
+        assert!(check("../../ui/src/button", "packages/ui/src/button.ts").is_none());
+        assert!(check("./util", "packages/app/src/util.ts").is_none());
+        assert!(check("lib/internal", "node_modules/lib/internal.js").is_none());
+
+        let message = check(
+            "@acme/ui/src/internal/state",
+            "packages/ui/src/internal/state.ts",
+        );
+        assert!(message.is_some_and(|message| message.contains("package \"@acme/ui\"")));
+        assert!(check(
+            "../../ui/src/internal/state",
+            "packages/ui/src/internal/state.ts"
+        )
+        .is_some());
+    }
+}
@@ -0,0 +1,136 @@
+//! What each specifier of each file resolved to, for other tools to reuse instead of resolving
+//! again, e.g. an `eslint-plugin-import` resolver, which is asked to resolve `source` from
+//! `file` and answers `{ found, path }`:
+//!
+//! ```js
+//! const dump = require('./resolutions.json');
+//! exports.interfaceVersion = 2;
+//! exports.resolve = (source, file) => dump[file]?.[source] ?? { found: false };
+//! ```
+
+use std::{collections::BTreeMap, path::Path};
+
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+
+use crate::incremental::Resolved;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Resolution {
+    /// `false` when the specifier could not be resolved
+    pub found: bool,
+    /// Absolute path of the file the import is linked to in the graph, e.g. the source of a
+    /// workspace package, `None` for Node.js builtins and files ignored by the `browser` field
+    /// of `package.json`
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(transparent)]
+pub struct ResolutionDump {
+    /// Resolution of each specifier by absolute path of its importer, sorted
+    pub files: BTreeMap<String, BTreeMap<String, Resolution>>,
+}
+
+impl ResolutionDump {
+    pub(crate) fn add(&mut self, importer: &Path, resolutions: &FxHashMap<String, Resolved>) {
+        let specifiers = self
+            .files
+            .entry(importer.display().to_string())
+            .or_default();
+        for (specifier, resolved) in resolutions {
+            let resolution = match resolved {
+                Resolved::Path { path, .. } => Resolution {
+                    found: true,
+                    path: Some(path.display().to_string()),
+                },
+                Resolved::Skipped => Resolution {
+                    found: true,
+                    path: None,
+                },
+                Resolved::Failed(_) => Resolution {
+                    found: false,
+                    path: None,
+                },
+            };
+            specifiers.insert(specifier.clone(), resolution);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Resolution;
+    use crate::{
+        service::{AnalyzeService, AnalyzeServiceOptions},
+        utils::TestDir,
+        workspace::test_workspace,
+    };
+
+    #[test]
+    fn test_resolutions() {
+        let root = TestDir::new("resolutions");
+        std::fs::write(
+            root.join("main.ts"),
+            "import './a'; import fs from 'node:fs'; import './missing';",
+        )
+        .unwrap();
+        std::fs::write(root.join("a.ts"), "export {};").unwrap();
+
+        let main = root.join("main.ts");
+        let options = AnalyzeServiceOptions::new(&*root, vec![main.clone().into()])
+            .with_cross_module(true)
+            .with_record_resolutions(true);
+        let service = AnalyzeService::new(options);
+        service.analyze();
+        let dump = service.resolutions();
+        let resolutions = &dump.files[&main.display().to_string()];
+        assert_eq!(
+            resolutions["./a"],
+            Resolution {
+                found: true,
+                path: Some(root.join("a.ts").display().to_string()),
+            }
+        );
+        assert_eq!(
+            resolutions["node:fs"],
+            Resolution {
+                found: true,
+                path: None,
+            }
+        );
+        assert!(!resolutions["./missing"].found);
+        assert!(dump.files[&root.join("a.ts").display().to_string()].is_empty());
+    }
+
+    #[test]
+    fn test_resolutions_workspace() {
+        let (root, _) = test_workspace(
+            "resolutions-workspace",
+            &[
+                (
+                    "packages/a",
+                    r#"{"name":"a","dependencies":{"b":"workspace:*"}}"#,
+                ),
+                ("packages/b", r#"{"name":"b","main":"dist/index.js"}"#),
+            ],
+        );
+        root.write("packages/a/src/index.ts", "import 'b';");
+        root.write("packages/b/src/index.ts", "");
+
+        let a = root.join("packages/a/src/index.ts");
+        let options = AnalyzeServiceOptions::new(&*root, vec![a.clone().into()])
+            .with_cross_module(true)
+            .with_record_resolutions(true);
+        let service = AnalyzeService::new(options);
+        service.analyze();
+        // The dump tells where the graph links the import, not where the resolver gave up
+        assert_eq!(
+            service.resolutions().files[&a.display().to_string()]["b"],
+            Resolution {
+                found: true,
+                path: Some(root.join("packages/b/src/index.ts").display().to_string()),
+            }
+        );
+    }
+}
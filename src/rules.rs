@@ -0,0 +1,1015 @@
+//! Dependency rules from the configuration, evaluated against every edge of the graph.
+
+use std::{fmt, path::Path};
+
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    cycles::{CycleKind, CycleReport},
+    dead_files::DeadFileReport,
+    graph::NodeKind,
+    message::Message,
+    utils::PathAtom,
+};
+
+/// `*` stops at `/`, `**` crosses directories
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// How the violations of a rule are reported, like ESLint's rule levels
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The rule is not checked
+    Off,
+    /// Violations are reported, they only fail the run past `--max-warnings`
+    Warn,
+    /// Violations fail the run
+    #[default]
+    Error,
+}
+
+/// Why a rule exists and where to read more, printed with each of its violations
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleDocs {
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub docs_url: Option<String>,
+}
+
+/// A violation of a configured rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleViolation {
+    /// Path the violation is reported for, relative to the current working directory
+    pub path: String,
+    /// Name of the broken rule, which overrides refer to
+    pub rule: String,
+    pub severity: Severity,
+    pub message: String,
+    pub docs: RuleDocs,
+}
+
+impl RuleViolation {
+    pub fn new(path: impl Into<String>, rule: &str, severity: Severity, message: String) -> Self {
+        Self {
+            path: path.into(),
+            rule: rule.to_string(),
+            severity,
+            message,
+            docs: RuleDocs::default(),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_docs(mut self, docs: &RuleDocs) -> Self {
+        self.docs = docs.clone();
+        self
+    }
+
+    /// The message followed by the docs of the rule
+    pub fn description(&self) -> String {
+        let mut description = self.message.clone();
+        if let Some(comment) = &self.docs.comment {
+            description.push_str(&format!(": {comment}"));
+        }
+        if let Some(docs_url) = &self.docs.docs_url {
+            description.push_str(&format!(" (see {docs_url})"));
+        }
+        description
+    }
+}
+
+impl fmt::Display for RuleViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Warn => write!(f, "{}: warning: {}", self.path, self.description()),
+            Severity::Off | Severity::Error => write!(f, "{}: {}", self.path, self.description()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleAction {
+    Allow,
+    #[default]
+    Forbid,
+}
+
+/// Glob patterns, one or several
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Patterns {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Default for Patterns {
+    fn default() -> Self {
+        Self::Many(Vec::new())
+    }
+}
+
+impl Patterns {
+    /// # Errors
+    /// When a pattern is not a valid glob, reported as part of the rule `name`
+    pub fn compile(&self, name: &str) -> Result<Vec<Pattern>, String> {
+        let patterns = match self {
+            Self::One(pattern) => std::slice::from_ref(pattern),
+            Self::Many(patterns) => patterns.as_slice(),
+        };
+        patterns
+            .iter()
+            .map(|pattern| {
+                Pattern::new(pattern).map_err(|error| format!("rule \"{name}\": {error}"))
+            })
+            .collect()
+    }
+}
+
+/// Returns `true` when one of `patterns` matches `path`
+pub fn matches_any(patterns: &[Pattern], path: &str) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| pattern.matches_with(path, MATCH_OPTIONS))
+}
+
+/// A rule as written in the configuration, e.g.
+/// `{ "name": "no-ui-to-db", "from": "src/ui/**", "to": "src/db/**" }`
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuleConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub docs: RuleDocs,
+    #[serde(default)]
+    pub action: RuleAction,
+    #[serde(default)]
+    pub severity: Severity,
+    /// Importers the rule applies to
+    from: Patterns,
+    /// Imported nodes the rule applies to
+    to: Patterns,
+}
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    pub docs: RuleDocs,
+    pub action: RuleAction,
+    pub severity: Severity,
+    from: Vec<Pattern>,
+    to: Vec<Pattern>,
+}
+
+impl Rule {
+    fn matches(&self, from: &str, to: &str) -> bool {
+        matches_any(&self.from, from) && matches_any(&self.to, to)
+    }
+}
+
+/// Rules evaluated in order, the first rule matching an edge decides whether it is allowed.
+/// Edges no rule matches are allowed, so `allow` rules carve exceptions out of later `forbid`
+/// rules.
+#[derive(Debug, Default, Clone)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    /// # Errors
+    /// When a pattern of a rule is not a valid glob
+    pub fn new(rules: &[RuleConfig]) -> Result<Self, String> {
+        let rules = rules
+            .iter()
+            .filter(|rule| rule.severity != Severity::Off)
+            .map(|rule| {
+                Ok(Rule {
+                    name: rule.name.clone(),
+                    docs: rule.docs.clone(),
+                    action: rule.action,
+                    severity: rule.severity,
+                    from: rule.from.compile(&rule.name)?,
+                    to: rule.to.compile(&rule.name)?,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Returns the rule forbidding an edge between the nodes `from` and `to`,
+    /// which are paths relative to the current working directory or package names.
+    pub fn check(&self, from: &str, to: &str) -> Option<&Rule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(from, to))
+            .filter(|rule| rule.action == RuleAction::Forbid)
+    }
+}
+
+/// The `noCircular` rule as written in the configuration
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoCircularConfig {
+    /// Cycles are only checked between modules matching these globs, every module by default
+    #[serde(default)]
+    include: Patterns,
+    /// Known cycles, allowed when all of their modules match these globs
+    #[serde(default)]
+    exceptions: Patterns,
+    /// Allow cycles which only types close, they are erased at runtime
+    #[serde(default)]
+    ignore_type_only: bool,
+    #[serde(default)]
+    pub severity: Severity,
+    #[serde(flatten)]
+    docs: RuleDocs,
+}
+
+/// Fails the run on circular dependencies, unlike the informational `--cycles` report
+#[derive(Debug, Clone)]
+pub struct NoCircular {
+    include: Vec<Pattern>,
+    exceptions: Vec<Pattern>,
+    ignore_type_only: bool,
+    severity: Severity,
+    docs: RuleDocs,
+}
+
+impl NoCircular {
+    /// # Errors
+    /// When a pattern is not a valid glob
+    pub fn new(config: &NoCircularConfig) -> Result<Self, String> {
+        Ok(Self {
+            include: config.include.compile("noCircular")?,
+            exceptions: config.exceptions.compile("noCircular")?,
+            ignore_type_only: config.ignore_type_only,
+            severity: config.severity,
+            docs: config.docs.clone(),
+        })
+    }
+
+    /// Returns `true` when `path` is checked for cycles
+    pub fn includes(&self, path: &str) -> bool {
+        self.include.is_empty() || matches_any(&self.include, path)
+    }
+
+    /// Violations of the cycles of `report`, which holds the cycles between included modules,
+    /// reported for the first module of each cycle.
+    pub fn check(&self, report: &CycleReport) -> Vec<RuleViolation> {
+        report
+            .cycles
+            .iter()
+            .filter(|cycle| !(self.ignore_type_only && cycle.kind == CycleKind::Type))
+            .filter(|cycle| {
+                !cycle
+                    .modules
+                    .iter()
+                    .all(|module| matches_any(&self.exceptions, module))
+            })
+            .map(|cycle| {
+                let kind = match cycle.kind {
+                    CycleKind::Value => "Circular dependency",
+                    CycleKind::Type => "Type-only circular dependency",
+                };
+                let message = format!(
+                    "{kind} between {} violates rule \"noCircular\"",
+                    cycle.modules.join(", ")
+                );
+                RuleViolation::new(&cycle.modules[0], "noCircular", self.severity, message)
+                    .with_docs(&self.docs)
+            })
+            .collect()
+    }
+}
+
+/// A package rule as written in the configuration, e.g.
+/// `{ "name": "server-only-pg", "packages": "pg", "allow": "src/server/**" }`
+#[derive(Debug, Clone, Deserialize)]
+pub struct PackageRuleConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub docs: RuleDocs,
+    /// Names of the packages the rule applies to, e.g. `@aws-sdk/*`
+    packages: Patterns,
+    /// When present, only importers matching these globs may import the packages
+    #[serde(default)]
+    allow: Option<Patterns>,
+    /// Importers matching these globs must not import the packages
+    #[serde(default)]
+    forbid: Patterns,
+    #[serde(default)]
+    severity: Severity,
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageRule {
+    pub name: String,
+    pub docs: RuleDocs,
+    pub severity: Severity,
+    packages: Vec<Pattern>,
+    allow: Option<Vec<Pattern>>,
+    forbid: Vec<Pattern>,
+}
+
+impl PackageRule {
+    /// Returns `true` when `importer` must not import `package`
+    fn forbids(&self, importer: &str, package: &str) -> bool {
+        matches_any(&self.packages, package)
+            && (self
+                .allow
+                .as_ref()
+                .is_some_and(|allow| !matches_any(allow, importer))
+                || matches_any(&self.forbid, importer))
+    }
+}
+
+/// Restrictions on which parts of the codebase may import which packages
+#[derive(Debug, Default, Clone)]
+pub struct PackageRules {
+    rules: Vec<PackageRule>,
+}
+
+impl PackageRules {
+    /// # Errors
+    /// When a pattern of a rule is not a valid glob
+    pub fn new(rules: &[PackageRuleConfig]) -> Result<Self, String> {
+        let rules = rules
+            .iter()
+            .filter(|rule| rule.severity != Severity::Off)
+            .map(|rule| {
+                Ok(PackageRule {
+                    name: rule.name.clone(),
+                    docs: rule.docs.clone(),
+                    severity: rule.severity,
+                    packages: rule.packages.compile(&rule.name)?,
+                    allow: rule
+                        .allow
+                        .as_ref()
+                        .map(|allow| allow.compile(&rule.name))
+                        .transpose()?,
+                    forbid: rule.forbid.compile(&rule.name)?,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Returns the first rule forbidding `importer`, a path relative to the current working
+    /// directory, to import `package`.
+    pub fn check(&self, importer: &str, package: &str) -> Option<&PackageRule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.forbids(importer, package))
+    }
+}
+
+/// An import of a package forbidden by a package rule
+pub struct PackageViolation<'a> {
+    pub rule: &'a PackageRule,
+    pub package: &'a str,
+    pub specifier: &'a str,
+}
+
+impl fmt::Display for PackageViolation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Importing \"{}\" from package \"{}\" violates rule \"{}\"",
+            self.specifier, self.package, self.rule.name
+        )
+    }
+}
+
+/// The `relativeImports` rule as written in the configuration
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelativeImportsConfig {
+    /// Most `../` segments a relative import may have
+    #[serde(default)]
+    max_parent_segments: Option<usize>,
+    /// Directories relative imports must not leave, e.g. `packages/*`
+    #[serde(default)]
+    roots: Patterns,
+    #[serde(default)]
+    pub severity: Severity,
+    #[serde(flatten)]
+    docs: RuleDocs,
+}
+
+/// Catches relative imports reaching around module encapsulation, e.g. `../../../core/internal`
+#[derive(Debug, Clone)]
+pub struct RelativeImports {
+    max_parent_segments: Option<usize>,
+    roots: Vec<Pattern>,
+    pub severity: Severity,
+    pub docs: RuleDocs,
+}
+
+impl RelativeImports {
+    /// # Errors
+    /// When a pattern is not a valid glob
+    pub fn new(config: &RelativeImportsConfig) -> Result<Self, String> {
+        Ok(Self {
+            max_parent_segments: config.max_parent_segments,
+            roots: config.roots.compile("relativeImports")?,
+            severity: config.severity,
+            docs: config.docs.clone(),
+        })
+    }
+
+    /// Checks an import of `specifier` from `importer` resolving to `target`, when resolved,
+    /// both paths relative to the current working directory. Only relative specifiers are checked.
+    pub fn check(&self, importer: &str, specifier: &str, target: Option<&str>) -> Option<String> {
+        if !specifier.starts_with('.') {
+            return None;
+        }
+        let parents = specifier
+            .split('/')
+            .filter(|segment| *segment == "..")
+            .count();
+        if let Some(max) = self.max_parent_segments.filter(|max| parents > *max) {
+            return Some(format!(
+                "Importing \"{specifier}\" climbs {parents} directories, more than the maximum of {max}, violates rule \"relativeImports\""
+            ));
+        }
+
+        let target = Path::new(target?);
+        let root = Path::new(importer).ancestors().skip(1).find(|dir| {
+            dir.to_str()
+                .is_some_and(|dir| matches_any(&self.roots, dir))
+        })?;
+        if target.starts_with(root) {
+            return None;
+        }
+        Some(format!(
+            "Importing \"{specifier}\" leaves \"{}\", violates rule \"relativeImports\"",
+            root.display()
+        ))
+    }
+}
+
+/// Entry-like files, which nothing imports but tools load, allowed to be orphans by default
+const ENTRY_LIKE_FILES: &[&str] = &[
+    "**/*.config.*",
+    "**/*.setup.*",
+    "**/setupTests.*",
+    "**/*.stories.*",
+];
+
+/// The `noOrphans` rule as written in the configuration
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct NoOrphansConfig {
+    /// Orphans are only reported among files matching these globs, every file by default
+    #[serde(default)]
+    include: Patterns,
+    /// Files allowed to be orphans, on top of configuration files, test setup files and stories
+    #[serde(default)]
+    allow: Patterns,
+    #[serde(default)]
+    pub severity: Severity,
+    #[serde(flatten)]
+    docs: RuleDocs,
+}
+
+/// Fails the run on orphan modules, source files which are neither entries nor imported
+#[derive(Debug, Clone)]
+pub struct NoOrphans {
+    include: Vec<Pattern>,
+    allow: Vec<Pattern>,
+    severity: Severity,
+    docs: RuleDocs,
+}
+
+impl NoOrphans {
+    /// # Errors
+    /// When a pattern is not a valid glob
+    pub fn new(config: &NoOrphansConfig) -> Result<Self, String> {
+        let defaults = Patterns::Many(ENTRY_LIKE_FILES.iter().map(ToString::to_string).collect());
+        let mut allow = defaults.compile("noOrphans")?;
+        allow.extend(config.allow.compile("noOrphans")?);
+        Ok(Self {
+            include: config.include.compile("noOrphans")?,
+            allow,
+            severity: config.severity,
+            docs: config.docs.clone(),
+        })
+    }
+
+    /// Violations of the orphans of `report`, reported for the orphans themselves.
+    pub fn check(&self, report: &DeadFileReport) -> Vec<RuleViolation> {
+        report
+            .files
+            .iter()
+            .filter(|file| self.include.is_empty() || matches_any(&self.include, &file.path))
+            .filter(|file| !matches_any(&self.allow, &file.path))
+            .map(|file| {
+                RuleViolation::new(
+                    &file.path,
+                    "noOrphans",
+                    self.severity,
+                    "Orphan module, neither an entry nor imported, violates rule \"noOrphans\""
+                        .to_string(),
+                )
+                .with_docs(&self.docs)
+            })
+            .collect()
+    }
+}
+
+/// The `maxDepth` rule as written in the configuration, the depth alone or along with a severity
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum MaxDepthConfig {
+    Depth(usize),
+    Rule {
+        depth: usize,
+        #[serde(default)]
+        severity: Severity,
+        #[serde(flatten)]
+        docs: RuleDocs,
+    },
+}
+
+/// Longest dependency chain allowed from an entry to any module, in imports
+#[derive(Debug, Clone)]
+pub struct MaxDepth {
+    pub depth: usize,
+    pub severity: Severity,
+    pub docs: RuleDocs,
+}
+
+impl From<MaxDepthConfig> for MaxDepth {
+    fn from(config: MaxDepthConfig) -> Self {
+        match config {
+            MaxDepthConfig::Depth(depth) => Self {
+                depth,
+                severity: Severity::default(),
+                docs: RuleDocs::default(),
+            },
+            MaxDepthConfig::Rule {
+                depth,
+                severity,
+                docs,
+            } => Self {
+                depth,
+                severity,
+                docs,
+            },
+        }
+    }
+}
+
+/// Violations of the `maxDepth` rule: modules whose shortest dependency chain from one of
+/// `entries` is longer than the maximum depth, reported for the entry.
+///
+/// Only the modules right past the limit are reported, the chain to deeper modules runs
+/// through them.
+pub fn max_depth_violations(
+    deps: &[(PathAtom, Message)],
+    entries: &[String],
+    max_depth: &MaxDepth,
+) -> Vec<RuleViolation> {
+    let mut graph = FxHashMap::<&str, Vec<&str>>::default();
+    for (src, dst) in deps {
+        if dst.kind == NodeKind::Module {
+            graph.entry(src).or_default().push(&dst.file_path);
+        }
+    }
+    for targets in graph.values_mut() {
+        targets.sort_unstable();
+        targets.dedup();
+    }
+
+    let mut violations = vec![];
+    for entry in entries {
+        // Module to the module it was first reached from
+        let mut parents = FxHashMap::<&str, Option<&str>>::default();
+        parents.insert(entry, None);
+        let mut frontier = vec![entry.as_str()];
+        for _ in 0..max_depth.depth {
+            let mut next = vec![];
+            for module in frontier {
+                for &target in graph.get(module).into_iter().flatten() {
+                    if !parents.contains_key(target) {
+                        parents.insert(target, Some(module));
+                        next.push(target);
+                    }
+                }
+            }
+            frontier = next;
+        }
+
+        let mut too_deep = frontier
+            .iter()
+            .flat_map(|module| {
+                graph
+                    .get(module)
+                    .into_iter()
+                    .flatten()
+                    .map(move |target| (*module, *target))
+            })
+            .filter(|(_, target)| !parents.contains_key(target))
+            .collect::<Vec<_>>();
+        too_deep.sort_unstable_by_key(|(_, target)| *target);
+        too_deep.dedup_by_key(|(_, target)| *target);
+        for (parent, target) in too_deep {
+            let mut chain = vec![target, parent];
+            while let Some(Some(module)) = parents.get(chain[chain.len() - 1]) {
+                chain.push(module);
+            }
+            chain.reverse();
+            violations.push(RuleViolation::new(
+                entry,
+                "maxDepth",
+                max_depth.severity,
+                format!(
+                    "Dependency chain of {} imports exceeds the maximum depth of {}: {} violates rule \"maxDepth\"",
+                    chain.len() - 1,
+                    max_depth.depth,
+                    chain.join(" -> ")
+                ),
+            )
+            .with_docs(&max_depth.docs));
+        }
+    }
+    violations
+}
+
+/// Severities of rules changed for some files, e.g.
+/// `{ "files": "test/**", "rules": { "noOrphans": "off", "no-lodash": "warn" } }`
+#[derive(Debug, Clone, Deserialize)]
+pub struct OverrideConfig {
+    files: Patterns,
+    /// Rule names to their severity, built-in rules go by their configuration key
+    rules: FxHashMap<String, Severity>,
+}
+
+#[derive(Debug, Clone)]
+struct Override {
+    files: Vec<Pattern>,
+    rules: FxHashMap<String, Severity>,
+}
+
+/// Overrides relaxing or tightening rules for the files they match, later overrides win.
+/// They change the severity of rules configured at the top level, a rule turned off there is
+/// not checked at all.
+#[derive(Debug, Default, Clone)]
+pub struct Overrides {
+    overrides: Vec<Override>,
+}
+
+impl Overrides {
+    /// # Errors
+    /// When a pattern is not a valid glob
+    pub fn new(overrides: &[OverrideConfig]) -> Result<Self, String> {
+        let overrides = overrides
+            .iter()
+            .map(|config| {
+                Ok(Override {
+                    files: config.files.compile("overrides")?,
+                    rules: config.rules.clone(),
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self { overrides })
+    }
+
+    /// Gives `violations` the severity the overrides matching their path set for their rule,
+    /// dropping those turned off.
+    pub fn apply(&self, mut violations: Vec<RuleViolation>) -> Vec<RuleViolation> {
+        if self.overrides.is_empty() {
+            return violations;
+        }
+        for violation in &mut violations {
+            for config in &self.overrides {
+                if let Some(severity) = config.rules.get(&violation.rule) {
+                    if matches_any(&config.files, &violation.path) {
+                        violation.severity = *severity;
+                    }
+                }
+            }
+        }
+        violations.retain(|violation| violation.severity != Severity::Off);
+        violations
+    }
+}
+
+/// An edge forbidden by a rule
+pub struct Violation<'a> {
+    pub rule: &'a Rule,
+    pub to: &'a str,
+}
+
+impl fmt::Display for Violation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Importing \"{}\" violates rule \"{}\"",
+            self.to, self.rule.name
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        max_depth_violations, MaxDepth, MaxDepthConfig, NoCircular, NoCircularConfig, NoOrphans,
+        NoOrphansConfig, OverrideConfig, Overrides, PackageRuleConfig, PackageRules,
+        RelativeImports, RelativeImportsConfig, RuleConfig, RuleSet, RuleViolation, Severity,
+    };
+    use crate::{
+        cycles::{Cycle, CycleKind, CycleReport},
+        dead_files::{DeadFile, DeadFileReport},
+        graph::{EdgeType, NodeKind},
+        message::Message,
+        utils::PathAtom,
+    };
+
+    #[test]
+    fn test_rule_set() {
+        let rules: Vec<RuleConfig> = serde_json::from_str(
+            r#"[
+                { "name": "ui-may-use-db-types", "action": "allow", "from": "src/ui/**", "to": "src/db/types.ts" },
+                { "name": "no-ui-to-db", "comment": "go through the API", "from": "src/ui/**", "to": ["src/db/**", "pg"] },
+                { "name": "no-lodash", "from": "**", "to": "lodash" }
+            ]"#,
+        )
+        .unwrap();
+        let rules = RuleSet::new(&rules).unwrap();
+
+        let violation = |from, to| rules.check(from, to).map(|rule| rule.name.as_str());
+        assert_eq!(
+            violation("src/ui/button.tsx", "src/db/client.ts"),
+            Some("no-ui-to-db")
+        );
+        assert_eq!(
+            violation("src/ui/forms/input.tsx", "pg"),
+            Some("no-ui-to-db")
+        );
+        assert_eq!(violation("src/ui/button.tsx", "src/db/types.ts"), None);
+        assert_eq!(violation("src/api/users.ts", "src/db/client.ts"), None);
+        assert_eq!(violation("src/api/users.ts", "lodash"), Some("no-lodash"));
+        // `*` does not cross directories
+        let rules: Vec<RuleConfig> =
+            serde_json::from_str(r#"[{ "name": "flat", "from": "src/*", "to": "**" }]"#).unwrap();
+        let rules = RuleSet::new(&rules).unwrap();
+        assert!(rules.check("src/a.ts", "b.ts").is_some());
+        assert!(rules.check("src/nested/a.ts", "b.ts").is_none());
+    }
+
+    #[test]
+    fn test_severity() {
+        let rules: Vec<RuleConfig> = serde_json::from_str(
+            r#"[
+                { "name": "disabled", "severity": "off", "from": "**", "to": "lodash" },
+                { "name": "no-moment", "severity": "warn", "from": "**", "to": "moment" },
+                { "name": "no-lodash", "from": "**", "to": "lodash" }
+            ]"#,
+        )
+        .unwrap();
+        let rules = RuleSet::new(&rules).unwrap();
+        let severity = |to| {
+            rules
+                .check("src/a.ts", to)
+                .map(|rule| (rule.name.as_str(), rule.severity))
+        };
+        assert_eq!(severity("lodash"), Some(("no-lodash", Severity::Error)));
+        assert_eq!(severity("moment"), Some(("no-moment", Severity::Warn)));
+
+        let max_depth: MaxDepthConfig =
+            serde_json::from_str(r#"{ "depth": 4, "severity": "warn" }"#).unwrap();
+        let max_depth = MaxDepth::from(max_depth);
+        assert_eq!((max_depth.depth, max_depth.severity), (4, Severity::Warn));
+
+        let docs: RuleConfig = serde_json::from_str(
+            r#"{ "name": "no-lodash", "comment": "use the standard library", "docsUrl": "https://example.com/lodash", "from": "**", "to": "lodash" }"#,
+        )
+        .unwrap();
+        let violation = RuleViolation::new(
+            "src/a.ts",
+            "no-lodash",
+            Severity::Warn,
+            "Importing \"lodash\"".to_string(),
+        )
+        .with_docs(&docs.docs);
+        assert_eq!(
+            violation.to_string(),
+            "src/a.ts: warning: Importing \"lodash\": use the standard library (see https://example.com/lodash)"
+        );
+    }
+
+    #[test]
+    fn test_overrides() {
+        let overrides: Vec<OverrideConfig> = serde_json::from_str(
+            r#"[
+                { "files": ["test/**", "scripts/**"], "rules": { "no-lodash": "off", "noOrphans": "warn" } },
+                { "files": "scripts/release.ts", "rules": { "noOrphans": "error" } }
+            ]"#,
+        )
+        .unwrap();
+        let overrides = Overrides::new(&overrides).unwrap();
+        let violation =
+            |path: &str, rule| RuleViolation::new(path, rule, Severity::Error, String::new());
+        let violations = overrides
+            .apply(vec![
+                violation("src/a.ts", "no-lodash"),
+                violation("test/a.ts", "no-lodash"),
+                violation("test/b.ts", "noOrphans"),
+                violation("scripts/release.ts", "noOrphans"),
+                violation("test/c.ts", "maxDepth"),
+            ])
+            .into_iter()
+            .map(|violation| (violation.path, violation.severity))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            violations,
+            [
+                ("src/a.ts".to_string(), Severity::Error),
+                ("test/b.ts".to_string(), Severity::Warn),
+                ("scripts/release.ts".to_string(), Severity::Error),
+                ("test/c.ts".to_string(), Severity::Error),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let rules: Vec<RuleConfig> =
+            serde_json::from_str(r#"[{ "name": "broken", "from": "src/[", "to": "**" }]"#).unwrap();
+        assert!(RuleSet::new(&rules)
+            .unwrap_err()
+            .starts_with("rule \"broken\""));
+    }
+
+    #[test]
+    fn test_no_circular() {
+        let cycle = |kind, modules: &[&str]| Cycle {
+            kind,
+            modules: modules.iter().map(ToString::to_string).collect(),
+            type_only_imports: vec![],
+        };
+        let report = CycleReport {
+            cycles: vec![
+                cycle(CycleKind::Value, &["src/a.ts", "src/b.ts"]),
+                cycle(CycleKind::Value, &["src/legacy/a.ts", "src/legacy/b.ts"]),
+                cycle(CycleKind::Value, &["src/c.ts", "src/legacy/c.ts"]),
+                cycle(CycleKind::Type, &["src/d.ts", "src/e.ts"]),
+            ],
+        };
+        let config: NoCircularConfig = serde_json::from_str(
+            r#"{ "include": "src/**", "exceptions": ["src/legacy/**"], "ignoreTypeOnly": true }"#,
+        )
+        .unwrap();
+        let no_circular = NoCircular::new(&config).unwrap();
+        assert!(no_circular.includes("src/a.ts"));
+        assert!(!no_circular.includes("scripts/a.ts"));
+
+        let violations = no_circular
+            .check(&report)
+            .into_iter()
+            .map(|violation| violation.path)
+            .collect::<Vec<_>>();
+        assert_eq!(violations, ["src/a.ts", "src/c.ts"]);
+    }
+
+    #[test]
+    fn test_package_rules() {
+        let rules: Vec<PackageRuleConfig> = serde_json::from_str(
+            r#"[
+                { "name": "server-only-pg", "packages": "pg", "allow": "src/server/**" },
+                { "name": "no-aws-in-ui", "packages": "@aws-sdk/*", "forbid": ["src/ui/**"] }
+            ]"#,
+        )
+        .unwrap();
+        let rules = PackageRules::new(&rules).unwrap();
+        let violation = |importer, package| {
+            rules
+                .check(importer, package)
+                .map(|rule| rule.name.as_str())
+        };
+
+        assert_eq!(violation("src/server/db.ts", "pg"), None);
+        assert_eq!(violation("src/ui/list.ts", "pg"), Some("server-only-pg"));
+        assert_eq!(
+            violation("src/ui/list.ts", "@aws-sdk/client-s3"),
+            Some("no-aws-in-ui")
+        );
+        assert_eq!(violation("src/server/db.ts", "@aws-sdk/client-s3"), None);
+        assert_eq!(violation("src/ui/list.ts", "react"), None);
+    }
+
+    #[test]
+    fn test_relative_imports() {
+        let config: RelativeImportsConfig =
+            serde_json::from_str(r#"{ "maxParentSegments": 2, "roots": "packages/*" }"#).unwrap();
+        let rules = RelativeImports::new(&config).unwrap();
+        let check =
+            |importer, specifier, target| rules.check(importer, specifier, target).is_some();
+
+        let importer = "packages/ui/src/forms/input.ts";
+        assert!(!check(
+            importer,
+            "../../index",
+            Some("packages/ui/index.ts")
+        ));
+        assert!(check(importer, "../../../app/a", Some("packages/app/a.ts")));
+        assert!(check(importer, "./../../../x", None));
+        assert!(check(
+            importer,
+            "../../../core",
+            Some("packages/core/index.ts")
+        ));
+        assert!(!check(
+            importer,
+            "@acme/core",
+            Some("packages/core/index.ts")
+        ));
+        // The nearest root is left, even without climbing above the limit
+        assert!(check(
+            "packages/ui/input.ts",
+            "../app/a",
+            Some("packages/app/a.ts")
+        ));
+        assert!(!check(
+            "scripts/release.ts",
+            "../packages/app/a",
+            Some("packages/app/a.ts")
+        ));
+    }
+
+    #[test]
+    fn test_max_depth_violations() {
+        let edge = |from: &str, to: &str| {
+            (
+                PathAtom::from(from),
+                Message::new(to, NodeKind::Module, EdgeType::Static),
+            )
+        };
+        let deps = vec![
+            edge("main.ts", "a.ts"),
+            edge("a.ts", "b.ts"),
+            edge("b.ts", "c.ts"),
+            edge("c.ts", "d.ts"),
+            // A shortcut keeps `c.ts` within the limit
+            edge("main.ts", "c.ts"),
+            edge("d.ts", "e.ts"),
+            edge("e.ts", "main.ts"),
+        ];
+        let entries = ["main.ts".to_string()];
+
+        let max_depth = |depth| MaxDepth::from(MaxDepthConfig::Depth(depth));
+        assert!(max_depth_violations(&deps, &entries, &max_depth(3)).is_empty());
+        let violations = max_depth_violations(&deps, &entries, &max_depth(2));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].path, "main.ts");
+        assert!(
+            violations[0].message.starts_with("Dependency chain of 3 imports exceeds the maximum depth of 2: main.ts -> c.ts -> d.ts -> e.ts"),
+            "{}",
+            violations[0].message
+        );
+    }
+
+    #[test]
+    fn test_no_orphans() {
+        let report = DeadFileReport {
+            files: [
+                "scripts/release.ts",
+                "src/old.ts",
+                "src/test/setup.ts",
+                "src/button.stories.tsx",
+                "vite.config.ts",
+            ]
+            .iter()
+            .map(|path| DeadFile {
+                path: path.to_string(),
+                size: 0,
+            })
+            .collect(),
+            total_size: 0,
+        };
+        let config: NoOrphansConfig = serde_json::from_str(
+            r#"{ "include": ["src/**", "*.ts"], "allow": "src/test/setup.ts" }"#,
+        )
+        .unwrap();
+        let orphans = NoOrphans::new(&config)
+            .unwrap()
+            .check(&report)
+            .into_iter()
+            .map(|violation| violation.path)
+            .collect::<Vec<_>>();
+        assert_eq!(orphans, ["src/old.ts"]);
+    }
+}
@@ -0,0 +1,226 @@
+//! Rule violations and diagnostics as a SARIF log, read by code scanning tools, e.g. GitHub
+//! shows the results of an uploaded log as annotations of pull requests.
+//!
+//! <https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html>
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{
+    collector::CollectorSink,
+    diagnostic::Diagnostic,
+    rules::{RuleViolation, Severity},
+};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+#[derive(Debug, Serialize)]
+pub struct SarifReport {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: [SarifRun; 1],
+}
+
+#[derive(Debug, Serialize)]
+struct SarifRun {
+    tool: Tool,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Serialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Debug, Serialize)]
+struct Driver {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifResult {
+    /// Name of the broken rule, or the kind of diagnostic
+    pub rule_id: String,
+    /// `warning` or `error`
+    pub level: &'static str,
+    pub message: Message,
+    pub locations: Vec<Location>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Message {
+    pub text: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Location {
+    pub physical_location: PhysicalLocation,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhysicalLocation {
+    pub artifact_location: ArtifactLocation,
+    pub region: Region,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArtifactLocation {
+    /// Relative to the root of the repository
+    pub uri: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Region {
+    pub start_line: usize,
+}
+
+impl SarifReport {
+    /// Reports `violations` and `diagnostics`, whose paths are relative to the current working
+    /// directory, expected to be the root of the repository.
+    pub fn new(violations: &[RuleViolation], diagnostics: &[Diagnostic]) -> Self {
+        let mut results = Vec::new();
+        for violation in violations {
+            let level = match violation.severity {
+                Severity::Off => continue,
+                Severity::Warn => "warning",
+                Severity::Error => "error",
+            };
+            results.push(SarifResult::new(
+                &violation.path,
+                1,
+                &violation.rule,
+                level,
+                violation.to_string(),
+            ));
+        }
+        for diagnostic in diagnostics {
+            let level = match diagnostic.severity {
+                Severity::Off => continue,
+                Severity::Warn => "warning",
+                Severity::Error => "error",
+            };
+            results.push(SarifResult::new(
+                &diagnostic.path.display().to_string(),
+                diagnostic.line(),
+                diagnostic.code.as_str(),
+                level,
+                diagnostic.message.clone(),
+            ));
+        }
+        Self {
+            schema: SCHEMA,
+            version: "2.1.0",
+            runs: [SarifRun {
+                tool: Tool {
+                    driver: Driver {
+                        name: env!("CARGO_PKG_NAME"),
+                        version: env!("CARGO_PKG_VERSION"),
+                    },
+                },
+                results,
+            }],
+        }
+    }
+
+    pub fn results(&self) -> &[SarifResult] {
+        &self.runs[0].results
+    }
+
+    /// # Errors
+    /// When the report cannot be written
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).unwrap();
+        fs::write(path, json)
+            .map_err(|error| format!("Failed to write {}: {error}", path.display()))
+    }
+}
+
+impl SarifResult {
+    fn new(path: &str, line: usize, rule_id: &str, level: &'static str, text: String) -> Self {
+        Self {
+            rule_id: rule_id.to_string(),
+            level,
+            message: Message { text },
+            locations: vec![Location {
+                physical_location: PhysicalLocation {
+                    artifact_location: ArtifactLocation {
+                        uri: path.to_string(),
+                    },
+                    region: Region { start_line: line },
+                },
+            }],
+        }
+    }
+}
+
+/// Writes a [`SarifReport`] once the run is over
+#[derive(Debug, Clone)]
+pub struct SarifSink {
+    path: PathBuf,
+}
+
+impl SarifSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl CollectorSink for SarifSink {
+    fn finish(
+        &mut self,
+        violations: &[RuleViolation],
+        diagnostics: &[Diagnostic],
+    ) -> Result<(), String> {
+        SarifReport::new(violations, diagnostics).write(&self.path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SarifReport;
+    use crate::{
+        diagnostic::{Diagnostic, DiagnosticCode, DiagnosticSpan},
+        rules::{RuleViolation, Severity},
+    };
+
+    #[test]
+    fn test_sarif_report() {
+        let violations = [
+            RuleViolation::new(
+                "src/a.ts",
+                "no-lodash",
+                Severity::Error,
+                "imports lodash".to_string(),
+            ),
+            RuleViolation::new("src/b.ts", "off", Severity::Off, String::new()),
+        ];
+        let diagnostics = [Diagnostic::new(
+            "src/c.ts",
+            DiagnosticCode::UnresolvedImport,
+            "Unable to resolve \"./d\"",
+        )
+        .with_span(DiagnosticSpan::new("\nimport './d';", 8, 13))];
+        let report = SarifReport::new(&violations, &diagnostics);
+        assert_eq!(report.results().len(), 2);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["version"], "2.1.0");
+        assert_eq!(json["runs"][0]["tool"]["driver"]["name"], "marsh");
+        let results = &json["runs"][0]["results"];
+        assert_eq!(results[0]["ruleId"], "no-lodash");
+        assert_eq!(results[0]["level"], "error");
+        assert_eq!(results[1]["ruleId"], "unresolved-import");
+        let location = &results[1]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/c.ts");
+        assert_eq!(location["region"]["startLine"], 2);
+    }
+}
@@ -0,0 +1,72 @@
+/// Collect the specifiers of `composes: a b from "./other.module.css"` declarations of a
+/// CSS module. Classes composed from the same file or `from global` are not dependencies.
+/// <https://github.com/css-modules/css-modules/blob/master/docs/composition.md>
+pub fn composes_specifiers(source_text: &str) -> Vec<String> {
+    let source_text = strip_comments(source_text);
+    let mut specifiers = vec![];
+    let mut rest = source_text.as_str();
+    while let Some(offset) = rest.find("composes") {
+        let preceded_by_boundary = rest[..offset]
+            .chars()
+            .next_back()
+            .is_none_or(|c| c.is_whitespace() || matches!(c, '{' | ';'));
+        rest = &rest[offset + "composes".len()..];
+        if !preceded_by_boundary {
+            continue;
+        }
+        let Some(value) = rest.trim_start().strip_prefix(':') else {
+            continue;
+        };
+        let end = value.find([';', '}']).unwrap_or(value.len());
+        if let Some(specifier) = composes_from(&value[..end]) {
+            specifiers.push(specifier.to_string());
+        }
+    }
+    specifiers
+}
+
+/// The quoted file of a `composes` value, e.g. `./other.css` for `a b from './other.css'`
+fn composes_from(value: &str) -> Option<&str> {
+    let (_, from) = value.rsplit_once(" from ")?;
+    let from = from.trim();
+    let quote = from.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+    from[1..].strip_suffix(quote)
+}
+
+/// Replaces `/* */` comments with spaces
+fn strip_comments(source_text: &str) -> String {
+    let mut stripped = String::with_capacity(source_text.len());
+    let mut rest = source_text;
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+        stripped.push(' ');
+        rest = rest[start + 2..]
+            .find("*/")
+            .map_or("", |end| &rest[start + 2 + end + 2..]);
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+#[cfg(test)]
+mod test {
+    use super::composes_specifiers;
+
+    #[test]
+    fn test_composes_specifiers() {
+        let source_text = r#"
+        .button {
+          composes: base rounded from "./base.module.css";
+          color: red;
+        }
+        .primary { composes: button; composes: accent from './theme.module.scss' }
+        .global { composes: reset from global; }
+        /* composes: commented from "./commented.css"; */
+        .icon-composes { decomposes: x from "./not-a-declaration.css"; }
+        "#;
+        assert_eq!(
+            composes_specifiers(source_text),
+            ["./base.module.css", "./theme.module.scss"]
+        );
+    }
+}
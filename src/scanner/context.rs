@@ -0,0 +1,130 @@
+use std::{fs, path::Path};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::normalize_path;
+
+/// Arguments of a webpack `require.context(directory, recursive, filter)` call.
+/// <https://webpack.js.org/guides/dependency-management/#requirecontext>
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequireContext {
+    pub directory: String,
+    pub recursive: bool,
+    /// Source of the regular expression, matched against `./`-prefixed paths relative to `directory`
+    pub filter: String,
+    pub case_insensitive: bool,
+}
+
+impl RequireContext {
+    pub fn new(directory: String) -> Self {
+        Self {
+            directory,
+            recursive: true,
+            filter: String::from(r"^\./.*$"),
+            case_insensitive: false,
+        }
+    }
+}
+
+/// Expand `context` against the file system relative to the directory of `importer`.
+/// Returns absolute paths of the matched files, or nothing if the filter is not
+/// a regular expression supported by the `regex` crate.
+pub fn expand_require_context(importer: &Path, context: &RequireContext) -> Vec<String> {
+    let Some(dir) = importer.parent() else {
+        return vec![];
+    };
+    let pattern = if context.case_insensitive {
+        format!("(?i){}", context.filter)
+    } else {
+        context.filter.clone()
+    };
+    let Ok(filter) = Regex::new(&pattern) else {
+        return vec![];
+    };
+
+    let root = normalize_path(&dir.join(&context.directory));
+    let mut files = vec![];
+    collect_files(&root, &root, context.recursive, &filter, &mut files);
+    files.retain(|file| Path::new(file) != importer);
+    files.sort_unstable();
+    files
+}
+
+fn collect_files(
+    root: &Path,
+    dir: &Path,
+    recursive: bool,
+    filter: &Regex,
+    files: &mut Vec<String>,
+) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if recursive && entry.file_name() != "node_modules" {
+                collect_files(root, &path, recursive, filter, files);
+            }
+            continue;
+        }
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        let key = format!("./{}", relative.to_string_lossy().replace('\\', "/"));
+        if filter.is_match(&key) {
+            files.push(path.to_string_lossy().to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{expand_require_context, RequireContext};
+    use crate::utils::TestDir;
+
+    #[test]
+    fn test_expand_require_context() {
+        let root = TestDir::new("require-context");
+        for file in [
+            "main.js",
+            "plugins/a.js",
+            "plugins/b.ts",
+            "plugins/nested/c.js",
+            "plugins/README.md",
+        ] {
+            root.write(file, "");
+        }
+        let importer = root.join("main.js");
+        let expand = |context: &RequireContext| {
+            expand_require_context(&importer, context)
+                .into_iter()
+                .map(|path| {
+                    path.strip_prefix(root.to_str().unwrap())
+                        .unwrap()
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut context = RequireContext::new("./plugins".to_string());
+        context.filter = String::from(r"\.js$");
+        assert_eq!(
+            expand(&context),
+            vec!["/plugins/a.js", "/plugins/nested/c.js"]
+        );
+
+        context.recursive = false;
+        assert_eq!(expand(&context), vec!["/plugins/a.js"]);
+
+        context.filter = String::from(r"\.(JS|TS)$");
+        context.case_insensitive = true;
+        assert_eq!(expand(&context), vec!["/plugins/a.js", "/plugins/b.ts"]);
+
+        assert_eq!(expand(&RequireContext::new(".".to_string())).len(), 4);
+    }
+}
@@ -0,0 +1,219 @@
+use oxc_ast::{
+    ast::{
+        Argument, AssignmentOperator, Declaration, Expression, ObjectPropertyKind, Program,
+        Statement, TSModuleDeclarationName,
+    },
+    syntax_directed_operations::BoundNames,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+
+/// Exports the module record leaves out: TypeScript declarations, type-only specifiers and
+/// CommonJS exports.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TypeExports {
+    /// Names of TypeScript declarations, type-only specifiers without a source and properties
+    /// assigned to `exports` or `module.exports`
+    pub names: FxHashSet<String>,
+    /// The subset of `names` which only exist as types: type aliases, interfaces and
+    /// type-only specifiers. Enums, namespaces and ambient declarations are values at runtime.
+    pub types: FxHashSet<String>,
+    /// `export type { imported as exported } from "specifier"`, as `exported` to
+    /// `(specifier, imported)`
+    pub reexports: FxHashMap<String, (String, String)>,
+    /// `export = value` or `module.exports = value`, whose members cannot be known statically
+    pub export_assignment: bool,
+    /// Whether the module assigns to `exports` or `module.exports`
+    pub commonjs: bool,
+}
+
+/// Collect the exports of `program` which are missing from its module record.
+pub fn type_exports(program: &Program<'_>) -> TypeExports {
+    let mut exports = TypeExports::default();
+    for statement in &program.body {
+        match statement {
+            Statement::ExportNamedDeclaration(decl) => {
+                for specifier in &decl.specifiers {
+                    if !decl.export_kind.is_type() && !specifier.export_kind.is_type() {
+                        continue;
+                    }
+                    let exported = specifier.exported.name().to_string();
+                    match &decl.source {
+                        Some(source) => {
+                            let imported = specifier.local.name().to_string();
+                            exports
+                                .reexports
+                                .insert(exported, (source.value.to_string(), imported));
+                        }
+                        None => {
+                            exports.types.insert(exported.clone());
+                            exports.names.insert(exported);
+                        }
+                    }
+                }
+                let Some(declaration) = &decl.declaration else {
+                    continue;
+                };
+                if !declaration.is_typescript_syntax() {
+                    continue;
+                }
+                if let Some(id) = declaration.id() {
+                    if matches!(
+                        declaration,
+                        Declaration::TSTypeAliasDeclaration(_)
+                            | Declaration::TSInterfaceDeclaration(_)
+                    ) {
+                        exports.types.insert(id.name.to_string());
+                    }
+                    exports.names.insert(id.name.to_string());
+                } else if let Declaration::TSModuleDeclaration(module) = declaration {
+                    if let TSModuleDeclarationName::Identifier(id) = &module.id {
+                        exports.names.insert(id.name.to_string());
+                    }
+                } else {
+                    declaration.bound_names(&mut |id| {
+                        exports.names.insert(id.name.to_string());
+                    });
+                }
+            }
+            Statement::TSExportAssignment(_) => exports.export_assignment = true,
+            Statement::ExpressionStatement(statement) => {
+                commonjs_exports(&statement.expression, &mut exports);
+            }
+            _ => {}
+        }
+    }
+    exports
+}
+
+/// Records the exports of a top level CommonJS statement:
+/// `exports.a = ...`, `module.exports.a = ...`, `module.exports = { a, b }`
+/// and `Object.defineProperty(exports, "a", ...)`.
+fn commonjs_exports(expression: &Expression<'_>, exports: &mut TypeExports) {
+    let is_exports_object = |expression: &Expression<'_>| {
+        expression.is_specific_id("exports")
+            || expression.is_specific_member_access("module", "exports")
+    };
+    match expression.get_inner_expression() {
+        Expression::AssignmentExpression(assignment)
+            if assignment.operator == AssignmentOperator::Assign =>
+        {
+            let Some(member) = assignment.left.as_member_expression() else {
+                return;
+            };
+            if member.is_specific_member_access("module", "exports") {
+                exports.commonjs = true;
+                let Expression::ObjectExpression(object) = assignment.right.get_inner_expression()
+                else {
+                    exports.export_assignment = true;
+                    return;
+                };
+                for property in &object.properties {
+                    match property {
+                        ObjectPropertyKind::ObjectProperty(property) => {
+                            match property.key.static_name() {
+                                Some(name) => {
+                                    exports.names.insert(name.to_string());
+                                }
+                                None => exports.export_assignment = true,
+                            }
+                        }
+                        ObjectPropertyKind::SpreadProperty(_) => exports.export_assignment = true,
+                    }
+                }
+            } else if is_exports_object(member.object()) {
+                exports.commonjs = true;
+                match member.static_property_name() {
+                    Some(name) => {
+                        exports.names.insert(name.to_string());
+                    }
+                    None => exports.export_assignment = true,
+                }
+            }
+        }
+        Expression::CallExpression(call)
+            if call
+                .callee
+                .is_specific_member_access("Object", "defineProperty") =>
+        {
+            let [Argument::StringLiteral(name), ..] = call.arguments.get(1..).unwrap_or_default()
+            else {
+                return;
+            };
+            if call.arguments[0]
+                .as_expression()
+                .is_some_and(is_exports_object)
+            {
+                exports.commonjs = true;
+                exports.names.insert(name.value.to_string());
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use oxc_allocator::Allocator;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    use super::type_exports;
+
+    #[test]
+    fn test_type_exports() {
+        let source_text = r"
+        export type A = string;
+        export interface B {}
+        export enum C {}
+        export declare const D: number;
+        export namespace E {}
+        export type { F } from './f';
+        export { type G, H } from './g';
+        export const value = 1;
+        ";
+        let allocator = Allocator::default();
+        let ret = Parser::new(&allocator, source_text, SourceType::ts()).parse();
+
+        let exports = type_exports(&ret.program);
+        let mut names = exports.names.iter().map(String::as_str).collect::<Vec<_>>();
+        names.sort_unstable();
+        assert_eq!(names, ["A", "B", "C", "D", "E"]);
+        let mut types = exports.types.iter().map(String::as_str).collect::<Vec<_>>();
+        types.sort_unstable();
+        assert_eq!(types, ["A", "B"]);
+        assert_eq!(exports.reexports["F"], ("./f".to_string(), "F".to_string()));
+        assert_eq!(exports.reexports["G"], ("./g".to_string(), "G".to_string()));
+        assert!(!exports.reexports.contains_key("H"));
+        assert!(!exports.export_assignment);
+    }
+
+    #[test]
+    fn test_commonjs_exports() {
+        let source_text = r"
+        exports.a = 1;
+        module.exports.b = 2;
+        exports['c'] = 3;
+        Object.defineProperty(exports, 'd', { value: 4 });
+        other.e = 5;
+        ";
+        let allocator = Allocator::default();
+        let ret = Parser::new(&allocator, source_text, SourceType::cjs()).parse();
+        let exports = type_exports(&ret.program);
+        let mut names = exports.names.iter().map(String::as_str).collect::<Vec<_>>();
+        names.sort_unstable();
+        assert_eq!(names, ["a", "b", "c", "d"]);
+        assert!(exports.commonjs);
+        assert!(!exports.export_assignment);
+
+        let source_text = "module.exports = { a, b: 1, 'c': 2 };";
+        let ret = Parser::new(&allocator, source_text, SourceType::cjs()).parse();
+        let exports = type_exports(&ret.program);
+        assert_eq!(exports.names.len(), 3);
+        assert!(!exports.export_assignment);
+
+        let source_text = "module.exports = function () {};";
+        let ret = Parser::new(&allocator, source_text, SourceType::cjs()).parse();
+        assert!(type_exports(&ret.program).export_assignment);
+    }
+}
@@ -0,0 +1,100 @@
+use std::path::Path;
+
+use glob::Pattern;
+
+use crate::utils::normalize_path;
+
+/// Expand Vite's `import.meta.glob` patterns against the file system.
+///
+/// Patterns are relative to the importing file, or to `root` when they start with `/`.
+/// Patterns starting with `!` exclude matches, and the importing file itself is never matched.
+/// Returns absolute paths of the matched files.
+/// <https://vitejs.dev/guide/features.html#glob-import>
+pub fn expand_glob(importer: &Path, root: &Path, patterns: &[String]) -> Vec<String> {
+    let Some(dir) = importer.parent() else {
+        return vec![];
+    };
+    let absolute = |pattern: &str| {
+        let path = match pattern.strip_prefix('/') {
+            Some(pattern) => root.join(pattern),
+            None => dir.join(pattern),
+        };
+        normalize_path(&path).to_string_lossy().to_string()
+    };
+
+    let (negative, positive): (Vec<_>, Vec<_>) = patterns
+        .iter()
+        .partition(|pattern| pattern.starts_with('!'));
+    let negative = negative
+        .iter()
+        .filter_map(|pattern| Pattern::new(&absolute(&pattern[1..])).ok())
+        .collect::<Vec<_>>();
+
+    let mut files = positive
+        .iter()
+        .filter_map(|pattern| glob::glob(&absolute(pattern)).ok())
+        .flatten()
+        .flatten()
+        .filter(|path| path != importer && path.is_file())
+        .filter(|path| !negative.iter().any(|pattern| pattern.matches_path(path)))
+        .map(|path| path.to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    files.sort_unstable();
+    files.dedup();
+    files
+}
+
+#[cfg(test)]
+mod test {
+    use super::expand_glob;
+    use crate::utils::TestDir;
+
+    fn fixture(name: &str, files: &[&str]) -> TestDir {
+        let dir = TestDir::new(name);
+        for file in files {
+            dir.write(file, "");
+        }
+        dir
+    }
+
+    #[test]
+    fn test_expand_glob() {
+        let root = fixture(
+            "expand-glob",
+            &[
+                "src/main.ts",
+                "src/modules/a.ts",
+                "src/modules/b.ts",
+                "src/modules/nested/c.ts",
+                "src/modules/d.js",
+                "shared/e.ts",
+            ],
+        );
+        let importer = root.join("src/main.ts");
+        let expand = |patterns: &[&str]| {
+            let patterns = patterns.iter().map(ToString::to_string).collect::<Vec<_>>();
+            expand_glob(&importer, &root, &patterns)
+                .into_iter()
+                .map(|path| {
+                    path.strip_prefix(root.to_str().unwrap())
+                        .unwrap()
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            expand(&["./modules/*.ts"]),
+            vec!["/src/modules/a.ts", "/src/modules/b.ts"]
+        );
+        assert_eq!(
+            expand(&["./modules/**/*.ts", "!**/b.ts"]),
+            vec!["/src/modules/a.ts", "/src/modules/nested/c.ts"]
+        );
+        assert_eq!(
+            expand(&["/shared/*.ts", "../shared/*.ts"]),
+            vec!["/shared/e.ts"]
+        );
+        assert_eq!(expand(&["./*.ts"]), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,295 @@
+//! Dependencies which are not part of the ESM module record.
+
+mod composes;
+mod context;
+mod exports;
+mod glob;
+mod reference;
+mod visitor;
+
+use std::path::Path;
+
+use oxc_ast::{ast::Program, Trivias, Visit};
+use oxc_span::Span;
+use serde::{Deserialize, Serialize};
+
+pub use self::{
+    composes::composes_specifiers,
+    context::{expand_require_context, RequireContext},
+    exports::{type_exports, TypeExports},
+    glob::expand_glob,
+    reference::triple_slash_references,
+};
+use visitor::DependencyVisitor;
+
+use crate::graph::EdgeType;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dependency {
+    pub specifier: String,
+    pub edge: EdgeType,
+}
+
+/// A dynamic import or `require` call whose specifier is computed at runtime,
+/// a blind spot of the graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonLiteralSpecifier {
+    /// Span of the specifier expression
+    pub span: Span,
+    pub edge: EdgeType,
+}
+
+/// Dependencies found in the source text. Globs and `require.context` calls are kept
+/// unexpanded, the files they match are looked up by [`ScannedDependencies::expanded`].
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ScannedDependencies {
+    pub dependencies: Vec<Dependency>,
+    /// Patterns of each `import.meta.glob` call
+    pub globs: Vec<Vec<String>>,
+    /// Arguments of each `require.context` call
+    pub contexts: Vec<RequireContext>,
+    #[serde(skip)]
+    pub non_literal: Vec<NonLiteralSpecifier>,
+}
+
+impl ScannedDependencies {
+    /// Dependencies along with the files matched by globs and `require.context` calls.
+    ///
+    /// `path` is the file the dependencies were scanned from, glob patterns starting with `/`
+    /// are expanded relative to `root`.
+    pub fn expanded(&self, path: &Path, root: &Path) -> Vec<Dependency> {
+        let globs = self
+            .globs
+            .iter()
+            .flat_map(|patterns| expand_glob(path, root, patterns))
+            .map(|specifier| Dependency {
+                specifier,
+                edge: EdgeType::Glob,
+            });
+        let contexts = self
+            .contexts
+            .iter()
+            .flat_map(|context| expand_require_context(path, context))
+            .map(|specifier| Dependency {
+                specifier,
+                edge: EdgeType::Context,
+            });
+        self.dependencies
+            .iter()
+            .cloned()
+            .chain(globs)
+            .chain(contexts)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash)]
+pub struct ScanOptions {
+    /// Follow `import()` expressions
+    pub dynamic_imports: bool,
+    /// Follow type-only imports and re-exports
+    pub type_imports: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            dynamic_imports: true,
+            type_imports: true,
+        }
+    }
+}
+
+/// Collect dependencies from triple-slash directives and expressions in `program`,
+/// along with the dependencies which cannot be followed.
+pub fn scan_dependencies(
+    source_text: &str,
+    trivias: &Trivias,
+    program: &Program<'_>,
+    options: ScanOptions,
+) -> ScannedDependencies {
+    let mut dependencies = triple_slash_references(source_text, trivias, program)
+        .into_iter()
+        .map(|specifier| Dependency {
+            specifier,
+            edge: EdgeType::Reference,
+        })
+        .collect::<Vec<_>>();
+
+    let mut visitor = DependencyVisitor {
+        source_text,
+        dynamic_imports: options.dynamic_imports,
+        type_imports: options.type_imports,
+        ..DependencyVisitor::default()
+    };
+    visitor.visit_program(program);
+    dependencies.extend(visitor.dependencies);
+
+    ScannedDependencies {
+        dependencies,
+        globs: visitor.globs,
+        contexts: visitor.contexts,
+        non_literal: visitor.non_literal,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use oxc_allocator::Allocator;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    use oxc_ast::Visit;
+
+    use super::{scan_dependencies, Dependency, DependencyVisitor, RequireContext, ScanOptions};
+    use crate::graph::EdgeType;
+
+    fn scan(source_text: &str, options: ScanOptions) -> Vec<Dependency> {
+        let allocator = Allocator::default();
+        let ret = Parser::new(&allocator, source_text, SourceType::ts()).parse();
+        let path = Path::new("/app/src/index.ts");
+        scan_dependencies(source_text, &ret.trivias, &ret.program, options)
+            .expanded(path, Path::new("/app"))
+    }
+
+    fn dependency(specifier: &str, edge: EdgeType) -> Dependency {
+        Dependency {
+            specifier: specifier.to_string(),
+            edge,
+        }
+    }
+
+    #[test]
+    fn test_type_only_reexport() {
+        let source_text = r"
+        export type { A } from './a';
+        export { type B } from './b';
+        export { c } from './c';
+        ";
+
+        assert_eq!(
+            scan(source_text, ScanOptions::default()),
+            vec![dependency("./a", EdgeType::Type)]
+        );
+        let options = ScanOptions {
+            type_imports: false,
+            ..ScanOptions::default()
+        };
+        assert_eq!(scan(source_text, options), vec![]);
+    }
+
+    #[test]
+    fn test_dynamic_import() {
+        let source_text = r"
+        const a = import('./a');
+        const b = await import(`./b`);
+        const c = import(`./${name}`);
+        ";
+
+        assert_eq!(
+            scan(source_text, ScanOptions::default()),
+            vec![
+                dependency("./a", EdgeType::Dynamic),
+                dependency("./b", EdgeType::Dynamic)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_non_literal_specifier() {
+        let source_text = "import(`./${name}`); require(name); require('./a');";
+        let allocator = Allocator::default();
+        let ret = Parser::new(&allocator, source_text, SourceType::ts()).parse();
+        let scanned = scan_dependencies(
+            source_text,
+            &ret.trivias,
+            &ret.program,
+            ScanOptions::default(),
+        );
+        let non_literal = scanned
+            .non_literal
+            .iter()
+            .map(|specifier| (specifier.span.source_text(source_text), specifier.edge))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            non_literal,
+            [
+                ("`./${name}`", EdgeType::Dynamic),
+                ("name", EdgeType::Require)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_require() {
+        let source_text = r"
+        const a = require('./a');
+        const { b } = require(`./b`);
+        const c = require(name);
+        const d = require('./d', 'extra');
+        const e = module.require('./e');
+        ";
+
+        assert_eq!(
+            scan(source_text, ScanOptions::default()),
+            vec![
+                dependency("./a", EdgeType::Require),
+                dependency("./b", EdgeType::Require)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_worker() {
+        let source_text = r"
+        const a = new Worker(new URL('./a.worker.ts', import.meta.url), { type: 'module' });
+        const b = new SharedWorker(new URL(`./b.worker.ts`, import.meta.url));
+        const c = new Worker(new URL('./c.worker.ts', location.href));
+        const d = new Worker('./d.worker.js');
+        ";
+
+        assert_eq!(
+            scan(source_text, ScanOptions::default()),
+            vec![
+                dependency("./a.worker.ts", EdgeType::Worker),
+                dependency("./b.worker.ts", EdgeType::Worker)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_require_context_arguments() {
+        let source_text = r"
+        require.context('./plugins');
+        require.context('./plugins', false, /\.vue$/i);
+        require.context(dir, true);
+        ";
+        let allocator = Allocator::default();
+        let ret = Parser::new(&allocator, source_text, SourceType::ts()).parse();
+        let mut visitor = DependencyVisitor {
+            source_text,
+            ..DependencyVisitor::default()
+        };
+        visitor.visit_program(&ret.program);
+
+        let mut plugins = RequireContext::new("./plugins".to_string());
+        assert_eq!(visitor.contexts[0], plugins);
+        plugins.recursive = false;
+        plugins.filter = String::from(r"\.vue$");
+        plugins.case_insensitive = true;
+        assert_eq!(visitor.contexts[1], plugins);
+        assert_eq!(visitor.contexts.len(), 2);
+    }
+
+    #[test]
+    fn test_ignore_dynamic_import() {
+        let options = ScanOptions {
+            dynamic_imports: false,
+            ..ScanOptions::default()
+        };
+
+        assert!(scan("import('./a')", options).is_empty());
+    }
+}
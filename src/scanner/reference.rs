@@ -0,0 +1,102 @@
+use oxc_ast::{ast::Program, CommentKind, Trivias};
+
+const REFERENCE_START: &str = "<reference";
+
+/// Collect `path` attributes of `/// <reference path="..." />` directives.
+///
+/// Like TypeScript, only directives preceding the first statement are honored.
+/// The returned paths are relative specifiers, e.g. `./globals.d.ts` for `path="globals.d.ts"`.
+/// <https://www.typescriptlang.org/docs/handbook/triple-slash-directives.html>
+pub fn triple_slash_references(
+    source_text: &str,
+    trivias: &Trivias,
+    program: &Program<'_>,
+) -> Vec<String> {
+    let end = program.body.first().map_or(program.span.end, |statement| {
+        oxc_span::GetSpan::span(statement).start
+    });
+
+    trivias
+        .comments()
+        .take_while(|comment| comment.span.end <= end)
+        .filter(|comment| comment.kind == CommentKind::Line)
+        .filter_map(|comment| {
+            let text = comment.span.source_text(source_text).strip_prefix('/')?;
+            let directive = text.trim_start().strip_prefix(REFERENCE_START)?;
+            let path = attribute_value(directive, "path")?;
+            if path.starts_with('.') || path.starts_with('/') {
+                Some(path.to_string())
+            } else {
+                Some(format!("./{path}"))
+            }
+        })
+        .collect()
+}
+
+/// Read the value of `name="value"` or `name='value'`.
+fn attribute_value<'a>(directive: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = directive;
+    while let Some(offset) = rest.find(name) {
+        let preceded_by_space = rest[..offset].ends_with(char::is_whitespace);
+        rest = &rest[offset + name.len()..];
+        if !preceded_by_space {
+            continue;
+        }
+        let Some(value) = rest.trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+        let value = &value[1..];
+        return value.find(quote).map(|end| &value[..end]);
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use oxc_allocator::Allocator;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    use super::triple_slash_references;
+
+    fn references(source_text: &str) -> Vec<String> {
+        let allocator = Allocator::default();
+        let ret = Parser::new(&allocator, source_text, SourceType::ts()).parse();
+        triple_slash_references(source_text, &ret.trivias, &ret.program)
+    }
+
+    #[test]
+    fn test_reference_path() {
+        let source_text = r#"
+        /// <reference path="./globals.d.ts" />
+        ///<reference path='legacy/app.ts'/>
+        /// <reference types="node" />
+        // <reference path="./not-a-directive.ts" />
+        const a = 1;
+        "#;
+
+        assert_eq!(
+            references(source_text),
+            vec!["./globals.d.ts", "./legacy/app.ts"]
+        );
+    }
+
+    #[test]
+    fn test_reference_after_statement() {
+        let source_text = r#"
+        const a = 1;
+        /// <reference path="./globals.d.ts" />
+        "#;
+
+        assert!(references(source_text).is_empty());
+    }
+
+    #[test]
+    fn test_reference_without_statement() {
+        let source_text = r#"/// <reference path="../types/index.d.ts" />"#;
+
+        assert_eq!(references(source_text), vec!["../types/index.d.ts"]);
+    }
+}
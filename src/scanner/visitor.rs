@@ -0,0 +1,195 @@
+use oxc_ast::{
+    ast::{
+        ArrayExpressionElement, CallExpression, ExportNamedDeclaration, Expression,
+        ImportExpression, NewExpression, RegExpFlags,
+    },
+    visit::walk,
+    Visit,
+};
+
+use oxc_span::{GetSpan, Span};
+
+use super::{Dependency, NonLiteralSpecifier, RequireContext};
+use crate::graph::EdgeType;
+
+/// Walks the AST for dependencies expressed as expressions rather than declarations.
+#[derive(Default)]
+pub struct DependencyVisitor<'s> {
+    pub source_text: &'s str,
+    pub dynamic_imports: bool,
+    pub type_imports: bool,
+    pub dependencies: Vec<Dependency>,
+    /// Patterns of each `import.meta.glob` call
+    pub globs: Vec<Vec<String>>,
+    /// Arguments of each `require.context` call
+    pub contexts: Vec<RequireContext>,
+    /// `import()` and `require()` calls which cannot be followed
+    pub non_literal: Vec<NonLiteralSpecifier>,
+}
+
+impl DependencyVisitor<'_> {
+    fn require_context(&self, it: &CallExpression<'_>) -> Option<RequireContext> {
+        let mut arguments = it.arguments.iter().map(|argument| argument.as_expression());
+        let directory = static_string(arguments.next()??)?;
+        let mut context = RequireContext::new(directory.to_string());
+        match arguments.next().flatten() {
+            Some(Expression::BooleanLiteral(recursive)) => context.recursive = recursive.value,
+            Some(_) => return None,
+            None => return Some(context),
+        }
+        match arguments.next().flatten() {
+            Some(Expression::RegExpLiteral(filter)) => {
+                context.filter = filter
+                    .regex
+                    .pattern
+                    .source_text(self.source_text)
+                    .to_string();
+                context.case_insensitive = filter.regex.flags.contains(RegExpFlags::I);
+            }
+            Some(_) => return None,
+            None => {}
+        }
+        Some(context)
+    }
+
+    fn add_non_literal(&mut self, span: Span, edge: EdgeType) {
+        self.non_literal.push(NonLiteralSpecifier { span, edge });
+    }
+
+    fn add(&mut self, specifier: &str, edge: EdgeType) {
+        self.dependencies.push(Dependency {
+            specifier: specifier.to_string(),
+            edge,
+        });
+    }
+}
+
+impl<'a> Visit<'a> for DependencyVisitor<'_> {
+    fn visit_import_expression(&mut self, it: &ImportExpression<'a>) {
+        if self.dynamic_imports {
+            match static_string(&it.source) {
+                Some(specifier) => self.add(specifier, EdgeType::Dynamic),
+                None => self.add_non_literal(it.source.span(), EdgeType::Dynamic),
+            }
+        }
+        walk::walk_import_expression(self, it);
+    }
+
+    fn visit_call_expression(&mut self, it: &CallExpression<'a>) {
+        // `require('./module')`
+        if it.callee.is_specific_id("require") {
+            if let [argument] = it.arguments.as_slice() {
+                if let Some(argument) = argument.as_expression() {
+                    match static_string(argument) {
+                        Some(specifier) => self.add(specifier, EdgeType::Require),
+                        None => self.add_non_literal(argument.span(), EdgeType::Require),
+                    }
+                }
+            }
+        }
+        // `import.meta.glob('./modules/*.ts')`
+        if let Expression::StaticMemberExpression(callee) = &it.callee {
+            if is_import_meta(&callee.object)
+                && matches!(callee.property.name.as_str(), "glob" | "globEager")
+            {
+                if let Some(patterns) = it
+                    .arguments
+                    .first()
+                    .and_then(|argument| argument.as_expression())
+                    .and_then(glob_patterns)
+                {
+                    self.globs.push(patterns);
+                }
+            }
+        }
+        // `require.context('./plugins', true, /\.js$/)`
+        if it.callee.is_specific_member_access("require", "context") {
+            if let Some(context) = self.require_context(it) {
+                self.contexts.push(context);
+            }
+        }
+        walk::walk_call_expression(self, it);
+    }
+
+    fn visit_export_named_declaration(&mut self, it: &ExportNamedDeclaration<'a>) {
+        // `export type { A } from './a'` is left out of the module record
+        if self.type_imports && it.export_kind.is_type() {
+            if let Some(source) = &it.source {
+                self.add(source.value.as_str(), EdgeType::Type);
+            }
+        }
+        walk::walk_export_named_declaration(self, it);
+    }
+
+    fn visit_new_expression(&mut self, it: &NewExpression<'a>) {
+        // `new Worker(new URL('./worker.ts', import.meta.url))`
+        if it.callee.is_specific_id("Worker") || it.callee.is_specific_id("SharedWorker") {
+            if let Some(specifier) = it
+                .arguments
+                .first()
+                .and_then(|argument| argument.as_expression())
+                .and_then(import_meta_url)
+            {
+                self.add(specifier, EdgeType::Worker);
+            }
+        }
+        walk::walk_new_expression(self, it);
+    }
+}
+
+/// Specifier of `new URL('./file', import.meta.url)`.
+fn import_meta_url<'a>(expression: &Expression<'a>) -> Option<&'a str> {
+    let Expression::NewExpression(new_expression) = expression.without_parentheses() else {
+        return None;
+    };
+    if !new_expression.callee.is_specific_id("URL") {
+        return None;
+    }
+    let [specifier, base] = new_expression.arguments.as_slice() else {
+        return None;
+    };
+    let Some(Expression::StaticMemberExpression(base)) = base.as_expression() else {
+        return None;
+    };
+    if !is_import_meta(&base.object) || base.property.name != "url" {
+        return None;
+    }
+    specifier.as_expression().and_then(static_string)
+}
+
+fn is_import_meta(expression: &Expression<'_>) -> bool {
+    matches!(
+        expression,
+        Expression::MetaProperty(meta) if meta.meta.name == "import" && meta.property.name == "meta"
+    )
+}
+
+/// Patterns of `import.meta.glob('./a/*.ts')` or `import.meta.glob(['./a/*.ts', '!**/b.ts'])`.
+fn glob_patterns(expression: &Expression<'_>) -> Option<Vec<String>> {
+    if let Expression::ArrayExpression(array) = expression.without_parentheses() {
+        let patterns = array
+            .elements
+            .iter()
+            .filter_map(ArrayExpressionElement::as_expression)
+            .filter_map(static_string)
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        return (!patterns.is_empty()).then_some(patterns);
+    }
+    static_string(expression).map(|pattern| vec![pattern.to_string()])
+}
+
+/// Value of a string literal or a template literal without substitutions.
+pub fn static_string<'a>(expression: &Expression<'a>) -> Option<&'a str> {
+    match expression.without_parentheses() {
+        Expression::StringLiteral(literal) => Some(literal.value.as_str()),
+        Expression::TemplateLiteral(literal) if literal.expressions.is_empty() => literal
+            .quasis
+            .first()?
+            .value
+            .cooked
+            .as_ref()
+            .map(|cooked| cooked.as_str()),
+        _ => None,
+    }
+}
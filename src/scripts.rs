@@ -0,0 +1,253 @@
+//! Rules written as Rhai scripts, for architectural checks the built-in rules cannot express.
+//!
+//! A script defines `check_edge(edge)`, `check_node(node)` or both. Edges are maps with `from`,
+//! `to`, `kind` (of the target node), `edge` and `symbols`, nodes have `path` and `kind`.
+//! Returning a string, or an array of strings, reports violations, anything else passes.
+//! `matches(path, glob)` matches paths like the patterns of the configuration.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use glob::Pattern;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    graph::NodeKind,
+    message::Message,
+    rules::{matches_any, RuleDocs, RuleViolation, Severity},
+    utils::PathAtom,
+};
+
+/// A script rule as written in the configuration, e.g.
+/// `{ "name": "no-test-utils", "path": "rules/no-test-utils.rhai" }`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptRuleConfig {
+    pub name: String,
+    /// Script file, relative to the current working directory
+    path: PathBuf,
+    #[serde(default)]
+    severity: Severity,
+    #[serde(flatten)]
+    docs: RuleDocs,
+}
+
+#[derive(Debug, Clone)]
+struct ScriptRule {
+    name: String,
+    severity: Severity,
+    docs: RuleDocs,
+    ast: AST,
+    check_edge: bool,
+    check_node: bool,
+}
+
+#[derive(Clone, Default)]
+pub struct ScriptRules {
+    engine: Arc<Engine>,
+    rules: Vec<ScriptRule>,
+}
+
+impl fmt::Debug for ScriptRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScriptRules")
+            .field("rules", &self.rules)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ScriptRules {
+    /// Compiles the scripts of `rules`, which are relative to `cwd`.
+    ///
+    /// # Errors
+    /// When a script cannot be read or does not compile
+    pub fn new(cwd: &Path, rules: &[ScriptRuleConfig]) -> Result<Self, String> {
+        let mut engine = Engine::new();
+        engine.register_fn("matches", |path: &str, glob: &str| {
+            Pattern::new(glob)
+                .map(|pattern| matches_any(&[pattern], path))
+                .map_err(|error| Box::<EvalAltResult>::from(error.to_string()))
+        });
+        let rules = rules
+            .iter()
+            .filter(|rule| rule.severity != Severity::Off)
+            .map(|rule| {
+                // Not `compile_file`, which WASM targets lack
+                let ast = std::fs::read_to_string(cwd.join(&rule.path))
+                    .map_err(|error| error.to_string())
+                    .and_then(|script| engine.compile(script).map_err(|error| error.to_string()))
+                    .map_err(|error| format!("rule \"{}\": {error}", rule.name))?;
+                let defines = |name| ast.iter_functions().any(|function| function.name == name);
+                Ok(ScriptRule {
+                    name: rule.name.clone(),
+                    severity: rule.severity,
+                    docs: rule.docs.clone(),
+                    check_edge: defines("check_edge"),
+                    check_node: defines("check_node"),
+                    ast,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self {
+            engine: Arc::new(engine),
+            rules,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Runs the scripts over every edge and node of the graph `deps`. Edges are reported
+    /// for their importer, nodes for themselves, script errors fail the run like violations.
+    pub fn check(&self, deps: &[(PathAtom, Message)]) -> Vec<RuleViolation> {
+        let mut nodes = FxHashMap::<&str, NodeKind>::default();
+        for (src, dst) in deps {
+            nodes.entry(src).or_insert(NodeKind::Module);
+            nodes.insert(&dst.file_path, dst.kind);
+        }
+        let mut nodes = nodes.into_iter().collect::<Vec<_>>();
+        nodes.sort_unstable_by_key(|(path, _)| *path);
+
+        let mut violations = vec![];
+        for rule in &self.rules {
+            if rule.check_edge {
+                for (src, dst) in deps {
+                    let mut edge = Map::new();
+                    edge.insert("from".into(), src.as_str().into());
+                    edge.insert("to".into(), dst.file_path.as_str().into());
+                    edge.insert("kind".into(), name_of(dst.kind).into());
+                    edge.insert("edge".into(), name_of(dst.edge).into());
+                    let symbols = dst.symbols.iter().cloned().map(Dynamic::from);
+                    edge.insert("symbols".into(), symbols.collect::<Array>().into());
+                    violations.extend(self.call(rule, "check_edge", edge, src));
+                }
+            }
+            if rule.check_node {
+                for (path, kind) in &nodes {
+                    let mut node = Map::new();
+                    node.insert("path".into(), path.to_string().into());
+                    node.insert("kind".into(), name_of(*kind).into());
+                    violations.extend(self.call(rule, "check_node", node, path));
+                }
+            }
+        }
+        violations
+    }
+
+    /// Calls `function` of `rule` with `argument`, reporting its violations for `path`
+    fn call(
+        &self,
+        rule: &ScriptRule,
+        function: &str,
+        argument: Map,
+        path: &str,
+    ) -> Vec<RuleViolation> {
+        let violation = |message| {
+            RuleViolation::new(path, &rule.name, rule.severity, message).with_docs(&rule.docs)
+        };
+        let result =
+            self.engine
+                .call_fn::<Dynamic>(&mut Scope::new(), &rule.ast, function, (argument,));
+        let messages = match result {
+            Ok(result) if result.is_string() => vec![result],
+            Ok(result) if result.is_array() => result.into_array().unwrap_or_default(),
+            Ok(_) => vec![],
+            Err(error) => {
+                return vec![RuleViolation {
+                    severity: Severity::Error,
+                    ..violation(format!("Script rule \"{}\" failed: {error}", rule.name))
+                }]
+            }
+        };
+        messages
+            .into_iter()
+            .filter_map(|message| message.into_string().ok())
+            .filter(|message| !message.is_empty())
+            .map(|message| violation(format!("{message}, violates rule \"{}\"", rule.name)))
+            .collect()
+    }
+}
+
+/// Name of a node or edge kind, as in the JSON output
+fn name_of(kind: impl Serialize) -> String {
+    serde_json::to_value(kind)
+        .ok()
+        .and_then(|value| value.as_str().map(String::from))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        graph::{EdgeType, NodeKind},
+        message::Message,
+        utils::{PathAtom, TestDir},
+    };
+
+    use super::{ScriptRuleConfig, ScriptRules};
+
+    #[test]
+    fn test_script_rules() {
+        let root = TestDir::new("scripts");
+        std::fs::write(
+            root.join("rules.rhai"),
+            r#"
+                fn check_edge(edge) {
+                    if matches(edge.from, "src/**") && edge.to.contains(".test.") {
+                        return `Importing "${edge.to}" pulls test code into the app`;
+                    }
+                }
+                fn check_node(node) {
+                    if node.kind == "package" && node.path == "moment" { ["Use date-fns"] }
+                }
+            "#,
+        )
+        .unwrap();
+        let config: Vec<ScriptRuleConfig> = serde_json::from_str(
+            r#"[{ "name": "custom", "path": "rules.rhai", "severity": "warn" }]"#,
+        )
+        .unwrap();
+        let rules = ScriptRules::new(&root, &config).unwrap();
+
+        let edge = |from: &str, to: &str, kind| {
+            (
+                PathAtom::from(from),
+                Message::new(to, kind, EdgeType::Static),
+            )
+        };
+        let deps = vec![
+            edge("src/main.ts", "src/a.test.ts", NodeKind::Module),
+            edge("test/a.ts", "src/b.test.ts", NodeKind::Module),
+            edge("src/main.ts", "moment", NodeKind::Package),
+        ];
+        let violations = rules
+            .check(&deps)
+            .into_iter()
+            .map(|violation| (violation.path, violation.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            violations,
+            [
+                (
+                    "src/main.ts".to_string(),
+                    "Importing \"src/a.test.ts\" pulls test code into the app, violates rule \"custom\"".to_string()
+                ),
+                (
+                    "moment".to_string(),
+                    "Use date-fns, violates rule \"custom\"".to_string()
+                ),
+            ]
+        );
+
+        let broken: Vec<ScriptRuleConfig> =
+            serde_json::from_str(r#"[{ "name": "broken", "path": "missing.rhai" }]"#).unwrap();
+        assert!(ScriptRules::new(&root, &broken)
+            .unwrap_err()
+            .starts_with("rule \"broken\""));
+    }
+}
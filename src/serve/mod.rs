@@ -0,0 +1,278 @@
+//! `marsh serve`, a local HTTP server exposing the graph and a viewer to explore it in a
+//! browser, without a separate frontend build:
+//!
+//! - `/`: the viewer, listing the nodes and the imports and dependents of the selected one
+//! - `/graph.json`: the graph, as printed by `--format json`
+//! - `/dependents?path=...`: the files which import `path`, directly or not
+//!
+//! The entries are analyzed once, when the server starts. Each connection is answered on its
+//! own thread, so a slow client does not hold up the others.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use serde_json::json;
+
+use crate::{
+    daemon::analyze,
+    graph::GraphBuilder,
+    service::AnalyzeServiceOptions,
+    utils::{normalize_path, percent_decode},
+};
+
+const INDEX_HTML: &str = include_str!("index.html");
+/// How long reading the request or writing the response of a connection may block
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn new(status: &'static str, content_type: &'static str, body: String) -> Self {
+        Self {
+            status,
+            content_type,
+            body,
+        }
+    }
+
+    fn text(status: &'static str, body: &str) -> Self {
+        Self::new(status, "text/plain; charset=utf-8", format!("{body}\n"))
+    }
+}
+
+pub struct Server {
+    cwd: PathBuf,
+    graph: GraphBuilder,
+}
+
+impl Server {
+    /// Analyzes the paths of `options`
+    pub fn new(cwd: &Path, options: AnalyzeServiceOptions) -> Self {
+        let (_, collector) = analyze(options, true);
+        Self {
+            cwd: cwd.to_path_buf(),
+            graph: collector.graph(),
+        }
+    }
+
+    /// Answers requests sent to `address`, e.g. `127.0.0.1:4000`, until the process ends
+    ///
+    /// # Errors
+    /// When `address` cannot be listened on, e.g. when the port is taken
+    pub fn serve(&self, address: &str) -> Result<(), String> {
+        let listener = TcpListener::bind(address)
+            .map_err(|error| format!("Failed to listen on {address}: {error}"))?;
+        self.serve_listener(&listener);
+        Ok(())
+    }
+
+    /// Answers the connections accepted by `listener`, each on its own thread
+    fn serve_listener(&self, listener: &TcpListener) {
+        thread::scope(|scope| {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                if stream.set_read_timeout(Some(TIMEOUT)).is_err()
+                    || stream.set_write_timeout(Some(TIMEOUT)).is_err()
+                {
+                    continue;
+                }
+                scope.spawn(move || self.serve_connection(BufReader::new(&stream), &stream));
+            }
+        });
+    }
+
+    /// Answers the one request read from a connection, which is closed afterwards
+    fn serve_connection(&self, mut reader: impl BufRead, mut writer: impl Write) {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).is_err() {
+            return;
+        }
+        // Headers are not needed, but have to be read before answering
+        let mut header = String::new();
+        loop {
+            header.clear();
+            match reader.read_line(&mut header) {
+                Ok(0) | Err(_) => break,
+                Ok(_) if header.trim().is_empty() => break,
+                Ok(_) => {}
+            }
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            let response = Response::text("400 Bad Request", "Invalid request");
+            let _ = write_response(&mut writer, &response, true);
+            return;
+        };
+        let response = if matches!(method, "GET" | "HEAD") {
+            self.respond(target)
+        } else {
+            Response::text("405 Method Not Allowed", "Only GET and HEAD are allowed")
+        };
+        let _ = write_response(&mut writer, &response, method != "HEAD");
+    }
+
+    fn respond(&self, target: &str) -> Response {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        match path {
+            "/" | "/index.html" => {
+                Response::new("200 OK", "text/html; charset=utf-8", INDEX_HTML.to_string())
+            }
+            "/graph.json" => Response::new(
+                "200 OK",
+                "application/json",
+                self.graph.to_json().to_string(),
+            ),
+            "/dependents" => {
+                let Some(file) = query_param(query, "path") else {
+                    return Response::text("400 Bad Request", "Missing the path parameter");
+                };
+                let dependents = self.graph.dependents(&self.node_name(&file));
+                Response::new("200 OK", "application/json", json!(dependents).to_string())
+            }
+            _ => Response::text("404 Not Found", &format!("{path} not found")),
+        }
+    }
+
+    /// Name of the node of `path`, relative to the working directory
+    fn node_name(&self, path: &str) -> String {
+        let path = self.cwd.join(path);
+        let path = path.strip_prefix(&self.cwd).unwrap_or(&path);
+        normalize_path(path).display().to_string()
+    }
+}
+
+/// Decoded value of the parameter `name` of a query string
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        (percent_decode(&key.replace('+', " ")) == name)
+            .then(|| percent_decode(&value.replace('+', " ")))
+    })
+}
+
+fn write_response(
+    writer: &mut impl Write,
+    response: &Response,
+    with_body: bool,
+) -> std::io::Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    )?;
+    if with_body {
+        writer.write_all(response.body.as_bytes())?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::{Cursor, Read, Write},
+        net::{TcpListener, TcpStream},
+    };
+
+    use serde_json::{json, Value};
+
+    use super::{query_param, Server};
+    use crate::{service::AnalyzeServiceOptions, utils::TestDir};
+
+    fn get(server: &Server, request: &str) -> (String, String) {
+        let mut output = Vec::new();
+        server.serve_connection(Cursor::new(request), &mut output);
+        let output = String::from_utf8(output).unwrap();
+        let (head, body) = output.split_once("\r\n\r\n").unwrap();
+        let status = head.lines().next().unwrap().to_string();
+        (status, body.to_string())
+    }
+
+    #[test]
+    fn test_serve() {
+        let root = TestDir::new("serve");
+        std::fs::create_dir_all(root.join("my dir")).unwrap();
+        for (file, contents) in [
+            ("main.ts", "import './a'; import './my dir/b';"),
+            ("a.ts", "import './my dir/b';"),
+            ("my dir/b.ts", ""),
+        ] {
+            root.write(file, contents);
+        }
+
+        let options = AnalyzeServiceOptions::new(&*root, vec![root.join("main.ts").into()])
+            .with_cross_module(true);
+        let server = Server::new(&root, options);
+
+        let (status, body) = get(&server, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert!(body.contains("/graph.json"));
+
+        let (status, body) = get(&server, "GET /graph.json HTTP/1.1\r\n\r\n");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        let graph = serde_json::from_str::<Value>(&body).unwrap();
+        assert_eq!(graph["edges"].as_array().unwrap().len(), 3);
+
+        let request = "GET /dependents?path=my%20dir%2Fb.ts HTTP/1.1\r\n\r\n";
+        let (status, body) = get(&server, request);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(
+            serde_json::from_str::<Value>(&body).unwrap(),
+            json!(["a.ts", "main.ts"])
+        );
+
+        let (status, _) = get(&server, "GET /dependents HTTP/1.1\r\n\r\n");
+        assert_eq!(status, "HTTP/1.1 400 Bad Request");
+        let (status, _) = get(&server, "GET /unknown HTTP/1.1\r\n\r\n");
+        assert_eq!(status, "HTTP/1.1 404 Not Found");
+        let (status, _) = get(&server, "POST /graph.json HTTP/1.1\r\n\r\n");
+        assert_eq!(status, "HTTP/1.1 405 Method Not Allowed");
+        let (status, body) = get(&server, "HEAD /graph.json HTTP/1.1\r\n\r\n");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert!(body.is_empty());
+    }
+
+    #[test]
+    fn test_slow_client() {
+        let root = TestDir::new("serve-slow-client");
+        root.write("main.ts", "");
+        let options = AnalyzeServiceOptions::new(&*root, vec![root.join("main.ts").into()]);
+        let server = Server::new(&root, options);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || server.serve_listener(&listener));
+
+        // A client which never sends its request does not hold up the next one
+        let _slow = TcpStream::connect(address).unwrap();
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream
+            .write_all(b"GET /graph.json HTTP/1.1\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+    }
+
+    #[test]
+    fn test_query_param() {
+        assert_eq!(
+            query_param("a=1&path=src%2Fmy+file.ts", "path").as_deref(),
+            Some("src/my file.ts")
+        );
+        assert_eq!(query_param("path", "path").as_deref(), Some(""));
+        assert_eq!(query_param("a=1", "path"), None);
+    }
+}
@@ -1,26 +1,195 @@
 use std::{
+    cell::RefCell,
     ffi::OsStr,
+    fs::Metadata,
     path::{Path, PathBuf},
-    sync::{Arc, Condvar, Mutex},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
 };
 
-use crate::{collector::{CollectorSender, CollectorService}, message::Message};
-use dashmap::DashMap;
+use crate::{
+    barrel::{definition_of, is_barrel},
+    boundaries::Boundaries,
+    cache::{AnalysisCache, ParsedSource},
+    collector::{CollectorMessage, CollectorSender, CollectorService, FileDependencies},
+    cycles::CycleReport,
+    dead_files::{format_size, DeadFileReport},
+    deep_imports::{package_name, DeepImportPolicy},
+    diagnostic::{Diagnostic, DiagnosticCode, DiagnosticSpan},
+    explain::ResolutionExplanation,
+    exports::{has_export, star_export_conflicts, trace_export, SymbolTrace},
+    file_system::{FileSystem, OsFileSystem, SharedFileSystem},
+    graph::{DependencyGraph, EdgeType, NodeKind},
+    incremental::{fingerprint, FileState, Incremental, Resolved, ResolvedPackage},
+    message::Message,
+    public_entries::PublicEntries,
+    resolutions::ResolutionDump,
+    rules::{
+        max_depth_violations, MaxDepth, NoCircular, NoOrphans, Overrides, PackageRules,
+        PackageViolation, RelativeImports, RuleSet, RuleViolation, Severity, Violation,
+    },
+    scanner::{composes_specifiers, scan_dependencies, type_exports, ScanOptions, TypeExports},
+    scripts::ScriptRules,
+    stats::Counters,
+    suppressions::Suppressions,
+    telemetry::{Span, Tracer},
+    unused_dependencies::UnusedDependencyReport,
+    workspace::Workspace,
+};
+use dashmap::{mapref::entry::Entry, DashMap, DashSet};
 use oxc_allocator::Allocator;
 use oxc_parser::{ParseOptions, Parser};
-use oxc_resolver::Resolver;
+use oxc_resolver::{PackageJson, Resolution, ResolveContext, ResolveError, ResolverGeneric};
 use oxc_semantic::{ModuleRecord, SemanticBuilder};
-use oxc_span::{SourceType, VALID_EXTENSIONS};
-use rayon::{iter::ParallelBridge, prelude::ParallelIterator};
+use oxc_span::{CompactStr, SourceType, VALID_EXTENSIONS};
+use oxc_syntax::module_record::{ExportImportName, ImportImportName, RequestedModule};
+use rayon::{
+    iter::{IntoParallelIterator, ParallelBridge},
+    prelude::ParallelIterator,
+    Scope,
+};
 use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
-    loader::{JavaScriptSource, PartialLoader, LINT_PARTIAL_LOADER_EXT},
-    utils::read_to_string,
+    loader::{JavaScriptSource, Loader, PartialLoader, LINT_PARTIAL_LOADER_EXT, MARKDOWN_EXT},
+    utils::{
+        declaration_implementation, intern_path, is_asset_path, is_css_module_path, is_json_path,
+        is_style_path, map_to_string, node_modules_package_name, normalize_path, original_source,
+        read_to_string, source_type_from_path, FileText, PathAtom, DECLARATION_EXTENSIONS,
+        EXTENSION_ALIAS,
+    },
 };
 
 pub type Error = miette::Error;
 
+/// Resolves through the file system of the analysis
+type Resolver = ResolverGeneric<SharedFileSystem>;
+
+/// Returns `true` when every request of `specifier` is erased at runtime,
+/// e.g. `import type { A } from "mod"` or `import { type A, type B } from "mod"`.
+fn is_type_only(
+    module_record: &ModuleRecord,
+    specifier: &str,
+    requested: &[RequestedModule],
+) -> bool {
+    if requested.iter().all(RequestedModule::is_type) {
+        return true;
+    }
+    if !requested.iter().all(RequestedModule::is_import) {
+        return false;
+    }
+    let mut entries = module_record
+        .import_entries
+        .iter()
+        .filter(|entry| entry.module_request.name() == specifier)
+        .peekable();
+    entries.peek().is_some() && entries.all(|entry| entry.is_type)
+}
+
+/// Names of the bindings `module_record` imports or re-exports from `specifier`
+fn imported_symbols(module_record: &ModuleRecord, specifier: &str) -> Vec<String> {
+    let imports = module_record
+        .import_entries
+        .iter()
+        .filter(|entry| entry.module_request.name() == specifier)
+        .map(|entry| match &entry.import_name {
+            ImportImportName::Name(name) => name.name().to_string(),
+            ImportImportName::NamespaceObject => "*".to_string(),
+            ImportImportName::Default(_) => "default".to_string(),
+        });
+    let reexports = module_record
+        .indirect_export_entries
+        .iter()
+        .chain(&module_record.star_export_entries)
+        .filter(|entry| {
+            entry
+                .module_request
+                .as_ref()
+                .is_some_and(|request| request.name() == specifier)
+        })
+        .filter_map(|entry| match &entry.import_name {
+            // `import { a } from "mod"; export { a }` is recorded as a re-export of the import
+            ExportImportName::Name(name)
+                if module_record
+                    .import_entries
+                    .iter()
+                    .any(|import| import.local_name.name() == name.name()) =>
+            {
+                None
+            }
+            ExportImportName::Name(name) => Some(name.name().to_string()),
+            _ => Some("*".to_string()),
+        });
+    imports.chain(reexports).collect()
+}
+
+/// The linked records of the modules `module_record` star-exports
+fn star_export_remotes(module_record: &ModuleRecord) -> Vec<Arc<ModuleRecord>> {
+    module_record
+        .star_export_entries
+        .iter()
+        .filter_map(|export_entry| {
+            let module_request = export_entry.module_request.as_ref()?;
+            let remote = module_record.loaded_modules.get(module_request.name())?;
+            Some(Arc::clone(remote.value()))
+        })
+        .collect()
+}
+
+/// Appends both the `exported_bindings` and `exported_bindings_from_star_export` of the
+/// modules `module_record` star-exports to its `exported_bindings_from_star_export`
+fn append_star_export_bindings(module_record: &ModuleRecord) {
+    for remote_module_record in star_export_remotes(module_record) {
+        let remote_exported_bindings_from_star_export = remote_module_record
+            .exported_bindings_from_star_export
+            .iter()
+            .flat_map(|r| r.value().clone());
+        let remote_bindings = remote_module_record
+            .exported_bindings
+            .keys()
+            .cloned()
+            .chain(remote_exported_bindings_from_star_export)
+            .collect::<Vec<_>>();
+        module_record
+            .exported_bindings_from_star_export
+            .entry(remote_module_record.resolved_absolute_path.clone())
+            .or_default()
+            .value_mut()
+            .extend(remote_bindings);
+    }
+}
+
+/// How imports resolving to TypeScript declaration files are handled
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DeclarationMode {
+    /// Keep declaration files as distinct nodes
+    #[default]
+    Keep,
+    /// Replace declaration files by their implementation sibling when there is one
+    Pair,
+    /// Drop declaration files from the graph
+    Exclude,
+}
+
+impl FromStr for DeclarationMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(Self::Keep),
+            "pair" => Ok(Self::Pair),
+            "exclude" => Ok(Self::Exclude),
+            _ => Err(format!(
+                "unknown declaration mode \"{s}\", expected one of keep, pair, exclude"
+            )),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct AnalyzeServiceOptions {
     /// Current working directory
     cwd: Box<Path>,
@@ -32,6 +201,129 @@ pub struct AnalyzeServiceOptions {
     tsconfig: Option<PathBuf>,
 
     cross_module: bool,
+
+    /// Collapse resolutions into `node_modules` into a single node per package,
+    /// without traversing into the package.
+    collapse_packages: bool,
+
+    /// `package.json` fields tried in order when resolving a package entry,
+    /// defaults to `["main"]` when empty
+    main_fields: Vec<String>,
+
+    /// Export conditions in addition to `module` and `require`
+    condition_names: Vec<String>,
+
+    /// Record imported stylesheets as leaf nodes
+    track_styles: bool,
+
+    /// Follow `import()` expressions
+    dynamic_imports: bool,
+
+    /// How imports resolving to `.d.ts` files are handled
+    declarations: DeclarationMode,
+
+    /// Attribute generated files to their original source through source maps
+    follow_source_maps: bool,
+
+    /// Report syntax errors, files are analyzed as far as they parse either way
+    syntax_errors: bool,
+
+    /// Package names and URL schemes, e.g. `virtual:`, of specifiers resolved by other tools,
+    /// reported as warnings instead of errors when unresolved
+    externals: Vec<String>,
+
+    /// Follow type-only imports and re-exports
+    type_imports: bool,
+
+    /// Report named imports which their target module does not export
+    check_exports: bool,
+
+    /// Report names re-exported ambiguously through several `export * from`
+    check_star_exports: bool,
+
+    /// Resolve workspace packages to their source entry
+    workspace_sources: bool,
+
+    /// Packages whose internals must not be imported, `*` for every package
+    deny_deep_imports: Vec<String>,
+
+    /// Load `.md` files and follow the imports of their JavaScript and TypeScript code blocks
+    markdown_code_blocks: bool,
+
+    /// Only build what the graph needs from each file, leaving out checks of the syntax
+    fast: bool,
+
+    /// Build the control flow graph of each source, which the analysis does not read
+    build_cfg: bool,
+
+    /// Build the JSDoc comments of each source, which the analysis does not read
+    build_jsdoc: bool,
+
+    /// Map source files into memory instead of reading them
+    mmap: bool,
+
+    /// Keep the parsed files in memory for the next runs, until invalidated
+    resident: bool,
+
+    /// Aborts the analysis when cancelled
+    cancellation: CancellationToken,
+
+    /// Files larger than this many bytes are skipped
+    max_file_size: Option<u64>,
+
+    /// The analysis stops when it reaches more files than this
+    max_files: Option<usize>,
+
+    /// Loaders of custom file types
+    loaders: Vec<Arc<dyn Loader>>,
+
+    /// Where files are read from, the disk when `None`
+    file_system: Option<Arc<dyn FileSystem>>,
+
+    /// Keep the resolution of every specifier, see [`AnalyzeService::resolutions`]
+    record_resolutions: bool,
+
+    /// Spans of the run are recorded in it, see [`crate::telemetry`]
+    tracer: Option<Arc<Tracer>>,
+
+    /// Directory parsed sources are cached in between runs
+    cache_dir: Option<PathBuf>,
+
+    /// Cache of parsed sources shared with other services, used instead of `cache_dir`
+    cache: Option<AnalysisCache>,
+
+    /// Only analyze the files changed since the previous run stored in the cache
+    incremental: bool,
+
+    /// Dependency rules every edge is checked against
+    rules: RuleSet,
+
+    /// Fail on circular dependencies
+    no_circular: Option<NoCircular>,
+
+    /// Constraints between tagged directories and packages
+    boundaries: Option<Boundaries>,
+
+    /// Restrictions on which files may import which packages
+    package_rules: PackageRules,
+
+    /// Longest dependency chain allowed from an entry
+    max_depth: Option<MaxDepth>,
+
+    /// Fail on source files which are neither entries nor imported
+    no_orphans: Option<NoOrphans>,
+
+    /// Fail on imports reaching into the internals of other first-party packages
+    public_entries: Option<PublicEntries>,
+
+    /// Fail on relative imports climbing too many directories or leaving their root
+    relative_imports: Option<RelativeImports>,
+
+    /// Rules written as scripts, run over the whole graph
+    scripts: ScriptRules,
+
+    /// Severities of rules changed for some files
+    overrides: Overrides,
 }
 
 impl AnalyzeServiceOptions {
@@ -45,6 +337,46 @@ impl AnalyzeServiceOptions {
             paths,
             tsconfig: None,
             cross_module: false,
+            collapse_packages: false,
+            main_fields: Vec::new(),
+            condition_names: Vec::new(),
+            track_styles: false,
+            dynamic_imports: true,
+            declarations: DeclarationMode::default(),
+            follow_source_maps: false,
+            syntax_errors: true,
+            externals: Vec::new(),
+            workspace_sources: false,
+            type_imports: true,
+            check_exports: false,
+            check_star_exports: false,
+            deny_deep_imports: Vec::new(),
+            markdown_code_blocks: false,
+            fast: false,
+            build_cfg: false,
+            build_jsdoc: false,
+            mmap: false,
+            resident: false,
+            cancellation: CancellationToken::default(),
+            max_file_size: None,
+            max_files: None,
+            loaders: Vec::new(),
+            file_system: None,
+            record_resolutions: false,
+            tracer: None,
+            cache_dir: None,
+            cache: None,
+            incremental: false,
+            rules: RuleSet::default(),
+            no_circular: None,
+            boundaries: None,
+            package_rules: PackageRules::default(),
+            max_depth: None,
+            no_orphans: None,
+            public_entries: None,
+            relative_imports: None,
+            scripts: ScriptRules::default(),
+            overrides: Overrides::default(),
         }
     }
 
@@ -61,8 +393,6 @@ impl AnalyzeServiceOptions {
         } else {
             tsconfig
         };
-        debug_assert!(tsconfig.is_file());
-
         self.tsconfig = Some(tsconfig);
         self
     }
@@ -75,410 +405,2968 @@ impl AnalyzeServiceOptions {
     }
 
     #[inline]
-    pub fn cwd(&self) -> &Path {
-        &self.cwd
+    #[must_use]
+    pub fn with_collapse_packages(mut self, collapse_packages: bool) -> Self {
+        self.collapse_packages = collapse_packages;
+        self
     }
-}
-
-#[derive(Clone)]
-pub struct AnalyzeService {
-    runtime: Arc<Runtime>,
-}
 
-impl AnalyzeService {
-    pub fn new(options: AnalyzeServiceOptions) -> Self {
-        let runtime = Arc::new(Runtime::new(options));
-        Self { runtime }
+    #[inline]
+    #[must_use]
+    pub fn with_main_fields(mut self, main_fields: Vec<String>) -> Self {
+        self.main_fields = main_fields;
+        self
     }
 
-    pub fn number_of_dependencies(&self) -> usize {
-        self.runtime.module_map.len() - self.runtime.paths.len()
+    #[inline]
+    #[must_use]
+    pub fn with_condition_names(mut self, condition_names: Vec<String>) -> Self {
+        self.condition_names = condition_names;
+        self
     }
 
-    /// # Panics
-    pub fn run(&self, tx_error: &CollectorSender) {
-        self.runtime
-            .paths
-            .iter()
-            .par_bridge()
-            .for_each_with(&self.runtime, |runtime, path| {
-                runtime.process_path(path, tx_error)
-            });
-        tx_error.send(None).unwrap();
+    #[inline]
+    #[must_use]
+    pub fn with_track_styles(mut self, track_styles: bool) -> Self {
+        self.track_styles = track_styles;
+        self
     }
 
-    /// For tests
-    #[cfg(test)]
-    pub(crate) fn run_source<'a>(
-        &self,
-        allocator: &'a Allocator,
-        source_text: &'a str,
-        check_syntax_errors: bool,
-        tx_error: &CollectorSender,
-    ) -> Vec<Message> {
-        use crate::collector::CollectorSender;
-
-        self.runtime
-            .paths
-            .iter()
-            .flat_map(|path| {
-                let source_type = SourceType::from_path(path).unwrap();
-                self.runtime.init_cache_state(path);
-                self.runtime.process_source(
-                    path,
-                    allocator,
-                    source_text,
-                    source_type,
-                    check_syntax_errors,
-                    tx_error,
-                )
-            })
-            .collect::<Vec<_>>()
+    #[inline]
+    #[must_use]
+    pub fn with_dynamic_imports(mut self, dynamic_imports: bool) -> Self {
+        self.dynamic_imports = dynamic_imports;
+        self
     }
-}
 
-/// `CacheState` and `CacheStateEntry` are used to fix the problem where
-/// there is a brief moment when a concurrent fetch can miss the cache.
-///
-/// Given `ModuleMap` is a `DashMap`, which conceptually is a `RwLock<HashMap>`.
-/// When two requests read the map at the exact same time from different threads,
-/// both will miss the cache so both thread will make a request.
-///
-/// See the "problem section" in <https://medium.com/@polyglot_factotum/rust-concurrency-patterns-condvars-and-locks-e278f18db74f>
-/// and the solution is copied here to fix the issue.
-type CacheState = Mutex<FxHashMap<Box<Path>, Arc<(Mutex<CacheStateEntry>, Condvar)>>>;
-
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum CacheStateEntry {
-    ReadyToConstruct,
-    PendingStore(usize),
-}
+    #[inline]
+    #[must_use]
+    pub fn with_declarations(mut self, declarations: DeclarationMode) -> Self {
+        self.declarations = declarations;
+        self
+    }
 
-/// Keyed by canonicalized path
-type ModuleMap = DashMap<Box<Path>, ModuleState>;
+    #[inline]
+    #[must_use]
+    pub fn with_follow_source_maps(mut self, follow_source_maps: bool) -> Self {
+        self.follow_source_maps = follow_source_maps;
+        self
+    }
 
-#[derive(Clone)]
-enum ModuleState {
-    Resolved(Arc<ModuleRecord>),
-    Ignored,
-}
+    #[inline]
+    #[must_use]
+    pub fn with_syntax_errors(mut self, syntax_errors: bool) -> Self {
+        self.syntax_errors = syntax_errors;
+        self
+    }
 
-pub struct Runtime {
-    cwd: Box<Path>,
-    /// All paths to lint
-    paths: FxHashSet<Box<Path>>,
-    resolver: Option<Resolver>,
-    module_map: ModuleMap,
-    cache_state: CacheState,
-}
+    #[inline]
+    #[must_use]
+    pub fn with_externals(mut self, externals: Vec<String>) -> Self {
+        self.externals = externals;
+        self
+    }
 
-impl Runtime {
-    fn new(options: AnalyzeServiceOptions) -> Self {
-        let resolver = options.cross_module.then(|| {
-            Self::get_resolver(
-                options
-                    .tsconfig
-                    .or_else(|| Some(options.cwd.join("tsconfig.json"))),
-            )
-        });
-        Self {
-            cwd: options.cwd,
-            paths: options.paths.iter().cloned().collect(),
-            resolver,
-            module_map: ModuleMap::default(),
-            cache_state: CacheState::default(),
-        }
+    #[inline]
+    #[must_use]
+    pub fn with_workspace_sources(mut self, workspace_sources: bool) -> Self {
+        self.workspace_sources = workspace_sources;
+        self
     }
 
-    fn get_resolver(tsconfig: Option<PathBuf>) -> Resolver {
-        use oxc_resolver::{ResolveOptions, TsconfigOptions, TsconfigReferences};
-        let tsconfig = tsconfig.and_then(|path| {
-            if path.is_file() {
-                Some(TsconfigOptions {
-                    config_file: path,
-                    references: TsconfigReferences::Auto,
-                })
-            } else {
-                None
-            }
-        });
+    #[inline]
+    #[must_use]
+    pub fn with_type_imports(mut self, type_imports: bool) -> Self {
+        self.type_imports = type_imports;
+        self
+    }
 
-        Resolver::new(ResolveOptions {
-            extensions: VALID_EXTENSIONS
-                .iter()
-                .map(|ext| format!(".{ext}"))
-                .collect(),
-            condition_names: vec!["module".into(), "require".into()],
-            tsconfig,
-            ..ResolveOptions::default()
-        })
+    #[inline]
+    #[must_use]
+    pub fn with_check_exports(mut self, check_exports: bool) -> Self {
+        self.check_exports = check_exports;
+        self
     }
 
-    fn get_source_type_and_text(
-        path: &Path,
-        ext: &str,
-    ) -> Option<Result<(SourceType, String), Error>> {
-        let source_type = SourceType::from_path(path);
-        let not_supported_yet = source_type
-            .as_ref()
-            .is_err_and(|_| !LINT_PARTIAL_LOADER_EXT.contains(&ext));
-        if not_supported_yet {
-            return None;
-        }
-        let source_type = source_type.unwrap_or_default();
-        let file_result = read_to_string(path)
-            .map_err(|e| Error::msg(format!("Failed to open file {path:?} with error \"{e}\"")));
-        Some(match file_result {
-            Ok(source_text) => Ok((source_type, source_text)),
-            Err(e) => Err(e),
-        })
+    #[inline]
+    #[must_use]
+    pub fn with_check_star_exports(mut self, check_star_exports: bool) -> Self {
+        self.check_star_exports = check_star_exports;
+        self
     }
 
-    fn process_path(&self, path: &Path, tx_error: &CollectorSender) {
-        if self.init_cache_state(path) {
-            return;
-        }
+    #[inline]
+    #[must_use]
+    pub fn with_deny_deep_imports(mut self, deny_deep_imports: Vec<String>) -> Self {
+        self.deny_deep_imports = deny_deep_imports;
+        self
+    }
 
-        let Some(ext) = path.extension().and_then(OsStr::to_str) else {
-            self.ignore_path(path);
-            return;
-        };
+    #[inline]
+    #[must_use]
+    pub fn with_markdown_code_blocks(mut self, markdown_code_blocks: bool) -> Self {
+        self.markdown_code_blocks = markdown_code_blocks;
+        self
+    }
 
-        let Some(source_type_and_text) = Self::get_source_type_and_text(path, ext) else {
-            self.ignore_path(path);
-            return;
-        };
+    #[inline]
+    #[must_use]
+    pub fn with_fast(mut self, fast: bool) -> Self {
+        self.fast = fast;
+        self
+    }
 
-        let (source_type, source_text) = match source_type_and_text {
-            Ok(source_text) => source_text,
-            Err(e) => {
-                self.ignore_path(path);
-                tx_error.send(Some((path.to_path_buf(), vec![format!("{}", e)]))).unwrap();
-                return;
-            }
-        };
+    /// Builds the semantic model of each source with its control flow graph, for checks
+    /// reading it. Off by default, no check does yet. Has no effect with `fast`.
+    #[inline]
+    #[must_use]
+    pub fn with_build_cfg(mut self, build_cfg: bool) -> Self {
+        self.build_cfg = build_cfg;
+        self
+    }
 
-        let sources = PartialLoader::parse(ext, &source_text);
-        let sources = sources
-            .unwrap_or_else(|| vec![JavaScriptSource::partial(&source_text, source_type, 0)]);
+    /// Builds the semantic model of each source with its JSDoc comments, for checks reading
+    /// them. Off by default, no check does yet. Has no effect with `fast`.
+    #[inline]
+    #[must_use]
+    pub fn with_build_jsdoc(mut self, build_jsdoc: bool) -> Self {
+        self.build_jsdoc = build_jsdoc;
+        self
+    }
 
-        if sources.is_empty() {
-            self.ignore_path(path);
-            return;
-        }
+    /// Keeps the parsed files in memory, so further runs of the service only read and parse
+    /// the files invalidated since, see [`AnalyzeService::invalidate`]
+    #[inline]
+    #[must_use]
+    pub fn with_resident(mut self, resident: bool) -> Self {
+        self.resident = resident;
+        self
+    }
 
-        for JavaScriptSource {
-            source_text,
-            source_type,
-            ..
-        } in sources
-        {
-            let allocator = Allocator::default();
-            let mut messages =
-                self.process_source(path, &allocator, source_text, source_type, true, tx_error);
+    /// Memory-maps source files instead of reading them.
+    ///
+    /// # Safety
+    /// Source files must not be modified or truncated while the analysis runs, see
+    /// [`crate::utils::map_to_string`]
+    #[inline]
+    #[must_use]
+    pub unsafe fn with_mmap(mut self, mmap: bool) -> Self {
+        self.mmap = mmap;
+        self
+    }
 
-            if !messages.is_empty() {
-                let path = path.strip_prefix(&self.cwd).unwrap_or(path);
-                let diagnostics = CollectorService::wrap_messages(path, messages);
-                tx_error.send(Some(diagnostics)).unwrap();
-            }
-        }
+    #[inline]
+    #[must_use]
+    pub fn with_rules(mut self, rules: RuleSet) -> Self {
+        self.rules = rules;
+        self
     }
 
-    #[allow(clippy::too_many_arguments)]
-    fn process_source<'a>(
-        &self,
+    #[inline]
+    #[must_use]
+    pub fn with_no_circular(mut self, no_circular: Option<NoCircular>) -> Self {
+        self.no_circular = no_circular;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_boundaries(mut self, boundaries: Option<Boundaries>) -> Self {
+        self.boundaries = boundaries;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_package_rules(mut self, package_rules: PackageRules) -> Self {
+        self.package_rules = package_rules;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_max_depth(mut self, max_depth: Option<MaxDepth>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_no_orphans(mut self, no_orphans: Option<NoOrphans>) -> Self {
+        self.no_orphans = no_orphans;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_public_entries(mut self, public_entries: Option<PublicEntries>) -> Self {
+        self.public_entries = public_entries;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_relative_imports(mut self, relative_imports: Option<RelativeImports>) -> Self {
+        self.relative_imports = relative_imports;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_scripts(mut self, scripts: ScriptRules) -> Self {
+        self.scripts = scripts;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn with_overrides(mut self, overrides: Overrides) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Caches parsed sources in `cache_dir`, see [`AnalysisCache`].
+    #[inline]
+    #[must_use]
+    pub fn with_cache_dir(mut self, cache_dir: Option<PathBuf>) -> Self {
+        self.cache_dir = cache_dir;
+        self
+    }
+
+    /// Caches parsed sources in `cache`, e.g. one kept in memory and shared by successive
+    /// services. Takes precedence over the cache directory.
+    #[inline]
+    #[must_use]
+    pub fn with_cache(mut self, cache: Option<AnalysisCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Reuses the previous run for files which did not change since, see [`Incremental`].
+    /// Takes effect along with a cache directory.
+    #[inline]
+    #[must_use]
+    pub fn with_incremental(mut self, incremental: bool) -> Self {
+        self.incremental = incremental;
+        self
+    }
+
+    /// Skips files larger than `max_file_size` bytes with a warning, e.g. bundles
+    #[inline]
+    #[must_use]
+    pub fn with_max_file_size(mut self, max_file_size: Option<u64>) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    /// Stops the analysis once it reaches more than `max_files` files, see
+    /// [`AnalyzeService::check_limits`]
+    #[inline]
+    #[must_use]
+    pub fn with_max_files(mut self, max_files: Option<usize>) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    /// Aborts [`AnalyzeService::run`] once `cancellation` is cancelled
+    #[inline]
+    #[must_use]
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    /// Registers a loader for custom file types, see [`Loader`].
+    #[inline]
+    #[must_use]
+    pub fn with_loader<L: Loader + 'static>(mut self, loader: L) -> Self {
+        self.loaders.push(Arc::new(loader));
+        self
+    }
+
+    /// Reads sources and resolves specifiers through `file_system` instead of the disk, e.g.
+    /// a [`crate::file_system::MemoryFileSystem`]. The cache, incremental runs, source maps and
+    /// workspaces still read from the disk, leave them off without one.
+    #[inline]
+    #[must_use]
+    pub fn with_file_system<F: FileSystem + 'static>(mut self, file_system: F) -> Self {
+        self.file_system = Some(Arc::new(file_system));
+        self
+    }
+
+    /// Keeps the resolution of every specifier of every file, see
+    /// [`AnalyzeService::resolutions`]
+    #[inline]
+    #[must_use]
+    pub fn with_record_resolutions(mut self, record_resolutions: bool) -> Self {
+        self.record_resolutions = record_resolutions;
+        self
+    }
+
+    /// Records the spans of the run in `tracer`, exported by the caller once it completes
+    #[inline]
+    #[must_use]
+    pub fn with_tracer(mut self, tracer: Option<Arc<Tracer>>) -> Self {
+        self.tracer = tracer;
+        self
+    }
+
+    #[inline]
+    pub fn cwd(&self) -> &Path {
+        &self.cwd
+    }
+}
+
+/// Aborts an analysis in flight, e.g. when files changed again before it completed.
+/// Clones share the same state, one is cancelled from another thread.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Clone)]
+pub struct AnalyzeService {
+    runtime: Arc<Runtime>,
+}
+
+impl AnalyzeService {
+    pub fn new(options: AnalyzeServiceOptions) -> Self {
+        let runtime = Arc::new(Runtime::new(options));
+        Self { runtime }
+    }
+
+    pub fn number_of_dependencies(&self) -> usize {
+        self.runtime.module_map.len() - self.runtime.paths.len()
+    }
+
+    /// Modules which only re-export other modules, see [`is_barrel`].
+    pub fn barrels(&self) -> Vec<String> {
+        self.runtime
+            .module_map
+            .iter()
+            .filter_map(|entry| match entry.value() {
+                ModuleState::Resolved(record) if is_barrel(record) => {
+                    Some(self.runtime.display_path(entry.key()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Rewrites static edges into barrel files to point at the modules defining the imported
+    /// bindings, so the graph reflects the real coupling.
+    ///
+    /// An edge into a barrel is kept when the barrel is imported as a namespace,
+    /// or when any binding imported through it cannot be traced.
+    pub fn flatten_barrels(&self, deps: &mut Vec<(PathAtom, Message)>) {
+        let runtime = &self.runtime;
+
+        // (importer, barrel) to the modules defining the imported bindings and their names,
+        // `None` when the edge into the barrel is kept
+        let mut flattened =
+            FxHashMap::<(PathAtom, PathAtom), Option<FxHashMap<String, Vec<String>>>>::default();
+        let importers = deps
+            .iter()
+            .map(|(src, _)| src.clone())
+            .collect::<FxHashSet<_>>();
+        for importer in importers {
+            let Some(ModuleState::Resolved(record)) = runtime
+                .module_map
+                .get(runtime.cwd.join(&importer).as_path())
+                .map(|entry| entry.value().clone())
+            else {
+                continue;
+            };
+            for entry in &record.import_entries {
+                let Some(target) = record.loaded_modules.get(entry.module_request.name()) else {
+                    continue;
+                };
+                let target = target.value();
+                if !is_barrel(target) {
+                    continue;
+                }
+                let name = match &entry.import_name {
+                    ImportImportName::Name(name) => Some(name.name().as_str()),
+                    ImportImportName::Default(_) => Some("default"),
+                    ImportImportName::NamespaceObject => None,
+                };
+                let definition =
+                    name.and_then(|name| definition_of(target, name, &runtime.type_exports));
+                let barrel = PathAtom::from(runtime.display_path(&target.resolved_absolute_path));
+                let slot = flattened
+                    .entry((importer.clone(), barrel))
+                    .or_insert_with(|| Some(FxHashMap::default()));
+                match (slot, name, definition) {
+                    (Some(definitions), Some(name), Some(definition)) => {
+                        let symbols = definitions
+                            .entry(runtime.display_path(&definition.resolved_absolute_path))
+                            .or_default();
+                        if !symbols.iter().any(|symbol| symbol == name) {
+                            symbols.push(name.to_string());
+                            symbols.sort_unstable();
+                        }
+                    }
+                    (slot, _, _) => *slot = None,
+                }
+            }
+        }
+
+        let mut seen = FxHashSet::default();
+        let mut rewritten = Vec::with_capacity(deps.len());
+        for (src, dst) in deps.drain(..) {
+            let definitions = (dst.edge.is_static() && dst.kind == NodeKind::Module)
+                .then(|| flattened.get(&(src.clone(), dst.file_path.clone())))
+                .flatten()
+                .and_then(Option::as_ref);
+            let messages = match definitions {
+                Some(definitions) => definitions
+                    .iter()
+                    .map(|(definition, symbols)| {
+                        Message::new(definition.clone(), NodeKind::Module, dst.edge)
+                            .with_symbols(symbols.clone())
+                    })
+                    .collect(),
+                None => vec![dst],
+            };
+            for message in messages {
+                if seen.insert((src.clone(), message.clone())) {
+                    rewritten.push((src.clone(), message));
+                }
+            }
+        }
+        *deps = rewritten;
+    }
+
+    /// `package.json` dependencies which none of the analyzed files imports.
+    pub fn unused_dependencies(&self) -> UnusedDependencyReport {
+        let package_imports = self
+            .runtime
+            .package_imports
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect::<Vec<_>>();
+        UnusedDependencyReport::new(
+            &self.runtime.cwd,
+            package_imports
+                .iter()
+                .map(|(path, packages)| (path.as_ref(), packages)),
+        )
+    }
+
+    /// Returns `true` when every binding `message` imports is a TypeScript type,
+    /// so the import can become `import type`. Side effect and namespace imports never are.
+    pub fn imports_only_types(&self, message: &Message) -> bool {
+        let runtime = &self.runtime;
+        let Some(ModuleState::Resolved(record)) = runtime
+            .module_map
+            .get(runtime.cwd.join(&message.file_path).as_path())
+            .map(|entry| entry.value().clone())
+        else {
+            return false;
+        };
+        !message.symbols.is_empty()
+            && message.symbols.iter().all(|symbol| {
+                let trace = trace_export(&record, symbol, &runtime.type_exports);
+                trace.found
+                    && trace.steps.last().is_some_and(|step| {
+                        step.name.as_ref().is_some_and(|name| {
+                            runtime
+                                .type_exports
+                                .get(step.record.resolved_absolute_path.as_path())
+                                .is_some_and(|exports| exports.types.contains(name))
+                        })
+                    })
+            })
+    }
+
+    /// Violations of the configured rules which are checked over the whole graph `deps`.
+    ///
+    /// `entries` are the paths analysis started from, relative to the current working directory.
+    pub fn graph_violations(
+        &self,
+        deps: &[(PathAtom, Message)],
+        entries: &[String],
+    ) -> Vec<RuleViolation> {
+        let mut violations = vec![];
+        if let Some(no_circular) = &self.runtime.no_circular {
+            let deps = deps
+                .iter()
+                .filter(|(src, dst)| {
+                    no_circular.includes(src) && no_circular.includes(&dst.file_path)
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            let report = CycleReport::new(&deps, |message| self.imports_only_types(message));
+            violations.extend(no_circular.check(&report));
+        }
+        if let Some(max_depth) = &self.runtime.max_depth {
+            violations.extend(max_depth_violations(deps, entries, max_depth));
+        }
+        if let Some(no_orphans) = &self.runtime.no_orphans {
+            let reachable = deps
+                .iter()
+                .flat_map(|(src, dst)| [src.as_str(), dst.file_path.as_str()])
+                .chain(entries.iter().map(String::as_str))
+                .collect();
+            let report = DeadFileReport::new(&self.runtime.cwd, &reachable);
+            violations.extend(no_orphans.check(&report));
+        }
+        if !self.runtime.scripts.is_empty() {
+            violations.extend(self.runtime.scripts.check(deps));
+        }
+        violations.retain(|violation| !self.runtime.is_suppressed(violation, None));
+        self.runtime.overrides.apply(violations)
+    }
+
+    /// Follows `name`, exported or imported by `path`, to the module defining it.
+    /// Returns `None` when `path` was not analyzed.
+    pub fn trace_symbol(&self, path: &Path, name: &str) -> Option<SymbolTrace> {
+        let ModuleState::Resolved(record) = self.runtime.module_map.get(path)?.value().clone()
+        else {
+            return None;
+        };
+        Some(trace_export(&record, name, &self.runtime.type_exports))
+    }
+
+    /// Resolves `specifier` from `importer` the way the analysis would,
+    /// recording every step taken for `--explain-resolution`.
+    pub fn explain_resolution(&self, importer: &Path, specifier: &str) -> ResolutionExplanation {
+        self.runtime.explain_resolution(importer, specifier)
+    }
+
+    /// Analyzes the paths, sending what is found to `tx_error`.
+    ///
+    /// Once the cancellation token is cancelled, or more files than allowed are reached, files
+    /// not being analyzed yet are skipped and the run returns as soon as the others complete.
+    /// What was sent is then incomplete, and the run is not stored for `--incremental`.
+    ///
+    /// A service can run again, e.g. once files changed. Only resident services, see
+    /// [`AnalyzeServiceOptions::with_resident`], reuse what the previous run parsed.
+    ///
+    /// # Panics
+    pub fn run(&self, tx_error: &CollectorSender) {
+        let _span = self.runtime.span("analyze");
+        self.runtime.start_run();
+        rayon::scope(|scope| {
+            for path in &self.runtime.paths {
+                self.runtime.enqueue(scope, intern_path(path), tx_error);
+            }
+        });
+        // All files are processed and linked at this point, even when the run was aborted
+        {
+            let _span = self.runtime.span("link");
+            self.runtime.link_star_exports();
+        }
+        if let Some(resident) = &self.runtime.resident {
+            // Files no longer reached are not kept
+            resident.retain(|path, _| self.runtime.claimed.contains(path));
+        }
+        self.runtime.flush_batches(tx_error);
+        if self.runtime.is_aborted() {
+            tx_error.send(None).unwrap();
+            return;
+        }
+        if let Some(incremental) = &self.runtime.incremental {
+            incremental.save();
+        }
+        // All module records are linked at this point
+        if self.runtime.check_exports {
+            self.runtime.report_missing_exports(tx_error);
+        }
+        if self.runtime.check_star_exports {
+            self.runtime.report_star_export_conflicts(tx_error);
+        }
+        tx_error.send(None).unwrap();
+    }
+
+    /// Marks the file at `path` as changed, added or removed since the last run, so the next
+    /// run of a resident service reads and resolves it again
+    pub fn invalidate(&self, path: &Path) {
+        if let Some(resident) = &self.runtime.resident {
+            resident.remove(normalize_path(path).as_path());
+        }
+        // Resolutions to or through the file may change too
+        if let Some(resolver) = &self.runtime.resolver {
+            resolver.clear_cache();
+        }
+    }
+
+    /// Analyzes the paths and returns the graph found, with the diagnostics reported instead
+    /// of printing them. This is how other tools embed the analysis.
+    pub fn analyze(&self) -> DependencyGraph {
+        let mut collector = CollectorService::default().with_sinks(Vec::new());
+        self.run(collector.sender());
+        collector.start();
+        let deps = std::mem::take(collector.deps_mut());
+        DependencyGraph::new(deps, collector.diagnostics)
+    }
+
+    /// Resolution of every specifier of every file the completed run analyzed, empty unless
+    /// recorded, see [`AnalyzeServiceOptions::with_record_resolutions`]
+    pub fn resolutions(&self) -> ResolutionDump {
+        let mut dump = ResolutionDump::default();
+        for entry in self.runtime.resolutions.iter().flatten() {
+            dump.add(entry.key(), entry.value());
+        }
+        dump
+    }
+
+    /// Workspace the current working directory belongs to, see [`Workspace::discover`]
+    pub fn workspace(&self) -> Option<&Workspace> {
+        self.runtime.workspace.as_ref()
+    }
+
+    /// Where the spans of the run are recorded, see [`AnalyzeServiceOptions::with_tracer`]
+    pub fn tracer(&self) -> Option<&Arc<Tracer>> {
+        self.runtime.tracer.as_ref()
+    }
+
+    /// What the run did so far, see [`crate::stats`]
+    pub fn counters(&self) -> &Counters {
+        &self.runtime.counters
+    }
+
+    /// Checks the completed run stayed within the limits of the options
+    ///
+    /// # Errors
+    /// When the run stopped as it reached more files than allowed
+    pub fn check_limits(&self) -> Result<(), String> {
+        match self.runtime.max_files {
+            Some(max_files) if self.runtime.exceeds_max_files() => Err(format!(
+                "More than {max_files} files are reached from the entries, stopping. \
+                 Check they do not lead into `node_modules` or build output, or raise --max-files"
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Number of files whose dependencies a worker thread sends to the collector at once
+const BATCH_SIZE: usize = 64;
+
+thread_local! {
+    /// Allocator of the thread, reused by every source it parses instead of allocating anew
+    static ALLOCATOR: RefCell<Allocator> = RefCell::default();
+}
+
+/// Runs `f` with the allocator of the thread, which is reset once `f` returns.
+/// Nested calls get a fresh allocator.
+fn with_allocator<T>(f: impl FnOnce(&Allocator) -> T) -> T {
+    ALLOCATOR.with(|allocator| match allocator.try_borrow_mut() {
+        Ok(mut allocator) => {
+            let result = f(&allocator);
+            allocator.reset();
+            result
+        }
+        Err(_) => f(&Allocator::default()),
+    })
+}
+
+/// Keyed by canonicalized path
+type ModuleMap = DashMap<Arc<Path>, ModuleState>;
+
+#[derive(Clone)]
+enum ModuleState {
+    Resolved(Arc<ModuleRecord>),
+    Ignored,
+}
+
+/// A dependency of a processed file
+enum Link {
+    /// A node which is not processed, e.g. a package or an asset
+    Node(Message),
+    /// A module, which is only part of the graph when it could be processed
+    Module {
+        specifier: String,
+        target: Arc<Path>,
+        message: Message,
+    },
+}
+
+impl Link {
+    fn message(&self) -> &Message {
+        match self {
+            Self::Node(message) | Self::Module { message, .. } => message,
+        }
+    }
+
+    /// The file linked to, relative to `cwd` for nodes, `None` for packages and unresolved
+    /// specifiers
+    fn path(&self, cwd: &Path) -> Option<PathBuf> {
+        match self {
+            Self::Module { target, .. } => Some(target.to_path_buf()),
+            Self::Node(message) => matches!(
+                message.kind,
+                NodeKind::Style | NodeKind::Asset | NodeKind::Json
+            )
+            .then(|| cwd.join(message.file_path.as_str())),
+        }
+    }
+}
+
+/// Dependencies of a processed file, linked once their targets are processed too
+struct FileLinks {
+    path: Arc<Path>,
+    /// Each dependency with the symbols imported from it
+    links: Vec<(Link, Vec<String>)>,
+}
+
+/// Dependencies of a processed file waiting for the modules it imports to be processed
+struct PendingLinks {
+    file: Mutex<Option<FileLinks>>,
+    /// Imported modules not processed yet, plus one until all of them are waited for
+    remaining: AtomicUsize,
+}
+
+/// Files waiting for a module to be processed, until it is
+enum Waiting {
+    Files(Vec<Arc<PendingLinks>>),
+    Processed,
+}
+
+pub struct Runtime {
+    cwd: Box<Path>,
+    /// All paths to lint
+    paths: FxHashSet<Box<Path>>,
+    resolver: Option<Resolver>,
+    /// Resolves `require()` calls to the `.json` files Node.js finds without their extension,
+    /// sharing the cache of `resolver`
+    json_resolver: Option<Resolver>,
+    collapse_packages: bool,
+    track_styles: bool,
+    scan_options: ScanOptions,
+    declarations: DeclarationMode,
+    follow_source_maps: bool,
+    syntax_errors: bool,
+    externals: Vec<String>,
+    /// Workspace `cwd` belongs to, if any
+    workspace: Option<Workspace>,
+    workspace_sources: bool,
+    check_exports: bool,
+    check_star_exports: bool,
+    deep_imports: DeepImportPolicy,
+    markdown_code_blocks: bool,
+    fast: bool,
+    build_cfg: bool,
+    build_jsdoc: bool,
+    mmap: bool,
+    /// Parsed files kept for the next runs, when resident
+    resident: Option<DashMap<Arc<Path>, ParsedSource>>,
+    cancellation: CancellationToken,
+    max_file_size: Option<u64>,
+    max_files: Option<usize>,
+    /// Number of files the analysis reached
+    files: AtomicUsize,
+    counters: Counters,
+    /// Dependencies waiting to be sent to the collector, per worker thread
+    batches: Vec<Mutex<Vec<FileDependencies>>>,
+    loaders: Vec<Arc<dyn Loader>>,
+    /// Where files are read from, the disk when `None`
+    file_system: Option<Arc<dyn FileSystem>>,
+    /// Resolutions of the specifiers of each file when recorded, keyed like `module_map`
+    resolutions: Option<DashMap<Arc<Path>, FxHashMap<String, Resolved>>>,
+    tracer: Option<Arc<Tracer>>,
+    cache: Option<AnalysisCache>,
+    incremental: Option<Incremental>,
+    /// `package.json` files resolutions belong to, keyed by path
+    packages: DashMap<PathBuf, Arc<ResolvedPackage>>,
+    rules: RuleSet,
+    no_circular: Option<NoCircular>,
+    boundaries: Option<Boundaries>,
+    package_rules: PackageRules,
+    max_depth: Option<MaxDepth>,
+    no_orphans: Option<NoOrphans>,
+    public_entries: Option<PublicEntries>,
+    relative_imports: Option<RelativeImports>,
+    scripts: ScriptRules,
+    overrides: Overrides,
+    /// Exports left out of module records, keyed like `module_map`
+    type_exports: DashMap<Arc<Path>, TypeExports>,
+    /// Names of the packages each file imports, keyed like `module_map`
+    package_imports: DashMap<Arc<Path>, FxHashSet<String>>,
+    /// Suppression comments of the files having some, by path relative to `cwd`
+    suppressions: DashMap<String, Suppressions>,
+    module_map: ModuleMap,
+    /// Files queued for processing, each of them is processed once
+    claimed: DashSet<Arc<Path>>,
+    /// Files whose dependencies wait for each module to be processed, keyed like
+    /// `module_map`
+    waiting: DashMap<Arc<Path>, Waiting>,
+}
+
+impl Runtime {
+    fn new(options: AnalyzeServiceOptions) -> Self {
+        let resolver = options.cross_module.then(|| Self::get_resolver(&options));
+        let json_resolver = resolver.as_ref().map(|resolver| {
+            resolver.clone_with_options(oxc_resolver::ResolveOptions {
+                extensions: vec![".json".to_string()],
+                ..resolver.options().clone()
+            })
+        });
+        let incremental = options
+            .cache_dir
+            .as_ref()
+            .filter(|_| options.incremental)
+            .map(|dir| {
+                let resolve_options = resolver.as_ref().map(Resolver::options);
+                let loading = format!(
+                    "{resolve_options:?} {:?} {} {}",
+                    (options.dynamic_imports, options.type_imports),
+                    options.markdown_code_blocks,
+                    options.fast
+                );
+                let tsconfig = resolve_options
+                    .and_then(|options| options.tsconfig.as_ref())
+                    .map(|tsconfig| tsconfig.config_file.as_path());
+                Incremental::load(dir, fingerprint(&loading, tsconfig))
+            });
+        let workspace = Workspace::discover(&options.cwd);
+        let boundaries = options
+            .boundaries
+            .clone()
+            .map(|boundaries| boundaries.with_workspace_packages(&options.cwd, workspace.as_ref()));
+        Self {
+            cwd: options.cwd,
+            paths: options.paths.iter().cloned().collect(),
+            resolver,
+            json_resolver,
+            collapse_packages: options.collapse_packages,
+            track_styles: options.track_styles,
+            scan_options: ScanOptions {
+                dynamic_imports: options.dynamic_imports,
+                type_imports: options.type_imports,
+            },
+            declarations: options.declarations,
+            follow_source_maps: options.follow_source_maps,
+            syntax_errors: options.syntax_errors,
+            externals: options.externals.clone(),
+            workspace,
+            workspace_sources: options.workspace_sources,
+            check_exports: options.check_exports,
+            check_star_exports: options.check_star_exports,
+            deep_imports: DeepImportPolicy::new(options.deny_deep_imports.clone()),
+            markdown_code_blocks: options.markdown_code_blocks,
+            fast: options.fast,
+            build_cfg: options.build_cfg,
+            build_jsdoc: options.build_jsdoc,
+            mmap: options.mmap,
+            resident: options.resident.then(DashMap::default),
+            cancellation: options.cancellation,
+            max_file_size: options.max_file_size,
+            max_files: options.max_files,
+            files: AtomicUsize::new(0),
+            counters: Counters::default(),
+            batches: (0..rayon::current_num_threads())
+                .map(|_| Mutex::default())
+                .collect(),
+            loaders: options.loaders.clone(),
+            file_system: options.file_system.clone(),
+            resolutions: options.record_resolutions.then(DashMap::default),
+            tracer: options.tracer.clone(),
+            cache: options
+                .cache
+                .clone()
+                .or_else(|| options.cache_dir.clone().map(AnalysisCache::new)),
+            incremental,
+            packages: DashMap::default(),
+            rules: options.rules.clone(),
+            no_circular: options.no_circular.clone(),
+            boundaries,
+            package_rules: options.package_rules.clone(),
+            max_depth: options.max_depth.clone(),
+            no_orphans: options.no_orphans.clone(),
+            public_entries: options.public_entries.clone(),
+            relative_imports: options.relative_imports.clone(),
+            scripts: options.scripts.clone(),
+            overrides: options.overrides.clone(),
+            type_exports: DashMap::default(),
+            package_imports: DashMap::default(),
+            suppressions: DashMap::default(),
+            module_map: ModuleMap::default(),
+            claimed: DashSet::default(),
+            waiting: DashMap::default(),
+        }
+    }
+
+    fn get_resolver(options: &AnalyzeServiceOptions) -> Resolver {
+        use oxc_resolver::{ResolveOptions, TsconfigOptions, TsconfigReferences};
+        let tsconfig = options
+            .tsconfig
+            .clone()
+            .or_else(|| Some(options.cwd.join("tsconfig.json")));
+        let file_system = options
+            .file_system
+            .clone()
+            .unwrap_or_else(|| Arc::new(OsFileSystem));
+        let tsconfig = tsconfig.and_then(|path| {
+            if file_system.metadata(&path).is_ok() {
+                Some(TsconfigOptions {
+                    config_file: path,
+                    references: TsconfigReferences::Auto,
+                })
+            } else {
+                None
+            }
+        });
+
+        let mut condition_names = vec!["module".to_string(), "require".to_string()];
+        for condition in &options.condition_names {
+            if !condition_names.contains(condition) {
+                condition_names.push(condition.clone());
+            }
+        }
+
+        let defaults = ResolveOptions::default();
+        let main_fields = if options.main_fields.is_empty() {
+            defaults.main_fields.clone()
+        } else {
+            options.main_fields.clone()
+        };
+
+        let options = ResolveOptions {
+            // Declaration files come last so an implementation next to them wins
+            extensions: VALID_EXTENSIONS
+                .iter()
+                .map(|ext| format!(".{ext}"))
+                .chain(DECLARATION_EXTENSIONS.iter().map(|ext| format!(".{ext}")))
+                .collect(),
+            extension_alias: EXTENSION_ALIAS
+                .iter()
+                .map(|(ext, aliases)| {
+                    (
+                        (*ext).to_string(),
+                        aliases.iter().map(ToString::to_string).collect(),
+                    )
+                })
+                .collect(),
+            condition_names,
+            main_fields,
+            builtin_modules: true,
+            tsconfig,
+            ..defaults
+        };
+        Resolver::new_with_file_system(SharedFileSystem(file_system), options)
+    }
+
+    /// The registered loader handling files with the extension `ext`
+    fn custom_loader(&self, ext: &str) -> Option<&dyn Loader> {
+        self.loaders
+            .iter()
+            .find(|loader| loader.extensions().contains(&ext))
+            .map(AsRef::as_ref)
+    }
+
+    fn get_source_type_and_text(
+        &self,
         path: &Path,
-        allocator: &'a Allocator,
-        source_text: &'a str,
+        ext: &str,
+    ) -> Option<Result<(SourceType, FileText), Error>> {
+        let source_type = source_type_from_path(path);
+        let not_supported_yet = source_type.as_ref().is_err_and(|_| {
+            let is_markdown = ext == MARKDOWN_EXT && self.markdown_code_blocks;
+            !(LINT_PARTIAL_LOADER_EXT.contains(&ext) || is_markdown)
+        }) && self.custom_loader(ext).is_none();
+        if not_supported_yet {
+            return None;
+        }
+        let source_type = source_type.unwrap_or_default();
+        let file_result = match &self.file_system {
+            Some(file_system) => file_system.read_to_string(path).map(FileText::Read),
+            // SAFETY: upheld by the caller of `with_mmap`
+            None if self.mmap => unsafe { map_to_string(path) },
+            None => read_to_string(path).map(FileText::Read),
+        };
+        let file_result = file_result
+            .map_err(|e| Error::msg(format!("Failed to open file {path:?} with error \"{e}\"")));
+        Some(match file_result {
+            Ok(source_text) => Ok((source_type, source_text)),
+            Err(e) => Err(e),
+        })
+    }
+
+    /// Specifier to import a script referenced by a `src` attribute with.
+    /// Root-relative paths start at the current working directory, like the project root
+    /// of bundlers serving HTML entries; remote scripts are skipped.
+    fn script_reference_specifier(&self, src: &str) -> Option<String> {
+        if src.is_empty()
+            || src.starts_with("//")
+            || src.contains("://")
+            || src.starts_with("data:")
+        {
+            return None;
+        }
+        match src.strip_prefix('/') {
+            Some(src) => Some(self.cwd.join(src).to_string_lossy().into_owned()),
+            None if src.starts_with('.') => Some(src.to_string()),
+            None => Some(format!("./{src}")),
+        }
+    }
+
+    /// Source entry a workspace package import of `importer` continues from, with
+    /// `--workspace-sources` or when the package is linked
+    fn workspace_source(
+        &self,
+        importer: &Path,
+        specifier: &str,
+        resolved: Option<&Path>,
+    ) -> Option<PathBuf> {
+        let workspace = self.workspace.as_ref()?;
+        if self.workspace_sources {
+            if let Some(source) = workspace.source_entry(specifier, resolved) {
+                return Some(source);
+            }
+        }
+        workspace.linked_source(importer, specifier, resolved)
+    }
+
+    /// A span named `name` when spans are recorded
+    fn span(&self, name: &'static str) -> Option<Span<'_>> {
+        self.tracer.as_ref().map(|tracer| tracer.span(name))
+    }
+
+    /// Whether the analysis stopped before completing
+    fn is_aborted(&self) -> bool {
+        self.cancellation.is_cancelled() || self.exceeds_max_files()
+    }
+
+    /// Forgets what the previous run of the service found, but for the resident files
+    fn start_run(&self) {
+        self.files.store(0, Ordering::Relaxed);
+        self.counters.reset();
+        self.claimed.clear();
+        self.waiting.clear();
+        self.module_map.clear();
+        self.type_exports.clear();
+        self.package_imports.clear();
+        self.suppressions.clear();
+        if let Some(resolutions) = &self.resolutions {
+            resolutions.clear();
+        }
+    }
+
+    /// The parsed sources of `path` kept from a previous run, when resident
+    fn resident_source(&self, path: &Path) -> Option<ParsedSource> {
+        let parsed = self.resident.as_ref()?.get(path)?.clone();
+        // Linked again by this run
+        parsed.module_record.loaded_modules.clear();
+        parsed
+            .module_record
+            .exported_bindings_from_star_export
+            .clear();
+        self.counters.cache_hit();
+        Some(parsed)
+    }
+
+    fn exceeds_max_files(&self) -> bool {
+        self.max_files
+            .is_some_and(|max_files| self.files.load(Ordering::Relaxed) > max_files)
+    }
+
+    /// Queues `path` for processing, unless it already was.
+    ///
+    /// Each file is a task of `scope`, which is the work-stealing queue of pending files:
+    /// processing a file queues the files it depends on instead of processing them itself,
+    /// so deep graphs neither grow the stack nor keep threads waiting on each other. The files
+    /// only waiting for `path` to be processed are linked once it is.
+    fn enqueue<'s>(&'s self, scope: &Scope<'s>, path: Arc<Path>, tx_error: &'s CollectorSender) {
+        if !self.claimed.insert(Arc::clone(&path)) {
+            return;
+        }
+        self.counters.discovered();
+        scope.spawn(move |scope| {
+            let targets = if self.track_styles && is_style_path(&path) {
+                self.process_style(&path, tx_error)
+            } else {
+                self.process_path(&path, tx_error)
+            };
+            self.counters.processed();
+            self.processed(&path, tx_error);
+            for target in targets {
+                self.enqueue(scope, target, tx_error);
+            }
+        });
+    }
+
+    /// Processes the file at `path`, returning the files it depends on to process next
+    fn process_path(&self, path: &Path, tx_error: &CollectorSender) -> Vec<Arc<Path>> {
+        if self.is_aborted() {
+            return vec![];
+        }
+        if self.max_files.is_some() {
+            self.files.fetch_add(1, Ordering::Relaxed);
+            if self.exceeds_max_files() {
+                self.ignore_path(path);
+                return vec![];
+            }
+        }
+
+        if let Some(parsed) = self.resident_source(path) {
+            return self.resolve_source(path, parsed, None, tx_error);
+        }
+
+        let metadata = self.disk_metadata(path);
+        if let Some(metadata) = &metadata {
+            if self.skip_too_large(path, metadata.len(), tx_error) {
+                return vec![];
+            }
+        }
+
+        // Files unchanged since the previous run are neither read, parsed nor resolved again
+        let mut state = None;
+        if let (Some(incremental), Some(metadata)) = (&self.incremental, &metadata) {
+            if let Some(targets) = self.reuse_unchanged(incremental, path, metadata, tx_error) {
+                return targets;
+            }
+            state = FileState::new(metadata);
+        }
+
+        let Some(ext) = path.extension().and_then(OsStr::to_str) else {
+            self.ignore_path(path);
+            return vec![];
+        };
+
+        let Some(source_type_and_text) = self.get_source_type_and_text(path, ext) else {
+            self.ignore_path(path);
+            return vec![];
+        };
+
+        let (source_type, source_text) = match source_type_and_text {
+            Ok(source_text) => source_text,
+            Err(e) => {
+                self.ignore_path(path);
+                let importer = path.strip_prefix(&self.cwd).unwrap_or(path);
+                let diagnostic =
+                    Diagnostic::new(importer, DiagnosticCode::UnreadableFile, format!("{e}"));
+                let diagnostics = CollectorService::wrap_diagnostics(vec![diagnostic]);
+                tx_error.send(Some(diagnostics)).unwrap();
+                return vec![];
+            }
+        };
+        // Files of a virtual file system are only measured once read
+        if metadata.is_none() && self.skip_too_large(path, source_text.len() as u64, tx_error) {
+            return vec![];
+        }
+        self.counters.read(source_text.len());
+
+        let key = self
+            .cache
+            .as_ref()
+            .map(|_| AnalysisCache::key(&source_text, (self.scan_options, self.fast)));
+        let parsed = {
+            let _span = self.span("parse").map(|span| {
+                let file = path.strip_prefix(&self.cwd).unwrap_or(path);
+                span.with_attribute("file", file.display().to_string())
+            });
+            self.load_file(path, ext, key, source_type, &source_text)
+        };
+        if let Some(resident) = &self.resident {
+            resident.insert(intern_path(path), parsed.clone());
+        }
+        let targets = self.resolve_source(path, parsed, state.as_mut(), tx_error);
+
+        if let (Some(incremental), Some(mut state)) = (&self.incremental, state) {
+            state.key = key;
+            incremental.record(path, state);
+        }
+        targets
+    }
+
+    /// The metadata of the file at `path` on disk, `None` for a virtual file system
+    fn disk_metadata(&self, path: &Path) -> Option<Metadata> {
+        if self.file_system.is_some() {
+            return None;
+        }
+        path.metadata().ok()
+    }
+
+    /// Ignores the file at `path` with a warning when its `size` exceeds `--max-file-size`
+    fn skip_too_large(&self, path: &Path, size: u64, tx_error: &CollectorSender) -> bool {
+        let Some(max_file_size) = self.max_file_size.filter(|max| size > *max) else {
+            return false;
+        };
+        self.ignore_path(path);
+        let warning = format!(
+            "Skipped, its size of {} exceeds the maximum of {}",
+            format_size(size),
+            format_size(max_file_size)
+        );
+        let importer = path.strip_prefix(&self.cwd).unwrap_or(path);
+        let diagnostic = Diagnostic::new(importer, DiagnosticCode::FileTooLarge, warning)
+            .with_severity(Severity::Warn);
+        let diagnostics = CollectorService::wrap_diagnostics(vec![diagnostic]);
+        tx_error.send(Some(diagnostics)).unwrap();
+        true
+    }
+
+    /// Takes `path` from the previous run when it did not change since, resolving its cached
+    /// record with the previous resolutions. Returns `None` when it has to be analyzed.
+    fn reuse_unchanged(
+        &self,
+        incremental: &Incremental,
+        path: &Path,
+        metadata: &Metadata,
+        tx_error: &CollectorSender,
+    ) -> Option<Vec<Arc<Path>>> {
+        let cache = self.cache.as_ref()?;
+        let previous = incremental.unchanged(path, metadata)?;
+        let parsed = previous.key.and_then(|key| cache.get(path, key))?;
+        let mut state = FileState::new(metadata)?;
+
+        state.resolutions = previous.reusable_resolutions();
+        let targets = self.resolve_source(path, parsed, Some(&mut state), tx_error);
+        state.key = previous.key;
+        incremental.record(path, state);
+        self.counters.cache_hit();
+        Some(targets)
+    }
+
+    /// Sends the edges from `path` to the collector, with the violations of rules they break
+    fn report_messages(&self, path: &Path, messages: Vec<Message>, tx_error: &CollectorSender) {
+        if !messages.is_empty() {
+            let path = path.strip_prefix(&self.cwd).unwrap_or(path);
+            self.check_rules(path, &messages, tx_error);
+            self.send_batched(CollectorService::wrap_messages(path, messages), tx_error);
+        }
+    }
+
+    /// Buffers the dependencies of a file with the others found by the worker thread,
+    /// sending them once there are enough of them
+    fn send_batched(&self, dependencies: FileDependencies, tx_error: &CollectorSender) {
+        let Some(batch) = rayon::current_thread_index().and_then(|index| self.batches.get(index))
+        else {
+            let dependencies = CollectorMessage::Dependencies(vec![dependencies]);
+            tx_error.send(Some(dependencies)).unwrap();
+            return;
+        };
+        let mut batch = batch.lock().unwrap();
+        batch.push(dependencies);
+        if batch.len() >= BATCH_SIZE {
+            let dependencies = CollectorMessage::Dependencies(std::mem::take(&mut *batch));
+            tx_error.send(Some(dependencies)).unwrap();
+        }
+    }
+
+    /// Sends the dependencies the worker threads have buffered
+    fn flush_batches(&self, tx_error: &CollectorSender) {
+        for batch in &self.batches {
+            let batch = std::mem::take(&mut *batch.lock().unwrap());
+            if !batch.is_empty() {
+                tx_error
+                    .send(Some(CollectorMessage::Dependencies(batch)))
+                    .unwrap();
+            }
+        }
+    }
+
+    /// Reports the edges from `path`, relative to the current working directory, which the
+    /// configured rules forbid.
+    fn check_rules(&self, path: &Path, messages: &[Message], tx_error: &CollectorSender) {
+        if self.rules.is_empty() && self.boundaries.is_none() {
+            return;
+        }
+        let from = path.to_string_lossy();
+        let violations = messages
+            .iter()
+            .flat_map(|message| {
+                let rule = self.rules.check(&from, &message.file_path).map(|rule| {
+                    let violation = Violation {
+                        rule,
+                        to: &message.file_path,
+                    };
+                    RuleViolation::new(
+                        from.as_ref(),
+                        &rule.name,
+                        rule.severity,
+                        violation.to_string(),
+                    )
+                    .with_docs(&rule.docs)
+                });
+                let boundary = self.boundaries.as_ref().and_then(|boundaries| {
+                    boundaries.check(&from, &message.file_path).map(|message| {
+                        RuleViolation::new(
+                            from.as_ref(),
+                            "boundaries",
+                            boundaries.severity,
+                            message,
+                        )
+                        .with_docs(&boundaries.docs)
+                    })
+                });
+                rule.into_iter()
+                    .chain(boundary)
+                    .filter(|violation| !self.is_suppressed(violation, Some(&message.file_path)))
+            })
+            .collect::<Vec<_>>();
+        self.report_violations(violations, None, tx_error);
+    }
+
+    /// Whether a suppression comment of the file of `violation` silences it. `import` is the
+    /// specifier or the target of the import it is reported for, if any.
+    fn is_suppressed(&self, violation: &RuleViolation, import: Option<&str>) -> bool {
+        self.suppressions
+            .get(&violation.path)
+            .is_some_and(|suppressions| suppressions.suppresses(&violation.rule, import))
+    }
+
+    /// Sends `violations` of the import `import`, if any, to the collector, with the
+    /// severities set by overrides. Suppressed violations are left out.
+    fn report_violations(
+        &self,
+        mut violations: Vec<RuleViolation>,
+        import: Option<&str>,
+        tx_error: &CollectorSender,
+    ) {
+        violations.retain(|violation| !self.is_suppressed(violation, import));
+        let violations = self.overrides.apply(violations);
+        if !violations.is_empty() {
+            tx_error
+                .send(Some(CollectorService::wrap_violations(violations)))
+                .unwrap();
+        }
+    }
+
+    /// Follows the `composes` declarations of a CSS module, which bundlers load as
+    /// dependencies of the stylesheet. Other stylesheets are not read.
+    /// Returns the stylesheets to process next.
+    fn process_style(&self, path: &Path, tx_error: &CollectorSender) -> Vec<Arc<Path>> {
+        let Some(resolver) = &self.resolver else {
+            return vec![];
+        };
+        if !is_css_module_path(path) {
+            return vec![];
+        }
+        // Stylesheets have no module record
+        self.ignore_path(path);
+        let source_text = match &self.file_system {
+            Some(file_system) => file_system.read_to_string(path),
+            None => read_to_string(path),
+        };
+        let Ok(source_text) = source_text else {
+            return vec![];
+        };
+
+        let dir = path.parent().unwrap();
+        let mut targets = vec![];
+        let messages = composes_specifiers(&source_text)
+            .into_iter()
+            .filter_map(|specifier| match self.resolve(resolver, dir, &specifier) {
+                Resolved::Path { path: target, .. } => {
+                    targets.push(intern_path(&target));
+                    Some(Message::new(
+                        self.display_path(&target),
+                        NodeKind::Style,
+                        EdgeType::Static,
+                    ))
+                }
+                Resolved::Skipped => None,
+                Resolved::Failed(error) => {
+                    self.unresolved(path, &specifier, EdgeType::Static, &error, tx_error)
+                }
+            })
+            .collect::<FxHashSet<_>>();
+        self.report_messages(path, messages.into_iter().collect(), tx_error);
+        targets
+    }
+
+    /// Parses the sources of `path`, e.g. each script block of a component, into one module
+    /// record, or takes it from the cache when the file is unchanged. `key` is its cache key.
+    /// Files without sources, e.g. a component without scripts, get an empty record.
+    fn load_file(
+        &self,
+        path: &Path,
+        ext: &str,
+        key: Option<u64>,
         source_type: SourceType,
+        file_text: &str,
+    ) -> ParsedSource {
+        let cache = self.cache.as_ref().zip(key);
+        if let Some(parsed) = cache.and_then(|(cache, key)| cache.get(path, key)) {
+            self.counters.cache_hit();
+            return parsed;
+        }
+        self.counters.parsed();
+
+        let sources = match self.custom_loader(ext) {
+            Some(loader) => Some(loader.load(path, file_text)),
+            None => PartialLoader::parse(ext, file_text),
+        };
+        let mut sources =
+            sources.unwrap_or_else(|| vec![JavaScriptSource::partial(file_text, source_type, 0)]);
+
+        // Scripts referenced by path become imports of a synthesized module, so they are edges
+        let references = PartialLoader::script_references(ext, file_text)
+            .into_iter()
+            .filter_map(|src| self.script_reference_specifier(src))
+            .map(|specifier| format!("import {};\n", serde_json::Value::String(specifier)))
+            .collect::<String>();
+        if !references.is_empty() {
+            sources.push(JavaScriptSource::partial(&references, SourceType::mjs(), 0));
+        }
+        let parsed = sources
+            .into_iter()
+            .map(|source| {
+                with_allocator(|allocator| {
+                    self.parse_source(path, allocator, source, file_text, true)
+                })
+            })
+            .collect();
+        let parsed = ParsedSource::merge(path, parsed);
+        if let Some((cache, key)) = cache {
+            cache.put(path, key, &parsed);
+        }
+        parsed
+    }
+
+    /// Parses `source` into its module record and the dependencies the record leaves out.
+    /// Nothing is resolved at this stage, so the result only depends on the source.
+    /// `file_text` is the whole file, positions of diagnostics are reported in it.
+    fn parse_source<'a>(
+        &self,
+        path: &Path,
+        allocator: &'a Allocator,
+        source: JavaScriptSource<'a>,
+        file_text: &str,
         check_syntax_errors: bool,
-        tx_error: &CollectorSender,
-    ) -> Vec<Message> {
+    ) -> ParsedSource {
+        let JavaScriptSource {
+            source_text,
+            source_type,
+            start,
+            ..
+        } = source;
+        // Diagnostics are reported for the file, at their span in it
+        let importer = path.strip_prefix(&self.cwd).unwrap_or(path);
+        let span = |offset: u32, len: u32| {
+            DiagnosticSpan::new(file_text, start + offset, start + offset + len)
+        };
+
         let ret = Parser::new(allocator, source_text, source_type)
             .with_options(ParseOptions {
-                parse_regular_expression: true,
+                parse_regular_expression: !self.fast,
                 allow_return_outside_function: true,
                 ..ParseOptions::default()
             })
-            .parse();
+            .parse();
+
+        let errors = ret
+            .errors
+            .iter()
+            .map(|e| {
+                let diagnostic =
+                    Diagnostic::new(importer, DiagnosticCode::SyntaxError, e.to_string());
+                match e.labels.as_ref().and_then(|labels| labels.first()) {
+                    Some(label) => {
+                        diagnostic.with_span(span(label.offset() as u32, label.len() as u32))
+                    }
+                    None => diagnostic,
+                }
+            })
+            .collect();
+
+        let program = allocator.alloc(ret.program);
+
+        let trivias = ret.trivias;
+        let scanned = scan_dependencies(source_text, &trivias, program, self.scan_options);
+
+        let unfollowed = scanned
+            .non_literal
+            .iter()
+            .map(|specifier| {
+                let call = if specifier.edge == EdgeType::Dynamic {
+                    "import"
+                } else {
+                    "require"
+                };
+                let message = format!(
+                    "{call}({}) cannot be followed, its specifier is not a string literal",
+                    specifier.span.source_text(source_text)
+                );
+                Diagnostic::new(importer, DiagnosticCode::UnfollowedImport, message)
+                    .with_severity(Severity::Warn)
+                    .with_span(span(specifier.span.start, specifier.span.size()))
+            })
+            .collect();
+
+        // Only the module record is needed, the semantic model is built only when asked for
+        let semantic_builder = if self.fast {
+            // Only the module record, without setting up what the semantic model needs
+            SemanticBuilder::new(source_text)
+        } else {
+            SemanticBuilder::new(source_text)
+                .with_cfg(self.build_cfg)
+                .with_trivias(trivias.clone())
+                .with_build_jsdoc(self.build_jsdoc)
+                .with_check_syntax_error(check_syntax_errors)
+        };
+        let semantic_builder = semantic_builder.build_module_record(path, program);
+        let module_record = semantic_builder.module_record();
+        if !self.fast && (self.build_cfg || self.build_jsdoc) {
+            self.counters.semantic_built();
+            semantic_builder.build(program);
+        }
+
+        let specifiers = module_record
+            .requested_modules
+            .keys()
+            .map(CompactStr::as_str)
+            .chain(
+                scanned
+                    .dependencies
+                    .iter()
+                    .map(|dependency| dependency.specifier.as_str()),
+            );
+        let suppressions = Suppressions::new(source_text, &trivias, program, specifiers);
+
+        ParsedSource {
+            module_record,
+            scanned,
+            type_exports: type_exports(program),
+            errors,
+            unfollowed,
+            suppressions,
+        }
+    }
+
+    /// Resolves the dependencies of the parsed source of `path`, which are linked once the
+    /// modules they import are processed, returning the files to process next.
+    ///
+    /// Specifiers found in the resolutions of `state` are not resolved again, the resolutions
+    /// of the other specifiers are added to it, along with the files the imports are linked to.
+    fn resolve_source(
+        &self,
+        path: &Path,
+        parsed: ParsedSource,
+        state: Option<&mut FileState>,
+        tx_error: &CollectorSender,
+    ) -> Vec<Arc<Path>> {
+        let (targets, file) = self.resolve_links(path, parsed, state, tx_error);
+        self.link_when_processed(file, tx_error);
+        targets
+    }
+
+    /// Resolves the dependencies of the parsed source of `path`, returning the files to
+    /// process next and the dependencies to link once they are
+    fn resolve_links(
+        &self,
+        path: &Path,
+        parsed: ParsedSource,
+        mut state: Option<&mut FileState>,
+        tx_error: &CollectorSender,
+    ) -> (Vec<Arc<Path>>, FileLinks) {
+        let ParsedSource {
+            module_record,
+            scanned,
+            type_exports,
+            errors,
+            unfollowed,
+            mut suppressions,
+        } = parsed;
+        let importer = path.strip_prefix(&self.cwd).unwrap_or(path);
+        if self.syntax_errors && !errors.is_empty() {
+            tx_error.send(Some(CollectorService::wrap_diagnostics(errors))).unwrap();
+        }
+
+        self.type_exports.insert(intern_path(path), type_exports);
+
+        let dependencies = scanned.expanded(path, &self.cwd);
+        let package_imports = module_record
+            .requested_modules
+            .keys()
+            .map(CompactStr::as_str)
+            .chain(
+                dependencies
+                    .iter()
+                    .map(|dependency| dependency.specifier.as_str()),
+            )
+            .filter_map(package_name)
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        // Files with several script blocks import packages from each of them
+        self.package_imports
+            .entry(intern_path(path))
+            .or_default()
+            .extend(package_imports);
+
+        if !unfollowed.is_empty() {
+            tx_error.send(Some(CollectorService::wrap_diagnostics(unfollowed))).unwrap();
+        }
+
+        let Some(resolver) = &self.resolver else {
+            let file = FileLinks {
+                path: intern_path(path),
+                links: vec![],
+            };
+            return (vec![], file);
+        };
+        self.module_map.insert(
+            intern_path(path),
+            ModuleState::Resolved(Arc::clone(&module_record)),
+        );
+        let display_path = self.display_path(path);
+        if !suppressions.is_empty() {
+            self.suppressions
+                .insert(display_path.clone(), suppressions.clone());
+        }
+
+        // Retrieve all dependency modules from this module.
+        let dir = path.parent().unwrap();
+        let requests = module_record
+            .requested_modules
+            .iter()
+            .filter_map(|(specifier, requested)| {
+                let edge = if is_type_only(&module_record, specifier, requested) {
+                    EdgeType::Type
+                } else {
+                    EdgeType::Static
+                };
+                (self.scan_options.type_imports || edge != EdgeType::Type)
+                    .then_some((specifier.as_str(), edge))
+            })
+            .chain(
+                dependencies
+                    .iter()
+                    .map(|dependency| (dependency.specifier.as_str(), dependency.edge)),
+            );
+        let stored = state.as_deref().map(|state| &state.resolutions);
+        let span = self
+            .span("resolve")
+            .map(|span| span.with_attribute("file", importer.display().to_string()));
+        let requests = requests
+            .par_bridge()
+            .map_with(resolver, |resolver, (specifier, edge)| {
+                let stored = stored.and_then(|stored| stored.get(specifier)).cloned();
+                let resolved =
+                    stored.unwrap_or_else(|| self.resolve_edge(resolver, dir, specifier, edge));
+                (specifier, edge, resolved)
+            })
+            .collect::<Vec<_>>();
+        drop(span.map(|span| span.with_attribute("specifiers", requests.len())));
+        if let Some(state) = state.as_mut() {
+            for (specifier, _, resolved) in &requests {
+                state
+                    .resolutions
+                    .insert((*specifier).to_string(), resolved.clone());
+            }
+        }
+        let recorded = self.resolutions.as_ref().map(|_| {
+            requests
+                .iter()
+                .map(|(specifier, _, resolved)| (*specifier, resolved.clone()))
+                .collect::<Vec<_>>()
+        });
+        let dependencies = requests
+            .into_par_iter()
+            .filter_map(|(specifier, edge, resolved)| {
+                let (link, target) =
+                    self.resolve_dependency(path, specifier, edge, resolved, tx_error)?;
+                Some((
+                    specifier,
+                    link,
+                    imported_symbols(&module_record, specifier),
+                    target,
+                ))
+            })
+            .collect::<Vec<_>>();
 
-        if !ret.errors.is_empty() {
-            tx_error.send(Some((path.to_path_buf(), ret.errors.iter().map(|e| format!("{}", e)).collect()))).unwrap();
+        // Workspace packages and declarations are linked to other files than resolved
+        let linked = if state.is_some() || recorded.is_some() {
+            dependencies
+                .iter()
+                .filter_map(|(specifier, link, ..)| Some((*specifier, link.path(&self.cwd)?)))
+                .collect::<Vec<_>>()
+        } else {
+            vec![]
         };
+        if let (Some(resolutions), Some(recorded)) = (&self.resolutions, recorded) {
+            let recorded = recorded.into_iter().map(|(specifier, resolved)| {
+                let target = linked.iter().find(|(linked, _)| *linked == specifier);
+                let resolved = match (target, resolved) {
+                    (Some((_, target)), Resolved::Path { package, .. }) => Resolved::Path {
+                        path: target.clone(),
+                        package,
+                    },
+                    (Some((_, target)), _) => Resolved::Path {
+                        path: target.clone(),
+                        package: None,
+                    },
+                    (None, resolved) => resolved,
+                };
+                (specifier.to_string(), resolved)
+            });
+            // Files with several script blocks resolve the specifiers of each of them
+            resolutions
+                .entry(intern_path(path))
+                .or_default()
+                .extend(recorded);
+        }
+        if let Some(state) = state {
+            state.targets = linked.into_iter().map(|(_, target)| target).collect();
+        }
 
-        let program = allocator.alloc(ret.program);
+        let mut targets = vec![];
+        let mut links = vec![];
+        for (specifier, link, symbols, target) in dependencies {
+            // Rules checked once linked only know the target of the import
+            suppressions.resolve(specifier, &link.message().file_path);
+            targets.extend(target);
+            links.push((link, symbols));
+        }
+        if !suppressions.is_empty() {
+            self.suppressions.insert(display_path, suppressions);
+        }
+        let file = FileLinks {
+            path: intern_path(path),
+            links,
+        };
+        (targets, file)
+    }
 
-        let trivias = ret.trivias;
+    /// Links `file` to the modules it depends on and reports its edges as soon as they are
+    /// processed, so edges reach the collector while the run goes on. Dependencies on modules
+    /// which could not be processed are left out.
+    fn link_when_processed(&self, file: FileLinks, tx_error: &CollectorSender) {
+        let targets = file
+            .links
+            .iter()
+            .filter_map(|(link, _)| match link {
+                Link::Module { target, .. } => Some(Arc::clone(target)),
+                Link::Node(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let pending = Arc::new(PendingLinks {
+            file: Mutex::new(Some(file)),
+            remaining: AtomicUsize::new(1),
+        });
+        for target in targets {
+            // The entry is locked, so the target cannot be marked processed meanwhile
+            match self.waiting.entry(target) {
+                Entry::Occupied(mut entry) => match entry.get_mut() {
+                    Waiting::Processed => continue,
+                    Waiting::Files(files) => files.push(Arc::clone(&pending)),
+                },
+                Entry::Vacant(entry) => {
+                    entry.insert(Waiting::Files(vec![Arc::clone(&pending)]));
+                }
+            }
+            pending.remaining.fetch_add(1, Ordering::AcqRel);
+        }
+        self.release(&pending, tx_error);
+    }
 
-        // Build the module record to unblock other threads from waiting for too long.
-        // The semantic model is not built at this stage.
-        let semantic_builder = SemanticBuilder::new(source_text)
-            .with_cfg(true)
-            .with_trivias(trivias)
-            .with_build_jsdoc(true)
-            .with_check_syntax_error(check_syntax_errors)
-            .build_module_record(path, program);
-        let module_record = semantic_builder.module_record();
+    /// Marks `path` as processed, linking the files which only waited for it
+    fn processed(&self, path: &Arc<Path>, tx_error: &CollectorSender) {
+        if let Some(Waiting::Files(files)) =
+            self.waiting.insert(Arc::clone(path), Waiting::Processed)
+        {
+            for pending in files {
+                self.release(&pending, tx_error);
+            }
+        }
+    }
 
-        if self.resolver.is_some() {
-            self.module_map.insert(
-                path.to_path_buf().into_boxed_path(),
-                ModuleState::Resolved(Arc::clone(&module_record)),
-            );
-            self.update_cache_state(path);
-
-            // Retrieve all dependency modules from this module.
-            let dir = path.parent().unwrap();
-            module_record
-                .requested_modules
-                .keys()
-                .par_bridge()
-                .map_with(self.resolver.as_ref().unwrap(), |resolver, specifier| {
-                    resolver
-                        .resolve(dir, specifier)
-                        .ok()
-                        .map(|r| (specifier, r))
-                })
-                .flatten()
-                .for_each_with(tx_error, |tx_error, (specifier, resolution)| {
-                    let path = resolution.path();
+    /// Links the file of `pending` once it waits for no module anymore
+    fn release(&self, pending: &PendingLinks, tx_error: &CollectorSender) {
+        if pending.remaining.fetch_sub(1, Ordering::AcqRel) != 1 {
+            return;
+        }
+        if let Some(file) = pending.file.lock().unwrap().take() {
+            let path = Arc::clone(&file.path);
+            let messages = self.link_file(file);
+            self.report_messages(&path, messages, tx_error);
+        }
+    }
 
-                    self.process_path(path, tx_error);
-                    let Some(target_module_record_ref) = self.module_map.get(path) else {
-                        return;
-                    };
-                    let ModuleState::Resolved(target_module_record) =
-                        target_module_record_ref.value()
-                    else {
-                        return;
+    /// Appends the modules `file` depends on to its `loaded_modules`, returning its edges
+    fn link_file(&self, file: FileLinks) -> Vec<Message> {
+        let module_record = self.module_record(&file.path);
+        let mut dependencies = FxHashMap::<Message, FxHashSet<String>>::default();
+        for (link, symbols) in file.links {
+            let message = match link {
+                Link::Node(message) => message,
+                Link::Module {
+                    specifier,
+                    target,
+                    message,
+                } => {
+                    let Some(target_module_record) = self.module_record(&target) else {
+                        continue;
                     };
-                    // Append target_module to loaded_modules
-                    module_record
-                        .loaded_modules
-                        .insert(specifier.clone(), Arc::clone(target_module_record));
-                });
-
-            // The thread is blocked here until all dependent modules are resolved.
+                    if let Some(module_record) =
+                        module_record.as_ref().filter(|_| message.edge.is_static())
+                    {
+                        module_record
+                            .loaded_modules
+                            .insert(specifier.into(), target_module_record);
+                    }
+                    message
+                }
+            };
+            // Merge the symbols of specifiers resolving to the same node
+            dependencies.entry(message).or_default().extend(symbols);
+        }
+        dependencies
+            .into_iter()
+            .map(|(message, symbols)| {
+                let mut symbols = symbols.into_iter().collect::<Vec<_>>();
+                symbols.sort_unstable();
+                message.with_symbols(symbols)
+            })
+            .collect()
+    }
 
-            // Resolve and append `star_export_bindings`
-            for export_entry in &module_record.star_export_entries {
-                let Some(remote_module_record_ref) =
-                    export_entry
-                        .module_request
-                        .as_ref()
-                        .and_then(|module_request| {
-                            module_record.loaded_modules.get(module_request.name())
-                        })
-                else {
+    /// Appends the bindings of the modules each module star-exports to its record, once
+    /// `loaded_modules` are linked. Modules are visited after those they star-export, so
+    /// chains of `export * from` are followed through; in cycles, the module visited first
+    /// is seen without the bindings it star-exports itself.
+    fn link_star_exports(&self) {
+        let module_records = self
+            .module_map
+            .iter()
+            .filter_map(|entry| match entry.value() {
+                ModuleState::Resolved(module_record) => Some(Arc::clone(module_record)),
+                ModuleState::Ignored => None,
+            })
+            .collect::<Vec<_>>();
+        let mut visited = FxHashSet::default();
+        for module_record in module_records {
+            let mut stack = vec![(module_record, false)];
+            while let Some((module_record, remotes_linked)) = stack.pop() {
+                if remotes_linked {
+                    append_star_export_bindings(&module_record);
+                    continue;
+                }
+                if !visited.insert(module_record.resolved_absolute_path.clone()) {
                     continue;
+                }
+                let remotes = star_export_remotes(&module_record);
+                stack.push((module_record, true));
+                stack.extend(remotes.into_iter().map(|remote| (remote, false)));
+            }
+        }
+    }
+
+    /// The module record of `path`, when it could be processed
+    fn module_record(&self, path: &Path) -> Option<Arc<ModuleRecord>> {
+        match self.module_map.get(path)?.value() {
+            ModuleState::Resolved(module_record) => Some(Arc::clone(module_record)),
+            ModuleState::Ignored => None,
+        }
+    }
+
+    /// Resolves one dependency of `path`, returning how it is linked and the file to process
+    /// next, if any, or `None` when it is left out of the graph.
+    fn resolve_dependency(
+        &self,
+        path: &Path,
+        specifier: &str,
+        edge: EdgeType,
+        resolved: Resolved,
+        tx_error: &CollectorSender,
+    ) -> Option<(Link, Option<Arc<Path>>)> {
+        if let Some(package) = self.deep_imports.check(specifier, resolved.package()) {
+            let importer = path.strip_prefix(&self.cwd).unwrap_or(path);
+            let diagnostic = Diagnostic::new(
+                importer,
+                DiagnosticCode::DeepImport,
+                format!("Deep import \"{specifier}\" bypasses the public entry of \"{package}\""),
+            );
+            tx_error
+                .send(Some(CollectorService::wrap_diagnostics(vec![diagnostic])))
+                .unwrap();
+        }
+
+        let importer = self.display_path(path);
+        if let Some(package) = package_name(specifier) {
+            if let Some(rule) = self.package_rules.check(&importer, package) {
+                let violation = PackageViolation {
+                    rule,
+                    package,
+                    specifier,
                 };
-                let remote_module_record = remote_module_record_ref.value();
+                let violation =
+                    RuleViolation::new(&importer, &rule.name, rule.severity, violation.to_string())
+                        .with_docs(&rule.docs);
+                self.report_violations(vec![violation], Some(specifier), tx_error);
+            }
+        }
 
-                // Append both remote `bindings` and `exported_bindings_from_star_export`
-                let remote_exported_bindings_from_star_export = remote_module_record
-                    .exported_bindings_from_star_export
-                    .iter()
-                    .flat_map(|r| r.value().clone());
-                let remote_bindings = remote_module_record
-                    .exported_bindings
-                    .keys()
-                    .cloned()
-                    .chain(remote_exported_bindings_from_star_export)
-                    .collect::<Vec<_>>();
-                module_record
-                    .exported_bindings_from_star_export
-                    .entry(remote_module_record.resolved_absolute_path.clone())
-                    .or_default()
-                    .value_mut()
-                    .extend(remote_bindings);
+        if let Some(relative_imports) = &self.relative_imports {
+            let target = resolved.path().map(|target| self.display_path(target));
+            if let Some(violation) = relative_imports.check(&importer, specifier, target.as_deref())
+            {
+                let violation = RuleViolation::new(
+                    &importer,
+                    "relativeImports",
+                    relative_imports.severity,
+                    violation,
+                )
+                .with_docs(&relative_imports.docs);
+                self.report_violations(vec![violation], Some(specifier), tx_error);
+            }
+        }
+
+        // Workspace packages continue from their source instead of their build output
+        let workspace_source = self.workspace_source(path, specifier, resolved.path());
+        let resolved = match (workspace_source, resolved) {
+            (Some(source), _) => source,
+            (None, Resolved::Path { path, package }) => {
+                // Stop traversal at the package boundary
+                if let Some(package_name) = self.collapsed_package_name(&path, package.as_deref()) {
+                    let message = Message::new(package_name, NodeKind::Package, edge);
+                    return Some((Link::Node(message), None));
+                }
+                path
+            }
+            (None, Resolved::Skipped) => return None,
+            (None, Resolved::Failed(error)) => {
+                return self
+                    .unresolved(path, specifier, edge, &error, tx_error)
+                    .map(|message| (Link::Node(message), None));
+            }
+        };
+
+        let importer = path;
+        let (mut path, kind) = self.declaration_target(&resolved)?;
+
+        // Continue from the original source of generated files, e.g. `dist` outputs
+        if self.follow_source_maps && kind == NodeKind::Module {
+            if let Some(original) = original_source(&path) {
+                path = original;
             }
         }
+        let path = path.as_path();
+
+        // Stylesheets are not parsed as modules, only CSS modules have dependencies
+        if self.track_styles && is_style_path(path) {
+            let message = Message::new(self.display_path(path), NodeKind::Style, edge);
+            return Some((Link::Node(message), Some(intern_path(path))));
+        }
 
+        // Assets are leaf nodes, they are not parsed
+        if is_asset_path(path) {
+            let message = Message::new(self.display_path(path), NodeKind::Asset, edge);
+            return Some((Link::Node(message), None));
+        }
 
-        let mut import_modules: Vec<Message> = vec![];
+        // JSON files are leaf nodes too, parsing them as JavaScript would fail
+        if is_json_path(path) {
+            let message = Message::new(self.display_path(path), NodeKind::Json, edge);
+            return Some((Link::Node(message), None));
+        }
 
-        module_record.loaded_modules.iter().for_each(|module| {
-            let module_path = String::from(module.resolved_absolute_path.to_str().unwrap_or("unknown"));
-            import_modules.push(Message {
-                file_path: module_path,
-            });
-        });
+        if let Some(public_entries) = &self.public_entries {
+            if let Some(violation) = public_entries.check(importer, specifier, path) {
+                let violation = RuleViolation::new(
+                    self.display_path(importer),
+                    "publicEntries",
+                    public_entries.severity,
+                    violation,
+                )
+                .with_docs(&public_entries.docs);
+                self.report_violations(vec![violation], Some(specifier), tx_error);
+            }
+        }
+
+        let target = intern_path(path);
+        let link = Link::Module {
+            specifier: specifier.to_string(),
+            target: Arc::clone(&target),
+            message: Message::new(self.display_path(path), kind, edge),
+        };
+        Some((link, Some(target)))
+    }
 
-        import_modules
+    /// Path relative to the current working directory, used as the node name
+    fn display_path(&self, path: &Path) -> String {
+        let path = path.strip_prefix(&self.cwd).unwrap_or(path);
+        String::from(path.to_str().unwrap_or("unknown"))
     }
 
-    fn init_cache_state(&self, path: &Path) -> bool {
-        if self.resolver.is_none() {
-            return false;
-        }
+    /// Whether `specifier` is left to other tools, e.g. a bundler plugin, by `externals`
+    fn is_external(&self, specifier: &str) -> bool {
+        self.externals.iter().any(|external| {
+            if external.ends_with(':') {
+                return specifier.starts_with(external.as_str());
+            }
+            specifier
+                .strip_prefix(external.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
 
-        let (lock, cvar) = {
-            let mut state_map = self.cache_state.lock().unwrap();
-            &*Arc::clone(
-                state_map
-                    .entry(path.to_path_buf().into_boxed_path())
-                    .or_insert_with(|| {
-                        Arc::new((
-                            Mutex::new(CacheStateEntry::ReadyToConstruct),
-                            Condvar::new(),
-                        ))
-                    }),
-            )
+    /// Reports `specifier` as unresolved with `error`, returning the node it is represented by.
+    fn unresolved(
+        &self,
+        path: &Path,
+        specifier: &str,
+        edge: EdgeType,
+        error: &str,
+        tx_error: &CollectorSender,
+    ) -> Option<Message> {
+        let importer = path.strip_prefix(&self.cwd).unwrap_or(path);
+        let severity = if self.is_external(specifier) {
+            Severity::Warn
+        } else {
+            Severity::Error
         };
-        let mut state = cvar
-            .wait_while(lock.lock().unwrap(), |state| {
-                matches!(*state, CacheStateEntry::PendingStore(_))
-            })
+        let diagnostic = Diagnostic::new(
+            importer,
+            DiagnosticCode::UnresolvedImport,
+            format!("Unable to resolve \"{specifier}\": {error}"),
+        )
+        .with_severity(severity);
+        tx_error
+            .send(Some(CollectorService::wrap_diagnostics(vec![diagnostic])))
             .unwrap();
 
-        let cache_hit = if self.module_map.contains_key(path) {
-            true
+        // Relative specifiers are only meaningful next to their importer
+        let node = if specifier.starts_with('.') {
+            let dir = importer.parent().unwrap_or(Path::new(""));
+            normalize_path(&dir.join(specifier)).display().to_string()
         } else {
-            let i = if let CacheStateEntry::PendingStore(i) = *state {
-                i
-            } else {
-                0
-            };
-            *state = CacheStateEntry::PendingStore(i + 1);
-            false
+            specifier.to_string()
         };
+        Some(Message::new(node, NodeKind::Unresolved, edge))
+    }
 
-        if *state == CacheStateEntry::ReadyToConstruct {
-            cvar.notify_one();
+    /// Reports named imports which the imported module does not export.
+    fn report_missing_exports(&self, tx_error: &CollectorSender) {
+        let mut reports = self
+            .module_map
+            .iter()
+            .filter_map(|entry| {
+                let ModuleState::Resolved(record) = entry.value() else {
+                    return None;
+                };
+                let diagnostics = record
+                    .import_entries
+                    .iter()
+                    .filter_map(|import| {
+                        let name = match &import.import_name {
+                            ImportImportName::Name(name) => name.name().as_str(),
+                            ImportImportName::Default(_) => "default",
+                            ImportImportName::NamespaceObject => return None,
+                        };
+                        let specifier = import.module_request.name();
+                        let target = record.loaded_modules.get(specifier)?;
+                        (!has_export(target.value(), name, &self.type_exports)).then(|| {
+                            format!(
+                                "imports '{name}' from '{specifier}' but '{specifier}' has no export '{name}'"
+                            )
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                (!diagnostics.is_empty()).then(|| (entry.key().clone(), diagnostics))
+            })
+            .collect::<Vec<_>>();
+        reports.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+        for (path, diagnostics) in reports {
+            let path = path.strip_prefix(&self.cwd).unwrap_or(&path);
+            let diagnostics = diagnostics
+                .into_iter()
+                .map(|message| Diagnostic::new(path, DiagnosticCode::MissingExport, message))
+                .collect();
+            tx_error
+                .send(Some(CollectorService::wrap_diagnostics(diagnostics)))
+                .unwrap();
         }
+    }
 
-        drop(state);
-        cache_hit
+    /// Reports names which several `export * from` of one module re-export differently.
+    fn report_star_export_conflicts(&self, tx_error: &CollectorSender) {
+        let mut reports = self
+            .module_map
+            .iter()
+            .filter_map(|entry| {
+                let ModuleState::Resolved(record) = entry.value() else {
+                    return None;
+                };
+                let diagnostics = star_export_conflicts(record, &self.type_exports)
+                    .into_iter()
+                    .map(|conflict| {
+                        let specifiers = conflict
+                            .specifiers
+                            .iter()
+                            .map(|specifier| format!("'{specifier}'"))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        format!(
+                            "'{}' is ambiguous, it is star-exported from {specifiers} which define it differently",
+                            conflict.name
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                (!diagnostics.is_empty()).then(|| (entry.key().clone(), diagnostics))
+            })
+            .collect::<Vec<_>>();
+        reports.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+        for (path, diagnostics) in reports {
+            let path = path.strip_prefix(&self.cwd).unwrap_or(&path);
+            let diagnostics = diagnostics
+                .into_iter()
+                .map(|message| Diagnostic::new(path, DiagnosticCode::AmbiguousExport, message))
+                .collect();
+            tx_error
+                .send(Some(CollectorService::wrap_diagnostics(diagnostics)))
+                .unwrap();
+        }
     }
 
-    fn update_cache_state(&self, path: &Path) {
-        let (lock, cvar) = {
-            let mut state_map = self.cache_state.lock().unwrap();
-            &*Arc::clone(
-                state_map
-                    .get_mut(path)
-                    .expect("Entry in http-cache state to have been previously inserted"),
-            )
+    fn explain_resolution(&self, importer: &Path, specifier: &str) -> ResolutionExplanation {
+        let dir = importer.parent().unwrap_or(importer);
+        let mut explanation = ResolutionExplanation::new(dir, specifier);
+        let Some(resolver) = &self.resolver else {
+            explanation.result = Err("cross module analysis is disabled".to_string());
+            return explanation;
         };
-        let mut state = lock.lock().unwrap();
-        if let CacheStateEntry::PendingStore(i) = *state {
-            let new = i - 1;
-            if new == 0 {
-                *state = CacheStateEntry::ReadyToConstruct;
-                // Notify the next thread waiting in line, if there is any.
-                cvar.notify_one();
-            } else {
-                *state = CacheStateEntry::PendingStore(new);
+
+        let options = resolver.options();
+        explanation.tsconfig = options
+            .tsconfig
+            .as_ref()
+            .map(|tsconfig| tsconfig.config_file.clone());
+        explanation.extensions.clone_from(&options.extensions);
+        explanation
+            .condition_names
+            .clone_from(&options.condition_names);
+        explanation.main_fields.clone_from(&options.main_fields);
+
+        let mut context = ResolveContext::default();
+        let resolution = resolver.resolve_with_context(dir, specifier, &mut context);
+        explanation.found = context.file_dependencies.into_iter().collect();
+        explanation.found.sort_unstable();
+        explanation.missing = context.missing_dependencies.into_iter().collect();
+        explanation.missing.sort_unstable();
+
+        let workspace_source = self.workspace_source(
+            importer,
+            specifier,
+            resolution.as_ref().ok().map(Resolution::path),
+        );
+        let resolved = match (workspace_source, resolution) {
+            (Some(source), _) => {
+                explanation
+                    .rewrites
+                    .push(format!("workspace source: {}", source.display()));
+                source
+            }
+            (None, Ok(resolution)) => {
+                let package = resolution
+                    .package_json()
+                    .map(|package_json| self.resolved_package(package_json));
+                if let Some(package_name) =
+                    self.collapsed_package_name(resolution.path(), package.as_deref())
+                {
+                    explanation
+                        .rewrites
+                        .push(format!("collapsed into package: {package_name}"));
+                }
+                resolution.into_path_buf()
+            }
+            (None, Err(error)) => {
+                explanation.result = Err(error.to_string());
+                return explanation;
+            }
+        };
+
+        let Some((mut path, kind)) = self.declaration_target(&resolved) else {
+            explanation.result = Err(format!(
+                "{} is a declaration file and declarations are excluded",
+                resolved.display()
+            ));
+            return explanation;
+        };
+        if path != resolved {
+            explanation
+                .rewrites
+                .push(format!("declaration paired with: {}", path.display()));
+        }
+        if self.follow_source_maps && kind == NodeKind::Module {
+            if let Some(original) = original_source(&path) {
+                explanation
+                    .rewrites
+                    .push(format!("source map original: {}", original.display()));
+                path = original;
+            }
+        }
+        explanation.result = Ok(path);
+        explanation
+    }
+
+    /// Applies the declaration mode to a resolved path,
+    /// returns `None` when the path is dropped from the graph.
+    fn declaration_target(&self, path: &Path) -> Option<(PathBuf, NodeKind)> {
+        if !SourceType::from_path(path)
+            .is_ok_and(|source_type| source_type.is_typescript_definition())
+        {
+            return Some((path.to_path_buf(), NodeKind::Module));
+        }
+        match self.declarations {
+            DeclarationMode::Keep => Some((path.to_path_buf(), NodeKind::Declaration)),
+            DeclarationMode::Exclude => None,
+            DeclarationMode::Pair => Some(match declaration_implementation(path) {
+                Some(implementation) => (implementation, NodeKind::Module),
+                None => (path.to_path_buf(), NodeKind::Declaration),
+            }),
+        }
+    }
+
+    /// Returns the package name when `path`, resolved into `package`, points into
+    /// `node_modules` and external packages should be collapsed into a single node.
+    fn collapsed_package_name(
+        &self,
+        path: &Path,
+        package: Option<&ResolvedPackage>,
+    ) -> Option<String> {
+        if !self.collapse_packages {
+            return None;
+        }
+        let name = node_modules_package_name(path)?;
+        // Prefer the name declared in the package root `package.json`, which differs from the
+        // directory name for aliased installs, e.g. `"react": "npm:@preact/compat"`.
+        let package_root = Path::new("node_modules").join(&name);
+        let declared_name = package
+            .filter(|package| package.directory.ends_with(&package_root))
+            .and_then(|package| package.name.clone());
+        Some(declared_name.unwrap_or(name))
+    }
+
+    /// Resolves `specifier` from `dir`
+    fn resolve(&self, resolver: &Resolver, dir: &Path, specifier: &str) -> Resolved {
+        self.counters.resolved();
+        self.resolved(resolver.resolve(dir, specifier))
+    }
+
+    /// Resolves `specifier` imported from `dir` by `edge`. Like Node.js, `require("./data")`
+    /// finds `data.json` when no module matches, imports need the extension
+    fn resolve_edge(
+        &self,
+        resolver: &Resolver,
+        dir: &Path,
+        specifier: &str,
+        edge: EdgeType,
+    ) -> Resolved {
+        let resolved = self.resolve(resolver, dir, specifier);
+        match (&resolved, &self.json_resolver) {
+            (Resolved::Failed(_), Some(json_resolver)) if edge == EdgeType::Require => {
+                match self.resolve(json_resolver, dir, specifier) {
+                    json @ Resolved::Path { .. } => json,
+                    _ => resolved,
+                }
             }
+            _ => resolved,
+        }
+    }
+
+    /// `resolution` in a form which outlives the resolver
+    fn resolved(&self, resolution: Result<Resolution, ResolveError>) -> Resolved {
+        match resolution {
+            Ok(resolution) => Resolved::Path {
+                package: resolution
+                    .package_json()
+                    .map(|package_json| self.resolved_package(package_json)),
+                path: resolution.into_path_buf(),
+            },
+            Err(ResolveError::Builtin(_) | ResolveError::Ignored(_)) => Resolved::Skipped,
+            Err(error) => Resolved::Failed(error.to_string()),
         }
     }
 
+    /// The parts of `package_json` the analysis looks at, shared by the resolutions into it
+    fn resolved_package(&self, package_json: &PackageJson) -> Arc<ResolvedPackage> {
+        if let Some(package) = self.packages.get(&package_json.path) {
+            return Arc::clone(&package);
+        }
+        let package = Arc::new(ResolvedPackage {
+            directory: package_json.directory().to_path_buf(),
+            name: package_json.name.clone(),
+            exports: package_json.raw_json().get("exports").cloned(),
+        });
+        self.packages
+            .insert(package_json.path.clone(), Arc::clone(&package));
+        package
+    }
+
     fn ignore_path(&self, path: &Path) {
         if self.resolver.is_some() {
             self.module_map
-                .insert(path.to_path_buf().into_boxed_path(), ModuleState::Ignored);
-            self.update_cache_state(path);
+                .insert(intern_path(path), ModuleState::Ignored);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{path::Path, sync::mpsc, time::Duration};
+
+    use oxc_span::SourceType;
+
+    use oxc_allocator::Allocator;
+    use oxc_parser::Parser;
+    use oxc_semantic::SemanticBuilder;
+
+    use super::{
+        imported_symbols, with_allocator, AnalyzeService, AnalyzeServiceOptions, CancellationToken,
+    };
+    use crate::{
+        collector::{CollectorMessage, CollectorService},
+        dead_files::DeadFileReport,
+        file_system::MemoryFileSystem,
+        graph::{EdgeType, NodeKind},
+        loader::{JavaScriptSource, Loader},
+        rules::Severity,
+        stats::StatsReport,
+        utils::TestDir,
+    };
+
+    /// Templates embedding scripts between `{{#script}}` and `{{/script}}`
+    struct TemplateLoader;
+
+    impl Loader for TemplateLoader {
+        fn extensions(&self) -> &[&str] {
+            &["tpl"]
+        }
+
+        fn load<'a>(&self, _path: &Path, source_text: &'a str) -> Vec<JavaScriptSource<'a>> {
+            let Some((_, rest)) = source_text.split_once("{{#script}}") else {
+                return vec![];
+            };
+            let Some((script, _)) = rest.split_once("{{/script}}") else {
+                return vec![];
+            };
+            #[allow(clippy::cast_possible_truncation)]
+            let start = (source_text.len() - rest.len()) as u32;
+            vec![JavaScriptSource::partial(script, SourceType::mjs(), start)]
+        }
+    }
+
+    #[test]
+    fn test_custom_loader() {
+        let root = TestDir::new("custom-loader");
+        for (file, contents) in [
+            ("main.ts", "import './view.tpl';"),
+            (
+                "view.tpl",
+                "<h1>{{title}}</h1>{{#script}}import { a } from './a';{{/script}}",
+            ),
+            ("a.ts", "export const a = 1;"),
+        ] {
+            root.write(file, contents);
+        }
+
+        let options = AnalyzeServiceOptions::new(&*root, vec![root.join("main.ts").into()])
+            .with_cross_module(true)
+            .with_loader(TemplateLoader);
+        let mut collector = CollectorService::default();
+        AnalyzeService::new(options).run(collector.sender());
+        collector.start();
+
+        let mut edges = collector
+            .deps()
+            .iter()
+            .map(|(src, dst)| (src.as_str(), dst.file_path.as_str()))
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+        assert_eq!(edges, [("main.ts", "view.tpl"), ("view.tpl", "a.ts")]);
+    }
+
+    #[test]
+    fn test_custom_loader_without_sources() {
+        let root = Path::new("/marsh-custom-loader-without-sources");
+        let file_system = MemoryFileSystem::default()
+            .with_file(root.join("main.ts"), "import './static.tpl';")
+            .with_file(root.join("static.tpl"), "<h1>{{title}}</h1>");
+        let options = AnalyzeServiceOptions::new(root, vec![root.join("main.ts").into()])
+            .with_cross_module(true)
+            .with_file_system(file_system)
+            .with_loader(TemplateLoader);
+        let graph = AnalyzeService::new(options).analyze();
+        let edges = graph
+            .edges
+            .iter()
+            .map(|edge| (edge.from.as_str(), edge.to.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(edges, [("main.ts", "static.tpl")]);
+    }
+
+    #[test]
+    fn test_template_only_components() {
+        let root = TestDir::new("template-only-components");
+        root.write(
+            "src/main.ts",
+            "import A from './A.vue'; import B from './B.astro'; import C from './C.svelte';",
+        );
+        root.write("src/A.vue", "<template><h1>A</h1></template>");
+        root.write("src/B.astro", "<h1>B</h1>");
+        root.write("src/C.svelte", "<h1>C</h1>");
+
+        let entries = vec![root.join("src/main.ts").into()];
+        let options = AnalyzeServiceOptions::new(&*root, entries).with_cross_module(true);
+        let graph = AnalyzeService::new(options).analyze();
+        let mut edges = graph
+            .edges
+            .iter()
+            .map(|edge| edge.to.as_str())
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+        assert_eq!(edges, ["src/A.vue", "src/B.astro", "src/C.svelte"]);
+
+        let reachable = graph.nodes.iter().map(|node| node.path.as_str()).collect();
+        assert!(DeadFileReport::new(&root, &reachable).files.is_empty());
+    }
+
+    /// Cancels the analysis when it reaches a `.cancel` file
+    struct CancellingLoader(CancellationToken);
+
+    impl Loader for CancellingLoader {
+        fn extensions(&self) -> &[&str] {
+            &["cancel"]
+        }
+
+        fn load<'a>(&self, _path: &Path, source_text: &'a str) -> Vec<JavaScriptSource<'a>> {
+            self.0.cancel();
+            vec![JavaScriptSource::new(source_text, SourceType::mjs())]
+        }
+    }
+
+    #[test]
+    fn test_cancellation() {
+        let root = TestDir::new("cancellation");
+        for (file, contents) in [
+            ("main.ts", "import './b.cancel';"),
+            ("b.cancel", "import './c';"),
+            ("c.ts", "import './d';"),
+            ("d.ts", ""),
+        ] {
+            root.write(file, contents);
+        }
+
+        let cancellation = CancellationToken::default();
+        let options = AnalyzeServiceOptions::new(&*root, vec![root.join("main.ts").into()])
+            .with_cross_module(true)
+            .with_cancellation(cancellation.clone())
+            .with_loader(CancellingLoader(cancellation.clone()));
+        let mut collector = CollectorService::default();
+        AnalyzeService::new(options).run(collector.sender());
+        collector.start();
+
+        // `c.ts` is reached after the cancellation, so it is not analyzed
+        assert!(cancellation.is_cancelled());
+        let edges = collector
+            .deps()
+            .iter()
+            .map(|(src, dst)| (src.as_str(), dst.file_path.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(edges, [("main.ts", "b.cancel")]);
+    }
+
+    #[test]
+    fn test_with_allocator() {
+        let (first, nested) = with_allocator(|allocator| {
+            let first = std::ptr::from_ref(allocator.alloc([0u8; 64]));
+            let nested = with_allocator(|nested| std::ptr::eq(nested, allocator));
+            (first, nested)
+        });
+        assert!(!nested);
+        // The allocator of the thread is reset, its memory is reused
+        with_allocator(|allocator| {
+            assert_eq!(std::ptr::from_ref(allocator.alloc([0u8; 64])), first);
+        });
+    }
+
+    #[test]
+    fn test_limits() {
+        let root = TestDir::new("limits");
+        for (file, contents) in [
+            ("main.ts", "import './a'; import './bundle';".to_string()),
+            ("a.ts", "import './b';".to_string()),
+            ("b.ts", String::new()),
+            (
+                "bundle.js",
+                format!("export const a = '{}';", "a".repeat(100)),
+            ),
+        ] {
+            root.write(file, &contents);
+        }
+        let options = || {
+            AnalyzeServiceOptions::new(&*root, vec![root.join("main.ts").into()])
+                .with_cross_module(true)
+        };
+
+        let (tx_error, rx_error) = mpsc::channel();
+        let service = AnalyzeService::new(options().with_max_file_size(Some(100)));
+        service.run(&tx_error);
+        assert!(service.check_limits().is_ok());
+        let diagnostics = rx_error
+            .try_iter()
+            .flatten()
+            .filter_map(|message| match message {
+                CollectorMessage::Diagnostics(diagnostics) => Some(diagnostics),
+                CollectorMessage::Dependencies(..) | CollectorMessage::Violations(..) => None,
+            })
+            .flatten()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            diagnostics,
+            ["bundle.js: Skipped, its size of 120 B exceeds the maximum of 100 B"]
+        );
+
+        let (tx_error, _rx_error) = mpsc::channel();
+        let service = AnalyzeService::new(options().with_max_files(Some(2)));
+        service.run(&tx_error);
+        assert!(service
+            .check_limits()
+            .unwrap_err()
+            .starts_with("More than 2 files"));
+        let service = AnalyzeService::new(options().with_max_files(Some(4)));
+        service.run(&tx_error);
+        assert!(service.check_limits().is_ok());
+    }
+
+    #[test]
+    fn test_embedded_source_diagnostics() {
+        let root = TestDir::new("embedded-diagnostics");
+        for (file, contents) in [
+            (
+                "a.vue",
+                "<template>\n  <div />\n</template>\n<script setup>\nconst name = 'a';\nimport(name);\n</script>\n",
+            ),
+            ("b.svelte", "<h1>b</h1>\n\n<script>\n  let = ;\n</script>\n"),
+        ] {
+            root.write(file, contents);
+        }
+
+        let paths = vec![root.join("a.vue").into(), root.join("b.svelte").into()];
+        let options = AnalyzeServiceOptions::new(&*root, paths).with_cross_module(true);
+        let (tx_error, rx_error) = mpsc::channel();
+        AnalyzeService::new(options).run(&tx_error);
+
+        let mut diagnostics = rx_error
+            .try_iter()
+            .flatten()
+            .filter_map(|message| match message {
+                CollectorMessage::Diagnostics(diagnostics) => Some(diagnostics),
+                CollectorMessage::Dependencies(..) | CollectorMessage::Violations(..) => None,
+            })
+            .flatten()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect::<Vec<_>>();
+        diagnostics.sort_unstable();
+        assert_eq!(diagnostics.len(), 2, "{diagnostics:?}");
+        assert!(
+            diagnostics[0].starts_with("a.vue: 6:8: import(name)"),
+            "{diagnostics:?}"
+        );
+        assert!(
+            diagnostics[1].starts_with("b.svelte: 4:9: "),
+            "{diagnostics:?}"
+        );
+    }
+    #[test]
+    fn test_fast() {
+        let root = TestDir::new("fast");
+        for (file, contents) in [
+            (
+                "main.ts",
+                "import { a } from './a';
+const pattern = /(?<a>/;
+",
+            ),
+            ("a.ts", "export const a = 1;"),
+        ] {
+            root.write(file, contents);
+        }
+
+        for (fast, syntax_errors) in [(false, true), (true, true), (false, false)] {
+            let options = AnalyzeServiceOptions::new(&*root, vec![root.join("main.ts").into()])
+                .with_cross_module(true)
+                .with_fast(fast)
+                .with_syntax_errors(syntax_errors);
+            let (tx_error, rx_error) = mpsc::channel();
+            AnalyzeService::new(options).run(&tx_error);
+
+            let (mut edges, mut diagnostics) = (vec![], vec![]);
+            for message in rx_error.try_iter().flatten() {
+                match message {
+                    CollectorMessage::Dependencies(files) => {
+                        edges.extend(files.into_iter().flat_map(|(_, deps)| deps));
+                    }
+                    CollectorMessage::Diagnostics(found) => diagnostics.extend(found),
+                    CollectorMessage::Violations(..) => {}
+                }
+            }
+            assert_eq!(edges.len(), 1);
+            assert_eq!(edges[0].file_path, "a.ts");
+            assert_eq!(edges[0].symbols, ["a"]);
+            // Regular expressions are only checked without `fast`, the graph is built either way
+            assert_eq!(
+                diagnostics.is_empty(),
+                fast || !syntax_errors,
+                "{diagnostics:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_analyze() {
+        let root = TestDir::new("analyze");
+        for (file, contents) in [
+            ("main.ts", "import { a } from './a';"),
+            ("a.ts", "export const a = 1; const = 2;"),
+        ] {
+            root.write(file, contents);
+        }
+
+        let options = AnalyzeServiceOptions::new(&*root, vec![root.join("main.ts").into()])
+            .with_cross_module(true);
+        let graph = AnalyzeService::new(options).analyze();
+        let nodes = graph
+            .nodes
+            .iter()
+            .map(|node| node.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(nodes, ["main.ts", "a.ts"]);
+        assert_eq!(graph.edges.len(), 1);
+        assert_eq!(graph.edges[0].symbols, ["a"]);
+        assert_eq!(graph.diagnostics.len(), 1);
+        assert_eq!(graph.diagnostics[0].path, Path::new("a.ts"));
+    }
+
+    #[test]
+    fn test_imported_symbols() {
+        let source_text = "
+            import React, { useState as useLocal, type FC } from 'react';
+            import * as path from 'node:path';
+            import { a } from './a';
+            export { a };
+            export { b, c as d } from './b';
+            export * from './c';
+            export * as e from './e';
+            import './side-effect';
+        ";
+        let allocator = Allocator::default();
+        let program = Parser::new(&allocator, source_text, SourceType::ts())
+            .parse()
+            .program;
+        let module_record = SemanticBuilder::new(source_text)
+            .build_module_record(Path::new("main.ts"), &program)
+            .module_record();
+        let symbols = |specifier| imported_symbols(&module_record, specifier);
+        assert_eq!(symbols("react"), ["default", "useState", "FC"]);
+        assert_eq!(symbols("node:path"), ["*"]);
+        // Re-exporting an import does not import it twice
+        assert_eq!(symbols("./a"), ["a"]);
+        assert_eq!(symbols("./b"), ["b", "c"]);
+        assert_eq!(symbols("./c"), ["*"]);
+        assert_eq!(symbols("./e"), ["*"]);
+        assert!(symbols("./side-effect").is_empty());
+    }
+
+    #[test]
+    fn test_extension_alias() {
+        let root = Path::new("/marsh-extension-alias");
+        let file_system = MemoryFileSystem::default()
+            .with_file(
+                root.join("main.ts"),
+                "import './a.js'; import './b.js'; import './c.mjs'; import './d.cjs'; import './e.js';",
+            )
+            .with_file(root.join("a.ts"), "")
+            .with_file(root.join("b.tsx"), "")
+            .with_file(root.join("c.mts"), "")
+            .with_file(root.join("d.cts"), "import './a.js';")
+            .with_file(root.join("e.js"), "")
+            .with_file(root.join("e.d.ts"), "");
+        let options = AnalyzeServiceOptions::new(root, vec![root.join("main.ts").into()])
+            .with_cross_module(true)
+            .with_file_system(file_system);
+        let graph = AnalyzeService::new(options).analyze();
+        let mut edges = graph
+            .edges
+            .iter()
+            .map(|edge| format!("{} -> {}", edge.from, edge.to))
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+        // TypeScript files are found for JavaScript extensions, implementations before
+        // declarations, and `.cts` files are parsed as modules
+        assert_eq!(
+            edges,
+            [
+                "d.cts -> a.ts",
+                "main.ts -> a.ts",
+                "main.ts -> b.tsx",
+                "main.ts -> c.mts",
+                "main.ts -> d.cts",
+                "main.ts -> e.js",
+            ]
+        );
+        assert!(graph.diagnostics.is_empty(), "{:?}", graph.diagnostics);
+    }
+
+    #[test]
+    fn test_explain_resolution() {
+        let root = Path::new("/marsh-explain-resolution");
+        let file_system = MemoryFileSystem::default()
+            .with_file(root.join("main.ts"), "import './a';")
+            .with_file(root.join("a.d.ts"), "")
+            .with_file(root.join("a.js"), "");
+        let options = AnalyzeServiceOptions::new(root, vec![root.join("main.ts").into()])
+            .with_cross_module(true)
+            .with_file_system(file_system);
+        let service = AnalyzeService::new(options);
+
+        // The declaration file comes last, `a.js` is found first
+        let explanation = service.explain_resolution(&root.join("main.ts"), "./a");
+        assert_eq!(explanation.directory, root);
+        assert_eq!(explanation.result, Ok(root.join("a.js")));
+        assert!(explanation.found.contains(&root.join("a.js")));
+        assert!(explanation.extensions.contains(&".d.ts".to_string()));
+        let text = explanation.to_string();
+        assert!(text.starts_with("Resolving \"./a\" from /marsh-explain-resolution\n"));
+        assert!(
+            text.ends_with("resolved: /marsh-explain-resolution/a.js"),
+            "{text}"
+        );
+
+        let explanation = service.explain_resolution(&root.join("main.ts"), "./missing");
+        assert!(explanation.result.is_err());
+        assert!(explanation.missing.contains(&root.join("missing.ts")));
+        assert!(explanation.to_string().contains("  unresolved: "));
+    }
+
+    #[test]
+    fn test_script_src() {
+        let root = Path::new("/marsh-script-src");
+        let file_system = MemoryFileSystem::default()
+            .with_file(
+                root.join("index.html"),
+                r#"<script type="module" src="/src/main.ts"></script>
+<script type="module" src="./relative.ts"></script>
+<script type="module" src="bare.ts"></script>
+<script type="module" src="https://cdn.example.com/remote.js"></script>
+<script type="module" src="//cdn.example.com/protocol-relative.js"></script>
+<script src="classic.js"></script>
+<script type="module">import './inline';</script>"#,
+            )
+            .with_file(root.join("src/main.ts"), "")
+            .with_file(root.join("relative.ts"), "")
+            .with_file(root.join("bare.ts"), "")
+            .with_file(root.join("classic.js"), "")
+            .with_file(root.join("inline.ts"), "");
+        let options = AnalyzeServiceOptions::new(root, vec![root.join("index.html").into()])
+            .with_cross_module(true)
+            .with_file_system(file_system);
+        let graph = AnalyzeService::new(options).analyze();
+        let mut edges = graph
+            .edges
+            .iter()
+            .map(|edge| format!("{} -> {}", edge.from, edge.to))
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+        // Root-relative sources start at the working directory, remote and classic scripts
+        // are left out
+        assert_eq!(
+            edges,
+            [
+                "index.html -> bare.ts",
+                "index.html -> inline.ts",
+                "index.html -> relative.ts",
+                "index.html -> src/main.ts",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let root = Path::new("/marsh-json");
+        let file_system = MemoryFileSystem::default()
+            .with_file(
+                root.join("main.ts"),
+                "import a from './a.json'; import b from './b'; const c = require('./c');",
+            )
+            .with_file(root.join("a.json"), "{}")
+            .with_file(root.join("b.json"), "{}")
+            .with_file(root.join("c.json"), "{ \"c\": 1 }");
+        let options = AnalyzeServiceOptions::new(root, vec![root.join("main.ts").into()])
+            .with_cross_module(true)
+            .with_file_system(file_system);
+        let graph = AnalyzeService::new(options).analyze();
+        let mut nodes = graph
+            .nodes
+            .iter()
+            .map(|node| (node.path.as_str(), node.kind))
+            .collect::<Vec<_>>();
+        nodes.sort_unstable_by_key(|(path, _)| *path);
+        // Only `require()` finds JSON files without their extension
+        assert_eq!(
+            nodes,
+            [
+                ("a.json", NodeKind::Json),
+                ("b", NodeKind::Unresolved),
+                ("c.json", NodeKind::Json),
+                ("main.ts", NodeKind::Module),
+            ]
+        );
+        let require = graph.edges.iter().find(|edge| edge.to == "c.json").unwrap();
+        assert_eq!(require.kind, EdgeType::Require);
+    }
+
+    #[test]
+    fn test_build_semantic() {
+        let root = Path::new("/marsh-build-semantic");
+        let semantic_models = |with: fn(AnalyzeServiceOptions) -> AnalyzeServiceOptions| {
+            let file_system = MemoryFileSystem::default()
+                .with_file(
+                    root.join("main.ts"),
+                    "/** @see a */\nimport { a } from './a';",
+                )
+                .with_file(root.join("a.ts"), "export const a = 1;");
+            let options = AnalyzeServiceOptions::new(root, vec![root.join("main.ts").into()])
+                .with_cross_module(true)
+                .with_file_system(file_system);
+            let service = AnalyzeService::new(with(options));
+            assert_eq!(service.analyze().edges.len(), 1);
+            service.counters().semantic_models()
+        };
+        assert_eq!(semantic_models(|options| options), 0);
+        assert_eq!(semantic_models(|options| options.with_build_cfg(true)), 2);
+        assert_eq!(semantic_models(|options| options.with_build_jsdoc(true)), 2);
+        assert_eq!(
+            semantic_models(|options| options.with_build_cfg(true).with_fast(true)),
+            0
+        );
+    }
+
+    #[test]
+    fn test_deep_chain() {
+        // Each file imports the next one, and the last one imports the first
+        const FILES: usize = 5000;
+        let root = Path::new("/marsh-deep-chain");
+        let file_system = (0..FILES).fold(MemoryFileSystem::default(), |file_system, i| {
+            let next = (i + 1) % FILES;
+            file_system.with_file(
+                root.join(format!("{i}.ts")),
+                format!("import {{ a{next} }} from './{next}'; export const a{i} = 1;"),
+            )
+        });
+        let options = AnalyzeServiceOptions::new(root, vec![root.join("0.ts").into()])
+            .with_cross_module(true)
+            .with_file_system(file_system);
+        let graph = AnalyzeService::new(options).analyze();
+        assert_eq!(graph.edges.len(), FILES);
+        assert!(graph.diagnostics.is_empty());
+        let last = graph
+            .edges
+            .iter()
+            .find(|edge| edge.from == format!("{}.ts", FILES - 1))
+            .unwrap();
+        assert_eq!(last.to, "0.ts");
+        assert_eq!(last.symbols, ["a0"]);
+    }
+
+    #[test]
+    fn test_resident() {
+        let root = TestDir::new("resident");
+        for (file, contents) in [
+            ("main.ts", "import './a'; import './b';"),
+            ("a.ts", "export const a = 1;"),
+            ("b.ts", "export const b = 1;"),
+        ] {
+            root.write(file, contents);
         }
+        let options = AnalyzeServiceOptions::new(&*root, vec![root.join("main.ts").into()])
+            .with_cross_module(true)
+            .with_resident(true);
+        let service = AnalyzeService::new(options);
+        let edges = |service: &AnalyzeService| {
+            let mut edges = service
+                .analyze()
+                .edges
+                .into_iter()
+                .map(|edge| format!("{} -> {}", edge.from, edge.to))
+                .collect::<Vec<_>>();
+            edges.sort_unstable();
+            edges
+        };
+        assert_eq!(edges(&service), ["main.ts -> a.ts", "main.ts -> b.ts"]);
+
+        // Only the invalidated file is read again, the other keeps its parse until invalidated
+        root.write("a.ts", "import './c';");
+        root.write("b.ts", "import './c';");
+        root.write("c.ts", "export const c = 1;");
+        service.invalidate(&root.join("a.ts"));
+        assert_eq!(
+            edges(&service),
+            ["a.ts -> c.ts", "main.ts -> a.ts", "main.ts -> b.ts"]
+        );
+        let report = StatsReport::new(service.counters(), 0, 1, Duration::ZERO);
+        assert_eq!((report.files_parsed, report.cache_hits), (2, 2));
+
+        service.invalidate(&root.join("b.ts"));
+        assert_eq!(
+            edges(&service),
+            [
+                "a.ts -> c.ts",
+                "b.ts -> c.ts",
+                "main.ts -> a.ts",
+                "main.ts -> b.ts"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_externals() {
+        let root = TestDir::new("externals");
+        std::fs::write(
+            root.join("main.ts"),
+            "import './missing'; import 'virtual:routes'; import 'electron/main'; import 'fs';",
+        )
+        .unwrap();
+
+        let severities = |externals: &[&str]| {
+            let options = AnalyzeServiceOptions::new(&*root, vec![root.join("main.ts").into()])
+                .with_cross_module(true)
+                .with_externals(externals.iter().map(ToString::to_string).collect());
+            let mut diagnostics = AnalyzeService::new(options)
+                .analyze()
+                .diagnostics
+                .into_iter()
+                .map(|diagnostic| (diagnostic.message, diagnostic.severity))
+                .collect::<Vec<_>>();
+            diagnostics.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+            diagnostics
+                .into_iter()
+                .map(|(message, severity)| {
+                    (message.split(':').next().unwrap().to_string(), severity)
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            severities(&["virtual:", "electron"]),
+            [
+                (
+                    "Unable to resolve \"./missing\"".to_string(),
+                    Severity::Error
+                ),
+                (
+                    "Unable to resolve \"electron/main\"".to_string(),
+                    Severity::Warn
+                ),
+                ("Unable to resolve \"virtual".to_string(), Severity::Warn),
+            ]
+        );
+        assert!(severities(&[])
+            .iter()
+            .all(|(_, severity)| *severity == Severity::Error));
     }
 }
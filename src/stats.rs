@@ -0,0 +1,221 @@
+//! Statistics of a run reported by `marsh stats`, to quantify regressions and tune the
+//! number of threads.
+
+use std::{
+    fmt,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use serde::Serialize;
+
+use crate::dead_files::format_size;
+
+/// Counters updated by the analysis as it goes
+#[derive(Debug, Default)]
+pub struct Counters {
+    files_discovered: AtomicUsize,
+    files_processed: AtomicUsize,
+    files_parsed: AtomicUsize,
+    cache_hits: AtomicUsize,
+    semantic_models: AtomicUsize,
+    bytes_processed: AtomicU64,
+    resolver_calls: AtomicUsize,
+}
+
+impl Counters {
+    /// A file was queued for processing
+    pub fn discovered(&self) {
+        self.files_discovered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A queued file was processed, or skipped
+    pub fn processed(&self) {
+        self.files_processed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A file was parsed instead of being taken from the cache
+    pub fn parsed(&self) {
+        self.files_parsed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A file was taken from the cache instead of being parsed
+    pub fn cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The semantic model of a source was built, see `--build-cfg` and `--build-jsdoc`
+    pub fn semantic_built(&self) {
+        self.semantic_models.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A file of `bytes` was read
+    pub fn read(&self, bytes: usize) {
+        self.bytes_processed
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// A specifier was resolved
+    pub fn resolved(&self) {
+        self.resolver_calls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Files processed and files queued so far, processed or not
+    pub fn files(&self) -> (usize, usize) {
+        (
+            self.files_processed.load(Ordering::Relaxed),
+            self.files_discovered.load(Ordering::Relaxed),
+        )
+    }
+
+    pub fn resolver_calls(&self) -> usize {
+        self.resolver_calls.load(Ordering::Relaxed)
+    }
+
+    pub fn semantic_models(&self) -> usize {
+        self.semantic_models.load(Ordering::Relaxed)
+    }
+
+    /// Starts counting a new run over
+    pub fn reset(&self) {
+        for counter in [
+            &self.files_discovered,
+            &self.files_processed,
+            &self.files_parsed,
+            &self.cache_hits,
+            &self.semantic_models,
+            &self.resolver_calls,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+        self.bytes_processed.store(0, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsReport {
+    pub files_parsed: usize,
+    /// Files taken from the cache, see `--cache`
+    pub cache_hits: usize,
+    /// Size of the files read, in bytes
+    pub bytes_processed: u64,
+    pub resolver_calls: usize,
+    /// Number of edges of the graph
+    pub edges: usize,
+    pub threads: usize,
+    /// Wall time of the analysis, in milliseconds
+    pub elapsed_ms: u128,
+    /// Files parsed or taken from the cache per second
+    pub files_per_second: f64,
+    pub bytes_per_second: f64,
+    /// Peak resident set size of the process, in bytes, when the platform tells it
+    pub peak_rss: Option<u64>,
+}
+
+impl StatsReport {
+    /// Report of a run which took `elapsed` and found `edges`
+    pub fn new(counters: &Counters, edges: usize, threads: usize, elapsed: Duration) -> Self {
+        let files_parsed = counters.files_parsed.load(Ordering::Relaxed);
+        let cache_hits = counters.cache_hits.load(Ordering::Relaxed);
+        let bytes_processed = counters.bytes_processed.load(Ordering::Relaxed);
+        let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+        #[allow(clippy::cast_precision_loss)]
+        let (files_per_second, bytes_per_second) = (
+            (files_parsed + cache_hits) as f64 / seconds,
+            bytes_processed as f64 / seconds,
+        );
+        Self {
+            files_parsed,
+            cache_hits,
+            bytes_processed,
+            resolver_calls: counters.resolver_calls.load(Ordering::Relaxed),
+            edges,
+            threads,
+            elapsed_ms: elapsed.as_millis(),
+            files_per_second,
+            bytes_per_second,
+            peak_rss: peak_rss(),
+        }
+    }
+}
+
+impl fmt::Display for StatsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<16}{}", "Files parsed", self.files_parsed)?;
+        writeln!(f, "{:<16}{}", "Cache hits", self.cache_hits)?;
+        writeln!(
+            f,
+            "{:<16}{}",
+            "Bytes processed",
+            format_size(self.bytes_processed)
+        )?;
+        writeln!(f, "{:<16}{}", "Resolver calls", self.resolver_calls)?;
+        writeln!(f, "{:<16}{}", "Edges", self.edges)?;
+        writeln!(f, "{:<16}{}", "Threads", self.threads)?;
+        writeln!(f, "{:<16}{} ms", "Time", self.elapsed_ms)?;
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let bytes_per_second = format_size(self.bytes_per_second as u64);
+        write!(
+            f,
+            "{:<16}{:.0} files/s, {bytes_per_second}/s",
+            "Throughput", self.files_per_second
+        )?;
+        if let Some(peak_rss) = self.peak_rss {
+            write!(f, "\n{:<16}{}", "Peak RSS", format_size(peak_rss))?;
+        }
+        Ok(())
+    }
+}
+
+/// Peak resident set size of the process, in bytes
+#[cfg(unix)]
+fn peak_rss() -> Option<u64> {
+    // SAFETY: `getrusage` only writes into the zeroed struct it is given
+    let usage = unsafe {
+        let mut usage = std::mem::zeroed::<libc::rusage>();
+        if libc::getrusage(libc::RUSAGE_SELF, &mut usage) != 0 {
+            return None;
+        }
+        usage
+    };
+    let max_rss = u64::try_from(usage.ru_maxrss).ok()?;
+    // Bytes on macOS, kibibytes elsewhere
+    if cfg!(target_os = "macos") {
+        Some(max_rss)
+    } else {
+        Some(max_rss * 1024)
+    }
+}
+
+#[cfg(not(unix))]
+fn peak_rss() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{Counters, StatsReport};
+
+    #[test]
+    fn test_stats_report() {
+        let counters = Counters::default();
+        counters.parsed();
+        counters.parsed();
+        counters.cache_hit();
+        counters.read(3072);
+        counters.resolved();
+
+        let report = StatsReport::new(&counters, 5, 4, Duration::from_millis(500));
+        assert_eq!(report.files_parsed, 2);
+        assert_eq!(report.cache_hits, 1);
+        assert_eq!(report.bytes_processed, 3072);
+        assert_eq!(report.resolver_calls, 1);
+        assert!((report.files_per_second - 6.0).abs() < f64::EPSILON);
+        let text = report.to_string();
+        assert!(text.contains("Bytes processed 3.0 KiB"));
+        assert!(text.contains("Throughput      6 files/s, 6.0 KiB/s"));
+    }
+}
@@ -0,0 +1,178 @@
+//! Inline comments suppressing known violations at their source instead of in the config:
+//! - `// marsh-ignore-next-line <rule>` for the imports of the next line
+//! - `/* marsh-disable <rule> */` for the whole file
+//!
+//! Several rules are separated by commas or spaces, without any rule every rule is
+//! suppressed. Text after `--` explains the suppression and is not read.
+
+use oxc_ast::{ast::Program, Trivias};
+use oxc_span::GetSpan;
+use serde::{Deserialize, Serialize};
+
+const IGNORE_NEXT_LINE: &str = "marsh-ignore-next-line";
+const DISABLE: &str = "marsh-disable";
+
+/// The suppression comments of a file
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Suppressions {
+    /// Rules of each `marsh-disable` comment, empty for every rule
+    disabled: Vec<Vec<String>>,
+    /// Imports following a `marsh-ignore-next-line` comment, by specifier and, once resolved,
+    /// by target, with the rules ignored for them
+    imports: Vec<(String, Vec<String>)>,
+}
+
+impl Suppressions {
+    /// Reads the suppression comments of `program`, of which `specifiers` are imported
+    pub fn new<'s>(
+        source_text: &str,
+        trivias: &Trivias,
+        program: &Program<'_>,
+        specifiers: impl Iterator<Item = &'s str> + Clone,
+    ) -> Self {
+        let mut suppressions = Self::default();
+        for comment in trivias.comments() {
+            let text = comment.span.source_text(source_text).trim_start();
+            if let Some(rules) = directive(text, DISABLE) {
+                suppressions.disabled.push(rules);
+            } else if let Some(rules) = directive(text, IGNORE_NEXT_LINE) {
+                let line = next_line(source_text, program, comment.span.end as usize);
+                for specifier in specifiers.clone() {
+                    if ['"', '\'', '`']
+                        .iter()
+                        .any(|quote| line.contains(&format!("{quote}{specifier}{quote}")))
+                    {
+                        suppressions
+                            .imports
+                            .push((specifier.to_string(), rules.clone()));
+                    }
+                }
+            }
+        }
+        suppressions
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.disabled.is_empty() && self.imports.is_empty()
+    }
+
+    /// Adds the suppressions of another source of the same file
+    pub fn extend(&mut self, other: Self) {
+        self.disabled.extend(other.disabled);
+        self.imports.extend(other.imports);
+    }
+
+    /// Suppresses the rules ignored for `specifier` for `target`, the file or package it
+    /// resolves to, too
+    pub fn resolve(&mut self, specifier: &str, target: &str) {
+        let rules = self
+            .imports
+            .iter()
+            .filter(|(import, _)| import == specifier)
+            .map(|(_, rules)| rules.clone())
+            .collect::<Vec<_>>();
+        for rules in rules {
+            self.imports.push((target.to_string(), rules));
+        }
+    }
+
+    /// Whether violations of `rule` are suppressed in the file, or for `import` only, a
+    /// specifier or target of the file
+    pub fn suppresses(&self, rule: &str, import: Option<&str>) -> bool {
+        let covers = |rules: &[String]| rules.is_empty() || rules.iter().any(|r| r == rule);
+        self.disabled.iter().any(|rules| covers(rules))
+            || import.is_some_and(|import| {
+                self.imports
+                    .iter()
+                    .any(|(suppressed, rules)| suppressed == import && covers(rules))
+            })
+    }
+}
+
+/// The rules of the comment `text` when it is the directive `name`
+fn directive(text: &str, name: &str) -> Option<Vec<String>> {
+    let rest = text.strip_prefix(name)?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let rules = rest.split_once("--").map_or(rest, |(rules, _)| rules);
+    Some(
+        rules
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|rule| !rule.is_empty())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// The line after the one ending at `offset`, along with the rest of the statement starting
+/// on it, e.g. the lines of a multiline import
+fn next_line<'a>(source_text: &'a str, program: &Program<'_>, offset: usize) -> &'a str {
+    let Some(start) = source_text[offset..].find('\n').map(|i| offset + i + 1) else {
+        return "";
+    };
+    let line_end = source_text[start..]
+        .find('\n')
+        .map_or(source_text.len(), |i| start + i);
+    let end = program
+        .body
+        .iter()
+        .map(|statement| statement.span())
+        .find(|span| (start..line_end).contains(&(span.start as usize)))
+        .map_or(line_end, |span| line_end.max(span.end as usize));
+    &source_text[start..end]
+}
+
+#[cfg(test)]
+mod test {
+    use oxc_allocator::Allocator;
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    use super::Suppressions;
+
+    fn suppressions(source_text: &str, specifiers: &[&str]) -> Suppressions {
+        let allocator = Allocator::default();
+        let ret = Parser::new(&allocator, source_text, SourceType::mjs()).parse();
+        Suppressions::new(
+            source_text,
+            &ret.trivias,
+            &ret.program,
+            specifiers.iter().copied(),
+        )
+    }
+
+    #[test]
+    fn test_suppressions() {
+        let source_text = r"
+// marsh-ignore-next-line boundaries, publicEntries -- migrating
+import {
+  a,
+} from './a';
+import b from './b';
+// marsh-ignore-next-line
+const c = require('c');
+// marsh-ignore-next-lines
+import d from './d';
+";
+        let mut ignored = suppressions(source_text, &["./a", "./b", "c", "./d"]);
+        assert!(ignored.suppresses("boundaries", Some("./a")));
+        assert!(ignored.suppresses("publicEntries", Some("./a")));
+        assert!(!ignored.suppresses("noCircular", Some("./a")));
+        assert!(!ignored.suppresses("boundaries", Some("./b")));
+        assert!(ignored.suppresses("noCircular", Some("c")));
+        assert!(!ignored.suppresses("noCircular", Some("./d")));
+        assert!(!ignored.suppresses("boundaries", None));
+
+        ignored.resolve("./a", "src/a.ts");
+        assert!(ignored.suppresses("boundaries", Some("src/a.ts")));
+
+        let disabled = suppressions(
+            "/* marsh-disable noOrphans */\nimport a from './a';",
+            &["./a"],
+        );
+        assert!(disabled.suppresses("noOrphans", None));
+        assert!(!disabled.suppresses("boundaries", Some("./a")));
+        assert!(suppressions("import a from './a';", &["./a"]).is_empty());
+    }
+}
@@ -0,0 +1,308 @@
+//! Spans of a run, exported to an OpenTelemetry collector over OTLP/HTTP with its JSON
+//! encoding, to see where the time of runs goes, e.g. across the CI runs of a team.
+//!
+//! Every span is a child of the root span of the trace, which lasts until the export: the
+//! parse and resolution of each file and the linking of the graph. Exporting starts a new
+//! trace, so resident commands export one trace per analysis.
+//!
+//! The spans are posted once per analysis, so a single blocking request is all it takes
+//! instead of the `opentelemetry-otlp` exporters, which bring an async runtime and an HTTP
+//! client stack to a tool which otherwise has neither.
+//!
+//! <https://opentelemetry.io/docs/specs/otlp/#otlphttp>
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::BuildHasher,
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{json, Value};
+
+/// Standard variable telling where OTLP exporters send to
+pub const ENDPOINT_VARIABLE: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+/// How long connecting to the collector, sending the spans or reading its answer may take
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+struct SpanData {
+    id: u64,
+    name: &'static str,
+    start: SystemTime,
+    end: SystemTime,
+    attributes: Vec<(&'static str, Value)>,
+}
+
+/// The spans of one trace, until exported
+#[derive(Debug)]
+struct Trace {
+    id: u128,
+    start: SystemTime,
+    spans: Vec<SpanData>,
+}
+
+impl Trace {
+    fn new() -> Self {
+        let random = RandomState::new();
+        Self {
+            id: u128::from(random.hash_one(1)) << 64 | u128::from(random.hash_one(2)),
+            start: SystemTime::now(),
+            spans: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Tracer {
+    /// Base URL of the collector, e.g. `http://localhost:4318`
+    endpoint: String,
+    next_id: AtomicU64,
+    trace: Mutex<Trace>,
+}
+
+/// A span in progress, ended when dropped
+pub struct Span<'a> {
+    tracer: &'a Tracer,
+    data: SpanData,
+}
+
+impl Tracer {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            // The root span is 1
+            next_id: AtomicU64::new(2),
+            trace: Mutex::new(Trace::new()),
+        }
+    }
+
+    /// Starts a span named `name`
+    pub fn span(&self, name: &'static str) -> Span<'_> {
+        let now = SystemTime::now();
+        Span {
+            tracer: self,
+            data: SpanData {
+                id: self.next_id.fetch_add(1, Ordering::Relaxed),
+                name,
+                start: now,
+                end: now,
+                attributes: Vec::new(),
+            },
+        }
+    }
+
+    /// The spans of `trace` under the root span `marsh`, ending now, as an OTLP request
+    fn to_otlp_json(trace: &Trace) -> Value {
+        let root = SpanData {
+            id: 1,
+            name: "marsh",
+            start: trace.start,
+            end: SystemTime::now(),
+            attributes: Vec::new(),
+        };
+        let spans = std::iter::once(&root)
+            .chain(trace.spans.iter())
+            .map(|span| {
+                let attributes = span
+                    .attributes
+                    .iter()
+                    .map(|(key, value)| json!({ "key": key, "value": any_value(value) }))
+                    .collect::<Vec<_>>();
+                let mut json = json!({
+                    "traceId": format!("{:032x}", trace.id),
+                    "spanId": format!("{:016x}", span.id),
+                    "name": span.name,
+                    // Internal
+                    "kind": 1,
+                    "startTimeUnixNano": unix_nanos(span.start).to_string(),
+                    "endTimeUnixNano": unix_nanos(span.end).to_string(),
+                    "attributes": attributes,
+                });
+                if span.id != root.id {
+                    json["parentSpanId"] = json!(format!("{:016x}", root.id));
+                }
+                json
+            })
+            .collect::<Vec<_>>();
+        json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": [
+                        { "key": "service.name", "value": { "stringValue": "marsh" } },
+                    ],
+                },
+                "scopeSpans": [{
+                    "scope": { "name": "marsh", "version": env!("CARGO_PKG_VERSION") },
+                    "spans": spans,
+                }],
+            }],
+        })
+    }
+
+    /// Sends the spans ended so far to `/v1/traces` of the endpoint, starting a new trace
+    ///
+    /// # Errors
+    /// When the endpoint is not a plain `http://` URL, or the collector cannot be reached or
+    /// rejects the spans
+    pub fn export(&self) -> Result<(), String> {
+        let endpoint = self.endpoint.trim_end_matches('/');
+        let Some(address) = endpoint.strip_prefix("http://") else {
+            return Err(format!(
+                "Unsupported OTLP endpoint {endpoint}, only http:// endpoints are supported"
+            ));
+        };
+        let (host, base) = address
+            .split_once('/')
+            .map_or((address, String::new()), |(host, path)| {
+                (host, format!("/{path}"))
+            });
+        let address = if host.contains(':') {
+            host.to_string()
+        } else {
+            format!("{host}:80")
+        };
+        let trace = std::mem::replace(&mut *self.trace.lock().unwrap(), Trace::new());
+        let body = Self::to_otlp_json(&trace).to_string();
+        let request = format!(
+            "POST {base}/v1/traces HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        let error =
+            |error: std::io::Error| format!("Failed to export spans to {endpoint}: {error}");
+        let mut stream = connect(&address).map_err(error)?;
+        stream.set_read_timeout(Some(TIMEOUT)).map_err(error)?;
+        stream.set_write_timeout(Some(TIMEOUT)).map_err(error)?;
+        stream.write_all(request.as_bytes()).map_err(error)?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).map_err(error)?;
+        let status = response.lines().next().unwrap_or_default();
+        if status
+            .split_whitespace()
+            .nth(1)
+            .is_some_and(|code| code.starts_with('2'))
+        {
+            Ok(())
+        } else {
+            Err(format!("{endpoint} rejected the spans: {status}"))
+        }
+    }
+}
+
+impl Span<'_> {
+    #[inline]
+    #[must_use]
+    pub fn with_attribute(mut self, key: &'static str, value: impl Into<Value>) -> Self {
+        self.data.attributes.push((key, value.into()));
+        self
+    }
+}
+
+impl Drop for Span<'_> {
+    fn drop(&mut self) {
+        self.data.end = SystemTime::now();
+        let data = self.data.clone();
+        self.tracer.trace.lock().unwrap().spans.push(data);
+    }
+}
+
+/// Connects to the first address of `address` accepting a connection within the timeout
+fn connect(address: &str) -> std::io::Result<TcpStream> {
+    let mut last_error = None;
+    for address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(error) => last_error = Some(error),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, "no address to connect to")
+    }))
+}
+
+fn unix_nanos(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+}
+
+/// `value` as an OTLP `AnyValue`
+fn any_value(value: &Value) -> Value {
+    match value {
+        Value::Bool(value) => json!({ "boolValue": value }),
+        Value::Number(number) if number.is_i64() || number.is_u64() => {
+            json!({ "intValue": number.to_string() })
+        }
+        Value::Number(number) => json!({ "doubleValue": number }),
+        Value::String(value) => json!({ "stringValue": value }),
+        value => json!({ "stringValue": value.to_string() }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+    };
+
+    use super::Tracer;
+
+    #[test]
+    fn test_tracer() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/otlp", listener.local_addr().unwrap());
+        let collector = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(&stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some(value) = header.strip_prefix("Content-Length: ") {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            (&stream)
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            (
+                request_line,
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            )
+        });
+
+        let tracer = Tracer::new(endpoint);
+        drop(tracer.span("parse").with_attribute("file", "a.ts"));
+        drop(tracer.span("link").with_attribute("files", 2));
+        tracer.export().unwrap();
+        // The next export is a new trace
+        assert!(tracer.trace.lock().unwrap().spans.is_empty());
+
+        let (request_line, json) = collector.join().unwrap();
+        assert_eq!(request_line, "POST /otlp/v1/traces HTTP/1.1\r\n");
+        let spans = &json["resourceSpans"][0]["scopeSpans"][0]["spans"];
+        assert_eq!(spans.as_array().unwrap().len(), 3);
+        assert_eq!(spans[0]["name"], "marsh");
+        assert_eq!(spans[1]["name"], "parse");
+        assert_eq!(spans[1]["parentSpanId"], spans[0]["spanId"]);
+        assert_eq!(spans[1]["traceId"].as_str().unwrap().len(), 32);
+        assert_eq!(spans[1]["attributes"][0]["value"]["stringValue"], "a.ts");
+        assert_eq!(spans[2]["attributes"][0]["value"]["intValue"], "2");
+
+        assert!(Tracer::new("https://collector").export().is_err());
+    }
+}
@@ -0,0 +1,247 @@
+//! Imports between workspace packages compared with the dependencies Turborepo knows of: those
+//! declared in `package.json`, which `^` in the `dependsOn` of a `turbo.json` task runs first,
+//! and the `package#task` dependencies of `turbo.json`. An import of a package Turborepo does
+//! not know of lets a task run before the package it needs is built.
+
+use std::{fmt, path::Path};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    message::Message,
+    package_level::PackageLevelGraph,
+    utils::{read_to_string, PathAtom},
+    workspace::Workspace,
+};
+
+pub const TURBO_FILE: &str = "turbo.json";
+
+/// Fields of `package.json` declaring dependencies
+const DEPENDENCY_FIELDS: &[&str] = &[
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+#[derive(Debug, Default, Deserialize)]
+struct TurboJson {
+    /// `pipeline` before Turborepo 2
+    #[serde(default, alias = "pipeline")]
+    tasks: FxHashMap<String, TurboTask>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TurboTask {
+    #[serde(default)]
+    depends_on: Vec<String>,
+}
+
+/// A package importing another one
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageImport {
+    pub package: String,
+    pub dependency: String,
+    /// Declared in the `package.json` of `package`
+    pub declared: bool,
+    /// Tasks of `dependency` run before those of `package`: it is declared and a task depends
+    /// on `^` tasks, or a `package#task` depends on a `dependency#task`
+    pub ordered: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct TurboReport {
+    /// Every import between packages, sorted
+    pub imports: Vec<PackageImport>,
+}
+
+impl TurboReport {
+    /// Compares the imports between the packages of `workspace` owning the files of `deps`,
+    /// relative to `cwd`, with `turbo.json` in `cwd`
+    ///
+    /// # Errors
+    /// When `turbo.json` cannot be read or is invalid
+    pub fn new(
+        cwd: &Path,
+        workspace: &Workspace,
+        deps: &[(PathAtom, Message)],
+    ) -> Result<Self, String> {
+        let turbo_json = cwd.join(TURBO_FILE);
+        let mut source_text = read_to_string(&turbo_json)
+            .map_err(|error| format!("Failed to read {TURBO_FILE}: {error}"))?;
+        // Turborepo reads `turbo.json` as JSONC
+        json_strip_comments::strip(&mut source_text)
+            .map_err(|error| format!("{TURBO_FILE}: {error}"))?;
+        let turbo = serde_json::from_str::<TurboJson>(&source_text)
+            .map_err(|error| format!("{TURBO_FILE}: {error}"))?;
+        // Tasks of `package#task` keys depend on those of the packages of their `dependsOn`
+        let mut explicit = FxHashSet::default();
+        let mut topological = false;
+        for (name, task) in &turbo.tasks {
+            topological |= task.depends_on.iter().any(|task| task.starts_with('^'));
+            let Some((package, _)) = name.split_once('#') else {
+                continue;
+            };
+            for task in &task.depends_on {
+                if let Some((dependency, _)) = task.split_once('#') {
+                    explicit.insert((package, dependency));
+                }
+            }
+        }
+
+        let graph = PackageLevelGraph::new(cwd, workspace, deps);
+        let mut declared_dependencies = FxHashMap::default();
+        let mut imports = Vec::new();
+        for edge in &graph.edges {
+            let (package, dependency) = (&edge.from, &edge.to);
+            let declared = declared_dependencies
+                .entry(package)
+                .or_insert_with(|| declared_dependencies_of(&cwd.join(&graph.nodes[package].path)))
+                .contains(dependency);
+            let ordered = (declared && topological)
+                || explicit.contains(&(package.as_str(), dependency.as_str()));
+            imports.push(PackageImport {
+                package: package.clone(),
+                dependency: dependency.clone(),
+                declared,
+                ordered,
+            });
+        }
+        Ok(Self { imports })
+    }
+}
+
+/// Dependencies of every kind declared in the `package.json` of `dir`
+fn declared_dependencies_of(dir: &Path) -> FxHashSet<String> {
+    let Ok(source_text) = read_to_string(&dir.join("package.json")) else {
+        return FxHashSet::default();
+    };
+    let Ok(json) = serde_json::from_str::<Value>(&source_text) else {
+        return FxHashSet::default();
+    };
+    DEPENDENCY_FIELDS
+        .iter()
+        .filter_map(|field| json.get(field)?.as_object())
+        .flat_map(|dependencies| dependencies.keys().cloned())
+        .collect()
+}
+
+impl fmt::Display for TurboReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut count = 0;
+        for import in &self.imports {
+            let PackageImport {
+                package,
+                dependency,
+                ..
+            } = import;
+            if !import.declared {
+                writeln!(
+                    f,
+                    "{package} imports {dependency} without declaring it in package.json"
+                )?;
+            } else if !import.ordered {
+                writeln!(
+                    f,
+                    "{package} imports {dependency}, no turbo.json task runs it first"
+                )?;
+            } else {
+                continue;
+            }
+            count += 1;
+        }
+        write!(
+            f,
+            "{count} of {} imports between packages lack a dependency",
+            self.imports.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TurboReport;
+    use crate::{
+        graph::{EdgeType, NodeKind},
+        message::Message,
+        utils::PathAtom,
+        workspace::test_workspace,
+    };
+
+    #[test]
+    fn test_turbo_report() {
+        let (root, workspace) = test_workspace(
+            "turbo",
+            &[
+                (
+                    "apps/web",
+                    r#"{ "name": "web", "dependencies": { "ui": "*" } }"#,
+                ),
+                (
+                    "apps/docs",
+                    r#"{ "name": "docs", "devDependencies": { "ui": "*" } }"#,
+                ),
+                ("packages/ui", r#"{ "name": "ui" }"#),
+                ("packages/icons", r#"{ "name": "icons" }"#),
+            ],
+        );
+        let deps = [
+            ("apps/web/main.ts", "packages/ui/index.ts"),
+            ("apps/web/main.ts", "packages/icons/index.ts"),
+            ("apps/docs/main.ts", "packages/ui/index.ts"),
+            ("packages/ui/index.ts", "packages/icons/index.ts"),
+        ]
+        .map(|(src, dst)| {
+            (
+                PathAtom::new(src),
+                Message::new(dst, NodeKind::Module, EdgeType::Static),
+            )
+        });
+
+        assert!(TurboReport::new(&root, &workspace, &deps).is_err());
+
+        // Only `ui#build` is ordered, no task depends on `^` tasks
+        let turbo_json =
+            r#"{ "tasks": { "build": {}, "ui#build": { "dependsOn": ["icons#build"] } } }"#;
+        root.write("turbo.json", turbo_json);
+        let report = TurboReport::new(&root, &workspace, &deps).unwrap();
+        let imports = report
+            .imports
+            .iter()
+            .map(|import| {
+                (
+                    import.package.as_str(),
+                    import.dependency.as_str(),
+                    import.declared,
+                    import.ordered,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            imports,
+            [
+                ("docs", "ui", true, false),
+                ("ui", "icons", false, true),
+                ("web", "icons", false, false),
+                ("web", "ui", true, false),
+            ]
+        );
+
+        let turbo_json = r#"{
+            // Turborepo 1
+            "pipeline": { "build": { "dependsOn": ["^build"], }, },
+        }"#;
+        root.write("turbo.json", turbo_json);
+        let report = TurboReport::new(&root, &workspace, &deps).unwrap();
+        assert!(report.imports[0].ordered);
+        assert!(!report.imports[1].ordered);
+        assert_eq!(
+            report.to_string().lines().last(),
+            Some("2 of 4 imports between packages lack a dependency")
+        );
+    }
+}
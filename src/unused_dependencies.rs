@@ -0,0 +1,203 @@
+//! `package.json` dependencies which no analyzed file imports.
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Serialize)]
+pub struct UnusedDependencies {
+    /// Path of the `package.json` relative to the current working directory
+    pub manifest: String,
+    /// Names of the unused packages, sorted
+    pub dependencies: Vec<String>,
+}
+
+/// Pruning candidates: `dependencies` of the manifests owning the analyzed files which none
+/// of those files imports.
+#[derive(Debug, Default, Serialize)]
+pub struct UnusedDependencyReport {
+    pub manifests: Vec<UnusedDependencies>,
+}
+
+impl UnusedDependencyReport {
+    /// `imports` maps analyzed files to the names of the packages they import.
+    ///
+    /// Each file belongs to its nearest `package.json`, files inside `node_modules` are skipped.
+    pub fn new<'a>(
+        cwd: &Path,
+        imports: impl IntoIterator<Item = (&'a Path, &'a FxHashSet<String>)>,
+    ) -> Self {
+        let mut manifests = FxHashMap::<PathBuf, FxHashSet<&str>>::default();
+        let mut nearest = FxHashMap::<PathBuf, Option<PathBuf>>::default();
+        for (path, packages) in imports {
+            if path.components().any(|c| c.as_os_str() == "node_modules") {
+                continue;
+            }
+            let Some(manifest) = path
+                .parent()
+                .and_then(|dir| find_manifest(dir, &mut nearest))
+            else {
+                continue;
+            };
+            manifests
+                .entry(manifest)
+                .or_default()
+                .extend(packages.iter().map(String::as_str));
+        }
+
+        let mut manifests = manifests
+            .into_iter()
+            .filter_map(|(manifest, used)| {
+                let mut dependencies = declared_dependencies(&manifest)
+                    .into_iter()
+                    .filter(|dependency| !is_used(dependency, &used))
+                    .collect::<Vec<_>>();
+                if dependencies.is_empty() {
+                    return None;
+                }
+                dependencies.sort_unstable();
+                let manifest = manifest.strip_prefix(cwd).unwrap_or(&manifest);
+                Some(UnusedDependencies {
+                    manifest: manifest.display().to_string(),
+                    dependencies,
+                })
+            })
+            .collect::<Vec<_>>();
+        manifests.sort_unstable_by(|a, b| a.manifest.cmp(&b.manifest));
+        Self { manifests }
+    }
+}
+
+impl fmt::Display for UnusedDependencyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.manifests.is_empty() {
+            return write!(f, "No unused dependencies found.");
+        }
+        let mut count = 0;
+        for manifest in &self.manifests {
+            writeln!(f, "{}", manifest.manifest)?;
+            for dependency in &manifest.dependencies {
+                writeln!(f, "  {dependency}")?;
+            }
+            count += manifest.dependencies.len();
+        }
+        write!(f, "{count} unused dependencies")
+    }
+}
+
+/// Nearest `package.json` at or above `dir`, memoized per directory
+pub(crate) fn find_manifest(
+    dir: &Path,
+    nearest: &mut FxHashMap<PathBuf, Option<PathBuf>>,
+) -> Option<PathBuf> {
+    if let Some(manifest) = nearest.get(dir) {
+        return manifest.clone();
+    }
+    let manifest = dir.join("package.json");
+    let manifest = if manifest.is_file() {
+        Some(manifest)
+    } else {
+        dir.parent()
+            .and_then(|parent| find_manifest(parent, nearest))
+    };
+    nearest.insert(dir.to_path_buf(), manifest.clone());
+    manifest
+}
+
+/// `name` field of `manifest`
+pub(crate) fn package_name(manifest: &Path) -> Option<String> {
+    let json = serde_json::from_str::<Value>(&fs::read_to_string(manifest).ok()?).ok()?;
+    json.get("name")?.as_str().map(str::to_string)
+}
+
+/// Keys of the `dependencies` field of `manifest`
+fn declared_dependencies(manifest: &Path) -> Vec<String> {
+    let Ok(source_text) = fs::read_to_string(manifest) else {
+        return Vec::new();
+    };
+    let Ok(json) = serde_json::from_str::<Value>(&source_text) else {
+        return Vec::new();
+    };
+    json.get("dependencies")
+        .and_then(Value::as_object)
+        .map(|dependencies| dependencies.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Returns `true` when `dependency` is imported, `@types/*` packages count for the package they
+/// describe, e.g. `@types/babel__core` for `@babel/core`.
+fn is_used(dependency: &str, used: &FxHashSet<&str>) -> bool {
+    if used.contains(dependency) {
+        return true;
+    }
+    let Some(described) = dependency.strip_prefix("@types/") else {
+        return false;
+    };
+    match described.split_once("__") {
+        Some((scope, name)) => used.contains(format!("@{scope}/{name}").as_str()),
+        None => used.contains(described),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rustc_hash::FxHashSet;
+
+    use super::{is_used, UnusedDependencyReport};
+    use crate::utils::TestDir;
+
+    #[test]
+    fn test_unused_dependency_report() {
+        let root = TestDir::new("unused-dependencies");
+        std::fs::create_dir_all(root.join("packages/ui/src")).unwrap();
+        std::fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        for (file, contents) in [
+            (
+                "package.json",
+                r#"{ "dependencies": { "react": "*", "lodash": "*" }, "devDependencies": { "vitest": "*" } }"#,
+            ),
+            (
+                "packages/ui/package.json",
+                r#"{ "dependencies": { "clsx": "*" } }"#,
+            ),
+            (
+                "node_modules/pkg/package.json",
+                r#"{ "dependencies": { "unused": "*" } }"#,
+            ),
+        ] {
+            root.write(file, contents);
+        }
+
+        let app = FxHashSet::from_iter(["react".to_string()]);
+        let ui = FxHashSet::from_iter(["clsx".to_string()]);
+        let pkg = FxHashSet::default();
+        let files = [
+            (root.join("src/main.ts"), &app),
+            (root.join("packages/ui/src/index.ts"), &ui),
+            (root.join("node_modules/pkg/index.js"), &pkg),
+        ];
+        let report = UnusedDependencyReport::new(
+            &root,
+            files
+                .iter()
+                .map(|(path, packages)| (path.as_path(), *packages)),
+        );
+        assert_eq!(report.manifests.len(), 1);
+        assert_eq!(report.manifests[0].manifest, "package.json");
+        assert_eq!(report.manifests[0].dependencies, ["lodash"]);
+    }
+
+    #[test]
+    fn test_is_used() {
+        let used = FxHashSet::from_iter(["react", "@babel/core"]);
+        assert!(is_used("react", &used));
+        assert!(is_used("@types/react", &used));
+        assert!(is_used("@types/babel__core", &used));
+        assert!(!is_used("@types/lodash", &used));
+    }
+}
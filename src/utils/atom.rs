@@ -0,0 +1,218 @@
+//! Interned paths, shared by every map and edge mentioning the same file instead of being
+//! cloned into each of them.
+//!
+//! Interned values live until [`evict_unused`] finds nothing refers to them anymore, which
+//! long-lived processes call after each run so the files of previous runs are not kept.
+
+use std::{
+    borrow::Borrow,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    path::Path,
+    sync::{Arc, OnceLock},
+};
+
+use dashmap::DashSet;
+use serde::{Serialize, Serializer};
+
+struct Interner<T: ?Sized>(OnceLock<DashSet<Arc<T>>>);
+
+impl<T: ?Sized + Eq + Hash> Interner<T>
+where
+    for<'a> Arc<T>: From<&'a T>,
+{
+    const fn new() -> Self {
+        Self(OnceLock::new())
+    }
+
+    fn intern(&self, value: &T) -> Arc<T> {
+        let set = self.0.get_or_init(DashSet::default);
+        if let Some(interned) = set.get(value) {
+            return Arc::clone(&interned);
+        }
+        let interned = Arc::<T>::from(value);
+        // Another thread may have interned the value in the meantime, keep theirs
+        if !set.insert(Arc::clone(&interned)) {
+            return set
+                .get(value)
+                .map_or(interned, |existing| Arc::clone(&existing));
+        }
+        interned
+    }
+
+    fn evict_unused(&self) {
+        if let Some(set) = self.0.get() {
+            // Only the interner holds them, no atom or path compares equal to them anymore
+            set.retain(|value| Arc::strong_count(value) > 1);
+        }
+    }
+}
+
+static ATOMS: Interner<str> = Interner::new();
+static PATHS: Interner<Path> = Interner::new();
+
+/// The one shared allocation of `path`, used as key of the maps of a run
+pub fn intern_path(path: &Path) -> Arc<Path> {
+    PATHS.intern(path)
+}
+
+/// Drops the interned atoms and paths nothing refers to anymore, e.g. those of the files of
+/// a previous run
+pub fn evict_unused() {
+    ATOMS.evict_unused();
+    PATHS.evict_unused();
+}
+
+/// Name of a node of the graph, a path relative to the current working directory or a package.
+///
+/// Equal atoms share one allocation, so clones are cheap and comparisons are a pointer check.
+#[derive(Clone)]
+pub struct PathAtom(Arc<str>);
+
+impl PathAtom {
+    pub fn new(name: &str) -> Self {
+        Self(ATOMS.intern(name))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for PathAtom {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for PathAtom {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for PathAtom {
+    fn as_ref(&self) -> &Path {
+        Path::new(&*self.0)
+    }
+}
+
+impl Borrow<str> for PathAtom {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for PathAtom {
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<String> for PathAtom {
+    fn from(name: String) -> Self {
+        Self::new(&name)
+    }
+}
+
+impl PartialEq for PathAtom {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for PathAtom {}
+
+impl PartialEq<str> for PathAtom {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for PathAtom {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl Hash for PathAtom {
+    // Hashed like `str`, so maps keyed by atoms can be looked up with `&str`
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl PartialOrd for PathAtom {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PathAtom {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl fmt::Debug for PathAtom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for PathAtom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl Serialize for PathAtom {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{path::PathBuf, sync::Arc};
+
+    use rustc_hash::FxHashSet;
+
+    use super::{evict_unused, intern_path, PathAtom, ATOMS};
+
+    #[test]
+    fn test_path_atom() {
+        let a = PathAtom::new("src/a.ts");
+        let b = PathAtom::from("src/a.ts".to_string());
+        assert_eq!(a, b);
+        assert!(Arc::ptr_eq(&a.0, &b.0));
+        assert_ne!(a, PathAtom::new("src/b.ts"));
+        assert_eq!(a, "src/a.ts");
+
+        let set = [a, PathAtom::new("react")]
+            .into_iter()
+            .collect::<FxHashSet<_>>();
+        assert!(set.contains("src/a.ts"));
+        assert!(!set.contains("src/b.ts"));
+
+        let (a, b) = (
+            PathBuf::from("/root/src/a.ts"),
+            PathBuf::from("/root/src/a.ts"),
+        );
+        assert!(Arc::ptr_eq(&intern_path(&a), &intern_path(&b)));
+    }
+
+    #[test]
+    fn test_evict_unused() {
+        let interned = |name| ATOMS.0.get().unwrap().contains(name);
+        let kept = PathAtom::new("src/kept.ts");
+        drop(PathAtom::new("src/dropped.ts"));
+        evict_unused();
+        assert!(interned("src/kept.ts"));
+        assert!(!interned("src/dropped.ts"));
+        assert_eq!(kept, PathAtom::new("src/kept.ts"));
+    }
+}
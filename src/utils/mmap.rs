@@ -0,0 +1,82 @@
+//! Source files mapped into memory instead of being read, see `--mmap`.
+//!
+//! Mapped files are paged in by the kernel on demand and never copied into the heap, which
+//! keeps peak memory and the number of system calls down on large repositories. A mapped file
+//! must not be written to or truncated while it is analyzed, see [`map_to_string`].
+
+use std::{fs::File, io, ops::Deref, path::Path};
+
+/// Text of a source file, either read or mapped into memory
+pub enum FileText {
+    Read(String),
+    Mapped(MappedText),
+}
+
+/// A file mapped into memory which was valid UTF-8 when mapped, only created by
+/// [`map_to_string`]
+pub struct MappedText(memmap2::Mmap);
+
+impl Deref for FileText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            Self::Read(text) => text,
+            // SAFETY: the mapping was validated as UTF-8 when it was created, and the caller
+            // of `map_to_string` guarantees the file is not modified while it is mapped
+            Self::Mapped(MappedText(mmap)) => unsafe { std::str::from_utf8_unchecked(mmap) },
+        }
+    }
+}
+
+/// Maps the file at `path` into memory, validating it is UTF-8 like [`super::read_to_string`].
+/// Empty files, which cannot be mapped, fall back to reading.
+///
+/// # Safety
+/// The file must not be modified or truncated, by this or any other process, until the
+/// returned text is dropped. Otherwise reading the text may crash with `SIGBUS` or see bytes
+/// which are not valid UTF-8.
+///
+/// # Errors
+/// When the file cannot be opened or mapped, or is not valid UTF-8
+pub unsafe fn map_to_string(path: &Path) -> io::Result<FileText> {
+    let file = File::open(path)?;
+    if file.metadata()?.len() == 0 {
+        return Ok(FileText::Read(String::new()));
+    }
+    // SAFETY: upheld by the caller
+    let mmap = unsafe { memmap2::Mmap::map(&file)? };
+    if simdutf8::basic::from_utf8(&mmap).is_err() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "stream did not contain valid UTF-8",
+        ));
+    }
+    Ok(FileText::Mapped(MappedText(mmap)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::map_to_string;
+    use crate::utils::TestDir;
+
+    #[test]
+    fn test_map_to_string() {
+        let dir = TestDir::new("mmap");
+        let (text, empty, binary) = (dir.join("a.ts"), dir.join("b.ts"), dir.join("c.ts"));
+        std::fs::write(&text, "import { a } from './ä';\n").unwrap();
+        std::fs::write(&empty, "").unwrap();
+        std::fs::write(&binary, [0x66, 0xff, 0xfe]).unwrap();
+
+        // SAFETY: the files are not modified while mapped
+        unsafe {
+            assert_eq!(
+                &*map_to_string(&text).unwrap(),
+                "import { a } from './ä';\n"
+            );
+            assert_eq!(&*map_to_string(&empty).unwrap(), "");
+            assert!(map_to_string(&binary).is_err());
+            assert!(map_to_string(&dir.join("missing.ts")).is_err());
+        }
+    }
+}
@@ -1,4 +1,21 @@
-use std::{io, path::Path};
+use std::{
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+use oxc_span::{SourceType, UnknownExtension};
+
+mod atom;
+mod mmap;
+mod source_map;
+#[cfg(test)]
+mod test_dir;
+
+pub use atom::{evict_unused, intern_path, PathAtom};
+pub use mmap::{map_to_string, FileText};
+pub use source_map::original_source;
+#[cfg(test)]
+pub use test_dir::TestDir;
 
 pub fn read_to_string(path: &Path) -> io::Result<String> {
     // `simdutf8` is faster than `std::str::from_utf8` which `fs::read_to_string` uses internally
@@ -13,3 +30,248 @@ pub fn read_to_string(path: &Path) -> io::Result<String> {
     // SAFETY: `simdutf8` has ensured it's a valid UTF-8 string
     Ok(unsafe { String::from_utf8_unchecked(bytes) })
 }
+
+/// Returns the package name of a path inside `node_modules`,
+/// e.g. `react` for `node_modules/react/index.js` and `@scope/pkg` for
+/// `node_modules/@scope/pkg/dist/index.js`.
+///
+/// Nested `node_modules` directories are honored, the innermost package wins.
+pub fn node_modules_package_name(path: &Path) -> Option<String> {
+    let components = path.components().collect::<Vec<_>>();
+    let index = components
+        .iter()
+        .rposition(|c| matches!(c, Component::Normal(name) if *name == "node_modules"))?;
+    let mut names = components[index + 1..].iter().map(|c| c.as_os_str().to_str());
+    let name = names.next()??;
+    if name.starts_with('@') {
+        let scoped = names.next()??;
+        Some(format!("{name}/{scoped}"))
+    } else {
+        Some(name.to_string())
+    }
+}
+
+/// Extensions of stylesheets which bundlers treat as dependencies
+pub const STYLE_EXTENSIONS: &[&str] = &["css", "scss", "sass", "less", "styl", "stylus", "pcss"];
+
+pub fn is_style_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(std::ffi::OsStr::to_str)
+        .is_some_and(|ext| STYLE_EXTENSIONS.contains(&ext))
+}
+
+/// Returns `true` for CSS modules, e.g. `button.module.css`, whose classes can be composed
+/// from other files
+pub fn is_css_module_path(path: &Path) -> bool {
+    is_style_path(path)
+        && path
+            .file_stem()
+            .and_then(std::ffi::OsStr::to_str)
+            .is_some_and(|stem| stem.ends_with(".module"))
+}
+
+/// Extensions of non-code assets which bundlers treat as dependencies
+pub const ASSET_EXTENSIONS: &[&str] = &[
+    "apng", "avif", "bmp", "gif", "ico", "jpeg", "jpg", "png", "svg", "webp", // images
+    "eot", "otf", "ttf", "woff", "woff2", // fonts
+    "mp3", "mp4", "ogg", "wav", "webm", // media
+    "wasm", "txt", // data
+];
+
+pub fn is_asset_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(std::ffi::OsStr::to_str)
+        .is_some_and(|ext| ASSET_EXTENSIONS.contains(&ext))
+}
+
+/// Extensions of JSON data files, which bundlers and Node.js turn into modules
+pub const JSON_EXTENSIONS: &[&str] = &["json", "json5", "jsonc"];
+
+pub fn is_json_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(std::ffi::OsStr::to_str)
+        .is_some_and(|ext| JSON_EXTENSIONS.contains(&ext))
+}
+
+/// Extensions TypeScript tries for an import written with a JavaScript extension, as required
+/// by `"moduleResolution": "node16"`, e.g. `./foo.ts` for `./foo.js`.
+/// Declaration files come last so an implementation next to them wins.
+/// <https://www.typescriptlang.org/docs/handbook/modules/reference.html#file-extension-substitution>
+pub const EXTENSION_ALIAS: &[(&str, &[&str])] = &[
+    (".js", &[".ts", ".tsx", ".js", ".jsx", ".d.ts"]),
+    (".jsx", &[".tsx", ".jsx", ".d.ts"]),
+    (".mjs", &[".mts", ".mjs", ".d.mts"]),
+    (".cjs", &[".cts", ".cjs", ".d.cts"]),
+];
+
+/// Source type of a JavaScript or TypeScript file from its extension.
+///
+/// Unlike [`SourceType::from_path`], `.cts` files are modules: TypeScript compiles their
+/// `import` declarations to `require` calls. `.cjs` files stay scripts, like in Node.js.
+pub fn source_type_from_path(path: &Path) -> Result<SourceType, UnknownExtension> {
+    let source_type = SourceType::from_path(path)?;
+    if source_type.is_typescript() && source_type.is_script() {
+        return Ok(source_type.with_module(true));
+    }
+    Ok(source_type)
+}
+
+pub const DECLARATION_EXTENSIONS: &[&str] = &["d.ts", "d.mts", "d.cts"];
+
+/// Returns the implementation sibling of a TypeScript declaration file,
+/// e.g. `foo.ts` or `foo.js` for `foo.d.ts`, and `foo.mts` or `foo.mjs` for `foo.d.mts`.
+pub fn declaration_implementation(path: &Path) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_str()?;
+    let (stem, extensions): (_, &[&str]) = if let Some(stem) = file_name.strip_suffix(".d.ts") {
+        (stem, &["ts", "tsx", "js", "jsx"])
+    } else if let Some(stem) = file_name.strip_suffix(".d.mts") {
+        (stem, &["mts", "mjs"])
+    } else if let Some(stem) = file_name.strip_suffix(".d.cts") {
+        (stem, &["cts", "cjs"])
+    } else {
+        return None;
+    };
+    extensions
+        .iter()
+        .map(|ext| path.with_file_name(format!("{stem}.{ext}")))
+        .find(|path| path.is_file())
+}
+
+/// Lexically normalizes `path` by resolving `.` and `..` components,
+/// without touching the file system.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if matches!(normalized.components().next_back(), Some(Component::Normal(_))) {
+                    normalized.pop();
+                } else {
+                    normalized.push("..");
+                }
+            }
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// `text` with its percent-encoded bytes decoded, e.g. of a URI
+pub fn percent_decode(text: &str) -> String {
+    let text = text.as_bytes();
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut i = 0;
+    while i < text.len() {
+        let decoded = (text[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        if let Some(byte) = decoded {
+            bytes.push(byte);
+            i += 3;
+        } else {
+            bytes.push(text[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// One-based line and column of the byte `offset` into `source_text`,
+/// the column counted in characters
+pub fn line_column(source_text: &str, offset: usize) -> (usize, usize) {
+    let before = &source_text[..offset.min(source_text.len())];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    let line = before.matches('\n').count() + 1;
+    (line, before[line_start..].chars().count() + 1)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::{
+        declaration_implementation, line_column, node_modules_package_name, normalize_path,
+        source_type_from_path, TestDir,
+    };
+
+    #[test]
+    fn test_node_modules_package_name() {
+        let cases = [
+            ("/app/node_modules/react/index.js", Some("react")),
+            ("/app/node_modules/@scope/pkg/dist/index.js", Some("@scope/pkg")),
+            ("/app/node_modules/a/node_modules/b/lib/b.js", Some("b")),
+            ("/app/node_modules/.pnpm/react@18.0.0/node_modules/react/index.js", Some("react")),
+            ("/app/src/index.ts", None),
+            ("/app/node_modules/@scope", None),
+        ];
+
+        for (path, expected) in cases {
+            assert_eq!(node_modules_package_name(Path::new(path)).as_deref(), expected, "{path}");
+        }
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        let cases = [
+            ("src/./missing", "src/missing"),
+            ("src/utils/../missing", "src/missing"),
+            ("./missing", "missing"),
+            ("../missing", "../missing"),
+            ("../../missing", "../../missing"),
+            ("/app/src/../lib/a.ts", "/app/lib/a.ts"),
+        ];
+
+        for (path, expected) in cases {
+            assert_eq!(normalize_path(Path::new(path)), Path::new(expected), "{path}");
+        }
+    }
+
+    #[test]
+    fn test_declaration_implementation() {
+        let dir = TestDir::new("declaration-implementation");
+        for file in ["a.d.ts", "a.js", "b.d.mts", "b.mjs", "c.d.ts"] {
+            dir.write(file, "");
+        }
+
+        let cases = [
+            ("a.d.ts", Some("a.js")),
+            ("b.d.mts", Some("b.mjs")),
+            ("c.d.ts", None),
+            ("a.js", None),
+        ];
+
+        for (file, expected) in cases {
+            assert_eq!(
+                declaration_implementation(&dir.join(file)),
+                expected.map(|expected| dir.join(expected)),
+                "{file}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_source_type_from_path() {
+        let source_type = |path: &str| source_type_from_path(Path::new(path)).unwrap();
+        for path in ["a.mts", "a.cts", "a.d.cts"] {
+            assert!(source_type(path).is_typescript(), "{path}");
+            assert!(source_type(path).is_module(), "{path}");
+            assert!(!source_type(path).is_jsx(), "{path}");
+        }
+        assert!(source_type("a.mjs").is_module());
+        assert!(source_type("a.cjs").is_script());
+        assert!(!source_type("a.cjs").is_typescript());
+    }
+
+    #[test]
+    fn test_line_column() {
+        let source_text = "a\nconst é = require(x);";
+        assert_eq!(line_column(source_text, 0), (1, 1));
+        assert_eq!(line_column(source_text, 2), (2, 1));
+        assert_eq!(
+            line_column(source_text, source_text.find('x').unwrap()),
+            (2, 19)
+        );
+    }
+}
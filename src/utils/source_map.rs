@@ -0,0 +1,115 @@
+//! Attributing generated files to their original source through `sourceMappingURL` comments.
+
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+
+use super::normalize_path;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SourceMap {
+    #[serde(default)]
+    source_root: Option<String>,
+    #[serde(default)]
+    sources: Vec<Option<String>>,
+}
+
+/// Returns the original source file of a generated file, following its `sourceMappingURL`.
+///
+/// Both external `.map` files and inline `data:` URLs are supported.
+/// Bundles mapping back to several sources cannot be attributed to one file and return `None`,
+/// as do sources which no longer exist on disk.
+pub fn original_source(path: &Path) -> Option<PathBuf> {
+    let source_text = std::fs::read_to_string(path).ok()?;
+    let url = source_mapping_url(&source_text)?;
+    let dir = path.parent()?;
+
+    let (source_map, map_dir) = if let Some(data) = url.strip_prefix("data:") {
+        let (_, payload) = data.split_once(";base64,")?;
+        let bytes = STANDARD.decode(payload).ok()?;
+        (
+            serde_json::from_slice::<SourceMap>(&bytes).ok()?,
+            dir.to_path_buf(),
+        )
+    } else {
+        let mut map_path = dir.join(url);
+        let bytes = std::fs::read(&map_path).ok()?;
+        map_path.pop();
+        (serde_json::from_slice::<SourceMap>(&bytes).ok()?, map_path)
+    };
+
+    let [Some(source)] = source_map.sources.as_slice() else {
+        return None;
+    };
+    let source_root = source_map.source_root.as_deref().unwrap_or_default();
+    let original = normalize_path(&map_dir.join(source_root).join(source));
+    original.is_file().then_some(original)
+}
+
+/// Returns the url of the last `//# sourceMappingURL=` comment.
+fn source_mapping_url(source_text: &str) -> Option<&str> {
+    source_text.lines().rev().map(str::trim).find_map(|line| {
+        line.strip_prefix("//# sourceMappingURL=")
+            .or_else(|| line.strip_prefix("//@ sourceMappingURL="))
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    use super::original_source;
+    use crate::utils::TestDir;
+
+    #[test]
+    fn test_original_source() {
+        let dir = TestDir::new("original-source");
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join("dist")).unwrap();
+        dir.write("src/a.ts", "");
+
+        std::fs::write(
+            dir.join("dist/a.js"),
+            "export {};\n//# sourceMappingURL=a.js.map\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("dist/a.js.map"),
+            r#"{"version":3,"sources":["../src/a.ts"],"mappings":""}"#,
+        )
+        .unwrap();
+
+        let inline = STANDARD.encode(r#"{"version":3,"sourceRoot":"../src","sources":["a.ts"]}"#);
+        std::fs::write(
+            dir.join("dist/inline.js"),
+            format!("export {{}};\n//# sourceMappingURL=data:application/json;base64,{inline}\n"),
+        )
+        .unwrap();
+
+        std::fs::write(
+            dir.join("dist/bundle.js"),
+            "export {};\n//# sourceMappingURL=bundle.js.map\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("dist/bundle.js.map"),
+            r#"{"version":3,"sources":["../src/a.ts","../src/b.ts"],"mappings":""}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            original_source(&dir.join("dist/a.js")),
+            Some(dir.join("src/a.ts"))
+        );
+        assert_eq!(
+            original_source(&dir.join("dist/inline.js")),
+            Some(dir.join("src/a.ts"))
+        );
+        assert_eq!(original_source(&dir.join("dist/bundle.js")), None);
+        assert_eq!(original_source(&dir.join("src/a.ts")), None);
+    }
+}
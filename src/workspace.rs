@@ -0,0 +1,498 @@
+//! Workspace packages declared by `package.json` `workspaces` or `pnpm-workspace.yaml`, as
+//! used by npm, Yarn and pnpm. Their entries are analyzed when no entry is given, and files
+//! are attributed to the package owning them.
+//!
+//! Imports of member packages linked into `node_modules`, or declared with the `workspace:`
+//! protocol, continue from the source of the package rather than stopping at the link.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Component, Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::utils::normalize_path;
+
+/// Directories build outputs are commonly emitted to
+pub const OUTPUT_DIRS: &[&str] = &["dist", "lib", "build", "out", "esm", "cjs", "es", "types"];
+
+/// Extensions tried for source entries, in order
+const SOURCE_EXTENSIONS: &[&str] = &["ts", "tsx", "mts", "cts", "js", "jsx", "mjs", "cjs"];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PackageJson {
+    name: Option<String>,
+    workspaces: Option<Workspaces>,
+    source: Option<String>,
+    module: Option<String>,
+    main: Option<String>,
+    #[serde(default)]
+    dependencies: BTreeMap<String, String>,
+    #[serde(default)]
+    dev_dependencies: BTreeMap<String, String>,
+    #[serde(default)]
+    peer_dependencies: BTreeMap<String, String>,
+    #[serde(default)]
+    optional_dependencies: BTreeMap<String, String>,
+}
+
+impl PackageJson {
+    /// Names of the dependencies declared with the `workspace:` protocol
+    fn workspace_dependencies(self) -> BTreeSet<String> {
+        [
+            self.dependencies,
+            self.dev_dependencies,
+            self.peer_dependencies,
+            self.optional_dependencies,
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|(_, version)| version.starts_with("workspace:"))
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Workspaces {
+    Patterns(Vec<String>),
+    Object {
+        #[serde(default)]
+        packages: Vec<String>,
+    },
+}
+
+#[derive(Debug, Default)]
+pub struct Workspace {
+    /// Directory declaring the workspace
+    root: PathBuf,
+    /// Package name to package directory
+    packages: BTreeMap<String, PathBuf>,
+    /// Package name to the names of its dependencies declared with the `workspace:` protocol
+    workspace_dependencies: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Workspace {
+    /// Finds the workspace root from `cwd` or its ancestors and collects its packages.
+    pub fn discover(cwd: &Path) -> Option<Self> {
+        let (root, patterns) = cwd
+            .ancestors()
+            .find_map(|dir| workspace_patterns(dir).map(|patterns| (dir, patterns)))?;
+
+        let (excludes, includes): (Vec<_>, Vec<_>) = patterns
+            .iter()
+            .partition(|pattern| pattern.starts_with('!'));
+        let excludes = excludes
+            .iter()
+            .filter_map(|pattern| glob::Pattern::new(pattern.trim_start_matches('!')).ok())
+            .collect::<Vec<_>>();
+
+        let mut packages = BTreeMap::new();
+        let mut workspace_dependencies = BTreeMap::new();
+        for pattern in includes {
+            let pattern = root.join(pattern).join("package.json");
+            let Ok(entries) = glob::glob(&pattern.to_string_lossy()) else {
+                continue;
+            };
+            for manifest in entries.flatten() {
+                let Some(dir) = manifest.parent() else {
+                    continue;
+                };
+                let relative = dir.strip_prefix(root).unwrap_or(dir);
+                if relative
+                    .components()
+                    .any(|component| component.as_os_str() == "node_modules")
+                    || excludes
+                        .iter()
+                        .any(|exclude| exclude.matches_path(relative))
+                {
+                    continue;
+                }
+                let Some(mut json) = read_package_json(&manifest) else {
+                    continue;
+                };
+                if let Some(name) = json.name.take() {
+                    workspace_dependencies.insert(name.clone(), json.workspace_dependencies());
+                    packages.insert(name, dir.to_path_buf());
+                }
+            }
+        }
+
+        Some(Self {
+            root: root.to_path_buf(),
+            packages,
+            workspace_dependencies,
+        })
+    }
+
+    /// Directory declaring the workspace
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Names and directories of the member packages, sorted by name
+    pub fn packages(&self) -> impl Iterator<Item = (&str, &Path)> {
+        self.packages
+            .iter()
+            .map(|(name, dir)| (name.as_str(), dir.as_path()))
+    }
+
+    /// Name of the member package owning `path`, the innermost one for nested packages.
+    /// Files installed in `node_modules` belong to no member package.
+    pub fn package_of_file(&self, path: &Path) -> Option<&str> {
+        self.packages
+            .iter()
+            .filter(|(_, dir)| {
+                path.strip_prefix(dir).is_ok_and(|relative| {
+                    !relative
+                        .components()
+                        .any(|component| component.as_os_str() == "node_modules")
+                })
+            })
+            .max_by_key(|(_, dir)| dir.components().count())
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Entry file of each member package, sorted by package name: its `src/index` or `index`
+    /// source, or else the file of its `source`, `module` or `main` field. Packages without
+    /// one, e.g. holding only configuration, are left out.
+    pub fn entries(&self) -> Vec<PathBuf> {
+        self.packages
+            .values()
+            .filter_map(|dir| package_entry(dir))
+            .collect()
+    }
+
+    /// Returns the source file a workspace package import should continue from.
+    ///
+    /// A `resolved` path inside a build output directory such as `dist` is mapped to its
+    /// counterpart in `src`. When the package could not be resolved at all, e.g. because it
+    /// was not built yet, the entry is looked up in `src` from the import subpath.
+    pub fn source_entry(&self, specifier: &str, resolved: Option<&Path>) -> Option<PathBuf> {
+        let (dir, subpath) = self.package_of(specifier)?;
+
+        if let Some(resolved) = resolved {
+            let relative = resolved.strip_prefix(dir).ok()?;
+            let mut components = relative.components();
+            let mut is_output = false;
+            while let Some(Component::Normal(name)) = components.clone().next() {
+                if !OUTPUT_DIRS.iter().any(|output| name == *output) {
+                    break;
+                }
+                is_output = true;
+                components.next();
+            }
+            if !is_output {
+                return None;
+            }
+            let rest = components.as_path();
+            // `index.js` and `index.d.ts` both map to `index`
+            let stem = rest.file_stem()?.to_str()?;
+            let stem = stem.strip_suffix(".d").unwrap_or(stem);
+            return find_source(&dir.join("src").join(rest.with_file_name(stem)));
+        }
+
+        let base = dir.join("src");
+        if subpath.is_empty() {
+            return find_source(&base.join("index"));
+        }
+        find_source(&base.join(subpath)).or_else(|| find_source(&base.join(subpath).join("index")))
+    }
+
+    /// Returns the source file an import of a linked member package continues from, whether
+    /// or not workspace sources are enabled.
+    ///
+    /// Member packages are linked when the package of `importer` declares them with the
+    /// `workspace:` protocol, or when `resolved` followed a `node_modules` link into them.
+    /// Imports resolving into a copy of the package, or not at all because nothing is
+    /// installed, are looked up in its `src` from the import subpath, or its entry.
+    pub fn linked_source(
+        &self,
+        importer: &Path,
+        specifier: &str,
+        resolved: Option<&Path>,
+    ) -> Option<PathBuf> {
+        let (dir, subpath) = self.package_of(specifier)?;
+        let resolved = resolved.filter(|resolved| resolved.starts_with(dir));
+        if resolved.is_none() {
+            let name = specifier[..specifier.len() - subpath.len()].trim_end_matches('/');
+            let declared = self
+                .package_of_file(importer)
+                .and_then(|package| self.workspace_dependencies.get(package))
+                .is_some_and(|dependencies| dependencies.contains(name));
+            if !declared {
+                return None;
+            }
+        }
+        self.source_entry(specifier, resolved).or_else(|| {
+            (resolved.is_none() && subpath.is_empty())
+                .then(|| package_entry(dir))
+                .flatten()
+        })
+    }
+
+    /// Splits a bare specifier into the directory of its workspace package and the subpath.
+    fn package_of<'s>(&self, specifier: &'s str) -> Option<(&Path, &'s str)> {
+        let mut split = 0;
+        let segments = if specifier.starts_with('@') { 2 } else { 1 };
+        for _ in 0..segments {
+            split = specifier[split..]
+                .find('/')
+                .map_or(specifier.len(), |index| split + index + 1);
+        }
+        let (name, subpath) = specifier.split_at(split);
+        let name = name.trim_end_matches('/');
+        let dir = self.packages.get(name)?;
+        Some((dir.as_path(), subpath))
+    }
+}
+
+fn read_package_json(path: &Path) -> Option<PackageJson> {
+    let bytes = std::fs::read(path).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// Returns the workspace patterns declared in `dir`, if it is a workspace root.
+fn workspace_patterns(dir: &Path) -> Option<Vec<String>> {
+    if let Ok(source_text) = std::fs::read_to_string(dir.join("pnpm-workspace.yaml")) {
+        return Some(pnpm_workspace_patterns(&source_text));
+    }
+    match read_package_json(&dir.join("package.json"))?.workspaces? {
+        Workspaces::Patterns(patterns) | Workspaces::Object { packages: patterns } => {
+            Some(patterns)
+        }
+    }
+}
+
+/// Reads the `packages` list of a `pnpm-workspace.yaml`.
+fn pnpm_workspace_patterns(source_text: &str) -> Vec<String> {
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+    for line in source_text.lines() {
+        let line = line.split_once('#').map_or(line, |(line, _)| line);
+        if line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) && !line.starts_with('-') {
+            in_packages = line.trim_end() == "packages:";
+            continue;
+        }
+        if let Some(pattern) = line.trim().strip_prefix('-').filter(|_| in_packages) {
+            patterns.push(pattern.trim().trim_matches(['"', '\'']).to_string());
+        }
+    }
+    patterns
+}
+
+/// Entry file of the package in `dir`, see [`Workspace::entries`]
+fn package_entry(dir: &Path) -> Option<PathBuf> {
+    find_source(&dir.join("src/index"))
+        .or_else(|| find_source(&dir.join("index")))
+        .or_else(|| {
+            let json = read_package_json(&dir.join("package.json"))?;
+            [json.source, json.module, json.main]
+                .into_iter()
+                .flatten()
+                .map(|field| normalize_path(&dir.join(field)))
+                .find(|path| path.is_file())
+        })
+}
+
+fn find_source(stem: &Path) -> Option<PathBuf> {
+    SOURCE_EXTENSIONS
+        .iter()
+        .map(|ext| {
+            let mut path = stem.as_os_str().to_os_string();
+            path.push(".");
+            path.push(ext);
+            PathBuf::from(path)
+        })
+        .find(|path| path.is_file())
+}
+
+/// A workspace for tests: the `package.json` of each of `packages`, by directory, and a root
+/// `package.json` with the parent directory of each in its `workspaces`, e.g. `packages/*`
+#[cfg(test)]
+pub(crate) fn test_workspace(
+    name: &str,
+    packages: &[(&str, &str)],
+) -> (crate::utils::TestDir, Workspace) {
+    let root = crate::utils::TestDir::new(name);
+    let mut patterns = BTreeSet::new();
+    for (dir, manifest) in packages {
+        root.write(&format!("{dir}/package.json"), manifest);
+        let parent = Path::new(dir).parent().unwrap_or(Path::new(""));
+        patterns.insert(parent.join("*").display().to_string());
+    }
+    let manifest = serde_json::json!({ "workspaces": patterns });
+    root.write("package.json", &manifest.to_string());
+    let workspace = Workspace::discover(&root).unwrap();
+    (root, workspace)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::{pnpm_workspace_patterns, Workspace};
+    use crate::utils::TestDir;
+
+    #[test]
+    fn test_pnpm_workspace_patterns() {
+        let source_text = "packages:\n  - 'packages/*'\n  - \"apps/*\" # apps\n  - '!**/test/**'\ncatalog:\n  - react\n";
+        assert_eq!(
+            pnpm_workspace_patterns(source_text),
+            ["packages/*", "apps/*", "!**/test/**"]
+        );
+    }
+
+    #[test]
+    fn test_packages() {
+        let root = TestDir::new("workspace-packages");
+        for (file, contents) in [
+            (
+                "pnpm-workspace.yaml",
+                "packages:\n  - 'packages/*'\n  - 'apps/*'\n",
+            ),
+            ("packages/ui/package.json", r#"{"name":"@myorg/ui"}"#),
+            ("packages/ui/src/index.tsx", ""),
+            (
+                "packages/legacy/package.json",
+                r#"{"name":"legacy","main":"./lib/main.js"}"#,
+            ),
+            ("packages/legacy/lib/main.js", ""),
+            ("packages/config/package.json", r#"{"name":"config"}"#),
+            ("apps/web/package.json", r#"{"name":"web"}"#),
+            ("apps/web/index.ts", ""),
+        ] {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let workspace = Workspace::discover(&root.join("apps/web")).unwrap();
+        assert_eq!(workspace.root(), &*root);
+        assert_eq!(
+            workspace
+                .packages()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>(),
+            ["@myorg/ui", "config", "legacy", "web"]
+        );
+        assert_eq!(
+            workspace.entries(),
+            [
+                root.join("packages/ui/src/index.tsx"),
+                root.join("packages/legacy/lib/main.js"),
+                root.join("apps/web/index.ts"),
+            ]
+        );
+        assert_eq!(
+            workspace.package_of_file(&root.join("packages/ui/src/button.tsx")),
+            Some("@myorg/ui")
+        );
+        assert_eq!(
+            workspace.package_of_file(&root.join("scripts/build.ts")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_source_entry() {
+        let root = TestDir::new("workspace-source-entry");
+        let ui = root.join("packages/ui");
+        std::fs::create_dir_all(ui.join("src/button")).unwrap();
+        std::fs::create_dir_all(ui.join("dist/esm")).unwrap();
+        std::fs::write(
+            root.join("package.json"),
+            r#"{"name":"root","workspaces":["packages/*"]}"#,
+        )
+        .unwrap();
+        std::fs::write(ui.join("package.json"), r#"{"name":"@myorg/ui"}"#).unwrap();
+        for file in ["src/index.ts", "src/button/index.tsx", "dist/esm/index.js"] {
+            std::fs::write(ui.join(file), "").unwrap();
+        }
+
+        let workspace = Workspace::discover(&root.join("packages")).unwrap();
+        let resolved = ui.join("dist/esm/index.js");
+        let cases = [
+            ("@myorg/ui", Some(resolved.as_path()), Some("src/index.ts")),
+            ("@myorg/ui", None, Some("src/index.ts")),
+            ("@myorg/ui/button", None, Some("src/button/index.tsx")),
+            ("@myorg/ui", Some(Path::new("/elsewhere/index.js")), None),
+            ("@myorg/other", None, None),
+        ];
+
+        for (specifier, resolved, expected) in cases {
+            assert_eq!(
+                workspace.source_entry(specifier, resolved),
+                expected.map(|expected| ui.join(expected)),
+                "{specifier}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_linked_source() {
+        let root = TestDir::new("workspace-linked-source");
+        for (file, contents) in [
+            ("package.json", r#"{"workspaces":["packages/*","apps/*"]}"#),
+            (
+                "packages/ui/package.json",
+                r#"{"name":"@myorg/ui","main":"dist/index.js"}"#,
+            ),
+            ("packages/ui/src/index.ts", ""),
+            ("packages/ui/src/button.ts", ""),
+            ("packages/ui/dist/index.js", ""),
+            (
+                "apps/web/package.json",
+                r#"{"name":"web","dependencies":{"@myorg/ui":"workspace:^"}}"#,
+            ),
+            ("apps/admin/package.json", r#"{"name":"admin"}"#),
+        ] {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let workspace = Workspace::discover(&root).unwrap();
+        let ui = root.join("packages/ui");
+        let web = root.join("apps/web/main.ts");
+        let admin = root.join("apps/admin/main.ts");
+        let linked = ui.join("dist/index.js");
+        let copy = root.join("node_modules/@myorg/ui/dist/index.js");
+        let cases = [
+            // Declared with `workspace:`, whether installed or not
+            (&web, "@myorg/ui", None, Some("src/index.ts")),
+            (&web, "@myorg/ui/button", None, Some("src/button.ts")),
+            (
+                &web,
+                "@myorg/ui",
+                Some(copy.as_path()),
+                Some("src/index.ts"),
+            ),
+            // Resolved through the `node_modules` link
+            (
+                &admin,
+                "@myorg/ui",
+                Some(linked.as_path()),
+                Some("src/index.ts"),
+            ),
+            (&admin, "@myorg/ui", None, None),
+            (&admin, "@myorg/ui", Some(copy.as_path()), None),
+            (&web, "react", None, None),
+        ];
+
+        for (importer, specifier, resolved, expected) in cases {
+            assert_eq!(
+                workspace.linked_source(importer, specifier, resolved),
+                expected.map(|expected| ui.join(expected)),
+                "{specifier} from {}",
+                importer.display()
+            );
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Imports reaching into the files of another workspace package, e.g.
+//! `@myorg/ui/src/internal/theme` or `../../ui/src/button`, instead of going through its
+//! published entry, listed per importing package for `--workspace-deep-imports`.
+//!
+//! Entries are what the `publicEntries` rule accepts: the package itself, the subpaths of its
+//! `exports` and the files its `package.json` declares.
+
+use std::{collections::BTreeMap, fmt, path::Path};
+
+use serde::Serialize;
+
+use crate::{
+    public_entries::{PublicEntries, PublicEntriesConfig},
+    resolutions::ResolutionDump,
+    workspace::Workspace,
+};
+
+#[derive(Debug, Serialize)]
+pub struct DeepImport {
+    /// Importing file, relative to the current working directory
+    pub file: String,
+    pub specifier: String,
+    /// Name of the package imported into
+    pub package: String,
+    /// Imported file, relative to the current working directory
+    pub target: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct WorkspaceDeepImportReport {
+    /// Deep imports by name of the importing package, in the order of their files
+    pub packages: BTreeMap<String, Vec<DeepImport>>,
+}
+
+impl WorkspaceDeepImportReport {
+    /// Checks the `resolutions` of the files of the packages of `workspace` against the
+    /// entries of the other packages
+    pub fn new(cwd: &Path, workspace: &Workspace, resolutions: &ResolutionDump) -> Self {
+        let public_entries = PublicEntries::new(&PublicEntriesConfig::default()).unwrap();
+        let relative = |path: &Path| path.strip_prefix(cwd).unwrap_or(path).display().to_string();
+        let mut report = Self::default();
+        for (importer, specifiers) in &resolutions.files {
+            let importer = Path::new(importer);
+            let Some(importer_package) = workspace.package_of_file(importer) else {
+                continue;
+            };
+            for (specifier, resolution) in specifiers {
+                let Some(target) = resolution.path.as_deref().map(Path::new) else {
+                    continue;
+                };
+                let Some(package) = workspace.package_of_file(target) else {
+                    continue;
+                };
+                if package == importer_package
+                    || public_entries.check(importer, specifier, target).is_none()
+                {
+                    continue;
+                }
+                report
+                    .packages
+                    .entry(importer_package.to_string())
+                    .or_default()
+                    .push(DeepImport {
+                        file: relative(importer),
+                        specifier: specifier.clone(),
+                        package: package.to_string(),
+                        target: relative(target),
+                    });
+            }
+        }
+        report
+    }
+}
+
+impl fmt::Display for WorkspaceDeepImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.packages.is_empty() {
+            return write!(f, "No deep imports between workspace packages found.");
+        }
+        let mut count = 0;
+        for (package, imports) in &self.packages {
+            writeln!(f, "{package}")?;
+            for import in imports {
+                writeln!(
+                    f,
+                    "  {}: \"{}\" reaches into {} ({})",
+                    import.file, import.specifier, import.package, import.target
+                )?;
+            }
+            count += imports.len();
+        }
+        write!(
+            f,
+            "{count} deep imports from {} packages",
+            self.packages.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::WorkspaceDeepImportReport;
+    use crate::{
+        resolutions::{Resolution, ResolutionDump},
+        workspace::test_workspace,
+    };
+
+    #[test]
+    fn test_workspace_deep_imports() {
+        let (root, workspace) = test_workspace(
+            "workspace-deep-imports",
+            &[
+                (
+                    "packages/ui",
+                    r#"{ "name": "@org/ui", "main": "dist/index.js", "exports": { ".": "./dist/index.js", "./theme": "./dist/theme.js" } }"#,
+                ),
+                ("apps/web", r#"{ "name": "web" }"#),
+            ],
+        );
+
+        let path = |file: &str| root.join(file).display().to_string();
+        let mut dump = ResolutionDump::default();
+        for (importer, specifier, target) in [
+            ("apps/web/main.ts", "@org/ui", "packages/ui/src/index.ts"),
+            (
+                "apps/web/main.ts",
+                "@org/ui/theme",
+                "packages/ui/src/theme.ts",
+            ),
+            (
+                "apps/web/main.ts",
+                "@org/ui/src/internal/x",
+                "packages/ui/src/internal/x.ts",
+            ),
+            (
+                "apps/web/app.ts",
+                "../../packages/ui/src/button",
+                "packages/ui/src/button.ts",
+            ),
+            ("apps/web/app.ts", "./main", "apps/web/main.ts"),
+            (
+                "packages/ui/src/index.ts",
+                "./button",
+                "packages/ui/src/button.ts",
+            ),
+        ] {
+            dump.files.entry(path(importer)).or_default().insert(
+                specifier.to_string(),
+                Resolution {
+                    found: true,
+                    path: Some(path(target)),
+                },
+            );
+        }
+        let report = WorkspaceDeepImportReport::new(&root, &workspace, &dump);
+
+        assert_eq!(report.packages.len(), 1);
+        assert_eq!(
+            report.packages["web"]
+                .iter()
+                .map(|import| (import.file.as_str(), import.specifier.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                ("apps/web/app.ts", "../../packages/ui/src/button"),
+                ("apps/web/main.ts", "@org/ui/src/internal/x"),
+            ]
+        );
+        assert_eq!(report.packages["web"][0].package, "@org/ui");
+        assert_eq!(
+            report.packages["web"][0].target,
+            "packages/ui/src/button.ts"
+        );
+        assert!(report
+            .to_string()
+            .ends_with("2 deep imports from 1 packages"));
+    }
+}